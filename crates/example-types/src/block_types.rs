@@ -15,7 +15,10 @@ use committable::{Commitment, Committable, RawCommitmentBuilder};
 use hotshot_types::{
     data::{BlockError, Leaf2},
     traits::{
-        block_contents::{BlockHeader, BuilderFee, EncodeBytes, TestableBlock, Transaction},
+        block_contents::{
+            BlockHeader, BuilderFee, EncodeBytes, NamespaceTable, NamespacedBlockPayload,
+            TestableBlock, Transaction,
+        },
         node_implementation::NodeType,
         BlockPayload, ValidatedState,
     },
@@ -106,6 +109,29 @@ impl TestTransaction {
 
         encoded
     }
+
+    /// Decode a list of transactions encoded by [`TestTransaction::encode`].
+    ///
+    /// # Errors
+    /// If a transaction is cut short
+    pub fn decode(mut bytes: &[u8]) -> Result<Vec<Self>, BlockError> {
+        let mut transactions = Vec::new();
+        while !bytes.is_empty() {
+            let truncated = || BlockError::InvalidBlockPayload("truncated transaction".to_string());
+            let (len, rest) = bytes
+                .split_first_chunk::<{ size_of::<u32>() }>()
+                .ok_or_else(truncated)?;
+            let len = u32::from_le_bytes(*len) as usize;
+            if rest.len() < len {
+                return Err(truncated());
+            }
+            let (txn, rest) = rest.split_at(len);
+            transactions.push(Self(txn.to_vec()));
+            bytes = rest;
+        }
+
+        Ok(transactions)
+    }
 }
 
 impl Committable for TestTransaction {
@@ -168,6 +194,19 @@ impl<TYPES: NodeType> TestableBlock<TYPES> for TestBlockPayload {
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct TestMetadata {
     pub num_transactions: u64,
+    /// Length of the encoded payload, which is the only namespace, [`TEST_NAMESPACE`]
+    pub payload_byte_len: u64,
+}
+
+impl TestMetadata {
+    /// Metadata of a payload of `transactions`
+    #[must_use]
+    pub fn new(transactions: &[TestTransaction]) -> Self {
+        Self {
+            num_transactions: transactions.len() as u64,
+            payload_byte_len: TestTransaction::encode(transactions).len() as u64,
+        }
+    }
 }
 
 impl EncodeBytes for TestMetadata {
@@ -196,9 +235,7 @@ impl<TYPES: NodeType> BlockPayload<TYPES> for TestBlockPayload {
         _instance_state: &Self::Instance,
     ) -> Result<(Self, Self::Metadata), Self::Error> {
         let txns_vec: Vec<TestTransaction> = transactions.into_iter().collect();
-        let metadata = TestMetadata {
-            num_transactions: txns_vec.len() as u64,
-        };
+        let metadata = TestMetadata::new(&txns_vec);
         Ok((
             Self {
                 transactions: txns_vec,
@@ -229,12 +266,7 @@ impl<TYPES: NodeType> BlockPayload<TYPES> for TestBlockPayload {
     }
 
     fn empty() -> (Self, Self::Metadata) {
-        (
-            Self::genesis(),
-            TestMetadata {
-                num_transactions: 0,
-            },
-        )
+        (Self::genesis(), TestMetadata::new(&[]))
     }
 
     fn builder_commitment(&self, _metadata: &Self::Metadata) -> BuilderCommitment {
@@ -253,6 +285,31 @@ impl<TYPES: NodeType> BlockPayload<TYPES> for TestBlockPayload {
     }
 }
 
+/// The only namespace of a [`TestBlockPayload`], which holds all of its transactions
+pub const TEST_NAMESPACE: u32 = 0;
+
+impl<TYPES: NodeType> NamespacedBlockPayload<TYPES> for TestBlockPayload {
+    type NamespaceId = u32;
+
+    fn namespace_table(metadata: &Self::Metadata) -> NamespaceTable<Self::NamespaceId> {
+        let len = usize::try_from(metadata.payload_byte_len).unwrap_or(usize::MAX);
+        NamespaceTable::new(vec![(TEST_NAMESPACE, 0..len)])
+            .expect("A single namespace from the start of the payload is a valid table")
+    }
+
+    fn decode_namespace(
+        namespace: &Self::NamespaceId,
+        bytes: &[u8],
+    ) -> Result<Vec<Self::Transaction>, Self::Error> {
+        if *namespace != TEST_NAMESPACE {
+            return Err(BlockError::InvalidBlockPayload(format!(
+                "no namespace {namespace}"
+            )));
+        }
+        TestTransaction::decode(bytes)
+    }
+}
+
 /// A [`BlockHeader`] that commits to [`TestBlockPayload`].
 #[derive(PartialEq, Eq, Hash, Clone, Debug, Deserialize, Serialize)]
 pub struct TestBlockHeader {
@@ -357,9 +414,7 @@ impl<
         builder_commitment: BuilderCommitment,
         _metadata: <TYPES::BlockPayload as BlockPayload<TYPES>>::Metadata,
     ) -> Self {
-        let metadata = TestMetadata {
-            num_transactions: 0,
-        };
+        let metadata = TestMetadata::new(&[]);
 
         Self {
             block_number: 0,
@@ -400,10 +455,8 @@ impl Committable for TestBlockHeader {
                 <TestBlockHeader as BlockHeader<TestTypes>>::block_number(self),
             )
             .constant_str("payload commitment")
-            .fixed_size_bytes(
-                <TestBlockHeader as BlockHeader<TestTypes>>::payload_commitment(self)
-                    .as_ref()
-                    .as_ref(),
+            .var_size_bytes(
+                <TestBlockHeader as BlockHeader<TestTypes>>::payload_commitment(self).as_ref(),
             )
             .finalize()
     }
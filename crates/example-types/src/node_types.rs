@@ -23,7 +23,7 @@ use hotshot::traits::{
 use hotshot_types::{
     constants::TEST_UPGRADE_CONSTANTS,
     data::{EpochNumber, ViewNumber},
-    signature_key::{BLSPubKey, BuilderKey},
+    signature_key::{BLSPubKey, BuilderKey, SchnorrPubKey},
     traits::node_implementation::{NodeType, Versions},
     upgrade_config::UpgradeConstants,
 };
@@ -201,6 +201,38 @@ impl NodeType for TestTwoStakeTablesTypes {
     type BuilderSignatureKey = BuilderKey;
 }
 
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    Hash,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+/// filler struct to implement node type and allow us
+/// to select our traits, signing with Schnorr keys instead of BLS keys
+pub struct TestSchnorrKeyTypes;
+impl NodeType for TestSchnorrKeyTypes {
+    const UPGRADE_CONSTANTS: UpgradeConstants = TEST_UPGRADE_CONSTANTS;
+
+    type AuctionResult = TestAuctionResult;
+    type View = ViewNumber;
+    type Epoch = EpochNumber;
+    type BlockHeader = TestBlockHeader;
+    type BlockPayload = TestBlockPayload;
+    type SignatureKey = SchnorrPubKey;
+    type Transaction = TestTransaction;
+    type ValidatedState = TestValidatedState;
+    type InstanceState = TestInstanceState;
+    type Membership = StaticCommittee<TestSchnorrKeyTypes>;
+    type BuilderSignatureKey = BuilderKey;
+}
+
 /// The Push CDN implementation
 #[derive(Clone, Debug, Deserialize, Serialize, Hash, Eq, PartialEq)]
 pub struct PushCdnImpl;
@@ -262,6 +294,8 @@ impl Versions for TestVersions {
     type Marketplace = StaticVersion<0, 3>;
 
     type Epochs = StaticVersion<0, 4>;
+
+    type AvidM = StaticVersion<0, 5>;
 }
 
 #[derive(Clone, Debug, Copy)]
@@ -278,6 +312,8 @@ impl Versions for MarketplaceUpgradeTestVersions {
     type Marketplace = StaticVersion<0, 3>;
 
     type Epochs = StaticVersion<0, 4>;
+
+    type AvidM = StaticVersion<0, 5>;
 }
 
 #[derive(Clone, Debug, Copy)]
@@ -294,6 +330,8 @@ impl Versions for MarketplaceTestVersions {
     type Marketplace = StaticVersion<0, 3>;
 
     type Epochs = StaticVersion<0, 4>;
+
+    type AvidM = StaticVersion<0, 5>;
 }
 
 #[derive(Clone, Debug, Copy)]
@@ -310,6 +348,8 @@ impl Versions for EpochsTestVersions {
     type Marketplace = StaticVersion<0, 5>;
 
     type Epochs = StaticVersion<0, 4>;
+
+    type AvidM = StaticVersion<0, 5>;
 }
 
 #[derive(Clone, Debug, Copy)]
@@ -326,6 +366,44 @@ impl Versions for EpochUpgradeTestVersions {
     type Marketplace = StaticVersion<0, 5>;
 
     type Epochs = StaticVersion<0, 4>;
+
+    type AvidM = StaticVersion<0, 5>;
+}
+
+#[derive(Clone, Debug, Copy)]
+pub struct AvidMTestVersions {}
+
+impl Versions for AvidMTestVersions {
+    type Base = StaticVersion<0, 4>;
+    type Upgrade = StaticVersion<0, 4>;
+    const UPGRADE_HASH: [u8; 32] = [
+        1, 0, 1, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0,
+        0, 0,
+    ];
+
+    type Marketplace = StaticVersion<0, 5>;
+
+    type Epochs = StaticVersion<0, 4>;
+
+    type AvidM = StaticVersion<0, 4>;
+}
+
+#[derive(Clone, Debug, Copy)]
+pub struct AvidMUpgradeTestVersions {}
+
+impl Versions for AvidMUpgradeTestVersions {
+    type Base = StaticVersion<0, 4>;
+    type Upgrade = StaticVersion<0, 5>;
+    const UPGRADE_HASH: [u8; 32] = [
+        1, 0, 1, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0,
+        0, 0,
+    ];
+
+    type Marketplace = StaticVersion<0, 6>;
+
+    type Epochs = StaticVersion<0, 4>;
+
+    type AvidM = StaticVersion<0, 5>;
 }
 
 #[cfg(test)]
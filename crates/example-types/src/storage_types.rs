@@ -19,6 +19,7 @@ use hotshot_types::{
         DaProposal, DaProposal2, Leaf, Leaf2, QuorumProposal, QuorumProposal2,
         QuorumProposalWrapper,
     },
+    drb::{DrbCheckpoint, DrbResult, DrbSeedInput, DrbSeedProof},
     event::HotShotAction,
     message::Proposal,
     simple_certificate::{NextEpochQuorumCertificate2, QuorumCertificate2, UpgradeCertificate},
@@ -27,10 +28,9 @@ use hotshot_types::{
         storage::Storage,
     },
     utils::View,
-    vid::VidSchemeType,
+    vid::VidCommitment,
     vote::HasViewNumber,
 };
-use jf_vid::VidScheme;
 
 use crate::testable_delay::{DelayConfig, SupportedTraitTypesForAsyncDelay, TestableDelay};
 
@@ -58,6 +58,10 @@ pub struct TestStorageState<TYPES: NodeType> {
         Option<hotshot_types::simple_certificate::NextEpochQuorumCertificate2<TYPES>>,
     action: TYPES::View,
     epoch: Option<TYPES::Epoch>,
+    drb_checkpoints: BTreeMap<TYPES::Epoch, DrbCheckpoint>,
+    drb_seeds: BTreeMap<TYPES::Epoch, DrbSeedInput>,
+    drb_seed_proofs: BTreeMap<TYPES::Epoch, DrbSeedProof<TYPES>>,
+    drb_results: BTreeMap<TYPES::Epoch, DrbResult>,
 }
 
 impl<TYPES: NodeType> Default for TestStorageState<TYPES> {
@@ -75,6 +79,10 @@ impl<TYPES: NodeType> Default for TestStorageState<TYPES> {
             high_qc2: None,
             action: TYPES::View::genesis(),
             epoch: None,
+            drb_checkpoints: BTreeMap::new(),
+            drb_seeds: BTreeMap::new(),
+            drb_seed_proofs: BTreeMap::new(),
+            drb_results: BTreeMap::new(),
         }
     }
 }
@@ -138,6 +146,22 @@ impl<TYPES: NodeType> TestStorage<TYPES> {
     pub async fn vids_cloned(&self) -> VidShares2<TYPES> {
         self.inner.read().await.vid2.clone()
     }
+
+    pub async fn drb_checkpoints_cloned(&self) -> BTreeMap<TYPES::Epoch, DrbCheckpoint> {
+        self.inner.read().await.drb_checkpoints.clone()
+    }
+
+    pub async fn drb_seeds_cloned(&self) -> BTreeMap<TYPES::Epoch, DrbSeedInput> {
+        self.inner.read().await.drb_seeds.clone()
+    }
+
+    pub async fn drb_seed_proofs_cloned(&self) -> BTreeMap<TYPES::Epoch, DrbSeedProof<TYPES>> {
+        self.inner.read().await.drb_seed_proofs.clone()
+    }
+
+    pub async fn drb_results_cloned(&self) -> BTreeMap<TYPES::Epoch, DrbResult> {
+        self.inner.read().await.drb_results.clone()
+    }
 }
 
 #[async_trait]
@@ -176,7 +200,7 @@ impl<TYPES: NodeType> Storage<TYPES> for TestStorage<TYPES> {
     async fn append_da(
         &self,
         proposal: &Proposal<TYPES, DaProposal<TYPES>>,
-        _vid_commit: <VidSchemeType as VidScheme>::Commit,
+        _vid_commit: VidCommitment,
     ) -> Result<()> {
         if self.should_return_err {
             bail!("Failed to append DA proposal to storage");
@@ -192,7 +216,7 @@ impl<TYPES: NodeType> Storage<TYPES> for TestStorage<TYPES> {
     async fn append_da2(
         &self,
         proposal: &Proposal<TYPES, DaProposal2<TYPES>>,
-        _vid_commit: <VidSchemeType as VidScheme>::Commit,
+        _vid_commit: VidCommitment,
     ) -> Result<()> {
         if self.should_return_err {
             bail!("Failed to append DA proposal (2) to storage");
@@ -364,6 +388,66 @@ impl<TYPES: NodeType> Storage<TYPES> for TestStorage<TYPES> {
         Ok(())
     }
 
+    async fn update_drb_checkpoint(
+        &self,
+        epoch: TYPES::Epoch,
+        checkpoint: DrbCheckpoint,
+    ) -> Result<()> {
+        if self.should_return_err {
+            bail!("Failed to update DRB checkpoint to storage");
+        }
+        Self::run_delay_settings_from_config(&self.delay_config).await;
+        self.inner
+            .write()
+            .await
+            .drb_checkpoints
+            .insert(epoch, checkpoint);
+        Ok(())
+    }
+
+    async fn add_drb_seed(&self, epoch: TYPES::Epoch, drb_seed_input: DrbSeedInput) -> Result<()> {
+        if self.should_return_err {
+            bail!("Failed to add DRB seed to storage");
+        }
+        Self::run_delay_settings_from_config(&self.delay_config).await;
+        self.inner
+            .write()
+            .await
+            .drb_seeds
+            .insert(epoch, drb_seed_input);
+        Ok(())
+    }
+
+    async fn add_drb_seed_proof(
+        &self,
+        epoch: TYPES::Epoch,
+        proof: DrbSeedProof<TYPES>,
+    ) -> Result<()> {
+        if self.should_return_err {
+            bail!("Failed to add DRB seed proof to storage");
+        }
+        Self::run_delay_settings_from_config(&self.delay_config).await;
+        self.inner
+            .write()
+            .await
+            .drb_seed_proofs
+            .insert(epoch, proof);
+        Ok(())
+    }
+
+    async fn add_drb_result(&self, epoch: TYPES::Epoch, drb_result: DrbResult) -> Result<()> {
+        if self.should_return_err {
+            bail!("Failed to add DRB result to storage");
+        }
+        Self::run_delay_settings_from_config(&self.delay_config).await;
+        self.inner
+            .write()
+            .await
+            .drb_results
+            .insert(epoch, drb_result);
+        Ok(())
+    }
+
     async fn migrate_consensus(
         &self,
         _convert_leaf: fn(Leaf<TYPES>) -> Leaf2<TYPES>,
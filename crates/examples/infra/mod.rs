@@ -61,6 +61,7 @@ use hotshot_types::{
         states::TestableState,
     },
     utils::genesis_epoch_from_version,
+    validator_config::ValidatorConfigFile,
     HotShotConfig, PeerConfig, ValidatorConfig,
 };
 use libp2p_networking::network::{
@@ -256,7 +257,7 @@ pub fn read_orchestrator_init_config<TYPES: NodeType>() -> (NetworkConfig<TYPES:
 
 /// Reads a network configuration from a given filepath
 /// # Panics
-/// if unable to convert the config file into toml
+/// if unable to convert the config file into toml, or if its VID config is invalid
 /// # Note
 /// This derived config is used for initialization of orchestrator,
 /// therefore `known_nodes_with_stake` will be an initialized
@@ -272,6 +273,9 @@ pub fn load_config_from_file<TYPES: NodeType>(
             .expect("Unable to convert config file to TOML");
 
     let mut config: NetworkConfig<TYPES::SignatureKey> = config_toml.into();
+    if let Err(e) = config.config.vid_config.validate() {
+        panic!("Invalid VID config in {config_file}: {e}");
+    }
 
     // initialize it with size for better assignment of peers' config
     config.config.known_nodes_with_stake =
@@ -377,7 +381,6 @@ pub trait RunDa<
 
         // Get KeyPair for certificate Aggregation
         let pk = validator_config.public_key.clone();
-        let sk = validator_config.private_key.clone();
 
         let network = self.network();
 
@@ -389,7 +392,7 @@ pub trait RunDa<
 
         SystemContext::init(
             pk,
-            sk,
+            validator_config.node_key(),
             config.node_index,
             config.config,
             membership,
@@ -914,14 +917,28 @@ pub async fn main_entry_point<
 
     let orchestrator_client: OrchestratorClient = OrchestratorClient::new(args.url.clone());
 
-    // We assume one node will not call this twice to generate two validator_config-s with same identity.
-    let validator_config = NetworkConfig::<TYPES::SignatureKey>::generate_init_validator_config(
-        orchestrator_client
-            .get_node_index_for_init_validator_config()
-            .await,
-        // we assign nodes to the DA committee by default
-        true,
-    );
+    let mut validator_config = match &args.keystore {
+        Some(keystore) => ValidatorConfigFile {
+            keystore: Some(keystore.clone()),
+            keystore_password_file: args.keystore_password_file.clone(),
+            // we assign nodes to the DA committee by default
+            is_da: true,
+            ..ValidatorConfigFile::default()
+        }
+        .validator_config()
+        .expect("failed to load the keystore"),
+        // We assume one node will not call this twice to generate two validator_config-s with same identity.
+        None => NetworkConfig::<TYPES::SignatureKey>::generate_init_validator_config(
+            orchestrator_client
+                .get_node_index_for_init_validator_config()
+                .await,
+            // we assign nodes to the DA committee by default
+            true,
+        ),
+    };
+    validator_config
+        .signing_guard
+        .clone_from(&args.signing_guard);
 
     // Derives our Libp2p private key from our private key, and then returns the public key of that key
     let libp2p_public_key =
@@ -0,0 +1,268 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Manages the keys of validators. Keys are stored in password encrypted keystore files, and
+//! their public keys can be exported in the format the orchestrator reads from
+//! `ORCHESTRATOR_PUBLIC_KEYS`. Validators load their keys from a keystore with `--keystore`.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, ensure, Context, Result};
+use clap::{Args, Parser, Subcommand};
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_types::{
+    keystore::{read_password_file, KeystoreFile, ValidatorKeys},
+    network::{PeerConfigKeys, PublicKeysFile},
+    traits::node_implementation::NodeType,
+};
+use zeroize::Zeroizing;
+
+/// The staking key type of the keystores
+type Key = <TestTypes as NodeType>::SignatureKey;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+/// Generate, rotate and export the keys of validators.
+struct Cli {
+    /// The command to run
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+/// The commands of the key manager
+enum Command {
+    /// Generate staking, state and libp2p keys into a new keystore
+    Generate {
+        /// The keystore to create
+        keystore: PathBuf,
+        /// The password to encrypt the keystore with
+        #[command(flatten)]
+        password: Password,
+    },
+    /// Replace some of the keys of a keystore, keeping the old keystore as a backup
+    Rotate {
+        /// The keystore to rotate the keys of
+        keystore: PathBuf,
+        /// Rotate the staking key
+        #[arg(long)]
+        staking: bool,
+        /// Rotate the state key
+        #[arg(long)]
+        state: bool,
+        /// Rotate the libp2p key
+        #[arg(long)]
+        libp2p: bool,
+        /// The password of the keystore
+        #[command(flatten)]
+        password: Password,
+    },
+    /// Encrypt a keystore with another password, keeping the old keystore as a backup
+    ChangePassword {
+        /// The keystore to encrypt again
+        keystore: PathBuf,
+        /// The current password of the keystore
+        #[command(flatten)]
+        password: Password,
+        /// A file containing the new password. If missing, the new password is prompted for.
+        #[arg(long, env = "HOTSHOT_KEYSTORE_NEW_PASSWORD_FILE")]
+        new_password_file: Option<PathBuf>,
+    },
+    /// Print the public keys of a keystore
+    Show {
+        /// The keystore to print the public keys of
+        keystore: PathBuf,
+    },
+    /// Export the public keys of keystores as a public keys file for the orchestrator
+    ExportPublicKeys {
+        /// The keystores to export
+        keystores: Vec<PathBuf>,
+        /// Keystores to export as DA nodes
+        #[arg(long)]
+        da: Vec<PathBuf>,
+        /// The stake of every node
+        #[arg(long, default_value_t = 1)]
+        stake: u64,
+        /// The file to write, stdout if missing
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(Args, Debug)]
+/// Where to read the password of a keystore from
+struct Password {
+    /// A file containing the password. If missing, the password is prompted for.
+    #[arg(long, env = "HOTSHOT_KEYSTORE_PASSWORD_FILE")]
+    password_file: Option<PathBuf>,
+}
+
+impl Password {
+    /// Read the password of an existing keystore
+    fn read(&self) -> Result<Zeroizing<String>> {
+        match &self.password_file {
+            Some(path) => read_password(path),
+            None => prompt("Keystore password: "),
+        }
+    }
+
+    /// Read the password of a new keystore, prompting twice to catch typos
+    fn read_new(path: Option<&Path>) -> Result<Zeroizing<String>> {
+        match path {
+            Some(path) => read_password(path),
+            None => {
+                let password = prompt("New keystore password: ")?;
+                ensure!(
+                    *password == *prompt("Repeat the password: ")?,
+                    "The passwords don't match"
+                );
+                Ok(password)
+            }
+        }
+    }
+}
+
+/// Prompt for a password on the terminal without echoing it
+fn prompt(message: &str) -> Result<Zeroizing<String>> {
+    rpassword::prompt_password(message)
+        .map(Zeroizing::new)
+        .context("Failed to read the password")
+}
+
+/// Read a password from the first line of the file at `path`
+fn read_password(path: &Path) -> Result<Zeroizing<String>> {
+    read_password_file(path)
+        .with_context(|| format!("Failed to read the password file {}", path.display()))
+}
+
+/// Read and decrypt the keystore at `path`
+fn open(path: &Path, password: &str) -> Result<ValidatorKeys<Key>> {
+    let keystore = KeystoreFile::<Key>::from_file(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    keystore
+        .decrypt(password)
+        .with_context(|| format!("Failed to decrypt {}", path.display()))
+}
+
+/// Replace the keystore at `path` with `keys` encrypted with `password`, moving the old keystore
+/// to a backup file
+fn replace(path: &Path, keys: &ValidatorKeys<Key>, password: &str) -> Result<PathBuf> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let backup = path.with_extension(format!("{timestamp}.bak"));
+    let new = path.with_extension("new");
+
+    KeystoreFile::encrypt(keys, password)
+        .to_file(&new)
+        .with_context(|| format!("Failed to write {}", new.display()))?;
+    fs::rename(path, &backup).with_context(|| format!("Failed to back up {}", path.display()))?;
+    fs::rename(&new, path).with_context(|| format!("Failed to replace {}", path.display()))?;
+
+    Ok(backup)
+}
+
+/// Print the public keys of `keystore`
+fn show(keystore: &KeystoreFile<Key>) {
+    println!("Staking key: {}", keystore.stake_table_key);
+    println!("State key: {}", keystore.state_ver_key);
+    println!("Libp2p peer ID: {}", keystore.libp2p_peer_id);
+}
+
+fn main() -> Result<()> {
+    match Cli::parse().command {
+        Command::Generate { keystore, password } => {
+            let password = Password::read_new(password.password_file.as_deref())?;
+            let keys = ValidatorKeys::<Key>::generate();
+            let file = KeystoreFile::encrypt(&keys, &password);
+            file.to_file(&keystore)
+                .with_context(|| format!("Failed to write {}", keystore.display()))?;
+            show(&file);
+        }
+        Command::Rotate {
+            keystore,
+            mut staking,
+            mut state,
+            mut libp2p,
+            password,
+        } => {
+            // Rotate every key unless told otherwise
+            if !(staking || state || libp2p) {
+                (staking, state, libp2p) = (true, true, true);
+            }
+
+            let password = password.read()?;
+            let mut keys = open(&keystore, &password)?;
+            if staking {
+                keys.rotate_staking_key();
+            }
+            if state {
+                keys.rotate_state_key();
+            }
+            if libp2p {
+                keys.rotate_libp2p_key();
+            }
+
+            let backup = replace(&keystore, &keys, &password)?;
+            println!("Old keys backed up to {}", backup.display());
+            show(&KeystoreFile::from_file(&keystore)?);
+        }
+        Command::ChangePassword {
+            keystore,
+            password,
+            new_password_file,
+        } => {
+            let keys = open(&keystore, &password.read()?)?;
+            let new_password = Password::read_new(new_password_file.as_deref())?;
+            let backup = replace(&keystore, &keys, &new_password)?;
+            println!("Old keystore backed up to {}", backup.display());
+        }
+        Command::Show { keystore } => {
+            show(
+                &KeystoreFile::<Key>::from_file(&keystore)
+                    .with_context(|| format!("Failed to read {}", keystore.display()))?,
+            );
+        }
+        Command::ExportPublicKeys {
+            keystores,
+            da,
+            stake,
+            out,
+        } => {
+            let mut public_keys = Vec::new();
+            for (path, is_da) in keystores
+                .iter()
+                .map(|path| (path, false))
+                .chain(da.iter().map(|path| (path, true)))
+            {
+                let keystore = KeystoreFile::<Key>::from_file(path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                if public_keys.iter().any(|keys: &PeerConfigKeys<Key>| {
+                    keys.stake_table_key == keystore.stake_table_key
+                }) {
+                    bail!("{} is listed twice", path.display());
+                }
+                public_keys.push(PeerConfigKeys {
+                    stake_table_key: keystore.stake_table_key,
+                    state_ver_key: keystore.state_ver_key,
+                    stake,
+                    da: is_da,
+                });
+            }
+
+            let contents = toml::to_string_pretty(&PublicKeysFile { public_keys })?;
+            match out {
+                Some(out) => fs::write(&out, contents)
+                    .with_context(|| format!("Failed to write {}", out.display()))?,
+                None => print!("{contents}"),
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -54,6 +54,9 @@ async fn main() {
                     advertise_address: Some(advertise_address.to_string()),
                     builder_address: Some(builder_address),
                     network_config_file: None,
+                    keystore: None,
+                    keystore_password_file: None,
+                    signing_guard: None,
                 },
             )
             .await;
@@ -155,6 +155,9 @@ async fn main() {
                     advertise_address: None,
                     builder_address: Some(builder_address),
                     network_config_file: None,
+                    keystore: None,
+                    keystore_password_file: None,
+                    signing_guard: None,
                 },
             )
             .await;
@@ -6,5 +6,16 @@
 
 //! Configuration file for stake table
 
-/// Capacity of a stake table
-pub const STAKE_TABLE_CAPACITY: usize = 200;
+/// Default capacity of a stake table
+pub const DEFAULT_STAKE_TABLE_CAPACITY: usize = 200;
+
+/// Capacity of every stake table, before it could be chosen per stake table
+#[deprecated(
+    note = "use `DEFAULT_STAKE_TABLE_CAPACITY`, stake tables can be created with any capacity"
+)]
+pub const STAKE_TABLE_CAPACITY: usize = DEFAULT_STAKE_TABLE_CAPACITY;
+
+/// Number of entries of a stake table committed to with a single rescue hash per column.
+/// The columns of stake tables with a larger capacity are committed to by chunks of this many
+/// entries, which are then Merkleized.
+pub const COMMITMENT_CHUNK_SIZE: usize = 200;
@@ -0,0 +1,611 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Stake delegation on top of a stake table.
+//!
+//! [`DelegatedStakeTable`] keeps the voting stake of every key, its own stake plus the stake
+//! delegated to it, in the underlying stake table, so that sampling, lookups and proofs all use
+//! the voting stake. The delegator sets are kept for the same snapshots as the underlying table
+//! and committed to along with it: the commitment of a snapshot is the pair of the underlying
+//! commitment and the rescue hash of all the `(key, number of delegators, (delegator, amount)*)`
+//! tuples, in registration then delegation order.
+
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    mem,
+    rand::SeedableRng,
+    vec::Vec,
+};
+use digest::crypto_common::rand_core::CryptoRngCore;
+use hotshot_types::traits::stake_table::{
+    DelegationScheme, SnapshotVersion, StakeTableError, StakeTableScheme,
+};
+use jf_crhf::CRHF;
+use jf_rescue::{crhf::VariableLengthRescueCRHF, RescueParameter};
+use primitive_types::U256;
+
+use crate::{
+    persistence::PersistentStakeTable,
+    utils::{u256_to_field, ToFields},
+};
+
+/// The delegators of every key with their amounts, in delegation order
+type Delegations<K, D> = HashMap<K, Vec<(D, U256)>>;
+
+/// Undelegated stake waiting for the end of its unbonding period
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Unbonding<D> {
+    /// The delegator the stake goes back to
+    delegator: D,
+    /// The undelegated amount
+    amount: U256,
+    /// The first epoch at which the stake can be withdrawn
+    release_epoch: u64,
+}
+
+/// A stake table with delegation, generic over the underlying stake table `T` holding the
+/// voting stakes, the delegator type `D` and the field `F` the delegator sets are committed over.
+#[derive(Debug, Clone)]
+pub struct DelegatedStakeTable<T, D, F>
+where
+    T: StakeTableScheme,
+{
+    /// The underlying stake table, holding the voting stake of every key
+    inner: T,
+    /// Number of epochs undelegated stake stays locked for
+    unbonding_period: u64,
+    /// Number of epochs the stake table advanced since its creation
+    epoch: u64,
+
+    /// The most up-to-date delegations, where the incoming transactions shall be performed on.
+    head: Delegations<T::Key, D>,
+    /// The delegations at the beginning of the current epoch
+    epoch_start: Delegations<T::Key, D>,
+    /// The delegations at the beginning of the last epoch
+    last_epoch_start: Delegations<T::Key, D>,
+
+    /// Commitment to the delegations at the beginning of the current epoch
+    epoch_start_comm: F,
+    /// Commitment to the delegations at the beginning of the last epoch
+    last_epoch_start_comm: F,
+
+    /// Undelegated stakes, by increasing release epoch
+    unbonding: VecDeque<Unbonding<D>>,
+}
+
+impl<T, D, F> DelegatedStakeTable<T, D, F>
+where
+    T: PersistentStakeTable,
+    T::Key: Eq + Hash + ToFields<F> + CanonicalSerialize + CanonicalDeserialize,
+    T::Aux: CanonicalSerialize + CanonicalDeserialize,
+    T::Commitment: CanonicalSerialize,
+    D: Clone + Eq + ToFields<F>,
+    F: RescueParameter,
+{
+    /// Add delegation to `inner`, with undelegated stakes locked for `unbonding_period` epochs.
+    /// # Panics
+    /// If unable to evaluate a preimage
+    #[must_use]
+    pub fn new(inner: T, unbonding_period: u64) -> Self {
+        let default_comm = VariableLengthRescueCRHF::<F, 1>::evaluate(Vec::<F>::new()).unwrap()[0];
+        Self {
+            inner,
+            unbonding_period,
+            epoch: 0,
+            head: HashMap::new(),
+            epoch_start: HashMap::new(),
+            last_epoch_start: HashMap::new(),
+            epoch_start_comm: default_comm,
+            last_epoch_start_comm: default_comm,
+            unbonding: VecDeque::new(),
+        }
+    }
+
+    /// The underlying stake table
+    #[must_use]
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Number of epochs undelegated stake stays locked for
+    #[must_use]
+    pub fn unbonding_period(&self) -> u64 {
+        self.unbonding_period
+    }
+
+    /// Number of epochs the stake table advanced since its creation
+    #[must_use]
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Update the stake table when the epoch number advances, should be manually called.
+    /// # Errors
+    /// If the delegations can't be committed to, in which case nothing changes
+    pub fn advance(&mut self) -> Result<(), StakeTableError> {
+        let head_comm = self.compute_comm(SnapshotVersion::Head, &self.head)?;
+        self.inner.advance_epoch();
+        self.last_epoch_start = mem::replace(&mut self.epoch_start, self.head.clone());
+        self.last_epoch_start_comm = mem::replace(&mut self.epoch_start_comm, head_comm);
+        self.epoch += 1;
+        Ok(())
+    }
+
+    /// Start the unbonding period of `amount` for `delegator`.
+    /// Return the epoch from which it can be withdrawn.
+    fn unbond(&mut self, delegator: D, amount: U256) -> u64 {
+        let release_epoch = self.epoch + self.unbonding_period;
+        self.unbonding.push_back(Unbonding {
+            delegator,
+            amount,
+            release_epoch,
+        });
+        release_epoch
+    }
+
+    /// Commit to `delegations`, ordering keys as the `version` of the underlying stake table
+    fn compute_comm(
+        &self,
+        version: SnapshotVersion,
+        delegations: &Delegations<T::Key, D>,
+    ) -> Result<F, StakeTableError> {
+        let mut preimage = vec![];
+        for (key, _, _) in self.inner.try_iter(version)? {
+            if let Some(delegators) = delegations.get(&key) {
+                preimage.extend(key.to_fields());
+                preimage.push(F::from(delegators.len() as u64));
+                for (delegator, amount) in delegators {
+                    preimage.extend(delegator.to_fields());
+                    preimage.push(u256_to_field(amount));
+                }
+            }
+        }
+        VariableLengthRescueCRHF::<F, 1>::evaluate(preimage)
+            .map(|comm| comm[0])
+            .map_err(|_| StakeTableError::RescueError)
+    }
+
+    /// returns the delegations of the `version`
+    fn version(
+        &self,
+        version: &SnapshotVersion,
+    ) -> Result<&Delegations<T::Key, D>, StakeTableError> {
+        match version {
+            SnapshotVersion::Head => Ok(&self.head),
+            SnapshotVersion::EpochStart => Ok(&self.epoch_start),
+            SnapshotVersion::LastEpochStart => Ok(&self.last_epoch_start),
+            SnapshotVersion::BlockNum(_) => Err(StakeTableError::SnapshotUnsupported),
+        }
+    }
+}
+
+impl<T, D, F> StakeTableScheme for DelegatedStakeTable<T, D, F>
+where
+    T: PersistentStakeTable,
+    T::Key: Eq + Hash + ToFields<F> + CanonicalSerialize + CanonicalDeserialize,
+    T::Aux: CanonicalSerialize + CanonicalDeserialize,
+    T::Commitment: CanonicalSerialize,
+    D: Clone + Eq + ToFields<F>,
+    F: RescueParameter,
+{
+    type Key = T::Key;
+    type Amount = U256;
+    /// The commitment to the underlying stake table and the one to the delegations
+    type Commitment = (T::Commitment, F);
+    type LookupProof = T::LookupProof;
+    type IntoIter = T::IntoIter;
+    type Aux = T::Aux;
+
+    fn register(
+        &mut self,
+        new_key: Self::Key,
+        amount: Self::Amount,
+        aux: Self::Aux,
+    ) -> Result<(), StakeTableError> {
+        self.inner.register(new_key, amount, aux)
+    }
+
+    /// Deregister the key, all its delegations start unbonding
+    fn deregister(&mut self, existing_key: &Self::Key) -> Result<(), StakeTableError> {
+        self.inner.deregister(existing_key)?;
+        for (delegator, amount) in self.head.remove(existing_key).unwrap_or_default() {
+            self.unbond(delegator, amount);
+        }
+        Ok(())
+    }
+
+    fn commitment(&self, version: SnapshotVersion) -> Result<Self::Commitment, StakeTableError> {
+        let delegations_comm = match version {
+            SnapshotVersion::Head => self.compute_comm(SnapshotVersion::Head, &self.head)?,
+            SnapshotVersion::EpochStart => self.epoch_start_comm,
+            SnapshotVersion::LastEpochStart => self.last_epoch_start_comm,
+            SnapshotVersion::BlockNum(_) => return Err(StakeTableError::SnapshotUnsupported),
+        };
+        Ok((self.inner.commitment(version)?, delegations_comm))
+    }
+
+    fn total_stake(&self, version: SnapshotVersion) -> Result<Self::Amount, StakeTableError> {
+        self.inner.total_stake(version)
+    }
+
+    fn len(&self, version: SnapshotVersion) -> Result<usize, StakeTableError> {
+        self.inner.len(version)
+    }
+
+    fn contains_key(&self, key: &Self::Key) -> bool {
+        self.inner.contains_key(key)
+    }
+
+    fn lookup(
+        &self,
+        version: SnapshotVersion,
+        key: &Self::Key,
+    ) -> Result<Self::Amount, StakeTableError> {
+        self.inner.lookup(version, key)
+    }
+
+    fn lookup_with_proof(
+        &self,
+        version: SnapshotVersion,
+        key: &Self::Key,
+    ) -> Result<(Self::Amount, Self::LookupProof), StakeTableError> {
+        self.inner.lookup_with_proof(version, key)
+    }
+
+    fn lookup_with_aux_and_proof(
+        &self,
+        version: SnapshotVersion,
+        key: &Self::Key,
+    ) -> Result<(Self::Amount, Self::Aux, Self::LookupProof), StakeTableError> {
+        self.inner.lookup_with_aux_and_proof(version, key)
+    }
+
+    /// Update the stake of the key itself, the delegated stake can't be removed this way
+    fn update(
+        &mut self,
+        key: &Self::Key,
+        delta: Self::Amount,
+        negative: bool,
+    ) -> Result<Self::Amount, StakeTableError> {
+        if negative {
+            let own_stake = self.inner.lookup(SnapshotVersion::Head, key)?
+                - self.delegated_stake(SnapshotVersion::Head, key)?;
+            if delta > own_stake {
+                return Err(StakeTableError::InsufficientFund);
+            }
+        }
+        self.inner.update(key, delta, negative)
+    }
+
+    fn sample(
+        &self,
+        rng: &mut (impl SeedableRng + CryptoRngCore),
+    ) -> Option<(&Self::Key, &Self::Amount)> {
+        self.inner.sample(rng)
+    }
+
+    fn try_iter(&self, version: SnapshotVersion) -> Result<Self::IntoIter, StakeTableError> {
+        self.inner.try_iter(version)
+    }
+}
+
+impl<T, D, F> DelegationScheme for DelegatedStakeTable<T, D, F>
+where
+    T: PersistentStakeTable,
+    T::Key: Eq + Hash + ToFields<F> + CanonicalSerialize + CanonicalDeserialize,
+    T::Aux: CanonicalSerialize + CanonicalDeserialize,
+    T::Commitment: CanonicalSerialize,
+    D: Clone + Eq + ToFields<F>,
+    F: RescueParameter,
+{
+    type Delegator = D;
+
+    fn delegate(
+        &mut self,
+        delegator: Self::Delegator,
+        key: &Self::Key,
+        amount: Self::Amount,
+    ) -> Result<(), StakeTableError> {
+        // A deregistered key may stay in the underlying table with no stake, so only keys with a
+        // stake of their own are open to delegation
+        let own_stake = self.inner.lookup(SnapshotVersion::Head, key)?
+            - self.delegated_stake(SnapshotVersion::Head, key)?;
+        if own_stake.is_zero() {
+            return Err(StakeTableError::KeyNotFound);
+        }
+        let delegators = self.head.get(key).map_or(&[][..], Vec::as_slice);
+        let pos = delegators.iter().position(|(d, _)| *d == delegator);
+        let delegated = match pos {
+            Some(pos) => delegators[pos]
+                .1
+                .checked_add(amount)
+                .ok_or(StakeTableError::StakeOverflow)?,
+            None => amount,
+        };
+        self.inner.update(key, amount, false)?;
+
+        let delegators = self.head.entry(key.clone()).or_default();
+        match pos {
+            Some(pos) => delegators[pos].1 = delegated,
+            None => delegators.push((delegator, delegated)),
+        }
+        Ok(())
+    }
+
+    fn undelegate(
+        &mut self,
+        delegator: &Self::Delegator,
+        key: &Self::Key,
+        amount: Self::Amount,
+    ) -> Result<u64, StakeTableError> {
+        let delegators = self
+            .head
+            .get(key)
+            .ok_or(StakeTableError::DelegationNotFound)?;
+        let pos = delegators
+            .iter()
+            .position(|(d, _)| d == delegator)
+            .ok_or(StakeTableError::DelegationNotFound)?;
+        if amount > delegators[pos].1 {
+            return Err(StakeTableError::InsufficientFund);
+        }
+        self.inner.update(key, amount, true)?;
+
+        if let Some(delegators) = self.head.get_mut(key) {
+            delegators[pos].1 -= amount;
+            if delegators[pos].1.is_zero() {
+                delegators.remove(pos);
+            }
+            if delegators.is_empty() {
+                self.head.remove(key);
+            }
+        }
+        Ok(self.unbond(delegator.clone(), amount))
+    }
+
+    fn withdraw_unbonded(&mut self) -> Vec<(Self::Delegator, Self::Amount)> {
+        let released = self
+            .unbonding
+            .iter()
+            .take_while(|unbonding| unbonding.release_epoch <= self.epoch)
+            .count();
+        self.unbonding
+            .drain(..released)
+            .map(|unbonding| (unbonding.delegator, unbonding.amount))
+            .collect()
+    }
+
+    fn delegated_stake(
+        &self,
+        version: SnapshotVersion,
+        key: &Self::Key,
+    ) -> Result<Self::Amount, StakeTableError> {
+        Ok(self
+            .delegators(version, key)?
+            .into_iter()
+            .fold(U256::zero(), |total, (_, amount)| total + amount))
+    }
+
+    fn delegators(
+        &self,
+        version: SnapshotVersion,
+        key: &Self::Key,
+    ) -> Result<Vec<(Self::Delegator, Self::Amount)>, StakeTableError> {
+        let delegations = self.version(&version)?;
+        // Errors if the key isn't in this version
+        self.inner.lookup(version, key)?;
+        Ok(delegations.get(key).cloned().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_std::vec::Vec;
+    use hotshot_types::traits::stake_table::{
+        DelegationScheme, SnapshotVersion, StakeTableError, StakeTableScheme,
+    };
+    use jf_signature::{
+        bls_over_bn254::BLSOverBN254CurveSignatureScheme, schnorr::SchnorrSignatureScheme,
+        SignatureScheme,
+    };
+    use primitive_types::{H160, U256};
+
+    use super::DelegatedStakeTable;
+    use crate::{
+        mt_based,
+        vec_based::{
+            self,
+            config::{FieldType as F, QCVerKey, StateVerKey},
+        },
+    };
+
+    type StakeTable = DelegatedStakeTable<vec_based::StakeTable<QCVerKey, StateVerKey, F>, H160, F>;
+
+    /// A stake table with an unbonding period of 2 epochs, where the first `registered` of
+    /// `keys` are registered with a stake of 100
+    fn stake_table(keys: &[(QCVerKey, StateVerKey)], registered: usize) -> StakeTable {
+        let mut st = StakeTable::new(vec_based::StakeTable::new(10), 2);
+        for key in keys.iter().take(registered) {
+            st.register(key.0, U256::from(100), key.1.clone()).unwrap();
+        }
+        st
+    }
+
+    fn keys(n: usize) -> Vec<(QCVerKey, StateVerKey)> {
+        let mut pseudo_rng = jf_utils::test_rng();
+        (0..n)
+            .map(|_| {
+                (
+                    BLSOverBN254CurveSignatureScheme::key_gen(&(), &mut pseudo_rng)
+                        .unwrap()
+                        .1,
+                    SchnorrSignatureScheme::key_gen(&(), &mut pseudo_rng)
+                        .unwrap()
+                        .1,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn crypto_test_delegation() -> Result<(), StakeTableError> {
+        let keys = keys(3);
+        let (alice, bob) = (H160::repeat_byte(1), H160::repeat_byte(2));
+        let mut st = stake_table(&keys, 2);
+
+        // Delegations add to the voting stake
+        st.delegate(alice, &keys[0].0, U256::from(30))?;
+        st.delegate(bob, &keys[0].0, U256::from(20))?;
+        st.delegate(alice, &keys[0].0, U256::from(10))?;
+        assert_eq!(
+            st.lookup(SnapshotVersion::Head, &keys[0].0)?,
+            U256::from(160)
+        );
+        assert_eq!(st.total_stake(SnapshotVersion::Head)?, U256::from(260));
+        assert_eq!(
+            st.delegators(SnapshotVersion::Head, &keys[0].0)?,
+            [(alice, U256::from(40)), (bob, U256::from(20))]
+        );
+        // Only registered keys can be delegated to
+        assert!(st.delegate(alice, &keys[2].0, U256::from(1)).is_err());
+
+        // Delegated stake can't be removed by updating the key's own stake
+        assert!(st.update(&keys[0].0, U256::from(101), true).is_err());
+        assert_eq!(
+            st.update(&keys[0].0, U256::from(100), true)?,
+            U256::from(60)
+        );
+        st.update(&keys[0].0, U256::from(100), false)?;
+
+        st.advance()?;
+        assert_eq!(
+            st.delegated_stake(SnapshotVersion::EpochStart, &keys[0].0)?,
+            U256::from(60)
+        );
+        assert!(st
+            .delegated_stake(SnapshotVersion::LastEpochStart, &keys[0].0)
+            .is_err());
+
+        // The commitment covers the delegators, not only the voting stakes
+        let mut undelegated = StakeTable::new(vec_based::StakeTable::new(10), 2);
+        undelegated.register(keys[0].0, U256::from(160), keys[0].1.clone())?;
+        undelegated.register(keys[1].0, U256::from(100), keys[1].1.clone())?;
+        undelegated.advance()?;
+        let (comm, delegations_comm) = st.commitment(SnapshotVersion::EpochStart)?;
+        let (undelegated_comm, undelegated_delegations_comm) =
+            undelegated.commitment(SnapshotVersion::EpochStart)?;
+        assert_eq!(comm, undelegated_comm);
+        assert_ne!(delegations_comm, undelegated_delegations_comm);
+        assert_eq!(
+            undelegated.commitment(SnapshotVersion::LastEpochStart)?.1,
+            st.commitment(SnapshotVersion::LastEpochStart)?.1
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn crypto_test_unbonding() -> Result<(), StakeTableError> {
+        let keys = keys(2);
+        let (alice, bob) = (H160::repeat_byte(1), H160::repeat_byte(2));
+        let mut st = stake_table(&keys, 2);
+        st.delegate(alice, &keys[0].0, U256::from(50))?;
+        st.delegate(bob, &keys[1].0, U256::from(30))?;
+
+        // Undelegated stake stops counting right away
+        assert_eq!(st.undelegate(&alice, &keys[0].0, U256::from(20))?, 2);
+        assert_eq!(
+            st.lookup(SnapshotVersion::Head, &keys[0].0)?,
+            U256::from(130)
+        );
+        assert!(st.undelegate(&alice, &keys[0].0, U256::from(31)).is_err());
+        assert!(st.undelegate(&alice, &keys[1].0, U256::from(1)).is_err());
+
+        // But is only withdrawn after the unbonding period
+        assert!(st.withdraw_unbonded().is_empty());
+        st.advance()?;
+        assert!(st.withdraw_unbonded().is_empty());
+
+        // Deregistering a key undelegates all its stake
+        st.deregister(&keys[1].0)?;
+        assert_eq!(st.lookup(SnapshotVersion::Head, &keys[1].0)?, U256::zero());
+        assert!(st.delegators(SnapshotVersion::Head, &keys[1].0)?.is_empty());
+        assert_eq!(
+            st.delegators(SnapshotVersion::EpochStart, &keys[1].0)?,
+            [(bob, U256::from(30))]
+        );
+
+        st.advance()?;
+        assert_eq!(st.withdraw_unbonded(), [(alice, U256::from(20))]);
+        st.advance()?;
+        assert_eq!(st.withdraw_unbonded(), [(bob, U256::from(30))]);
+
+        // Undelegating everything removes the delegation
+        st.undelegate(&alice, &keys[0].0, U256::from(30))?;
+        assert!(st.delegators(SnapshotVersion::Head, &keys[0].0)?.is_empty());
+        assert_eq!(
+            st.lookup(SnapshotVersion::Head, &keys[0].0)?,
+            U256::from(100)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn crypto_test_delegation_to_inactive_keys() -> Result<(), StakeTableError> {
+        let keys = keys(2);
+        let alice = H160::repeat_byte(1);
+        let mut st = stake_table(&keys, 2);
+        st.delegate(alice, &keys[0].0, U256::from(50))?;
+
+        // A deregistered key stays in the underlying table, but can't be delegated to
+        st.deregister(&keys[0].0)?;
+        assert!(st.contains_key(&keys[0].0));
+        assert!(matches!(
+            st.delegate(alice, &keys[0].0, U256::from(10)),
+            Err(StakeTableError::KeyNotFound)
+        ));
+        assert_eq!(st.lookup(SnapshotVersion::Head, &keys[0].0)?, U256::zero());
+        assert!(st.delegators(SnapshotVersion::Head, &keys[0].0)?.is_empty());
+
+        // Neither can a key which withdrew all of its own stake
+        st.delegate(alice, &keys[1].0, U256::from(50))?;
+        st.update(&keys[1].0, U256::from(100), true)?;
+        assert!(matches!(
+            st.delegate(alice, &keys[1].0, U256::from(10)),
+            Err(StakeTableError::KeyNotFound)
+        ));
+        assert_eq!(
+            st.delegators(SnapshotVersion::Head, &keys[1].0)?,
+            [(alice, U256::from(50))]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn crypto_test_delegation_proofs() -> Result<(), StakeTableError> {
+        let keys = (0..3).map(ark_bn254::Fq::from).collect::<Vec<_>>();
+        let mut st =
+            DelegatedStakeTable::<_, H160, ark_bn254::Fq>::new(mt_based::StakeTable::new(3), 1);
+        for key in &keys {
+            st.register(*key, U256::from(100), ())?;
+        }
+        st.delegate(H160::repeat_byte(1), &keys[1], U256::from(50))?;
+        st.advance()?;
+
+        // Proofs are against the voting stake
+        let (amount, proof) = st.lookup_with_proof(SnapshotVersion::EpochStart, &keys[1])?;
+        assert_eq!(amount, U256::from(150));
+        assert!(proof
+            .verify(&st.commitment(SnapshotVersion::EpochStart)?.0)
+            .is_ok());
+
+        Ok(())
+    }
+}
@@ -6,6 +6,8 @@
 
 //! This crate contains some stake table implementations for `HotShot` system.
 pub mod config;
+pub mod delegation;
 pub mod mt_based;
+pub mod persistence;
 pub mod utils;
 pub mod vec_based;
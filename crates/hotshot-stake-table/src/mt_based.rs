@@ -9,13 +9,18 @@
 mod config;
 mod internal;
 
-use ark_std::{collections::HashMap, rand::SeedableRng, sync::Arc};
+use ark_std::{
+    collections::{BTreeMap, HashMap},
+    rand::SeedableRng,
+    sync::Arc,
+};
 use digest::crypto_common::rand_core::CryptoRngCore;
 use hotshot_types::traits::stake_table::{SnapshotVersion, StakeTableError, StakeTableScheme};
 use primitive_types::{U256, U512};
 use serde::{Deserialize, Serialize};
 
 use self::internal::{to_merkle_path, Key, MerkleCommitment, MerkleProof, PersistentMerkleNode};
+use crate::persistence::PersistentStakeTable;
 
 /// Locally maintained stake table, generic over public key type `K`.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -27,6 +32,10 @@ pub struct StakeTable<K: Key> {
     epoch_start: Arc<PersistentMerkleNode<K>>,
     /// The stake table used for leader election.
     last_epoch_start: Arc<PersistentMerkleNode<K>>,
+    /// Roots of the head stake table recorded at past block heights.
+    /// Nodes are shared with later versions, so each record only costs the
+    /// nodes that changed since the previous one.
+    history: BTreeMap<u64, Arc<PersistentMerkleNode<K>>>,
 
     /// Height of the underlying merkle tree, determines the capacity.
     /// The capacity is `TREE_BRANCH.pow(height)`.
@@ -182,6 +191,7 @@ impl<K: Key> StakeTable<K> {
             head: Arc::new(PersistentMerkleNode::Empty),
             epoch_start: Arc::new(PersistentMerkleNode::Empty),
             last_epoch_start: Arc::new(PersistentMerkleNode::Empty),
+            history: BTreeMap::new(),
             height,
             mapping: HashMap::new(),
         }
@@ -196,7 +206,41 @@ impl<K: Key> StakeTable<K> {
             SnapshotVersion::Head => Ok(Arc::clone(&self.head)),
             SnapshotVersion::EpochStart => Ok(Arc::clone(&self.epoch_start)),
             SnapshotVersion::LastEpochStart => Ok(Arc::clone(&self.last_epoch_start)),
-            SnapshotVersion::BlockNum(_) => Err(StakeTableError::SnapshotUnsupported),
+            SnapshotVersion::BlockNum(block_num) => self
+                .history
+                .range(..=block_num)
+                .next_back()
+                .map(|(_, root)| Arc::clone(root))
+                .ok_or(StakeTableError::SnapshotUnsupported),
+        }
+    }
+
+    /// Record the head stake table as the snapshot at height `block_num`, it's then
+    /// queryable with `SnapshotVersion::BlockNum` for every height until the next record.
+    /// Recording the same height again overwrites the previous record.
+    /// # Errors
+    /// Errors if a later block height has already been recorded
+    pub fn record_block(&mut self, block_num: u64) -> Result<(), StakeTableError> {
+        if self
+            .history
+            .last_key_value()
+            .is_some_and(|(last, _)| *last > block_num)
+        {
+            return Err(StakeTableError::OutdatedBlockNum);
+        }
+        self.history.insert(block_num, Arc::clone(&self.head));
+        Ok(())
+    }
+
+    /// Forget the snapshots that are not needed to answer queries at `block_num` or later.
+    pub fn prune_history(&mut self, block_num: u64) {
+        if let Some(first_kept) = self
+            .history
+            .range(..=block_num)
+            .next_back()
+            .map(|(height, _)| *height)
+        {
+            self.history = self.history.split_off(&first_kept);
         }
     }
 
@@ -227,6 +271,33 @@ impl<K: Key> StakeTable<K> {
     }
 }
 
+impl<K: Key> PersistentStakeTable for StakeTable<K> {
+    /// The height of the Merkle tree
+    fn size_param(&self) -> usize {
+        self.height
+    }
+
+    fn with_size_param(size_param: usize) -> Self {
+        Self::new(size_param)
+    }
+
+    fn set_stake(&mut self, key: &K, amount: U256) -> Result<(), StakeTableError> {
+        self.set_value(key, amount).map(|_| ())
+    }
+
+    fn advance_epoch(&mut self) {
+        self.advance();
+    }
+
+    fn recorded_blocks(&self) -> Vec<u64> {
+        self.history.keys().copied().collect()
+    }
+
+    fn record_block_snapshot(&mut self, block_num: u64) -> Result<(), StakeTableError> {
+        self.record_block(block_num)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use ark_std::{rand::SeedableRng, vec::Vec};
@@ -346,4 +417,78 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn crypto_test_historical_snapshots() -> Result<(), StakeTableError> {
+        let mut st = StakeTable::<Key>::new(3);
+        let keys = (0..4).map(Key::from).collect::<Vec<_>>();
+
+        // Nothing is recorded yet
+        assert!(st.len(SnapshotVersion::BlockNum(0)).is_err());
+
+        st.register(keys[0], U256::from(100), ())?;
+        st.register(keys[1], U256::from(100), ())?;
+        st.record_block(10)?;
+        let comm_10 = st.commitment(SnapshotVersion::Head)?;
+
+        st.set_value(&keys[0], U256::from(50))?;
+        st.register(keys[2], U256::from(100), ())?;
+        st.record_block(20)?;
+        st.update(&keys[1], U256::from(1), false)?;
+
+        // Queries are answered with the latest record at or before the block height
+        assert!(st.total_stake(SnapshotVersion::BlockNum(9)).is_err());
+        assert_eq!(
+            st.total_stake(SnapshotVersion::BlockNum(15))?,
+            U256::from(200)
+        );
+        assert_eq!(st.len(SnapshotVersion::BlockNum(15))?, 2);
+        assert_eq!(
+            st.total_stake(SnapshotVersion::BlockNum(25))?,
+            U256::from(250)
+        );
+        assert_eq!(
+            st.lookup(SnapshotVersion::BlockNum(10), &keys[0])?,
+            U256::from(100)
+        );
+        assert_eq!(
+            st.lookup(SnapshotVersion::BlockNum(20), &keys[1])?,
+            U256::from(100)
+        );
+        assert!(st.lookup(SnapshotVersion::BlockNum(19), &keys[2]).is_err());
+        assert_eq!(st.try_iter(SnapshotVersion::BlockNum(20))?.count(), 3);
+
+        // Proofs verify against the historical commitment only
+        let comm = st.commitment(SnapshotVersion::BlockNum(12))?;
+        assert_eq!(comm, comm_10);
+        let (amount, proof) = st.lookup_with_proof(SnapshotVersion::BlockNum(12), &keys[0])?;
+        assert_eq!(amount, U256::from(100));
+        assert!(proof.verify(&comm).is_ok());
+        assert!(proof
+            .verify(&st.commitment(SnapshotVersion::BlockNum(20))?)
+            .is_err());
+
+        // Heights are recorded in order
+        assert!(st.record_block(15).is_err());
+        st.record_block(20)?;
+        assert_eq!(
+            st.total_stake(SnapshotVersion::BlockNum(20))?,
+            U256::from(251)
+        );
+
+        // Pruning keeps the snapshots needed from the given height on
+        st.prune_history(15);
+        assert_eq!(
+            st.total_stake(SnapshotVersion::BlockNum(15))?,
+            U256::from(200)
+        );
+        st.prune_history(20);
+        assert!(st.total_stake(SnapshotVersion::BlockNum(15)).is_err());
+        assert_eq!(
+            st.total_stake(SnapshotVersion::BlockNum(30))?,
+            U256::from(251)
+        );
+
+        Ok(())
+    }
 }
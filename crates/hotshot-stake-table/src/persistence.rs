@@ -0,0 +1,764 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Persistence of stake tables.
+//!
+//! A stake table is saved with the entries of its `Head`, `EpochStart` and `LastEpochStart`
+//! snapshots and of the snapshots recorded at past block heights, in registration order, and the
+//! commitments of the finalized snapshots. Saving a delta only records the entries registered and
+//! the stakes changed since a previous save, and the block height snapshots recorded or pruned
+//! since.
+//!
+//! Every saved blob is laid out as
+//! `magic (4 bytes) | format version (u16 LE) | kind (u8) | payload | sha256 checksum (32 bytes)`,
+//! where the checksum covers everything before it. On restore, the stake table is rebuilt from
+//! the entries and its commitments are checked against the saved ones.
+
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{
+    fmt::{self, Display, Formatter},
+    vec::Vec,
+};
+use hotshot_types::traits::stake_table::{SnapshotVersion, StakeTableError, StakeTableScheme};
+use jf_utils::canonical;
+use primitive_types::U256;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Magic bytes at the start of every saved stake table
+const MAGIC: [u8; 4] = *b"HSST";
+
+/// Version of the format written by this implementation
+pub const FORMAT_VERSION: u16 = 1;
+
+/// Length of the header: magic bytes, format version and kind
+const HEADER_LEN: usize = 7;
+
+/// Length of the checksum at the end of a saved stake table
+const CHECKSUM_LEN: usize = 32;
+
+/// Kind byte of a full snapshot
+const KIND_FULL: u8 = 0;
+
+/// Kind byte of a delta
+const KIND_DELTA: u8 = 1;
+
+/// Error type for stake table persistence
+#[derive(Debug)]
+pub enum PersistenceError {
+    /// The bytes aren't a saved stake table
+    Malformed,
+    /// The stake table was saved with an unsupported format version
+    UnsupportedVersion(u16),
+    /// The checksum doesn't match the saved bytes
+    ChecksumMismatch,
+    /// A full snapshot was expected and got a delta, or the other way around
+    UnexpectedKind,
+    /// The delta was computed against a different state than the one it's applied to
+    BaseMismatch,
+    /// The commitment of the named snapshot doesn't match the saved one
+    CommitmentMismatch(&'static str),
+    /// The payload couldn't be (de)serialized
+    Serialization(String),
+    /// The stake table couldn't be rebuilt
+    StakeTable(StakeTableError),
+}
+
+impl Display for PersistenceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed => write!(f, "not a saved stake table"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported stake table format version {version}")
+            }
+            Self::ChecksumMismatch => write!(f, "stake table checksum mismatch"),
+            Self::UnexpectedKind => write!(f, "unexpected kind of saved stake table"),
+            Self::BaseMismatch => write!(f, "stake table delta doesn't apply to this state"),
+            Self::CommitmentMismatch(version) => {
+                write!(f, "commitment mismatch for the {version} snapshot")
+            }
+            Self::Serialization(e) => write!(f, "stake table serialization failed: {e}"),
+            Self::StakeTable(e) => write!(f, "failed to rebuild the stake table: {e}"),
+        }
+    }
+}
+
+impl ark_std::error::Error for PersistenceError {}
+
+impl From<StakeTableError> for PersistenceError {
+    fn from(e: StakeTableError) -> Self {
+        Self::StakeTable(e)
+    }
+}
+
+impl From<bincode::Error> for PersistenceError {
+    fn from(e: bincode::Error) -> Self {
+        Self::Serialization(e.to_string())
+    }
+}
+
+/// An entry of a stake table snapshot
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "K: CanonicalSerialize + CanonicalDeserialize, \
+                 A: CanonicalSerialize + CanonicalDeserialize")]
+struct Entry<K, A> {
+    /// The key
+    #[serde(with = "canonical")]
+    key: K,
+    /// The stake of the key
+    amount: U256,
+    /// The auxiliary information of the key
+    #[serde(with = "canonical")]
+    aux: A,
+}
+
+/// A snapshot recorded at a block height
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "K: CanonicalSerialize + CanonicalDeserialize, \
+                 A: CanonicalSerialize + CanonicalDeserialize")]
+struct BlockSnapshot<K, A> {
+    /// The block height the snapshot was recorded at
+    height: u64,
+    /// Entries of the snapshot
+    entries: Vec<Entry<K, A>>,
+    /// Serialized commitment of the snapshot
+    comm: Vec<u8>,
+}
+
+/// Everything saved about a stake table
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "K: CanonicalSerialize + CanonicalDeserialize, \
+                 A: CanonicalSerialize + CanonicalDeserialize")]
+struct StakeTableState<K, A> {
+    /// The parameter the stake table was created with
+    size_param: u64,
+    /// Entries of the `Head` snapshot
+    head: Vec<Entry<K, A>>,
+    /// Entries of the `EpochStart` snapshot
+    epoch_start: Vec<Entry<K, A>>,
+    /// Entries of the `LastEpochStart` snapshot
+    last_epoch_start: Vec<Entry<K, A>>,
+    /// Serialized commitment of the `EpochStart` snapshot
+    epoch_start_comm: Vec<u8>,
+    /// Serialized commitment of the `LastEpochStart` snapshot
+    last_epoch_start_comm: Vec<u8>,
+    /// Snapshots recorded at block heights, by increasing height
+    blocks: Vec<BlockSnapshot<K, A>>,
+}
+
+impl<K, A> StakeTableState<K, A>
+where
+    K: CanonicalSerialize + CanonicalDeserialize,
+    A: CanonicalSerialize + CanonicalDeserialize,
+{
+    /// Digest identifying the state, which deltas are computed against
+    fn digest(&self) -> Result<[u8; 32], PersistenceError> {
+        Ok(Sha256::digest(bincode::serialize(self)?).into())
+    }
+}
+
+/// Changes of a snapshot between two saves. Keys are only ever appended to a stake table, so a
+/// snapshot extends the one of the same version at any earlier save.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "K: CanonicalSerialize + CanonicalDeserialize, \
+                 A: CanonicalSerialize + CanonicalDeserialize")]
+struct SnapshotDelta<K, A> {
+    /// Entries registered since the base
+    appended: Vec<Entry<K, A>>,
+    /// Position and new stake of the entries whose stake changed since the base
+    updated: Vec<(u64, U256)>,
+}
+
+impl<K: Clone + PartialEq, A: Clone> SnapshotDelta<K, A> {
+    /// The changes from `base` to `new`
+    fn between(base: &[Entry<K, A>], new: &[Entry<K, A>]) -> Result<Self, PersistenceError> {
+        if new.len() < base.len() {
+            return Err(PersistenceError::BaseMismatch);
+        }
+
+        let mut updated = Vec::new();
+        for (position, (old, new)) in base.iter().zip(new).enumerate() {
+            if old.key != new.key {
+                return Err(PersistenceError::BaseMismatch);
+            }
+            if old.amount != new.amount {
+                updated.push((position as u64, new.amount));
+            }
+        }
+
+        Ok(Self {
+            appended: new[base.len()..].to_vec(),
+            updated,
+        })
+    }
+
+    /// Apply the changes to `entries`
+    fn apply(self, entries: &mut Vec<Entry<K, A>>) -> Result<(), PersistenceError> {
+        for (position, amount) in self.updated {
+            usize::try_from(position)
+                .ok()
+                .and_then(|position| entries.get_mut(position))
+                .ok_or(PersistenceError::BaseMismatch)?
+                .amount = amount;
+        }
+        entries.extend(self.appended);
+
+        Ok(())
+    }
+}
+
+/// Changes of a stake table between two saves
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "K: CanonicalSerialize + CanonicalDeserialize, \
+                 A: CanonicalSerialize + CanonicalDeserialize")]
+struct StakeTableDelta<K, A> {
+    /// Digest of the state the delta applies to
+    base_digest: [u8; 32],
+    /// Changes of the `Head` snapshot
+    head: SnapshotDelta<K, A>,
+    /// Changes of the `EpochStart` snapshot
+    epoch_start: SnapshotDelta<K, A>,
+    /// Changes of the `LastEpochStart` snapshot
+    last_epoch_start: SnapshotDelta<K, A>,
+    /// Serialized commitment of the new `EpochStart` snapshot
+    epoch_start_comm: Vec<u8>,
+    /// Serialized commitment of the new `LastEpochStart` snapshot
+    last_epoch_start_comm: Vec<u8>,
+    /// Height of the first block height snapshot kept, the earlier ones having been pruned
+    first_block: Option<u64>,
+    /// Block height snapshots recorded since the base, by increasing height
+    new_blocks: Vec<BlockSnapshot<K, A>>,
+}
+
+/// Apply the changes of the block height snapshots of a delta to `blocks`
+fn apply_blocks<K, A>(
+    first_block: Option<u64>,
+    new_blocks: Vec<BlockSnapshot<K, A>>,
+    blocks: &mut Vec<BlockSnapshot<K, A>>,
+) {
+    blocks.retain(|block| first_block.is_some_and(|first| block.height >= first));
+    for block in new_blocks {
+        // Recording the same height again overwrites the previous record
+        if blocks
+            .last()
+            .is_some_and(|last| last.height == block.height)
+        {
+            blocks.pop();
+        }
+        blocks.push(block);
+    }
+}
+
+/// Wrap a payload of `kind` with the header and checksum
+fn encode<T: Serialize>(kind: u8, payload: &T) -> Result<Vec<u8>, PersistenceError> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN);
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes.push(kind);
+    bincode::serialize_into(&mut bytes, payload)?;
+    let checksum = Sha256::digest(&bytes);
+    bytes.extend_from_slice(&checksum);
+
+    Ok(bytes)
+}
+
+/// Check the header and checksum of `bytes` and decode its payload of `kind`
+fn decode<T: DeserializeOwned>(kind: u8, bytes: &[u8]) -> Result<T, PersistenceError> {
+    if bytes.len() < HEADER_LEN + CHECKSUM_LEN || bytes[..4] != MAGIC {
+        return Err(PersistenceError::Malformed);
+    }
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    if version != FORMAT_VERSION {
+        return Err(PersistenceError::UnsupportedVersion(version));
+    }
+    let (content, checksum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+    if Sha256::digest(content).as_slice() != checksum {
+        return Err(PersistenceError::ChecksumMismatch);
+    }
+    if content[6] != kind {
+        return Err(PersistenceError::UnexpectedKind);
+    }
+
+    Ok(bincode::deserialize(&content[HEADER_LEN..])?)
+}
+
+/// Serialize a commitment for comparison
+fn commitment_bytes<C: CanonicalSerialize>(commitment: &C) -> Result<Vec<u8>, PersistenceError> {
+    let mut bytes = Vec::new();
+    commitment
+        .serialize_compressed(&mut bytes)
+        .map_err(|e| PersistenceError::Serialization(e.to_string()))?;
+
+    Ok(bytes)
+}
+
+/// A stake table which can be saved, in full or as a delta, and restored
+pub trait PersistentStakeTable: StakeTableScheme<Amount = U256> + Sized
+where
+    Self::Key: CanonicalSerialize + CanonicalDeserialize + PartialEq,
+    Self::Aux: CanonicalSerialize + CanonicalDeserialize,
+    Self::Commitment: CanonicalSerialize,
+{
+    /// The parameter the stake table was created with, from which it can be recreated empty
+    fn size_param(&self) -> usize;
+
+    /// Create an empty stake table with `size_param`
+    fn with_size_param(size_param: usize) -> Self;
+
+    /// Set the stake of a registered `key` in the head
+    ///
+    /// # Errors
+    /// Errors if the key isn't registered
+    fn set_stake(&mut self, key: &Self::Key, amount: U256) -> Result<(), StakeTableError>;
+
+    /// Snapshot the head as the start of a new epoch
+    fn advance_epoch(&mut self);
+
+    /// The block heights the head was recorded at, by increasing height
+    fn recorded_blocks(&self) -> Vec<u64>;
+
+    /// Record the head as the snapshot at `block_num`
+    ///
+    /// # Errors
+    /// Errors if a later block height has already been recorded
+    fn record_block_snapshot(&mut self, block_num: u64) -> Result<(), StakeTableError>;
+
+    /// Save the stake table with all its snapshots.
+    ///
+    /// # Errors
+    /// If the stake table can't be serialized
+    fn save(&self) -> Result<Vec<u8>, PersistenceError> {
+        encode(KIND_FULL, &state(self)?)
+    }
+
+    /// Save the changes since the stake table was `base`, to be restored on top of a save of
+    /// `base`.
+    ///
+    /// # Errors
+    /// If the stake table isn't a later version of `base`, or can't be serialized
+    fn save_delta(&self, base: &Self) -> Result<Vec<u8>, PersistenceError> {
+        let (base, new) = (state(base)?, state(self)?);
+        let delta = StakeTableDelta {
+            base_digest: base.digest()?,
+            head: SnapshotDelta::between(&base.head, &new.head)?,
+            epoch_start: SnapshotDelta::between(&base.epoch_start, &new.epoch_start)?,
+            last_epoch_start: SnapshotDelta::between(
+                &base.last_epoch_start,
+                &new.last_epoch_start,
+            )?,
+            epoch_start_comm: new.epoch_start_comm,
+            last_epoch_start_comm: new.last_epoch_start_comm,
+            first_block: new.blocks.first().map(|block| block.height),
+            new_blocks: new
+                .blocks
+                .into_iter()
+                .filter(|block| {
+                    !base
+                        .blocks
+                        .iter()
+                        .any(|old| old.height == block.height && old.comm == block.comm)
+                })
+                .collect(),
+        };
+
+        encode(KIND_DELTA, &delta)
+    }
+
+    /// Restore a stake table from a full save followed by the deltas saved since, in order.
+    ///
+    /// # Errors
+    /// If any of the saves is corrupted, has an unsupported version, or the deltas don't chain up
+    /// from the full save, or if the commitments of the rebuilt stake table don't match.
+    fn restore(full: &[u8], deltas: &[Vec<u8>]) -> Result<Self, PersistenceError> {
+        let mut state: StakeTableState<Self::Key, Self::Aux> = decode(KIND_FULL, full)?;
+        for delta in deltas {
+            let delta: StakeTableDelta<Self::Key, Self::Aux> = decode(KIND_DELTA, delta)?;
+            if delta.base_digest != state.digest()? {
+                return Err(PersistenceError::BaseMismatch);
+            }
+            delta.head.apply(&mut state.head)?;
+            delta.epoch_start.apply(&mut state.epoch_start)?;
+            delta.last_epoch_start.apply(&mut state.last_epoch_start)?;
+            state.epoch_start_comm = delta.epoch_start_comm;
+            state.last_epoch_start_comm = delta.last_epoch_start_comm;
+            apply_blocks(delta.first_block, delta.new_blocks, &mut state.blocks);
+        }
+
+        let size_param =
+            usize::try_from(state.size_param).map_err(|_| PersistenceError::Malformed)?;
+        let mut table = Self::with_size_param(size_param);
+        let block_comms: Vec<_> = state
+            .blocks
+            .iter()
+            .map(|block| (block.height, block.comm.clone()))
+            .collect();
+        // Replaying the snapshots from the oldest, advancing or recording in between, leaves each
+        // of them at its version. Keys are only ever appended, so a snapshot with fewer keys is
+        // older, and snapshots with as many keys can be replayed in any order.
+        let mut blocks = state.blocks.into_iter().peekable();
+        for (entries, advance) in [
+            (state.last_epoch_start, true),
+            (state.epoch_start, true),
+            (state.head, false),
+        ] {
+            while let Some(block) = blocks.next_if(|block| block.entries.len() <= entries.len()) {
+                replay(&mut table, block.entries)?;
+                table.record_block_snapshot(block.height)?;
+            }
+            replay(&mut table, entries)?;
+            if advance {
+                table.advance_epoch();
+            }
+        }
+        if blocks.next().is_some() {
+            // A snapshot can't have more keys than the head
+            return Err(PersistenceError::Malformed);
+        }
+
+        if commitment_bytes(&table.commitment(SnapshotVersion::EpochStart)?)?
+            != state.epoch_start_comm
+        {
+            return Err(PersistenceError::CommitmentMismatch("EpochStart"));
+        }
+        if commitment_bytes(&table.commitment(SnapshotVersion::LastEpochStart)?)?
+            != state.last_epoch_start_comm
+        {
+            return Err(PersistenceError::CommitmentMismatch("LastEpochStart"));
+        }
+        for (height, comm) in block_comms {
+            if commitment_bytes(&table.commitment(SnapshotVersion::BlockNum(height))?)? != comm {
+                return Err(PersistenceError::CommitmentMismatch("BlockNum"));
+            }
+        }
+
+        Ok(table)
+    }
+}
+
+/// The saved state of `table`
+fn state<T>(table: &T) -> Result<StakeTableState<T::Key, T::Aux>, PersistenceError>
+where
+    T: PersistentStakeTable,
+    T::Key: CanonicalSerialize + CanonicalDeserialize + PartialEq,
+    T::Aux: CanonicalSerialize + CanonicalDeserialize,
+    T::Commitment: CanonicalSerialize,
+{
+    let entries = |version| -> Result<Vec<Entry<T::Key, T::Aux>>, PersistenceError> {
+        Ok(table
+            .try_iter(version)?
+            .map(|(key, amount, aux)| Entry { key, amount, aux })
+            .collect())
+    };
+
+    let blocks = table
+        .recorded_blocks()
+        .into_iter()
+        .map(|height| {
+            let version = SnapshotVersion::BlockNum(height);
+            Ok(BlockSnapshot {
+                height,
+                entries: entries(version)?,
+                comm: commitment_bytes(&table.commitment(version)?)?,
+            })
+        })
+        .collect::<Result<_, PersistenceError>>()?;
+
+    Ok(StakeTableState {
+        size_param: table.size_param() as u64,
+        head: entries(SnapshotVersion::Head)?,
+        epoch_start: entries(SnapshotVersion::EpochStart)?,
+        last_epoch_start: entries(SnapshotVersion::LastEpochStart)?,
+        epoch_start_comm: commitment_bytes(&table.commitment(SnapshotVersion::EpochStart)?)?,
+        last_epoch_start_comm: commitment_bytes(
+            &table.commitment(SnapshotVersion::LastEpochStart)?,
+        )?,
+        blocks,
+    })
+}
+
+/// Bring the head of `table` to `entries`, which extend it
+fn replay<T>(table: &mut T, entries: Vec<Entry<T::Key, T::Aux>>) -> Result<(), PersistenceError>
+where
+    T: PersistentStakeTable,
+    T::Key: CanonicalSerialize + CanonicalDeserialize + PartialEq,
+    T::Aux: CanonicalSerialize + CanonicalDeserialize,
+    T::Commitment: CanonicalSerialize,
+{
+    let registered = table.len(SnapshotVersion::Head)?;
+    for (position, entry) in entries.into_iter().enumerate() {
+        if position < registered {
+            table.set_stake(&entry.key, entry.amount)?;
+        } else {
+            table.register(entry.key, entry.amount, entry.aux)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use ark_std::vec::Vec;
+    use hotshot_types::traits::stake_table::{SnapshotVersion, StakeTableScheme};
+    use jf_signature::{
+        bls_over_bn254::BLSOverBN254CurveSignatureScheme, schnorr::SchnorrSignatureScheme,
+        SignatureScheme,
+    };
+    use primitive_types::U256;
+
+    use super::{PersistenceError, PersistentStakeTable, FORMAT_VERSION};
+    use crate::{
+        mt_based,
+        vec_based::{
+            config::{FieldType as F, QCVerKey, StateVerKey},
+            StakeTable,
+        },
+    };
+
+    type VecStakeTable = StakeTable<QCVerKey, StateVerKey, F>;
+
+    /// Random consensus and state keys
+    fn keys(num_keys: usize) -> Vec<(QCVerKey, StateVerKey)> {
+        let mut rng = jf_utils::test_rng();
+        (0..num_keys)
+            .map(|_| {
+                (
+                    BLSOverBN254CurveSignatureScheme::key_gen(&(), &mut rng)
+                        .unwrap()
+                        .1,
+                    SchnorrSignatureScheme::key_gen(&(), &mut rng).unwrap().1,
+                )
+            })
+            .collect()
+    }
+
+    /// The keys and stakes of the `version` snapshot of `table`
+    fn entries<T: StakeTableScheme>(
+        table: &T,
+        version: SnapshotVersion,
+    ) -> Vec<(T::Key, T::Amount)> {
+        table
+            .try_iter(version)
+            .unwrap()
+            .map(|(key, amount, _)| (key, amount))
+            .collect()
+    }
+
+    /// Check that all the snapshots of `a` and `b` are the same
+    fn assert_same_snapshots<T>(a: &T, b: &T)
+    where
+        T: StakeTableScheme,
+        T::Key: PartialEq + ark_std::fmt::Debug,
+        T::Amount: PartialEq + ark_std::fmt::Debug,
+        T::Commitment: PartialEq + ark_std::fmt::Debug,
+    {
+        assert_eq!(
+            entries(a, SnapshotVersion::Head),
+            entries(b, SnapshotVersion::Head)
+        );
+        assert_eq!(
+            entries(a, SnapshotVersion::EpochStart),
+            entries(b, SnapshotVersion::EpochStart)
+        );
+        assert_eq!(
+            entries(a, SnapshotVersion::LastEpochStart),
+            entries(b, SnapshotVersion::LastEpochStart)
+        );
+        assert_eq!(
+            a.commitment(SnapshotVersion::EpochStart).unwrap(),
+            b.commitment(SnapshotVersion::EpochStart).unwrap()
+        );
+        assert_eq!(
+            a.commitment(SnapshotVersion::LastEpochStart).unwrap(),
+            b.commitment(SnapshotVersion::LastEpochStart).unwrap()
+        );
+    }
+
+    #[test]
+    fn full_save_round_trip() {
+        let keys = keys(6);
+        let mut st = VecStakeTable::new(10);
+        for (key, state_key) in &keys[..3] {
+            st.register(*key, U256::from(100), state_key.clone())
+                .unwrap();
+        }
+        st.advance();
+        st.set_value(&keys[0].0, U256::from(5)).unwrap();
+        for (key, state_key) in &keys[3..5] {
+            st.register(*key, U256::from(200), state_key.clone())
+                .unwrap();
+        }
+        st.advance();
+        st.deregister(&keys[1].0).unwrap();
+        st.register(keys[5].0, U256::from(1), keys[5].1.clone())
+            .unwrap();
+
+        let restored = VecStakeTable::restore(&st.save().unwrap(), &[]).unwrap();
+        assert_same_snapshots(&st, &restored);
+        // The restored stake table keeps working, it knows about the registered keys.
+        assert!(restored.contains_key(&keys[4].0));
+
+        let mut mt = mt_based::StakeTable::<ark_bn254::Fq>::new(3);
+        for key in 0..5u64 {
+            mt.register(ark_bn254::Fq::from(key), U256::from(key + 1), ())
+                .unwrap();
+        }
+        mt.advance();
+        mt.set_value(&ark_bn254::Fq::from(2u64), U256::from(50))
+            .unwrap();
+        let restored =
+            mt_based::StakeTable::<ark_bn254::Fq>::restore(&mt.save().unwrap(), &[]).unwrap();
+        assert_same_snapshots(&mt, &restored);
+    }
+
+    /// Check that the snapshots of `a` and `b` at each of `heights` are the same
+    fn assert_same_block_snapshots<T>(a: &T, b: &T, heights: &[u64])
+    where
+        T: PersistentStakeTable,
+        T::Key: CanonicalSerialize + CanonicalDeserialize + PartialEq + ark_std::fmt::Debug,
+        T::Aux: CanonicalSerialize + CanonicalDeserialize,
+        T::Commitment: CanonicalSerialize + PartialEq + ark_std::fmt::Debug,
+    {
+        assert_eq!(a.recorded_blocks(), b.recorded_blocks());
+        for height in heights {
+            let version = SnapshotVersion::BlockNum(*height);
+            assert_eq!(entries(a, version), entries(b, version));
+            assert_eq!(
+                a.commitment(version).unwrap(),
+                b.commitment(version).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn block_snapshots_round_trip() {
+        let keys = keys(5);
+        let mut st = VecStakeTable::new(10);
+        st.register(keys[0].0, U256::from(10), keys[0].1.clone())
+            .unwrap();
+        st.record_block(10).unwrap();
+        // The epoch snapshots are taken between block snapshots with more keys.
+        st.advance();
+        st.register(keys[1].0, U256::from(20), keys[1].1.clone())
+            .unwrap();
+        st.record_block(20).unwrap();
+        st.advance();
+        st.set_value(&keys[0].0, U256::from(15)).unwrap();
+        st.register(keys[2].0, U256::from(30), keys[2].1.clone())
+            .unwrap();
+        st.record_block(30).unwrap();
+        st.set_value(&keys[1].0, U256::from(25)).unwrap();
+        let heights = [10, 15, 20, 25, 30, 35];
+
+        let full = st.save().unwrap();
+        let restored = VecStakeTable::restore(&full, &[]).unwrap();
+        assert_same_snapshots(&st, &restored);
+        assert_same_block_snapshots(&st, &restored, &heights);
+
+        // Deltas record the snapshots recorded, overwritten and pruned since their base.
+        let mut base = st.clone();
+        st.record_block(40).unwrap();
+        st.register(keys[3].0, U256::from(40), keys[3].1.clone())
+            .unwrap();
+        st.record_block(40).unwrap();
+        let mut deltas = vec![st.save_delta(&base).unwrap()];
+        base = st.clone();
+        st.prune_history(25);
+        st.register(keys[4].0, U256::from(50), keys[4].1.clone())
+            .unwrap();
+        st.record_block(50).unwrap();
+        deltas.push(st.save_delta(&base).unwrap());
+
+        let restored = VecStakeTable::restore(&full, &deltas).unwrap();
+        assert_same_snapshots(&st, &restored);
+        assert_eq!(restored.recorded_blocks(), vec![20, 30, 40, 50]);
+        assert_same_block_snapshots(&st, &restored, &[20, 25, 30, 40, 45, 50]);
+        assert!(restored.total_stake(SnapshotVersion::BlockNum(15)).is_err());
+
+        let mut mt = mt_based::StakeTable::<ark_bn254::Fq>::new(3);
+        mt.register(ark_bn254::Fq::from(1u64), U256::from(1), ())
+            .unwrap();
+        mt.record_block(10).unwrap();
+        mt.advance();
+        mt.register(ark_bn254::Fq::from(2u64), U256::from(2), ())
+            .unwrap();
+        mt.record_block(20).unwrap();
+        mt.set_value(&ark_bn254::Fq::from(1u64), U256::from(3))
+            .unwrap();
+        let restored =
+            mt_based::StakeTable::<ark_bn254::Fq>::restore(&mt.save().unwrap(), &[]).unwrap();
+        assert_same_snapshots(&mt, &restored);
+        assert_same_block_snapshots(&mt, &restored, &[10, 20, 30]);
+    }
+
+    #[test]
+    fn deltas_restore_later_states() {
+        let keys = keys(5);
+        let mut st = VecStakeTable::new(10);
+        for (key, state_key) in &keys[..2] {
+            st.register(*key, U256::from(10), state_key.clone())
+                .unwrap();
+        }
+        st.advance();
+        let full = st.save().unwrap();
+
+        let mut deltas = Vec::new();
+        let mut base = st.clone();
+        for (key, state_key) in &keys[2..] {
+            st.register(*key, U256::from(20), state_key.clone())
+                .unwrap();
+            st.set_value(&keys[0].0, U256::from(1)).unwrap();
+            st.advance();
+            deltas.push(st.save_delta(&base).unwrap());
+            base = st.clone();
+        }
+
+        assert_same_snapshots(&st, &VecStakeTable::restore(&full, &deltas).unwrap());
+
+        // Deltas must be applied in order, on top of the state they were computed against.
+        assert!(matches!(
+            VecStakeTable::restore(&full, &deltas[1..]),
+            Err(PersistenceError::BaseMismatch)
+        ));
+        assert!(matches!(
+            VecStakeTable::restore(&deltas[0], &[]),
+            Err(PersistenceError::UnexpectedKind)
+        ));
+    }
+
+    #[test]
+    fn corrupted_saves_are_rejected() {
+        let keys = keys(2);
+        let mut st = VecStakeTable::new(10);
+        for (key, state_key) in &keys {
+            st.register(*key, U256::from(10), state_key.clone())
+                .unwrap();
+        }
+        st.advance();
+        let saved = st.save().unwrap();
+
+        let mut corrupted = saved.clone();
+        let middle = corrupted.len() / 2;
+        corrupted[middle] ^= 1;
+        assert!(matches!(
+            VecStakeTable::restore(&corrupted, &[]),
+            Err(PersistenceError::ChecksumMismatch)
+        ));
+
+        let mut future = saved.clone();
+        future[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            VecStakeTable::restore(&future, &[]),
+            Err(PersistenceError::UnsupportedVersion(_))
+        ));
+
+        assert!(matches!(
+            VecStakeTable::restore(&saved[..8], &[]),
+            Err(PersistenceError::Malformed)
+        ));
+    }
+}
@@ -7,7 +7,7 @@
 //! Utilities to help building a stake table.
 
 use ark_ff::{Field, PrimeField};
-use primitive_types::U256;
+use primitive_types::{H160, U256};
 
 /// A trait that converts into a field element.
 pub trait ToFields<F: Field> {
@@ -18,6 +18,15 @@ pub trait ToFields<F: Field> {
     fn to_fields(&self) -> Vec<F>;
 }
 
+/// Delegators are identified by their Ethereum address
+impl<F: PrimeField> ToFields<F> for H160 {
+    const SIZE: usize = 1;
+
+    fn to_fields(&self) -> Vec<F> {
+        vec![F::from_be_bytes_mod_order(self.as_bytes())]
+    }
+}
+
 /// convert a U256 to a field element.
 pub(crate) fn u256_to_field<F: PrimeField>(v: &U256) -> F {
     let mut bytes = vec![0u8; 32];
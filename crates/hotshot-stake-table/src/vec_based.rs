@@ -6,7 +6,13 @@
 
 //! A vector based stake table implementation. The commitment is the rescue hash of the list of (key, amount) pairs;
 
-use ark_std::{collections::HashMap, hash::Hash, rand::SeedableRng};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    hash::Hash,
+    rand::SeedableRng,
+};
 use digest::crypto_common::rand_core::CryptoRngCore;
 use hotshot_types::traits::stake_table::{SnapshotVersion, StakeTableError, StakeTableScheme};
 use jf_crhf::CRHF;
@@ -15,7 +21,8 @@ use primitive_types::{U256, U512};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    config::STAKE_TABLE_CAPACITY,
+    config::{COMMITMENT_CHUNK_SIZE, DEFAULT_STAKE_TABLE_CAPACITY},
+    persistence::PersistentStakeTable,
     utils::{u256_to_field, ToFields},
 };
 
@@ -42,6 +49,25 @@ impl<K1, K2> Default for StakeTableSnapshot<K1, K2> {
     }
 }
 
+/// The head stake table recorded at some block height
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct BlockRecord {
+    /// number of registered keys
+    num_keys: usize,
+    /// total stakes
+    total_stake: U256,
+}
+
+/// Commitments of the finalized snapshots before and after a change of capacity, for their
+/// consumers (e.g. the light client contract) to migrate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitmentMigration<C> {
+    /// Commitment of the `EpochStart` snapshot, before and after
+    pub epoch_start: (C, C),
+    /// Commitment of the `LastEpochStart` snapshot, before and after
+    pub last_epoch_start: (C, C),
+}
+
 /// Locally maintained stake table, generic over public key type `K`.
 /// Whose commitment is a rescue hash of all key-value pairs over field `F`.
 /// NOTE: the commitment is only available for the finalized versions, and is
@@ -80,6 +106,15 @@ where
     /// Commitment of the stake table snapshot version `LastEpochStart`
     last_epoch_start_comm: (F, F, F),
 
+    /// Change log of the head stake table at the recorded block heights.
+    /// Keys are never removed from the table, so a past snapshot is the prefix of
+    /// `num_keys` keys along with their amounts at that height.
+    block_history: BTreeMap<u64, BlockRecord>,
+    /// For each position, the `(block_num, amount)` recorded whenever its amount changed
+    amount_history: Vec<Vec<(u64, U256)>>,
+    /// Positions whose amount changed since the last recorded block height
+    dirty: BTreeSet<usize>,
+
     /// The mapping from public keys to their location in the Merkle tree.
     #[serde(skip)]
     bls_mapping: HashMap<K1, usize>,
@@ -109,6 +144,8 @@ where
     ) -> Result<(), StakeTableError> {
         if self.bls_mapping.contains_key(&new_key) {
             Err(StakeTableError::ExistingKey)
+        } else if self.head.bls_keys.len() >= self.capacity {
+            Err(StakeTableError::CapacityExceeded)
         } else {
             let pos = self.bls_mapping.len();
             self.head.bls_keys.push(new_key.clone());
@@ -116,6 +153,7 @@ where
             self.head.stake_amount.push(amount);
             self.head_total_stake += amount;
             self.bls_mapping.insert(new_key, pos);
+            self.dirty.insert(pos);
             Ok(())
         }
     }
@@ -125,6 +163,7 @@ where
             Some(pos) => {
                 self.head_total_stake -= self.head.stake_amount[*pos];
                 self.head.stake_amount[*pos] = U256::zero();
+                self.dirty.insert(*pos);
                 Ok(())
             }
             None => Err(StakeTableError::KeyNotFound),
@@ -136,7 +175,8 @@ where
             // IMPORTANT: we don't support committing the head version b/c it's not finalized.
             SnapshotVersion::EpochStart => Ok(self.epoch_start_comm),
             SnapshotVersion::LastEpochStart => Ok(self.last_epoch_start_comm),
-            _ => Err(StakeTableError::SnapshotUnsupported),
+            SnapshotVersion::BlockNum(_) => Ok(self.compute_comm(&self.version(&version)?)),
+            SnapshotVersion::Head => Err(StakeTableError::SnapshotUnsupported),
         }
     }
 
@@ -145,12 +185,19 @@ where
             SnapshotVersion::Head => Ok(self.head_total_stake),
             SnapshotVersion::EpochStart => Ok(self.epoch_start_total_stake),
             SnapshotVersion::LastEpochStart => Ok(self.last_epoch_start_total_stake),
-            SnapshotVersion::BlockNum(_) => Err(StakeTableError::SnapshotUnsupported),
+            SnapshotVersion::BlockNum(block_num) => self
+                .block_record(block_num)
+                .map(|(_, record)| record.total_stake),
         }
     }
 
     fn len(&self, version: SnapshotVersion) -> Result<usize, StakeTableError> {
-        Ok(self.version(&version)?.bls_keys.len())
+        match version {
+            SnapshotVersion::BlockNum(block_num) => self
+                .block_record(block_num)
+                .map(|(_, record)| record.num_keys),
+            _ => Ok(self.version(&version)?.bls_keys.len()),
+        }
     }
 
     fn contains_key(&self, key: &Self::Key) -> bool {
@@ -211,6 +258,7 @@ where
             self.head_total_stake += delta;
             self.head.stake_amount[pos] += delta;
         }
+        self.dirty.insert(pos);
         Ok(self.head.stake_amount[pos])
     }
 
@@ -259,16 +307,19 @@ where
     /// If unable to evaluate a preimage
     #[must_use]
     pub fn new(capacity: usize) -> Self {
-        let bls_comm_preimage = vec![F::default(); capacity * <K1 as ToFields<F>>::SIZE];
-        let default_bls_comm =
-            VariableLengthRescueCRHF::<F, 1>::evaluate(&bls_comm_preimage).unwrap()[0];
-        let schnorr_comm_preimage = vec![F::default(); capacity * <K2 as ToFields<F>>::SIZE];
-        let default_schnorr_comm =
-            VariableLengthRescueCRHF::<F, 1>::evaluate(&schnorr_comm_preimage).unwrap()[0];
-        let stake_comm_preimage = vec![F::default(); capacity];
-        let default_stake_comm =
-            VariableLengthRescueCRHF::<F, 1>::evaluate(&stake_comm_preimage).unwrap()[0];
-        let default_comm = (default_bls_comm, default_schnorr_comm, default_stake_comm);
+        let default_column_comm = |size| {
+            commit_column(
+                capacity,
+                COMMITMENT_CHUNK_SIZE,
+                &[],
+                &vec![F::default(); size],
+            )
+        };
+        let default_comm = (
+            default_column_comm(<K1 as ToFields<F>>::SIZE),
+            default_column_comm(<K2 as ToFields<F>>::SIZE),
+            default_column_comm(1),
+        );
         Self {
             capacity,
             head: StakeTableSnapshot::default(),
@@ -277,12 +328,43 @@ where
             head_total_stake: U256::zero(),
             epoch_start_total_stake: U256::zero(),
             last_epoch_start_total_stake: U256::zero(),
+            block_history: BTreeMap::new(),
+            amount_history: vec![],
+            dirty: BTreeSet::new(),
             bls_mapping: HashMap::new(),
             epoch_start_comm: default_comm,
             last_epoch_start_comm: default_comm,
         }
     }
 
+    /// The maximum number of keys the stake table can hold
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change the capacity of the stake table, which changes the commitments of the finalized
+    /// snapshots. Return their commitments before and after the change.
+    /// # Errors
+    /// Errors if more than `capacity` keys are registered
+    pub fn resize(
+        &mut self,
+        capacity: usize,
+    ) -> Result<CommitmentMigration<(F, F, F)>, StakeTableError> {
+        if self.head.bls_keys.len() > capacity {
+            return Err(StakeTableError::CapacityExceeded);
+        }
+        let (old_epoch_start_comm, old_last_epoch_start_comm) =
+            (self.epoch_start_comm, self.last_epoch_start_comm);
+        self.capacity = capacity;
+        self.epoch_start_comm = self.compute_comm(&self.epoch_start);
+        self.last_epoch_start_comm = self.compute_comm(&self.last_epoch_start);
+        Ok(CommitmentMigration {
+            epoch_start: (old_epoch_start_comm, self.epoch_start_comm),
+            last_epoch_start: (old_last_epoch_start_comm, self.last_epoch_start_comm),
+        })
+    }
+
     /// Update the stake table when the epoch number advances, should be manually called.
     pub fn advance(&mut self) {
         // Could we avoid this `clone()`?
@@ -305,48 +387,131 @@ where
                 self.head.stake_amount[*pos] = value;
                 self.head_total_stake -= old_value;
                 self.head_total_stake += value;
+                self.dirty.insert(*pos);
                 Ok(old_value)
             }
             None => Err(StakeTableError::KeyNotFound),
         }
     }
 
+    /// Record the head stake table as the snapshot at height `block_num`, it's then
+    /// queryable with `SnapshotVersion::BlockNum` for every height until the next record.
+    /// Recording the same height again overwrites the previous record.
+    /// # Errors
+    /// Errors if a later block height has already been recorded
+    pub fn record_block(&mut self, block_num: u64) -> Result<(), StakeTableError> {
+        if self
+            .block_history
+            .last_key_value()
+            .is_some_and(|(last, _)| *last > block_num)
+        {
+            return Err(StakeTableError::OutdatedBlockNum);
+        }
+        self.amount_history
+            .resize_with(self.head.bls_keys.len(), Vec::new);
+        for pos in ark_std::mem::take(&mut self.dirty) {
+            let amounts = &mut self.amount_history[pos];
+            if amounts.last().is_some_and(|(last, _)| *last == block_num) {
+                amounts.pop();
+            }
+            amounts.push((block_num, self.head.stake_amount[pos]));
+        }
+        self.block_history.insert(
+            block_num,
+            BlockRecord {
+                num_keys: self.head.bls_keys.len(),
+                total_stake: self.head_total_stake,
+            },
+        );
+        Ok(())
+    }
+
+    /// Forget the snapshots that are not needed to answer queries at `block_num` or later.
+    pub fn prune_history(&mut self, block_num: u64) {
+        let Ok((first_kept, _)) = self.block_record(block_num) else {
+            return;
+        };
+        self.block_history = self.block_history.split_off(&first_kept);
+        for amounts in &mut self.amount_history {
+            // Keep the amount in effect at `first_kept`
+            let in_effect = amounts.partition_point(|(height, _)| *height <= first_kept);
+            amounts.drain(..in_effect.saturating_sub(1));
+        }
+    }
+
+    /// Returns the latest record at or before `block_num` along with its block height
+    fn block_record(&self, block_num: u64) -> Result<(u64, BlockRecord), StakeTableError> {
+        self.block_history
+            .range(..=block_num)
+            .next_back()
+            .map(|(height, record)| (*height, *record))
+            .ok_or(StakeTableError::SnapshotUnsupported)
+    }
+
+    /// Rebuild the snapshot of the stake table at `block_num` from the change log
+    fn historical_snapshot(
+        &self,
+        block_num: u64,
+    ) -> Result<StakeTableSnapshot<K1, K2>, StakeTableError> {
+        let (height, record) = self.block_record(block_num)?;
+        let stake_amount = self.amount_history[..record.num_keys]
+            .iter()
+            .map(|amounts| {
+                let in_effect = amounts.partition_point(|(h, _)| *h <= height);
+                amounts[in_effect - 1].1
+            })
+            .collect();
+        Ok(StakeTableSnapshot {
+            bls_keys: self.head.bls_keys[..record.num_keys].to_vec(),
+            schnorr_keys: self.head.schnorr_keys[..record.num_keys].to_vec(),
+            stake_amount,
+        })
+    }
+
     /// Helper function to recompute the stake table commitment for head version
+    fn compute_head_comm(&self) -> (F, F, F) {
+        self.compute_comm(&self.head)
+    }
+
+    /// Helper function to compute the commitment of a stake table snapshot
     /// Commitment of a stake table is a triple `(bls_keys_comm, schnorr_keys_comm, stake_amount_comm)`
     /// TODO(Chengyu): The BLS verification keys doesn't implement Default. Thus we directly pad with `F::default()`.
-    fn compute_head_comm(&mut self) -> (F, F, F) {
-        let padding_len = self.capacity - self.head.bls_keys.len();
-        // Compute rescue hash for bls keys
-        let mut bls_comm_preimage = self
-            .head
+    fn compute_comm(&self, snapshot: &StakeTableSnapshot<K1, K2>) -> (F, F, F) {
+        let bls_keys = snapshot
             .bls_keys
             .iter()
             .flat_map(ToFields::to_fields)
             .collect::<Vec<_>>();
-        bls_comm_preimage.resize(self.capacity * <K1 as ToFields<F>>::SIZE, F::default());
-        let bls_comm = VariableLengthRescueCRHF::<F, 1>::evaluate(bls_comm_preimage).unwrap()[0];
+        let bls_comm = commit_column(
+            self.capacity,
+            COMMITMENT_CHUNK_SIZE,
+            &bls_keys,
+            &vec![F::default(); <K1 as ToFields<F>>::SIZE],
+        );
 
-        // Compute rescue hash for Schnorr keys
-        let schnorr_comm_preimage = self
-            .head
+        let schnorr_keys = snapshot
             .schnorr_keys
             .iter()
-            .chain(ark_std::iter::repeat(&K2::default()).take(padding_len))
             .flat_map(ToFields::to_fields)
             .collect::<Vec<_>>();
-        let schnorr_comm =
-            VariableLengthRescueCRHF::<F, 1>::evaluate(schnorr_comm_preimage).unwrap()[0];
+        let schnorr_comm = commit_column(
+            self.capacity,
+            COMMITMENT_CHUNK_SIZE,
+            &schnorr_keys,
+            &K2::default().to_fields(),
+        );
 
-        // Compute rescue hash for stake amounts
-        let mut stake_comm_preimage = self
-            .head
+        let stake_amount = snapshot
             .stake_amount
             .iter()
             .map(|x| u256_to_field(x))
             .collect::<Vec<_>>();
-        stake_comm_preimage.resize(self.capacity, F::default());
-        let stake_comm =
-            VariableLengthRescueCRHF::<F, 1>::evaluate(stake_comm_preimage).unwrap()[0];
+        let stake_comm = commit_column(
+            self.capacity,
+            COMMITMENT_CHUNK_SIZE,
+            &stake_amount,
+            &[F::default()],
+        );
         (bls_comm, schnorr_comm, stake_comm)
     }
 
@@ -360,15 +525,18 @@ where
     }
 
     /// returns the snapshot version
+    /// Past block heights are rebuilt from the change log
     fn version(
         &self,
         version: &SnapshotVersion,
-    ) -> Result<&StakeTableSnapshot<K1, K2>, StakeTableError> {
+    ) -> Result<Cow<'_, StakeTableSnapshot<K1, K2>>, StakeTableError> {
         match version {
-            SnapshotVersion::Head => Ok(&self.head),
-            SnapshotVersion::EpochStart => Ok(&self.epoch_start),
-            SnapshotVersion::LastEpochStart => Ok(&self.last_epoch_start),
-            SnapshotVersion::BlockNum(_) => Err(StakeTableError::SnapshotUnsupported),
+            SnapshotVersion::Head => Ok(Cow::Borrowed(&self.head)),
+            SnapshotVersion::EpochStart => Ok(Cow::Borrowed(&self.epoch_start)),
+            SnapshotVersion::LastEpochStart => Ok(Cow::Borrowed(&self.last_epoch_start)),
+            SnapshotVersion::BlockNum(block_num) => {
+                self.historical_snapshot(*block_num).map(Cow::Owned)
+            }
         }
     }
 }
@@ -380,7 +548,83 @@ where
     F: RescueParameter,
 {
     fn default() -> Self {
-        Self::new(STAKE_TABLE_CAPACITY)
+        Self::new(DEFAULT_STAKE_TABLE_CAPACITY)
+    }
+}
+
+/// Commit to a column of a stake table with `capacity` entries, holding `entries` of
+/// `padding.len()` field elements each followed by `padding` for every empty entry.
+///
+/// Columns of at most `chunk_size` entries are committed to with a single rescue hash. Larger
+/// ones are split in chunks of `chunk_size` entries, whose rescue hashes are the leaves of a
+/// binary Merkle tree, so that committing to a large and mostly empty table stays cheap.
+/// # Panics
+/// If unable to evaluate a preimage
+fn commit_column<F: RescueParameter>(
+    capacity: usize,
+    chunk_size: usize,
+    entries: &[F],
+    padding: &[F],
+) -> F {
+    let hash = |preimage: Vec<F>| VariableLengthRescueCRHF::<F, 1>::evaluate(preimage).unwrap()[0];
+    let padded = |chunk: &[F], num_entries: usize| {
+        let padding_len = (num_entries * padding.len()).saturating_sub(chunk.len());
+        chunk
+            .iter()
+            .chain(padding.iter().cycle().take(padding_len))
+            .copied()
+            .collect::<Vec<_>>()
+    };
+
+    if capacity <= chunk_size {
+        return hash(padded(entries, capacity));
+    }
+    let mut nodes = entries
+        .chunks(chunk_size * padding.len())
+        .map(|chunk| hash(padded(chunk, chunk_size)))
+        .collect::<Vec<_>>();
+    let mut empty_node = hash(padded(&[], chunk_size));
+    let mut num_nodes = capacity.div_ceil(chunk_size);
+    while num_nodes > 1 {
+        nodes = nodes
+            .chunks(2)
+            .map(|pair| hash(vec![pair[0], pair.get(1).copied().unwrap_or(empty_node)]))
+            .collect();
+        empty_node = hash(vec![empty_node, empty_node]);
+        num_nodes = num_nodes.div_ceil(2);
+    }
+    nodes.first().copied().unwrap_or(empty_node)
+}
+
+impl<K1, K2, F> PersistentStakeTable for StakeTable<K1, K2, F>
+where
+    K1: Eq + Hash + Clone + ToFields<F> + CanonicalSerialize + CanonicalDeserialize,
+    K2: Eq + Hash + Clone + Default + ToFields<F> + CanonicalSerialize + CanonicalDeserialize,
+    F: RescueParameter,
+{
+    /// The capacity of the stake table
+    fn size_param(&self) -> usize {
+        self.capacity
+    }
+
+    fn with_size_param(size_param: usize) -> Self {
+        Self::new(size_param)
+    }
+
+    fn set_stake(&mut self, key: &K1, amount: U256) -> Result<(), StakeTableError> {
+        self.set_value(key, amount).map(|_| ())
+    }
+
+    fn advance_epoch(&mut self) {
+        self.advance();
+    }
+
+    fn recorded_blocks(&self) -> Vec<u64> {
+        self.block_history.keys().copied().collect()
+    }
+
+    fn record_block_snapshot(&mut self, block_num: u64) -> Result<(), StakeTableError> {
+        self.record_block(block_num)
     }
 }
 
@@ -388,6 +632,8 @@ where
 mod tests {
     use ark_std::{rand::SeedableRng, vec::Vec};
     use hotshot_types::traits::stake_table::{SnapshotVersion, StakeTableError, StakeTableScheme};
+    use jf_crhf::CRHF;
+    use jf_rescue::crhf::VariableLengthRescueCRHF;
     use jf_signature::{
         bls_over_bn254::BLSOverBN254CurveSignatureScheme, schnorr::SchnorrSignatureScheme,
         SignatureScheme,
@@ -395,6 +641,7 @@ mod tests {
     use primitive_types::U256;
 
     use super::{
+        commit_column,
         config::{FieldType as F, QCVerKey, StateVerKey},
         StakeTable,
     };
@@ -518,4 +765,169 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn crypto_test_historical_snapshots() -> Result<(), StakeTableError> {
+        let mut st = StakeTable::<QCVerKey, StateVerKey, F>::new(10);
+        let mut pseudo_rng = jf_utils::test_rng();
+        let keys = (0..3)
+            .map(|_| {
+                (
+                    BLSOverBN254CurveSignatureScheme::key_gen(&(), &mut pseudo_rng)
+                        .unwrap()
+                        .1,
+                    SchnorrSignatureScheme::key_gen(&(), &mut pseudo_rng)
+                        .unwrap()
+                        .1,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // Nothing is recorded yet
+        assert!(st.len(SnapshotVersion::BlockNum(0)).is_err());
+
+        for key in keys.iter().take(2) {
+            st.register(key.0, U256::from(100), key.1.clone())?;
+        }
+        st.record_block(10)?;
+        st.advance();
+        let comm_10 = st.commitment(SnapshotVersion::EpochStart)?;
+
+        st.set_value(&keys[0].0, U256::from(50))?;
+        st.register(keys[2].0, U256::from(100), keys[2].1.clone())?;
+        st.record_block(20)?;
+        st.deregister(&keys[1].0)?;
+
+        // Queries are answered with the latest record at or before the block height
+        assert!(st.total_stake(SnapshotVersion::BlockNum(9)).is_err());
+        assert_eq!(
+            st.total_stake(SnapshotVersion::BlockNum(15))?,
+            U256::from(200)
+        );
+        assert_eq!(st.len(SnapshotVersion::BlockNum(15))?, 2);
+        assert_eq!(
+            st.total_stake(SnapshotVersion::BlockNum(25))?,
+            U256::from(250)
+        );
+        assert_eq!(
+            st.lookup(SnapshotVersion::BlockNum(10), &keys[0].0)?,
+            U256::from(100)
+        );
+        assert_eq!(
+            st.lookup_with_aux_and_proof(SnapshotVersion::BlockNum(20), &keys[1].0)?
+                .0,
+            U256::from(100)
+        );
+        assert!(st
+            .lookup(SnapshotVersion::BlockNum(19), &keys[2].0)
+            .is_err());
+        let entries = st
+            .try_iter(SnapshotVersion::BlockNum(20))?
+            .map(|(_, amount, _)| amount)
+            .collect::<Vec<_>>();
+        assert_eq!(entries, [U256::from(50), U256::from(100), U256::from(100)]);
+
+        // Historical commitments match the ones of the finalized versions
+        assert_eq!(st.commitment(SnapshotVersion::BlockNum(12))?, comm_10);
+        assert_ne!(st.commitment(SnapshotVersion::BlockNum(20))?, comm_10);
+
+        // Heights are recorded in order
+        assert!(st.record_block(15).is_err());
+        st.record_block(20)?;
+        assert_eq!(
+            st.total_stake(SnapshotVersion::BlockNum(20))?,
+            U256::from(150)
+        );
+        assert_eq!(
+            st.lookup(SnapshotVersion::BlockNum(20), &keys[1].0)?,
+            U256::from(0)
+        );
+
+        // Pruning keeps the snapshots needed from the given height on
+        st.prune_history(15);
+        assert_eq!(st.commitment(SnapshotVersion::BlockNum(15))?, comm_10);
+        st.prune_history(20);
+        assert!(st.total_stake(SnapshotVersion::BlockNum(15)).is_err());
+        assert_eq!(
+            st.lookup(SnapshotVersion::BlockNum(30), &keys[0].0)?,
+            U256::from(50)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn crypto_test_chunked_commitment() {
+        let hash =
+            |preimage: Vec<F>| VariableLengthRescueCRHF::<F, 1>::evaluate(preimage).unwrap()[0];
+        let entries = [F::from(1u64), F::from(2u64), F::from(3u64)];
+        let zero = F::default();
+
+        // Up to the chunk size, the column is hashed at once
+        assert_eq!(
+            commit_column(4, 4, &entries, &[zero]),
+            hash(vec![entries[0], entries[1], entries[2], zero])
+        );
+
+        // Beyond, chunks are Merkleized, with empty chunks for the missing leaves
+        let empty_chunk = hash(vec![zero, zero]);
+        let expected = hash(vec![
+            hash(vec![
+                hash(vec![entries[0], entries[1]]),
+                hash(vec![entries[2], zero]),
+            ]),
+            hash(vec![empty_chunk, empty_chunk]),
+        ]);
+        assert_eq!(commit_column(5, 2, &entries, &[zero]), expected);
+        assert_eq!(commit_column(7, 2, &entries, &[zero]), expected);
+    }
+
+    #[test]
+    fn crypto_test_capacity() -> Result<(), StakeTableError> {
+        let mut pseudo_rng = jf_utils::test_rng();
+        let keys = (0..3)
+            .map(|_| {
+                (
+                    BLSOverBN254CurveSignatureScheme::key_gen(&(), &mut pseudo_rng)
+                        .unwrap()
+                        .1,
+                    SchnorrSignatureScheme::key_gen(&(), &mut pseudo_rng)
+                        .unwrap()
+                        .1,
+                )
+            })
+            .collect::<Vec<_>>();
+        let stake_table = |capacity| {
+            let mut st = StakeTable::<QCVerKey, StateVerKey, F>::new(capacity);
+            for key in keys.iter().take(2) {
+                st.register(key.0, U256::from(100), key.1.clone()).unwrap();
+            }
+            st.advance();
+            st.advance();
+            st
+        };
+
+        // The capacity is enforced
+        let mut st = stake_table(2);
+        assert!(st
+            .register(keys[2].0, U256::from(100), keys[2].1.clone())
+            .is_err());
+        assert!(st.resize(1).is_err());
+
+        // Large tables are committed to by chunks, and resizing migrates the commitments
+        let large = stake_table(1000);
+        let comm = st.commitment(SnapshotVersion::EpochStart)?;
+        let large_comm = large.commitment(SnapshotVersion::EpochStart)?;
+        assert_ne!(comm, large_comm);
+        let migration = st.resize(1000)?;
+        assert_eq!(migration.epoch_start, (comm, large_comm));
+        assert_eq!(
+            migration.last_epoch_start.1,
+            large.commitment(SnapshotVersion::LastEpochStart)?
+        );
+        assert_eq!(st.commitment(SnapshotVersion::EpochStart)?, large_comm);
+        st.register(keys[2].0, U256::from(100), keys[2].1.clone())?;
+
+        Ok(())
+    }
 }
@@ -51,8 +51,10 @@ use hotshot_types::{
     consensus::{Consensus, ConsensusMetricsValue, OuterConsensus, VidShares, View, ViewInner},
     constants::{EVENT_CHANNEL_SIZE, EXTERNAL_EVENT_CHANNEL_SIZE},
     data::{Leaf2, QuorumProposal, QuorumProposal2},
+    drb::{DrbCheckpoint, DrbResult, DrbSeedInput, DrbSeedProof},
     event::{EventType, LeafInfo},
     message::{convert_proposal, DataMessage, Message, MessageKind, Proposal},
+    signer::{NodeKey, Signer},
     simple_certificate::{NextEpochQuorumCertificate2, QuorumCertificate2, UpgradeCertificate},
     traits::{
         consensus_api::ConsensusApi,
@@ -98,8 +100,11 @@ pub struct SystemContext<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versi
     /// The public key of this node
     public_key: TYPES::SignatureKey,
 
-    /// The private key of this node
-    private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    /// The private key of this node, unless it signs through a signer holding the key elsewhere
+    private_key: Option<<TYPES::SignatureKey as SignatureKey>::PrivateKey>,
+
+    /// Signs votes, proposals and requests with the key of this node
+    pub signer: Arc<dyn Signer<TYPES::SignatureKey>>,
 
     /// Configuration items for this hotshot instance
     pub config: HotShotConfig<TYPES::SignatureKey>,
@@ -161,6 +166,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> Clone
         Self {
             public_key: self.public_key.clone(),
             private_key: self.private_key.clone(),
+            signer: Arc::clone(&self.signer),
             config: self.config.clone(),
             network: Arc::clone(&self.network),
             memberships: Arc::clone(&self.memberships),
@@ -190,13 +196,17 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> SystemContext<T
     ///
     /// Use this instead of `init` if you want to start the tasks manually
     ///
+    /// The node signs with `key`, either its private key or a signer holding the key elsewhere,
+    /// e.g. a [`RemoteSigner`](hotshot_types::signer::RemoteSigner) in a separate process.
+    ///
     /// # Panics
     ///
-    /// Panics if storage migration fails.
+    /// Panics if storage migration fails, or if the VID config is invalid or the signing guard of
+    /// `key` can't be read. `init` returns an error for the latter instead.
     #[allow(clippy::too_many_arguments)]
     pub async fn new(
         public_key: TYPES::SignatureKey,
-        private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+        key: NodeKey<TYPES::SignatureKey>,
         nonce: u64,
         config: HotShotConfig<TYPES::SignatureKey>,
         memberships: Arc<RwLock<TYPES::Membership>>,
@@ -225,7 +235,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> SystemContext<T
 
         Self::new_from_channels(
             public_key,
-            private_key,
+            key,
             nonce,
             config,
             memberships,
@@ -247,10 +257,15 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> SystemContext<T
     ///
     /// Use this function if you want to use some preexisting channels and to spin up the tasks
     /// and start consensus manually.  Mostly useful for tests
+    ///
+    /// # Panics
+    ///
+    /// Panics if the VID config is invalid or if the signing guard of `key` can't be read. `init`
+    /// returns an error instead.
     #[allow(clippy::too_many_arguments, clippy::type_complexity)]
     pub async fn new_from_channels(
         public_key: TYPES::SignatureKey,
-        private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+        key: NodeKey<TYPES::SignatureKey>,
         nonce: u64,
         config: HotShotConfig<TYPES::SignatureKey>,
         memberships: Arc<RwLock<TYPES::Membership>>,
@@ -267,6 +282,18 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> SystemContext<T
     ) -> Arc<Self> {
         debug!("Creating a new hotshot");
 
+        #[allow(clippy::panic)]
+        if let Err(e) = config.vid_config.validate() {
+            panic!("Invalid VID config: {e}");
+        }
+
+        let private_key = key.private_key().cloned();
+        #[allow(clippy::panic)]
+        let signer = match key.into_signer() {
+            Ok(signer) => signer,
+            Err(e) => panic!("Failed to open the signing guard: {e}"),
+        };
+
         let consensus_metrics = Arc::new(metrics);
         let anchored_leaf = initializer.anchor_leaf;
         let instance_state = initializer.instance_state;
@@ -325,7 +352,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> SystemContext<T
             saved_payloads.insert(anchored_leaf.view_number(), Arc::new(payload));
         }
 
-        let consensus = Consensus::new(
+        let mut consensus = Consensus::new(
             validated_state_map,
             Some(initializer.saved_vid_shares),
             anchored_leaf.view_number(),
@@ -341,6 +368,26 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> SystemContext<T
             Arc::clone(&consensus_metrics),
             config.epoch_height,
         );
+        consensus.drb_seeds_and_results.checkpoints = initializer.drb_checkpoints;
+        consensus
+            .drb_seeds_and_results
+            .seeds
+            .extend(initializer.drb_seeds);
+        consensus
+            .drb_seeds_and_results
+            .seed_proofs
+            .extend(initializer.drb_seed_proofs);
+        consensus
+            .drb_seeds_and_results
+            .results
+            .extend(initializer.drb_results);
+
+        // Let the membership randomize leader selection with the DRB results we already know.
+        let mut membership_writer = memberships.write().await;
+        for (epoch, drb_result) in &consensus.drb_seeds_and_results.results {
+            membership_writer.add_drb_result(*epoch, *drb_result);
+        }
+        drop(membership_writer);
 
         let consensus = Arc::new(RwLock::new(consensus));
 
@@ -352,8 +399,9 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> SystemContext<T
             id: nonce,
             consensus: OuterConsensus::new(consensus),
             instance_state: Arc::new(instance_state),
-            public_key,
             private_key,
+            signer,
+            public_key,
             config,
             start_view: initializer.start_view,
             start_epoch: initializer.start_epoch,
@@ -606,11 +654,11 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> SystemContext<T
     /// To construct a [`SystemContext`] without setting up tasks, use `fn new` instead.
     /// # Errors
     ///
-    /// Can throw an error if `Self::new` fails.
+    /// Errors if the VID config is invalid or the signing guard of `key` can't be read.
     #[allow(clippy::too_many_arguments)]
     pub async fn init(
         public_key: TYPES::SignatureKey,
-        private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+        key: NodeKey<TYPES::SignatureKey>,
         node_id: u64,
         config: HotShotConfig<TYPES::SignatureKey>,
         memberships: Arc<RwLock<TYPES::Membership>>,
@@ -627,9 +675,22 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> SystemContext<T
         ),
         HotShotError<TYPES>,
     > {
+        // Check the config and open the signing guard here rather than in `new`, so that failing
+        // to is an error
+        config
+            .vid_config
+            .validate()
+            .map_err(|e| HotShotError::InvalidConfig(format!("Invalid VID config: {e}")))?;
+        let key = match key {
+            NodeKey::Guarded { .. } => NodeKey::Signer(key.into_signer().map_err(|e| {
+                HotShotError::InvalidConfig(format!("Failed to open the signing guard: {e}"))
+            })?),
+            key => key,
+        };
+
         let hotshot = Self::new(
             public_key,
-            private_key,
+            key,
             node_id,
             config,
             memberships,
@@ -789,7 +850,7 @@ where
         let epoch_height = config.epoch_height;
         let left_system_context = SystemContext::new(
             public_key.clone(),
-            private_key.clone(),
+            NodeKey::Private(private_key.clone()),
             nonce,
             config.clone(),
             Arc::clone(&memberships),
@@ -802,7 +863,7 @@ where
         .await;
         let right_system_context = SystemContext::new(
             public_key,
-            private_key,
+            NodeKey::Private(private_key),
             nonce,
             config,
             memberships,
@@ -974,8 +1035,8 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> ConsensusApi<TY
         &self.hotshot.public_key
     }
 
-    fn private_key(&self) -> &<TYPES::SignatureKey as SignatureKey>::PrivateKey {
-        &self.hotshot.private_key
+    fn private_key(&self) -> Option<&<TYPES::SignatureKey as SignatureKey>::PrivateKey> {
+        self.hotshot.private_key.as_ref()
     }
 }
 
@@ -1030,6 +1091,18 @@ pub struct HotShotInitializer<TYPES: NodeType> {
 
     /// Saved VID shares
     pub saved_vid_shares: VidShares<TYPES>,
+
+    /// Progress of interrupted DRB calculations, to be resumed
+    pub drb_checkpoints: BTreeMap<TYPES::Epoch, DrbCheckpoint>,
+
+    /// Stored DRB seed inputs, for results which were not yet computed
+    pub drb_seeds: BTreeMap<TYPES::Epoch, DrbSeedInput>,
+
+    /// Stored proofs of DRB seeds, served to peers missing the corresponding results
+    pub drb_seed_proofs: BTreeMap<TYPES::Epoch, DrbSeedProof<TYPES>>,
+
+    /// Stored DRB results
+    pub drb_results: BTreeMap<TYPES::Epoch, DrbResult>,
 }
 
 impl<TYPES: NodeType> HotShotInitializer<TYPES> {
@@ -1058,6 +1131,10 @@ impl<TYPES: NodeType> HotShotInitializer<TYPES> {
             undecided_state: BTreeMap::new(),
             instance_state,
             saved_vid_shares: BTreeMap::new(),
+            drb_checkpoints: BTreeMap::new(),
+            drb_seeds: BTreeMap::new(),
+            drb_seed_proofs: BTreeMap::new(),
+            drb_results: BTreeMap::new(),
             epoch_height,
         })
     }
@@ -1141,6 +1218,10 @@ impl<TYPES: NodeType> HotShotInitializer<TYPES> {
             decided_upgrade_certificate,
             undecided_leaves: BTreeMap::new(),
             undecided_state: BTreeMap::new(),
+            drb_checkpoints: BTreeMap::new(),
+            drb_seeds: BTreeMap::new(),
+            drb_seed_proofs: BTreeMap::new(),
+            drb_results: BTreeMap::new(),
         };
 
         initializer.update_undecided()
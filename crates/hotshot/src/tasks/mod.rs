@@ -8,7 +8,13 @@
 
 /// Provides trait to create task states from a `SystemContextHandle`
 pub mod task_state;
-use std::{collections::BTreeMap, fmt::Debug, num::NonZeroUsize, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    num::NonZeroUsize,
+    sync::Arc,
+    time::Duration,
+};
 
 use async_broadcast::{broadcast, RecvError};
 use async_lock::RwLock;
@@ -17,12 +23,17 @@ use futures::{
     future::{BoxFuture, FutureExt},
     stream, StreamExt,
 };
-use hotshot_task::task::Task;
+use hotshot_task::{
+    queue::{OverflowPolicy, QueueConfig},
+    supervisor::{FailureHook, RestartPolicy, TaskFailure},
+    task::Task,
+};
 #[cfg(feature = "rewind")]
 use hotshot_task_impls::rewind::RewindTaskState;
 use hotshot_task_impls::{
     da::DaTaskState,
     events::HotShotEvent,
+    helpers::broadcast_event,
     network::{NetworkEventTaskState, NetworkMessageTaskState},
     request::NetworkRequestState,
     response::{run_response_task, NetworkResponseState},
@@ -33,8 +44,11 @@ use hotshot_task_impls::{
 };
 use hotshot_types::{
     consensus::{Consensus, OuterConsensus},
-    constants::EVENT_CHANNEL_SIZE,
+    constants::{EVENT_CHANNEL_SIZE, TASK_EVENT_QUEUE_SIZE},
+    error::HotShotError,
+    event::{Event, EventType},
     message::{Message, UpgradeLock},
+    signer::NodeKey,
     traits::{
         network::ConnectedNetwork,
         node_implementation::{ConsensusTime, NodeImplementation, NodeType},
@@ -84,9 +98,10 @@ pub fn add_response_task<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versi
         handle.hotshot.consensus(),
         Arc::clone(&handle.memberships),
         handle.public_key().clone(),
-        handle.private_key().clone(),
+        Arc::clone(&handle.hotshot.signer),
         handle.hotshot.id,
         handle.hotshot.upgrade_lock.clone(),
+        handle.hotshot.config.vid_config,
     );
     handle
         .network_registry
@@ -97,22 +112,44 @@ pub fn add_response_task<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versi
         ));
 }
 
-/// Add a task which updates our queue length metric at a set interval
+/// Add a task which updates our queue length and task liveness metrics at a set interval
 pub fn add_queue_len_task<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
     handle: &mut SystemContextHandle<TYPES, I, V>,
 ) {
     let consensus = handle.hotshot.consensus();
     let rx = handle.internal_event_stream.1.clone();
+    let supervisor = handle.consensus_registry.supervisor();
+    let queue_stats = handle.consensus_registry.queue_stats();
     let shutdown_signal = create_shutdown_event_monitor(handle).fuse();
     let task_handle = spawn(async move {
         futures::pin_mut!(shutdown_signal);
+        // last reported number of dropped events per task, so we only add the difference
+        let mut reported_drops: HashMap<String, usize> = HashMap::new();
         loop {
             futures::select! {
                 () = shutdown_signal => {
                     return;
                 },
                 () = sleep(Duration::from_millis(500)).fuse() => {
-                    consensus.read().await.metrics.internal_event_queue_len.set(rx.len());
+                    let live_tasks = supervisor.live_tasks().await;
+                    let consensus_reader = consensus.read().await;
+                    let metrics = &consensus_reader.metrics;
+                    metrics.internal_event_queue_len.set(rx.len());
+                    metrics.live_tasks.set(live_tasks);
+
+                    for (task, stats) in &queue_stats {
+                        metrics.task_event_lag.create(vec![task.clone()]).set(stats.lag());
+
+                        let dropped = stats.dropped();
+                        let reported = reported_drops.entry(task.clone()).or_default();
+                        if dropped > *reported {
+                            metrics
+                                .task_events_dropped
+                                .create(vec![task.clone()])
+                                .add(dropped - *reported);
+                            *reported = dropped;
+                        }
+                    }
                 }
             }
         }
@@ -120,6 +157,47 @@ pub fn add_queue_len_task<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Vers
     handle.network_registry.register(task_handle);
 }
 
+/// Creates the hook which reports failed consensus tasks to the application.
+///
+/// Every failure is counted in the consensus metrics and emitted as an `EventType::Error`
+/// on the external event stream.
+fn task_failure_hook<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    handle: &SystemContextHandle<TYPES, I, V>,
+) -> FailureHook {
+    let consensus = handle.hotshot.consensus();
+    let output_event_stream = handle.output_event_stream.0.clone();
+
+    Arc::new(move |failure: TaskFailure| {
+        let consensus = Arc::clone(&consensus);
+        let output_event_stream = output_event_stream.clone();
+
+        async move {
+            let consensus_reader = consensus.read().await;
+            consensus_reader.metrics.number_of_task_failures.add(1);
+            if failure.will_restart {
+                consensus_reader.metrics.number_of_task_restarts.add(1);
+            }
+            let view_number = consensus_reader.cur_view();
+            drop(consensus_reader);
+
+            broadcast_event(
+                Event {
+                    view_number,
+                    event: EventType::Error {
+                        error: Arc::new(HotShotError::TaskFailed {
+                            task: failure.task,
+                            reason: failure.reason,
+                        }),
+                    },
+                },
+                &output_event_stream,
+            )
+            .await;
+        }
+        .boxed()
+    })
+}
+
 /// Add the network task to handle messages and publish events.
 #[allow(clippy::missing_panics_doc)]
 pub fn add_network_message_task<
@@ -221,10 +299,39 @@ pub fn add_network_event_task<
 pub async fn add_consensus_tasks<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
     handle: &mut SystemContextHandle<TYPES, I, V>,
 ) {
-    handle.add_task(ViewSyncTaskState::<TYPES, V>::create_from(handle).await);
-    handle.add_task(VidTaskState::<TYPES, I, V>::create_from(handle).await);
-    handle.add_task(DaTaskState::<TYPES, I, V>::create_from(handle).await);
-    handle.add_task(TransactionTaskState::<TYPES, I, V>::create_from(handle).await);
+    let restart_policy = match handle.hotshot.config.task_restart_limit {
+        0 => RestartPolicy::Never,
+        limit => RestartPolicy::Limited(limit),
+    };
+    let failure_hook = task_failure_hook(handle);
+    handle
+        .consensus_registry
+        .supervise(restart_policy, Some(failure_hook));
+
+    // Tasks which only react to their own events get a dedicated queue, so that a slow VID or
+    // builder task cannot hold back anything else. Stale block requests are worthless, so those
+    // tasks drop their oldest events when they fall behind.
+    let blocking_queue = QueueConfig {
+        capacity: TASK_EVENT_QUEUE_SIZE,
+        overflow: OverflowPolicy::Block,
+    };
+    let lossy_queue = QueueConfig {
+        capacity: TASK_EVENT_QUEUE_SIZE,
+        overflow: OverflowPolicy::DropOldest,
+    };
+
+    handle
+        .add_restartable_task::<ViewSyncTaskState<TYPES, V>>(Some(blocking_queue))
+        .await;
+    handle
+        .add_restartable_task::<VidTaskState<TYPES, I, V>>(Some(lossy_queue))
+        .await;
+    handle
+        .add_restartable_task::<DaTaskState<TYPES, I, V>>(Some(blocking_queue))
+        .await;
+    handle
+        .add_restartable_task::<TransactionTaskState<TYPES, I, V>>(Some(lossy_queue))
+        .await;
 
     {
         let mut upgrade_certificate_lock = handle
@@ -245,7 +352,9 @@ pub async fn add_consensus_tasks<TYPES: NodeType, I: NodeImplementation<TYPES>,
 
     // only spawn the upgrade task if we are actually configured to perform an upgrade.
     if V::Base::VERSION < V::Upgrade::VERSION {
-        handle.add_task(UpgradeTaskState::<TYPES, V>::create_from(handle).await);
+        handle
+            .add_restartable_task::<UpgradeTaskState<TYPES, V>>(Some(blocking_queue))
+            .await;
     }
 
     {
@@ -254,10 +363,18 @@ pub async fn add_consensus_tasks<TYPES: NodeType, I: NodeImplementation<TYPES>,
             quorum_proposal_recv::QuorumProposalRecvTaskState, quorum_vote::QuorumVoteTaskState,
         };
 
-        handle.add_task(QuorumProposalTaskState::<TYPES, I, V>::create_from(handle).await);
-        handle.add_task(QuorumVoteTaskState::<TYPES, I, V>::create_from(handle).await);
-        handle.add_task(QuorumProposalRecvTaskState::<TYPES, I, V>::create_from(handle).await);
-        handle.add_task(ConsensusTaskState::<TYPES, I, V>::create_from(handle).await);
+        handle
+            .add_restartable_task::<QuorumProposalTaskState<TYPES, I, V>>(None)
+            .await;
+        handle
+            .add_restartable_task::<QuorumVoteTaskState<TYPES, I, V>>(None)
+            .await;
+        handle
+            .add_restartable_task::<QuorumProposalRecvTaskState<TYPES, I, V>>(None)
+            .await;
+        handle
+            .add_restartable_task::<ConsensusTaskState<TYPES, I, V>>(Some(blocking_queue))
+            .await;
     }
     add_queue_len_task(handle);
     #[cfg(feature = "rewind")]
@@ -338,7 +455,7 @@ where
         let epoch_height = config.epoch_height;
         let hotshot = SystemContext::new(
             public_key,
-            private_key,
+            NodeKey::Private(private_key),
             nonce,
             config,
             memberships,
@@ -374,6 +491,9 @@ where
     }
 
     /// Add byzantine network tasks with the trait
+    ///
+    /// # Panics
+    /// If the node of `handle` signs through a signer instead of holding its private key
     #[allow(clippy::too_many_lines)]
     async fn add_network_tasks(&'static mut self, handle: &mut SystemContextHandle<TYPES, I, V>) {
         // channels between the task spawned in this function and the network tasks.
@@ -415,7 +535,11 @@ where
         // and broadcast the transformed events to the replacement event stream we just created.
         let shutdown_signal = create_shutdown_event_monitor(handle).fuse();
         let public_key = handle.public_key().clone();
-        let private_key = handle.private_key().clone();
+        // Byzantine handles are spawned with their private key, which they misbehave with
+        let private_key = handle
+            .private_key()
+            .cloned()
+            .expect("Byzantine handles hold their private key");
         let upgrade_lock = handle.hotshot.upgrade_lock.clone();
         let consensus = Arc::clone(&handle.hotshot.consensus());
         let send_handle = spawn(async move {
@@ -7,10 +7,12 @@
 use std::{
     collections::{BTreeMap, HashMap},
     sync::{atomic::AtomicBool, Arc},
+    time::Instant,
 };
 
 use async_trait::async_trait;
 use chrono::Utc;
+use hotshot_task::dependency_task::DependencyTasks;
 use hotshot_task_impls::{
     builder::BuilderClient, consensus::ConsensusTaskState, da::DaTaskState,
     quorum_proposal::QuorumProposalTaskState, quorum_proposal_recv::QuorumProposalRecvTaskState,
@@ -53,7 +55,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
             delay: handle.hotshot.config.data_request_delay,
             membership: Arc::clone(&handle.hotshot.memberships),
             public_key: handle.public_key().clone(),
-            private_key: handle.private_key().clone(),
+            signer: Arc::clone(&handle.hotshot.signer),
             id: handle.hotshot.id,
             shutdown_flag: Arc::new(AtomicBool::new(false)),
             spawned_tasks: BTreeMap::new(),
@@ -75,7 +77,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
             membership: Arc::clone(&handle.hotshot.memberships),
             vote_collectors: BTreeMap::default(),
             public_key: handle.public_key().clone(),
-            private_key: handle.private_key().clone(),
+            signer: Arc::clone(&handle.hotshot.signer),
             id: handle.hotshot.id,
             start_proposing_view: handle.hotshot.config.start_proposing_view,
             stop_proposing_view: handle.hotshot.config.stop_proposing_view,
@@ -97,7 +99,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
             network: Arc::clone(&handle.hotshot.network),
             vote_collector: None.into(),
             public_key: handle.public_key().clone(),
-            private_key: handle.private_key().clone(),
+            signer: Arc::clone(&handle.hotshot.signer),
             id: handle.hotshot.id,
             start_proposing_view: 5,
             stop_proposing_view: 10,
@@ -124,10 +126,11 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
             network: Arc::clone(&handle.hotshot.network),
             membership: Arc::clone(&handle.hotshot.memberships),
             public_key: handle.public_key().clone(),
-            private_key: handle.private_key().clone(),
+            signer: Arc::clone(&handle.hotshot.signer),
             id: handle.hotshot.id,
             upgrade_lock: handle.hotshot.upgrade_lock.clone(),
             epoch_height: handle.epoch_height,
+            vid_config: handle.hotshot.config.vid_config,
         }
     }
 }
@@ -146,10 +149,11 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
             cur_epoch: handle.cur_epoch().await,
             vote_collectors: BTreeMap::default(),
             public_key: handle.public_key().clone(),
-            private_key: handle.private_key().clone(),
+            signer: Arc::clone(&handle.hotshot.signer),
             id: handle.hotshot.id,
             storage: Arc::clone(&handle.storage),
             upgrade_lock: handle.hotshot.upgrade_lock.clone(),
+            vid_config: handle.hotshot.config.vid_config,
         }
     }
 }
@@ -167,7 +171,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
             cur_epoch: handle.cur_epoch().await,
             membership: Arc::clone(&handle.hotshot.memberships),
             public_key: handle.public_key().clone(),
-            private_key: handle.private_key().clone(),
+            signer: Arc::clone(&handle.hotshot.signer),
             num_timeouts_tracked: 0,
             replica_task_map: HashMap::default().into(),
             pre_commit_relay_map: HashMap::default().into(),
@@ -194,7 +198,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
             cur_epoch: handle.cur_epoch().await,
             membership: Arc::clone(&handle.hotshot.memberships),
             public_key: handle.public_key().clone(),
-            private_key: handle.private_key().clone(),
+            signer: Arc::clone(&handle.hotshot.signer),
             instance_state: handle.hotshot.instance_state(),
             id: handle.hotshot.id,
             builder_clients: handle
@@ -215,6 +219,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
                 .fallback_builder_url
                 .clone(),
             epoch_height: handle.epoch_height,
+            vid_config: handle.hotshot.config.vid_config,
         }
     }
 }
@@ -231,19 +236,23 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
 
         Self {
             public_key: handle.public_key().clone(),
-            private_key: handle.private_key().clone(),
+            signer: Arc::clone(&handle.hotshot.signer),
             consensus: OuterConsensus::new(consensus),
             instance_state: handle.hotshot.instance_state(),
             latest_voted_view: handle.cur_view().await,
-            vote_dependencies: BTreeMap::new(),
+            vote_dependencies: DependencyTasks::new(),
             network: Arc::clone(&handle.hotshot.network),
             membership: Arc::clone(&handle.hotshot.memberships),
             drb_computation: None,
+            drb_result_recovery: None,
+            drb_difficulty: handle.hotshot.config.drb_difficulty,
             output_event_stream: handle.hotshot.external_event_stream.0.clone(),
             id: handle.hotshot.id,
             storage: Arc::clone(&handle.storage),
             upgrade_lock: handle.hotshot.upgrade_lock.clone(),
             epoch_height: handle.hotshot.config.epoch_height,
+            timeout: handle.hotshot.config.next_view_timeout,
+            vid_config: handle.hotshot.config.vid_config,
             consensus_metrics,
         }
     }
@@ -259,12 +268,12 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
         Self {
             latest_proposed_view: handle.cur_view().await,
             cur_epoch: handle.cur_epoch().await,
-            proposal_dependencies: BTreeMap::new(),
+            proposal_dependencies: DependencyTasks::new(),
             consensus: OuterConsensus::new(consensus),
             instance_state: handle.hotshot.instance_state(),
             membership: Arc::clone(&handle.hotshot.memberships),
             public_key: handle.public_key().clone(),
-            private_key: handle.private_key().clone(),
+            signer: Arc::clone(&handle.hotshot.signer),
             storage: Arc::clone(&handle.storage),
             timeout: handle.hotshot.config.next_view_timeout,
             id: handle.hotshot.id,
@@ -285,7 +294,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
 
         Self {
             public_key: handle.public_key().clone(),
-            private_key: handle.private_key().clone(),
+            signer: Arc::clone(&handle.hotshot.signer),
             consensus: OuterConsensus::new(consensus),
             cur_view: handle.cur_view().await,
             cur_epoch: handle.cur_epoch().await,
@@ -310,7 +319,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
 
         Self {
             public_key: handle.public_key().clone(),
-            private_key: handle.private_key().clone(),
+            signer: Arc::clone(&handle.hotshot.signer),
             instance_state: handle.hotshot.instance_state(),
             network: Arc::clone(&handle.hotshot.network),
             membership: Arc::clone(&handle.hotshot.memberships),
@@ -338,7 +347,10 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> CreateTaskState
     async fn create_from(handle: &SystemContextHandle<TYPES, I, V>) -> Self {
         Self {
             events: Vec::new(),
+            received_at: Vec::new(),
+            started: Instant::now(),
             id: handle.hotshot.id,
+            config: handle.hotshot.config.clone(),
         }
     }
 }
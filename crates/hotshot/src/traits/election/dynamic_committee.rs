@@ -0,0 +1,513 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    num::NonZeroU64,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use hotshot_stake_table::{
+    config::DEFAULT_STAKE_TABLE_CAPACITY,
+    utils::ToFields,
+    vec_based::{config::FieldType, StakeTable},
+};
+use hotshot_types::{
+    light_client::{LightClientStakeTable, StateVerKey},
+    stake_table::StakeTableEntry,
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+        signature_key::{SignatureKey, StakeTableEntryType},
+        stake_table::{SnapshotVersion, StakeTableError, StakeTableScheme},
+    },
+    PeerConfig,
+};
+use primitive_types::U256;
+use tokio::time::sleep;
+use utils::anytrace::*;
+
+use super::{
+    helpers::{failure_threshold, success_threshold, total_stake, upgrade_threshold},
+    stake_source::{StakeSource, StakeUpdate},
+};
+
+/// Number of epochs before the latest one whose committees are kept
+const EPOCHS_TO_KEEP: u64 = 8;
+
+/// Delay before the first retry of a failed fetch, doubled after every further failure
+const STAKE_UPDATE_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Longest delay between two retries of a failed fetch
+const STAKE_UPDATE_MAX_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// The committee of one epoch, materialized from a stake table snapshot
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct EpochCommittee<K: SignatureKey> {
+    /// The nodes eligible for leadership.
+    /// NOTE: This is currently a hack because the DA leader needs to be the quorum
+    /// leader but without voting rights.
+    eligible_leaders: Vec<StakeTableEntry<K>>,
+
+    /// The nodes on the committee and their stake
+    stake_table: Vec<StakeTableEntry<K>>,
+
+    /// The nodes on the DA committee and their stake
+    da_stake_table: Vec<StakeTableEntry<K>>,
+
+    /// The nodes on the committee and their stake, indexed by public key
+    indexed_stake_table: BTreeMap<K, StakeTableEntry<K>>,
+
+    /// The nodes on the DA committee and their stake, indexed by public key
+    indexed_da_stake_table: BTreeMap<K, StakeTableEntry<K>>,
+
+    /// The stake table light client states of the epoch are signed against
+    light_client_stake_table: Option<LightClientStakeTable>,
+}
+
+impl<K: SignatureKey + ToFields<FieldType>> EpochCommittee<K> {
+    /// Build the committee from the staked nodes of a snapshot, the DA committee being the
+    /// staked nodes among `da_keys`
+    fn new(snapshot: impl Iterator<Item = (K, U256, StateVerKey)>, da_keys: &BTreeSet<K>) -> Self {
+        let stake_table: Vec<StakeTableEntry<K>> = snapshot
+            .filter(|(_, amount, _)| *amount > U256::zero())
+            .map(|(stake_key, stake_amount, _)| StakeTableEntry {
+                stake_key,
+                stake_amount,
+            })
+            .collect();
+
+        let da_stake_table: Vec<StakeTableEntry<K>> = stake_table
+            .iter()
+            .filter(|entry| da_keys.contains(&entry.stake_key))
+            .cloned()
+            .collect();
+
+        let indexed_stake_table = stake_table
+            .iter()
+            .map(|entry| (entry.stake_key.clone(), entry.clone()))
+            .collect();
+
+        let indexed_da_stake_table = da_stake_table
+            .iter()
+            .map(|entry| (entry.stake_key.clone(), entry.clone()))
+            .collect();
+
+        Self {
+            eligible_leaders: stake_table.clone(),
+            stake_table,
+            da_stake_table,
+            indexed_stake_table,
+            indexed_da_stake_table,
+            light_client_stake_table: None,
+        }
+    }
+
+    /// Build the committee from the `EpochStart` snapshot of `stake_table`, along with the stake
+    /// table light client states are signed against
+    fn from_epoch_start(
+        stake_table: &StakeTable<K, StateVerKey, FieldType>,
+        da_keys: &BTreeSet<K>,
+    ) -> std::result::Result<Self, StakeTableError> {
+        let snapshot: Vec<_> = stake_table.try_iter(SnapshotVersion::EpochStart)?.collect();
+        let (bls_key_comm, schnorr_key_comm, amount_comm) =
+            stake_table.commitment(SnapshotVersion::EpochStart)?;
+
+        let mut stakes = HashMap::new();
+        for (_, amount, state_ver_key) in snapshot.iter().filter(|(_, amount, _)| !amount.is_zero())
+        {
+            let stake: &mut U256 = stakes.entry(state_ver_key.clone()).or_default();
+            *stake = stake.saturating_add(*amount);
+        }
+
+        let mut committee = Self::new(snapshot.into_iter(), da_keys);
+        committee.light_client_stake_table = Some(LightClientStakeTable::new(
+            bls_key_comm,
+            schnorr_key_comm,
+            amount_comm,
+            stakes,
+        ));
+        Ok(committee)
+    }
+}
+
+#[derive(Debug)]
+/// Committee election whose stake table changes at every epoch root, with the updates read from
+/// a [`StakeSource`].
+///
+/// Works with any signature key whose stake table entries are [`StakeTableEntry`]s and which
+/// the stake table can commit to.
+pub struct DynamicCommittee<T: NodeType, S>
+where
+    T::SignatureKey: ToFields<FieldType>,
+{
+    /// The stake table. Its `EpochStart` snapshot holds the stake of the latest epoch whose
+    /// root was added.
+    stake_table: StakeTable<T::SignatureKey, StateVerKey, FieldType>,
+
+    /// Keys of the DA committee members
+    da_keys: BTreeSet<T::SignatureKey>,
+
+    /// The committee the membership was created with, used before the first epoch root
+    initial_committee: EpochCommittee<T::SignatureKey>,
+
+    /// The committee of each epoch whose root was added
+    epoch_committees: BTreeMap<T::Epoch, EpochCommittee<T::SignatureKey>>,
+
+    /// Where the stake table updates come from
+    stake_source: S,
+}
+
+impl<TYPES, S> DynamicCommittee<TYPES, S>
+where
+    TYPES: NodeType,
+    TYPES::SignatureKey:
+        SignatureKey<StakeTableEntry = StakeTableEntry<TYPES::SignatureKey>> + ToFields<FieldType>,
+    S: StakeSource<TYPES>,
+{
+    /// Create a committee reading the stake table updates from `stake_source`
+    pub fn with_stake_source(
+        committee_members: Vec<PeerConfig<TYPES::SignatureKey>>,
+        da_members: Vec<PeerConfig<TYPES::SignatureKey>>,
+        stake_source: S,
+    ) -> Self {
+        let mut stake_table =
+            StakeTable::new(committee_members.len().max(DEFAULT_STAKE_TABLE_CAPACITY));
+        for member in committee_members {
+            let entry = member.stake_table_entry;
+            if let Err(e) = stake_table.register(
+                entry.stake_key.clone(),
+                entry.stake_amount,
+                member.state_ver_key,
+            ) {
+                tracing::warn!(
+                    "Failed to add {:?} to the initial stake table: {}",
+                    entry.stake_key,
+                    e
+                );
+            }
+        }
+        stake_table.advance();
+
+        let da_keys = da_members
+            .iter()
+            .map(|member| member.stake_table_entry.stake_key.clone())
+            .collect();
+        let initial_committee = EpochCommittee::from_epoch_start(&stake_table, &da_keys)
+            .unwrap_or_else(|_| EpochCommittee::new(std::iter::empty(), &da_keys));
+
+        Self {
+            stake_table,
+            da_keys,
+            initial_committee,
+            epoch_committees: BTreeMap::new(),
+            stake_source,
+        }
+    }
+
+    /// The stake source of the committee
+    #[must_use]
+    pub fn stake_source(&self) -> &S {
+        &self.stake_source
+    }
+
+    /// The committee for `epoch`: the one of the latest epoch root added at or before it, or the
+    /// initial committee if there is none.
+    fn committee(&self, epoch: Option<TYPES::Epoch>) -> &EpochCommittee<TYPES::SignatureKey> {
+        epoch
+            .and_then(|epoch| self.epoch_committees.range(..=epoch).next_back())
+            .map_or(&self.initial_committee, |(_, committee)| committee)
+    }
+
+    /// Apply a single update to the head of the stake table
+    fn apply_update(&mut self, update: StakeUpdate<TYPES::SignatureKey>) -> Result<()> {
+        match update {
+            StakeUpdate::Register {
+                key,
+                state_ver_key,
+                amount,
+            } => self
+                .stake_table
+                .register(key.clone(), amount, state_ver_key)
+                .wrap()
+                .context(warn!("Failed to register {:?}", key)),
+            StakeUpdate::Deregister { key } => self
+                .stake_table
+                .deregister(&key)
+                .wrap()
+                .context(warn!("Failed to deregister {:?}", key)),
+            StakeUpdate::SetStake { key, amount } => self
+                .stake_table
+                .set_value(&key, amount)
+                .map(|_| ())
+                .wrap()
+                .context(warn!("Failed to set the stake of {:?}", key)),
+        }
+    }
+
+    /// Apply the updates of the epoch root for `epoch` and snapshot the committee of the epoch
+    fn apply_epoch_root(
+        &mut self,
+        epoch: TYPES::Epoch,
+        updates: Vec<StakeUpdate<TYPES::SignatureKey>>,
+    ) {
+        // Epoch roots are decided in order, ignore ones we've already applied
+        if self
+            .epoch_committees
+            .last_key_value()
+            .is_some_and(|(latest, _)| *latest >= epoch)
+        {
+            return;
+        }
+
+        for update in updates {
+            if let Err(e) = self.apply_update(update) {
+                tracing::warn!("Skipping stake table update for epoch {:?}: {}", epoch, e);
+            }
+        }
+        self.stake_table.advance();
+
+        let committee = match EpochCommittee::from_epoch_start(&self.stake_table, &self.da_keys) {
+            Ok(committee) => committee,
+            Err(e) => {
+                tracing::error!(
+                    "Failed to read the stake table for epoch {:?}: {}",
+                    epoch,
+                    e
+                );
+                return;
+            }
+        };
+        self.epoch_committees.insert(epoch, committee);
+
+        if let Some(oldest_epoch) = epoch.u64().checked_sub(EPOCHS_TO_KEEP) {
+            self.epoch_committees = self
+                .epoch_committees
+                .split_off(&TYPES::Epoch::new(oldest_epoch));
+        }
+    }
+}
+
+#[async_trait]
+impl<TYPES, S> Membership<TYPES> for DynamicCommittee<TYPES, S>
+where
+    TYPES: NodeType,
+    TYPES::SignatureKey:
+        SignatureKey<StakeTableEntry = StakeTableEntry<TYPES::SignatureKey>> + ToFields<FieldType>,
+    S: StakeSource<TYPES> + Default,
+{
+    type Error = utils::anytrace::Error;
+
+    /// Create a new election, with the default stake source
+    fn new(
+        committee_members: Vec<PeerConfig<<TYPES as NodeType>::SignatureKey>>,
+        da_members: Vec<PeerConfig<<TYPES as NodeType>::SignatureKey>>,
+    ) -> Self {
+        Self::with_stake_source(committee_members, da_members, S::default())
+    }
+
+    /// Get the stake table for the epoch
+    fn stake_table(
+        &self,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> Vec<<<TYPES as NodeType>::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.committee(epoch).stake_table.clone()
+    }
+
+    /// Get the DA stake table for the epoch
+    fn da_stake_table(
+        &self,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> Vec<<<TYPES as NodeType>::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.committee(epoch).da_stake_table.clone()
+    }
+
+    /// Get all members of the committee for the epoch
+    fn committee_members(
+        &self,
+        _view_number: <TYPES as NodeType>::View,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> BTreeSet<<TYPES as NodeType>::SignatureKey> {
+        self.committee(epoch)
+            .stake_table
+            .iter()
+            .map(TYPES::SignatureKey::public_key)
+            .collect()
+    }
+
+    /// Get all members of the DA committee for the epoch
+    fn da_committee_members(
+        &self,
+        _view_number: <TYPES as NodeType>::View,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> BTreeSet<<TYPES as NodeType>::SignatureKey> {
+        self.committee(epoch)
+            .da_stake_table
+            .iter()
+            .map(TYPES::SignatureKey::public_key)
+            .collect()
+    }
+
+    /// Get all eligible leaders of the committee for the epoch
+    fn committee_leaders(
+        &self,
+        _view_number: <TYPES as NodeType>::View,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> BTreeSet<<TYPES as NodeType>::SignatureKey> {
+        self.committee(epoch)
+            .eligible_leaders
+            .iter()
+            .map(TYPES::SignatureKey::public_key)
+            .collect()
+    }
+
+    /// Get the stake table entry for a public key
+    fn stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> Option<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.committee(epoch)
+            .indexed_stake_table
+            .get(pub_key)
+            .cloned()
+    }
+
+    /// Get the DA stake table entry for a public key
+    fn da_stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> Option<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.committee(epoch)
+            .indexed_da_stake_table
+            .get(pub_key)
+            .cloned()
+    }
+
+    /// Check if a node has stake in the committee
+    fn has_stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> bool {
+        self.committee(epoch)
+            .indexed_stake_table
+            .get(pub_key)
+            .is_some_and(|x| x.stake() > U256::zero())
+    }
+
+    /// Check if a node has stake in the DA committee
+    fn has_da_stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> bool {
+        self.committee(epoch)
+            .indexed_da_stake_table
+            .get(pub_key)
+            .is_some_and(|x| x.stake() > U256::zero())
+    }
+
+    /// Index the vector of eligible leaders of the epoch with the current view number
+    fn lookup_leader(
+        &self,
+        view_number: <TYPES as NodeType>::View,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> Result<TYPES::SignatureKey> {
+        let eligible_leaders = &self.committee(epoch).eligible_leaders;
+        ensure!(
+            !eligible_leaders.is_empty(),
+            error!("No eligible leader has stake in epoch {:?}", epoch)
+        );
+
+        #[allow(clippy::cast_possible_truncation)]
+        let index = *view_number as usize % eligible_leaders.len();
+        Ok(TYPES::SignatureKey::public_key(&eligible_leaders[index]))
+    }
+
+    /// Get the total number of nodes in the committee
+    fn total_nodes(&self, epoch: Option<<TYPES as NodeType>::Epoch>) -> usize {
+        self.committee(epoch).stake_table.len()
+    }
+
+    /// Get the total number of DA nodes in the committee
+    fn da_total_nodes(&self, epoch: Option<<TYPES as NodeType>::Epoch>) -> usize {
+        self.committee(epoch).da_stake_table.len()
+    }
+
+    /// Get the voting success threshold for the committee, in terms of stake
+    fn success_threshold(&self, epoch: Option<<TYPES as NodeType>::Epoch>) -> NonZeroU64 {
+        success_threshold(total_stake::<TYPES::SignatureKey>(
+            &self.committee(epoch).stake_table,
+        ))
+    }
+
+    /// Get the voting success threshold for the DA committee, in terms of stake
+    fn da_success_threshold(&self, epoch: Option<<TYPES as NodeType>::Epoch>) -> NonZeroU64 {
+        success_threshold(total_stake::<TYPES::SignatureKey>(
+            &self.committee(epoch).da_stake_table,
+        ))
+    }
+
+    /// Get the voting failure threshold for the committee, in terms of stake
+    fn failure_threshold(&self, epoch: Option<<TYPES as NodeType>::Epoch>) -> NonZeroU64 {
+        failure_threshold(total_stake::<TYPES::SignatureKey>(
+            &self.committee(epoch).stake_table,
+        ))
+    }
+
+    /// Get the voting upgrade threshold for the committee, in terms of stake
+    fn upgrade_threshold(&self, epoch: Option<<TYPES as NodeType>::Epoch>) -> NonZeroU64 {
+        upgrade_threshold(total_stake::<TYPES::SignatureKey>(
+            &self.committee(epoch).stake_table,
+        ))
+    }
+
+    /// The stake table committed to at the start of the epoch
+    fn light_client_stake_table(
+        &self,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> Option<LightClientStakeTable> {
+        self.committee(epoch).light_client_stake_table.clone()
+    }
+
+    /// Fetch the stake table updates of the epoch from the stake source, retrying with backoff
+    /// until it succeeds, and apply them under the write lock
+    async fn add_epoch_root(
+        &self,
+        epoch: TYPES::Epoch,
+        block_header: TYPES::BlockHeader,
+    ) -> Option<Box<dyn FnOnce(&mut Self) + Send>> {
+        if self.epoch_committees.contains_key(&epoch) {
+            return None;
+        }
+
+        // The updates are deltas on top of the previous epoch, so skipping an epoch would corrupt
+        // every later committee. Keep trying until the source is back.
+        let mut delay = STAKE_UPDATE_RETRY_DELAY;
+        let updates = loop {
+            match self.stake_source.stake_updates(epoch, &block_header).await {
+                Ok(updates) => break updates,
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to get the stake table updates for epoch {:?}, retrying in {:?}: {}",
+                        epoch,
+                        delay,
+                        e
+                    );
+                    sleep(delay).await;
+                    delay = (delay * 2).min(STAKE_UPDATE_MAX_RETRY_DELAY);
+                }
+            }
+        };
+
+        Some(Box::new(move |committee: &mut Self| {
+            committee.apply_epoch_root(epoch, updates);
+        }))
+    }
+}
@@ -4,8 +4,10 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
-use std::{collections::BTreeSet, hash::Hash};
+use std::{cmp::max, collections::BTreeSet, hash::Hash, num::NonZeroU64};
 
+use hotshot_types::traits::signature_key::{SignatureKey, StakeTableEntryType};
+use primitive_types::U256;
 use rand::{rngs::StdRng, Rng, SeedableRng};
 
 /// Helper which allows producing random numbers within a range and preventing duplicates
@@ -363,6 +365,37 @@ impl<
     }
 }
 
+#[must_use]
+/// Total stake of `stake_table`
+pub fn total_stake<K: SignatureKey>(stake_table: &[K::StakeTableEntry]) -> U256 {
+    stake_table
+        .iter()
+        .fold(U256::zero(), |total, entry| total + entry.stake())
+}
+
+/// `stake`, saturated to a `u64`
+fn saturate(stake: U256) -> NonZeroU64 {
+    NonZeroU64::new(stake.min(U256::from(u64::MAX)).as_u64()).unwrap_or(NonZeroU64::MIN)
+}
+
+#[must_use]
+/// Stake needed for a quorum out of `total_stake`: more than two thirds
+pub fn success_threshold(total_stake: U256) -> NonZeroU64 {
+    saturate(total_stake * 2 / 3 + 1)
+}
+
+#[must_use]
+/// Stake needed to prove at least one honest node out of `total_stake`: more than a third
+pub fn failure_threshold(total_stake: U256) -> NonZeroU64 {
+    saturate(total_stake / 3 + 1)
+}
+
+#[must_use]
+/// Stake needed to upgrade out of `total_stake`: at least nine tenths, and always a quorum
+pub fn upgrade_threshold(total_stake: U256) -> NonZeroU64 {
+    saturate(max(total_stake * 9 / 10, total_stake * 2 / 3 + 1))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
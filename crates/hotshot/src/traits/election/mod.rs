@@ -6,12 +6,24 @@
 
 //! elections used for consensus
 
+/// committee updated at every epoch root from a stake source, round robin leader
+pub mod dynamic_committee;
+
 /// leader completely randomized every view
 pub mod randomized_committee;
 
 /// quorum randomized every view, with configurable overlap
 pub mod randomized_committee_members;
 
+/// decorator demoting leaders which keep failing to get their views decided
+pub mod reputation_committee;
+
+/// leader sampled proportional to stake every view, randomized by the epoch's DRB result
+pub mod stake_weighted_committee;
+
+/// sources of stake table updates for dynamic committees
+pub mod stake_source;
+
 /// static (round robin) committee election
 pub mod static_committee;
 
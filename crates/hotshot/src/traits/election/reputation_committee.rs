@@ -0,0 +1,494 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    num::NonZeroU64,
+    path::PathBuf,
+};
+
+use async_trait::async_trait;
+use hotshot_types::{
+    data::{Leaf2, ViewChangeEvidence2},
+    drb::DrbResult,
+    light_client::LightClientStakeTable,
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+        signature_key::SignatureKey,
+    },
+    utils::{is_epoch_root, option_epoch_from_block_number},
+    PeerConfig,
+};
+use serde::{Deserialize, Serialize};
+use utils::anytrace::*;
+
+/// Configuration of the leader reputation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ReputationConfig {
+    /// Minimum number of failed views between two epoch roots for a leader to be demoted
+    pub min_failures: u64,
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        Self { min_failures: 2 }
+    }
+}
+
+/// How the views before a decided view failed, from the view change evidence of its leaf
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ViewFailure {
+    /// The views timed out and the next leader proposed with a timeout certificate
+    Timeout,
+    /// The views timed out and the nodes recovered with view sync
+    ViewSync,
+}
+
+/// Outcomes of the views a leader was scheduled for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LeaderOutcomes {
+    /// Views whose proposal was decided
+    pub decided: u64,
+    /// Views which failed and were recovered from with a timeout certificate
+    pub timeouts: u64,
+    /// Views which failed and were recovered from with view sync
+    pub view_syncs: u64,
+}
+
+impl LeaderOutcomes {
+    /// Number of failed views
+    #[must_use]
+    pub fn failures(&self) -> u64 {
+        self.timeouts + self.view_syncs
+    }
+}
+
+/// Reputation derived from the decided leaves since genesis
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+struct ReputationState<T: NodeType> {
+    /// The view of the latest decided leaf
+    last_decided_view: T::View,
+
+    /// Outcomes of the views since the last decided epoch root, per leader
+    outcomes: BTreeMap<T::SignatureKey, LeaderOutcomes>,
+
+    /// Leaders demoted in each epoch
+    demoted: BTreeMap<T::Epoch, BTreeSet<T::SignatureKey>>,
+}
+
+impl<T: NodeType> Default for ReputationState<T> {
+    fn default() -> Self {
+        Self {
+            last_decided_view: T::View::genesis(),
+            outcomes: BTreeMap::new(),
+            demoted: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Membership decorator demoting unresponsive leaders.
+///
+/// The outcome of each view is computed from the decided leaves only: a decided view counts for
+/// its leader, and every view between a decided leaf and its parent counts as a failure for its
+/// leader in the epoch of the decided block. Local `Timeout` and `ViewSyncTrigger` events differ
+/// between nodes, so they are only taken into account through the view change evidence of the
+/// decided leaves.
+///
+/// Demotions are fixed when an epoch root is decided: leaders failing too often since the
+/// previous epoch root are skipped for the whole next epoch, in favor of the next scheduled leader
+/// which isn't demoted. Leaders are never demoted before epochs are enabled.
+///
+/// The reputation is a function of the leaves decided since genesis. A committee created with
+/// [`ReputationCommittee::persistent`] saves it after each decided leaf and restores it on
+/// startup, so the leaders don't depend on when the node restarted.
+pub struct ReputationCommittee<T: NodeType, M> {
+    /// The membership whose leaders are demoted
+    inner: M,
+
+    /// Reputation configuration
+    config: ReputationConfig,
+
+    /// Reputation derived from the decided leaves
+    state: ReputationState<T>,
+
+    /// The file the reputation is persisted to, if any
+    path: Option<PathBuf>,
+}
+
+impl<TYPES: NodeType, M: Membership<TYPES>> ReputationCommittee<TYPES, M> {
+    /// Demote the unresponsive leaders of `inner` with `config`, keeping the reputation in memory
+    pub fn with_config(inner: M, config: ReputationConfig) -> Self {
+        Self {
+            inner,
+            config,
+            state: ReputationState::default(),
+            path: None,
+        }
+    }
+
+    /// Demote the unresponsive leaders of `inner` with `config`, persisting the reputation to
+    /// `path` and restoring it from there if it exists.
+    ///
+    /// # Errors
+    /// Errors if the file exists but can't be read or is malformed.
+    pub fn persistent(
+        inner: M,
+        config: ReputationConfig,
+        path: impl Into<PathBuf>,
+    ) -> Result<Self> {
+        let path = path.into();
+        let state = match fs::read(&path) {
+            Ok(bytes) => bincode::deserialize(&bytes)
+                .wrap()
+                .context(error!("Leader reputation at {:?} is malformed", path))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => ReputationState::default(),
+            Err(e) => bail!(error!(
+                "Failed to read the leader reputation at {:?}: {}",
+                path, e
+            )),
+        };
+
+        Ok(Self {
+            inner,
+            config,
+            state,
+            path: Some(path),
+        })
+    }
+
+    /// Persist the reputation, if we persist at all.
+    ///
+    /// The reputation is written to a temporary file which then replaces the previous one, so a
+    /// crash leaves either the old or the new reputation behind.
+    fn persist(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let bytes = bincode::serialize(&self.state)
+            .wrap()
+            .context(error!("Failed to serialize the leader reputation"))?;
+        let tmp_path = path.with_extension("tmp");
+        let write = || -> std::io::Result<()> {
+            let mut file = OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&tmp_path)?;
+            file.write_all(&bytes)?;
+            file.sync_all()?;
+            fs::rename(&tmp_path, path)
+        };
+        write().wrap().context(error!(
+            "Failed to persist the leader reputation to {:?}",
+            path
+        ))
+    }
+
+    /// The decorated membership
+    #[must_use]
+    pub fn inner(&self) -> &M {
+        &self.inner
+    }
+
+    /// The leaders demoted in `epoch`
+    #[must_use]
+    pub fn demoted_leaders(&self, epoch: TYPES::Epoch) -> BTreeSet<TYPES::SignatureKey> {
+        self.state.demoted.get(&epoch).cloned().unwrap_or_default()
+    }
+
+    /// The leader of `view`: the one of the inner membership unless it's demoted in `epoch`, in
+    /// which case the next scheduled leader which isn't.
+    fn effective_leader(
+        &self,
+        view: TYPES::View,
+        epoch: Option<TYPES::Epoch>,
+    ) -> Result<TYPES::SignatureKey> {
+        let leader = self.inner.leader(view, epoch)?;
+        let Some(demoted) = epoch.and_then(|epoch| self.state.demoted.get(&epoch)) else {
+            return Ok(leader);
+        };
+        if !demoted.contains(&leader) {
+            return Ok(leader);
+        }
+
+        for offset in 1..=self.inner.total_nodes(epoch) as u64 {
+            let Ok(candidate) = self.inner.leader(TYPES::View::new(*view + offset), epoch) else {
+                continue;
+            };
+            if !demoted.contains(&candidate) {
+                return Ok(candidate);
+            }
+        }
+
+        // Everyone is demoted, keep the schedule
+        Ok(leader)
+    }
+
+    /// Record the outcome of `view` of `epoch` for its leader
+    fn record(&mut self, view: TYPES::View, epoch: TYPES::Epoch, outcome: Option<ViewFailure>) {
+        let leader = match self.effective_leader(view, Some(epoch)) {
+            Ok(leader) => leader,
+            Err(e) => {
+                tracing::warn!("Can't attribute the outcome of view {:?}: {}", view, e);
+                return;
+            }
+        };
+
+        let outcomes = self.state.outcomes.entry(leader).or_default();
+        match outcome {
+            None => outcomes.decided += 1,
+            Some(ViewFailure::Timeout) => outcomes.timeouts += 1,
+            Some(ViewFailure::ViewSync) => outcomes.view_syncs += 1,
+        }
+    }
+
+    /// Record that `view` was decided with block `block_number`, on top of the leaf of
+    /// `parent_view`, the views in between having failed with `failure`. An `epoch_height` of 0
+    /// means epochs are disabled.
+    ///
+    /// This is what [`Membership::add_decided_leaf`] derives from each leaf, and must be called
+    /// with the same views in the same order on every node.
+    pub fn add_decided_view(
+        &mut self,
+        view: TYPES::View,
+        parent_view: TYPES::View,
+        block_number: u64,
+        epoch_height: u64,
+        failure: ViewFailure,
+    ) {
+        if view <= self.state.last_decided_view {
+            return;
+        }
+        self.state.last_decided_view = view;
+        self.update(view, parent_view, block_number, epoch_height, failure);
+
+        if let Err(e) = self.persist() {
+            tracing::error!("{}", e);
+        }
+    }
+
+    /// Update the reputation with a newly decided view, see [`Self::add_decided_view`]
+    fn update(
+        &mut self,
+        view: TYPES::View,
+        parent_view: TYPES::View,
+        block_number: u64,
+        epoch_height: u64,
+        failure: ViewFailure,
+    ) {
+        let Some(epoch) =
+            option_epoch_from_block_number::<TYPES>(epoch_height != 0, block_number, epoch_height)
+        else {
+            return;
+        };
+
+        // The failed views were proposing the same block.
+        for failed_view in (*parent_view + 1)..*view {
+            self.record(TYPES::View::new(failed_view), epoch, Some(failure));
+        }
+        self.record(view, epoch, None);
+
+        // Demotions of past epochs are no longer needed.
+        self.state.demoted = self.state.demoted.split_off(&epoch);
+
+        if !is_epoch_root(block_number, epoch_height) {
+            return;
+        }
+
+        // Every node decides the same views up to the epoch root, demote the unresponsive leaders
+        // since the previous one in the next epoch.
+        let outcomes = std::mem::take(&mut self.state.outcomes);
+        let config = self.config;
+        let demoted: BTreeSet<_> = outcomes
+            .into_iter()
+            .filter(|(_, outcomes)| {
+                outcomes.failures() >= config.min_failures && outcomes.failures() > outcomes.decided
+            })
+            .map(|(leader, _)| leader)
+            .collect();
+        if !demoted.is_empty() {
+            tracing::info!(
+                "Demoting {} leaders in epoch {:?}",
+                demoted.len(),
+                epoch + 1
+            );
+            self.state.demoted.insert(epoch + 1, demoted);
+        }
+    }
+}
+
+#[async_trait]
+impl<TYPES: NodeType, M: Membership<TYPES> + 'static> Membership<TYPES>
+    for ReputationCommittee<TYPES, M>
+{
+    type Error = utils::anytrace::Error;
+
+    /// Create a new election, with the default reputation configuration
+    fn new(
+        committee_members: Vec<PeerConfig<<TYPES as NodeType>::SignatureKey>>,
+        da_members: Vec<PeerConfig<<TYPES as NodeType>::SignatureKey>>,
+    ) -> Self {
+        Self::with_config(
+            M::new(committee_members, da_members),
+            ReputationConfig::default(),
+        )
+    }
+
+    fn stake_table(
+        &self,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> Vec<<<TYPES as NodeType>::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.inner.stake_table(epoch)
+    }
+
+    fn da_stake_table(
+        &self,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> Vec<<<TYPES as NodeType>::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.inner.da_stake_table(epoch)
+    }
+
+    fn committee_members(
+        &self,
+        view_number: <TYPES as NodeType>::View,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> BTreeSet<<TYPES as NodeType>::SignatureKey> {
+        self.inner.committee_members(view_number, epoch)
+    }
+
+    fn da_committee_members(
+        &self,
+        view_number: <TYPES as NodeType>::View,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> BTreeSet<<TYPES as NodeType>::SignatureKey> {
+        self.inner.da_committee_members(view_number, epoch)
+    }
+
+    fn committee_leaders(
+        &self,
+        view_number: <TYPES as NodeType>::View,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> BTreeSet<<TYPES as NodeType>::SignatureKey> {
+        self.inner.committee_leaders(view_number, epoch)
+    }
+
+    fn stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> Option<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.inner.stake(pub_key, epoch)
+    }
+
+    fn da_stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> Option<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.inner.da_stake(pub_key, epoch)
+    }
+
+    fn has_stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> bool {
+        self.inner.has_stake(pub_key, epoch)
+    }
+
+    fn has_da_stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> bool {
+        self.inner.has_da_stake(pub_key, epoch)
+    }
+
+    /// The scheduled leader of the inner membership, skipping demoted ones
+    fn lookup_leader(
+        &self,
+        view_number: <TYPES as NodeType>::View,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> Result<TYPES::SignatureKey> {
+        self.effective_leader(view_number, epoch)
+    }
+
+    fn total_nodes(&self, epoch: Option<<TYPES as NodeType>::Epoch>) -> usize {
+        self.inner.total_nodes(epoch)
+    }
+
+    fn da_total_nodes(&self, epoch: Option<<TYPES as NodeType>::Epoch>) -> usize {
+        self.inner.da_total_nodes(epoch)
+    }
+
+    fn success_threshold(&self, epoch: Option<<TYPES as NodeType>::Epoch>) -> NonZeroU64 {
+        self.inner.success_threshold(epoch)
+    }
+
+    fn da_success_threshold(&self, epoch: Option<<TYPES as NodeType>::Epoch>) -> NonZeroU64 {
+        self.inner.da_success_threshold(epoch)
+    }
+
+    fn failure_threshold(&self, epoch: Option<<TYPES as NodeType>::Epoch>) -> NonZeroU64 {
+        self.inner.failure_threshold(epoch)
+    }
+
+    fn upgrade_threshold(&self, epoch: Option<<TYPES as NodeType>::Epoch>) -> NonZeroU64 {
+        self.inner.upgrade_threshold(epoch)
+    }
+
+    async fn add_epoch_root(
+        &self,
+        epoch: TYPES::Epoch,
+        block_header: TYPES::BlockHeader,
+    ) -> Option<Box<dyn FnOnce(&mut Self) + Send>> {
+        let callback = self.inner.add_epoch_root(epoch, block_header).await?;
+        Some(Box::new(move |committee: &mut Self| {
+            callback(&mut committee.inner);
+        }))
+    }
+
+    fn add_drb_result(&mut self, epoch: TYPES::Epoch, drb_result: DrbResult) {
+        self.inner.add_drb_result(epoch, drb_result);
+    }
+
+    /// Record the outcomes of the views up to the leaf's
+    fn add_decided_leaf(&mut self, leaf: &Leaf2<TYPES>, epoch_height: u64) {
+        let failure = match &leaf.view_change_evidence {
+            Some(ViewChangeEvidence2::ViewSync(_)) => ViewFailure::ViewSync,
+            _ => ViewFailure::Timeout,
+        };
+        self.add_decided_view(
+            leaf.view_number(),
+            leaf.justify_qc().view_number,
+            leaf.height(),
+            if leaf.with_epoch { epoch_height } else { 0 },
+            failure,
+        );
+        self.inner.add_decided_leaf(leaf, epoch_height);
+    }
+
+    /// The light client stake table of the inner membership
+    fn light_client_stake_table(
+        &self,
+        epoch: Option<TYPES::Epoch>,
+    ) -> Option<LightClientStakeTable> {
+        self.inner.light_client_stake_table(epoch)
+    }
+
+    async fn sync_l1(&self) -> Option<Box<dyn FnOnce(&mut Self) + Send>> {
+        let callback = self.inner.sync_l1().await?;
+        Some(Box::new(move |committee: &mut Self| {
+            callback(&mut committee.inner);
+        }))
+    }
+}
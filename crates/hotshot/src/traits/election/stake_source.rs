@@ -0,0 +1,134 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+use async_lock::RwLock;
+use async_trait::async_trait;
+use hotshot_types::{
+    light_client::StateVerKey,
+    traits::{node_implementation::NodeType, signature_key::SignatureKey},
+};
+use primitive_types::U256;
+use utils::anytrace::*;
+
+/// A change to the stake table, applied at an epoch root
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StakeUpdate<K: SignatureKey> {
+    /// A new validator joins the stake table with `amount` stake
+    Register {
+        /// The validator's consensus key
+        key: K,
+        /// The validator's light client state key
+        state_ver_key: StateVerKey,
+        /// The stake of the validator
+        amount: U256,
+    },
+    /// A validator leaves the stake table, its stake drops to zero
+    Deregister {
+        /// The validator's consensus key
+        key: K,
+    },
+    /// The stake of a registered validator changes to `amount`
+    SetStake {
+        /// The validator's consensus key
+        key: K,
+        /// The new stake of the validator
+        amount: U256,
+    },
+}
+
+#[async_trait]
+/// Where a dynamic membership reads the changes to the stake table from, e.g. a staking contract
+pub trait StakeSource<TYPES: NodeType>: Debug + Send + Sync + 'static {
+    /// Get the stake table changes taking effect in `epoch`, as of the epoch root `block_header`.
+    ///
+    /// Every node must get the same updates for the same epoch root, so implementations should
+    /// read them at a point fixed by the header (e.g. the L1 block it references).
+    ///
+    /// # Errors
+    /// If the updates can't be fetched
+    async fn stake_updates(
+        &self,
+        epoch: TYPES::Epoch,
+        block_header: &TYPES::BlockHeader,
+    ) -> Result<Vec<StakeUpdate<TYPES::SignatureKey>>>;
+}
+
+/// A local stake source for tests, returning the updates scheduled for each epoch
+#[derive(Clone, Debug)]
+pub struct MockStakeSource<TYPES: NodeType> {
+    /// The updates for each epoch, shared between clones of the source
+    updates: Arc<RwLock<BTreeMap<TYPES::Epoch, Vec<StakeUpdate<TYPES::SignatureKey>>>>>,
+
+    /// Number of upcoming fetches which fail, shared between clones of the source
+    failures: Arc<AtomicU32>,
+}
+
+impl<TYPES: NodeType> Default for MockStakeSource<TYPES> {
+    fn default() -> Self {
+        Self {
+            updates: Arc::new(RwLock::new(BTreeMap::new())),
+            failures: Arc::new(AtomicU32::new(0)),
+        }
+    }
+}
+
+impl<TYPES: NodeType> MockStakeSource<TYPES> {
+    /// Schedule `updates` to take effect in `epoch`, after any already scheduled for it
+    pub async fn schedule(
+        &self,
+        epoch: TYPES::Epoch,
+        updates: Vec<StakeUpdate<TYPES::SignatureKey>>,
+    ) {
+        self.updates
+            .write()
+            .await
+            .entry(epoch)
+            .or_default()
+            .extend(updates);
+    }
+
+    /// Make the next `count` fetches fail, e.g. to simulate an unreachable L1
+    pub fn fail_next(&self, count: u32) {
+        self.failures.store(count, Ordering::SeqCst);
+    }
+}
+
+#[async_trait]
+impl<TYPES: NodeType> StakeSource<TYPES> for MockStakeSource<TYPES> {
+    async fn stake_updates(
+        &self,
+        epoch: TYPES::Epoch,
+        _block_header: &TYPES::BlockHeader,
+    ) -> Result<Vec<StakeUpdate<TYPES::SignatureKey>>> {
+        let failing = self
+            .failures
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                count.checked_sub(1)
+            })
+            .is_ok();
+        ensure!(
+            !failing,
+            warn!("Stake source unavailable for epoch {:?}", epoch)
+        );
+
+        Ok(self
+            .updates
+            .read()
+            .await
+            .get(&epoch)
+            .cloned()
+            .unwrap_or_default())
+    }
+}
@@ -0,0 +1,404 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    num::NonZeroU64,
+};
+
+use hotshot_types::{
+    drb::{
+        leader_randomness, sample_committee, DrbResult, StakeAliasTable, INITIAL_DRB_RESULT,
+        KEEP_PREVIOUS_RESULT_COUNT,
+    },
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+        signature_key::{SignatureKey, StakeTableEntryType},
+    },
+    PeerConfig,
+};
+use primitive_types::U256;
+use utils::anytrace::*;
+
+use super::helpers::{failure_threshold, success_threshold, total_stake, upgrade_threshold};
+
+/// Leader selection for one epoch.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+struct EpochLeaders {
+    /// The DRB result of the epoch
+    drb_result: DrbResult,
+
+    /// Alias table over the eligible leaders of the epoch
+    table: StakeAliasTable,
+}
+
+/// How the DA committee of each epoch is sampled from the stake table
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct DaSamplingConfig {
+    /// Number of nodes on the DA committee
+    pub committee_size: usize,
+
+    /// Minimum stake for a node to be sampled
+    pub min_stake: U256,
+}
+
+/// A DA committee and the stake of its members
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+struct DaCommittee<T: NodeType> {
+    /// The nodes on the DA committee and their stake
+    stake_table: Vec<<T::SignatureKey as SignatureKey>::StakeTableEntry>,
+
+    /// The nodes on the DA committee and their stake, indexed by public key
+    indexed_stake_table:
+        BTreeMap<T::SignatureKey, <T::SignatureKey as SignatureKey>::StakeTableEntry>,
+}
+
+impl<T: NodeType> DaCommittee<T> {
+    /// Build the DA committee from its members' stake table entries
+    fn new(stake_table: Vec<<T::SignatureKey as SignatureKey>::StakeTableEntry>) -> Self {
+        let indexed_stake_table = stake_table
+            .iter()
+            .map(|entry| (T::SignatureKey::public_key(entry), entry.clone()))
+            .collect();
+
+        Self {
+            stake_table,
+            indexed_stake_table,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// Committee election picking the leader of each view with probability proportional to stake,
+/// randomized by the DRB result of the epoch. The DA committee of each epoch is sampled from the
+/// stake table with the same DRB result.
+pub struct StakeWeightedCommittee<T: NodeType> {
+    /// The nodes eligible for leadership.
+    /// NOTE: This is currently a hack because the DA leader needs to be the quorum
+    /// leader but without voting rights.
+    eligible_leaders: Vec<<T::SignatureKey as SignatureKey>::StakeTableEntry>,
+
+    /// The nodes on the committee and their stake
+    stake_table: Vec<<T::SignatureKey as SignatureKey>::StakeTableEntry>,
+
+    /// The nodes on the committee and their stake, indexed by public key
+    indexed_stake_table:
+        BTreeMap<T::SignatureKey, <T::SignatureKey as SignatureKey>::StakeTableEntry>,
+
+    /// How the DA committee of each epoch is sampled
+    da_sampling: DaSamplingConfig,
+
+    /// The DA committee before epochs
+    initial_da_committee: DaCommittee<T>,
+
+    /// The DA committee of the epochs whose DRB result we know
+    epoch_da_committees: BTreeMap<T::Epoch, DaCommittee<T>>,
+
+    /// Leader selection before epochs, with the initial DRB result
+    initial_leaders: Option<EpochLeaders>,
+
+    /// Leader selection for the epochs whose DRB result we know
+    epoch_leaders: BTreeMap<T::Epoch, EpochLeaders>,
+}
+
+impl<TYPES: NodeType> StakeWeightedCommittee<TYPES> {
+    /// Create a new election, sampling the DA committee of each epoch with `da_sampling`.
+    ///
+    /// `da_members` is the DA committee before epochs. The DA committee of an epoch whose DRB
+    /// result isn't known yet is empty.
+    pub fn with_da_sampling(
+        committee_members: Vec<PeerConfig<<TYPES as NodeType>::SignatureKey>>,
+        da_members: Vec<PeerConfig<<TYPES as NodeType>::SignatureKey>>,
+        da_sampling: DaSamplingConfig,
+    ) -> Self {
+        // For each eligible leader, get the stake table entry
+        let eligible_leaders: Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> =
+            committee_members
+                .iter()
+                .map(|member| member.stake_table_entry.clone())
+                .filter(|entry| entry.stake() > U256::zero())
+                .collect();
+
+        // For each member, get the stake table entry
+        let members: Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> =
+            committee_members
+                .iter()
+                .map(|member| member.stake_table_entry.clone())
+                .filter(|entry| entry.stake() > U256::zero())
+                .collect();
+
+        // For each member, get the stake table entry
+        let da_members: Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> = da_members
+            .iter()
+            .map(|member| member.stake_table_entry.clone())
+            .filter(|entry| entry.stake() > U256::zero())
+            .collect();
+
+        // Index the stake table by public key
+        let indexed_stake_table: BTreeMap<
+            TYPES::SignatureKey,
+            <TYPES::SignatureKey as SignatureKey>::StakeTableEntry,
+        > = members
+            .iter()
+            .map(|entry| (TYPES::SignatureKey::public_key(entry), entry.clone()))
+            .collect();
+
+        let mut committee = Self {
+            eligible_leaders,
+            stake_table: members,
+            indexed_stake_table,
+            da_sampling,
+            initial_da_committee: DaCommittee::new(da_members),
+            epoch_da_committees: BTreeMap::new(),
+            initial_leaders: None,
+            epoch_leaders: BTreeMap::new(),
+        };
+        committee.initial_leaders = committee.epoch_leaders(INITIAL_DRB_RESULT);
+
+        committee
+    }
+
+    /// Build the leader selection for an epoch with `drb_result`, `None` if no leader has stake.
+    fn epoch_leaders(&self, drb_result: DrbResult) -> Option<EpochLeaders> {
+        let stakes: Vec<U256> = self
+            .eligible_leaders
+            .iter()
+            .map(StakeTableEntryType::stake)
+            .collect();
+
+        Some(EpochLeaders {
+            drb_result,
+            table: StakeAliasTable::new(&stakes)?,
+        })
+    }
+
+    /// Sample the DA committee for an epoch with `drb_result`, among the nodes with at least the
+    /// minimum stake.
+    fn sample_da_committee(&self, drb_result: &DrbResult) -> DaCommittee<TYPES> {
+        let candidates: Vec<&<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> = self
+            .stake_table
+            .iter()
+            .filter(|entry| entry.stake() >= self.da_sampling.min_stake)
+            .collect();
+        let stakes: Vec<U256> = candidates.iter().map(|entry| entry.stake()).collect();
+
+        DaCommittee::new(
+            sample_committee(&stakes, self.da_sampling.committee_size, drb_result)
+                .into_iter()
+                .map(|index| candidates[index].clone())
+                .collect(),
+        )
+    }
+
+    /// The DA committee of `epoch`, `None` if we don't know the DRB result it's sampled with
+    fn da_committee(&self, epoch: Option<TYPES::Epoch>) -> Option<&DaCommittee<TYPES>> {
+        match epoch {
+            Some(epoch) => self.epoch_da_committees.get(&epoch),
+            None => Some(&self.initial_da_committee),
+        }
+    }
+}
+
+impl<TYPES: NodeType> Membership<TYPES> for StakeWeightedCommittee<TYPES> {
+    type Error = utils::anytrace::Error;
+
+    /// Create a new election, sampling DA committees as large as `da_members` from the nodes
+    /// with stake
+    fn new(
+        committee_members: Vec<PeerConfig<<TYPES as NodeType>::SignatureKey>>,
+        da_members: Vec<PeerConfig<<TYPES as NodeType>::SignatureKey>>,
+    ) -> Self {
+        let da_sampling = DaSamplingConfig {
+            committee_size: da_members.len(),
+            min_stake: U256::one(),
+        };
+
+        Self::with_da_sampling(committee_members, da_members, da_sampling)
+    }
+
+    /// Get the stake table for the current view
+    fn stake_table(
+        &self,
+        _epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> Vec<<<TYPES as NodeType>::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.stake_table.clone()
+    }
+
+    /// Get the DA stake table sampled for the epoch, empty until its DRB result is known
+    fn da_stake_table(
+        &self,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> Vec<<<TYPES as NodeType>::SignatureKey as SignatureKey>::StakeTableEntry> {
+        self.da_committee(epoch)
+            .map(|committee| committee.stake_table.clone())
+            .unwrap_or_default()
+    }
+
+    /// Get all members of the committee for the current view
+    fn committee_members(
+        &self,
+        _view_number: <TYPES as NodeType>::View,
+        _epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> BTreeSet<<TYPES as NodeType>::SignatureKey> {
+        self.stake_table
+            .iter()
+            .map(TYPES::SignatureKey::public_key)
+            .collect()
+    }
+
+    /// Get all members of the DA committee sampled for the epoch, none until its DRB result is known
+    fn da_committee_members(
+        &self,
+        _view_number: <TYPES as NodeType>::View,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> BTreeSet<<TYPES as NodeType>::SignatureKey> {
+        self.da_committee(epoch)
+            .map(|committee| {
+                committee
+                    .stake_table
+                    .iter()
+                    .map(TYPES::SignatureKey::public_key)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get all eligible leaders of the committee for the current view
+    fn committee_leaders(
+        &self,
+        _view_number: <TYPES as NodeType>::View,
+        _epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> BTreeSet<<TYPES as NodeType>::SignatureKey> {
+        self.eligible_leaders
+            .iter()
+            .map(TYPES::SignatureKey::public_key)
+            .collect()
+    }
+
+    /// Get the stake table entry for a public key
+    fn stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        _epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> Option<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        // Only return the stake if it is above zero
+        self.indexed_stake_table.get(pub_key).cloned()
+    }
+
+    /// Get the DA stake table entry for a public key
+    fn da_stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> Option<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
+        // Only return the stake if it is above zero
+        self.da_committee(epoch)?
+            .indexed_stake_table
+            .get(pub_key)
+            .cloned()
+    }
+
+    /// Check if a node has stake in the committee
+    fn has_stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        _epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> bool {
+        self.indexed_stake_table
+            .get(pub_key)
+            .is_some_and(|x| x.stake() > U256::zero())
+    }
+
+    /// Check if a node has stake in the committee
+    fn has_da_stake(
+        &self,
+        pub_key: &<TYPES as NodeType>::SignatureKey,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> bool {
+        self.da_committee(epoch)
+            .and_then(|committee| committee.indexed_stake_table.get(pub_key))
+            .is_some_and(|x| x.stake() > U256::zero())
+    }
+
+    /// Sample the leader of the view from the alias table of the epoch
+    fn lookup_leader(
+        &self,
+        view_number: <TYPES as NodeType>::View,
+        epoch: Option<<TYPES as NodeType>::Epoch>,
+    ) -> Result<TYPES::SignatureKey> {
+        let leaders = match epoch {
+            Some(epoch) => self
+                .epoch_leaders
+                .get(&epoch)
+                .context(info!("No DRB result for epoch {:?}", epoch))?,
+            None => self
+                .initial_leaders
+                .as_ref()
+                .context(error!("No eligible leader has stake"))?,
+        };
+
+        let index = leaders
+            .table
+            .sample(leader_randomness(*view_number, &leaders.drb_result));
+
+        Ok(TYPES::SignatureKey::public_key(
+            &self.eligible_leaders[index],
+        ))
+    }
+
+    /// Get the total number of nodes in the committee
+    fn total_nodes(&self, _epoch: Option<<TYPES as NodeType>::Epoch>) -> usize {
+        self.stake_table.len()
+    }
+
+    /// Get the total number of DA nodes in the committee
+    fn da_total_nodes(&self, epoch: Option<<TYPES as NodeType>::Epoch>) -> usize {
+        self.da_committee(epoch)
+            .map_or(0, |committee| committee.stake_table.len())
+    }
+
+    /// Get the voting success threshold for the committee, in terms of stake
+    fn success_threshold(&self, _epoch: Option<<TYPES as NodeType>::Epoch>) -> NonZeroU64 {
+        success_threshold(total_stake::<TYPES::SignatureKey>(&self.stake_table))
+    }
+
+    /// Get the voting success threshold for the DA committee, in terms of the sampled stake
+    fn da_success_threshold(&self, epoch: Option<<TYPES as NodeType>::Epoch>) -> NonZeroU64 {
+        success_threshold(
+            self.da_committee(epoch)
+                .map(|committee| total_stake::<TYPES::SignatureKey>(&committee.stake_table))
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Get the voting failure threshold for the committee, in terms of stake
+    fn failure_threshold(&self, _epoch: Option<<TYPES as NodeType>::Epoch>) -> NonZeroU64 {
+        failure_threshold(total_stake::<TYPES::SignatureKey>(&self.stake_table))
+    }
+
+    /// Get the voting upgrade threshold for the committee, in terms of stake
+    fn upgrade_threshold(&self, _epoch: Option<<TYPES as NodeType>::Epoch>) -> NonZeroU64 {
+        upgrade_threshold(total_stake::<TYPES::SignatureKey>(&self.stake_table))
+    }
+
+    /// Build the alias table and sample the DA committee for the epoch, dropping the ones for
+    /// epochs whose DRB results are no longer kept
+    fn add_drb_result(&mut self, epoch: TYPES::Epoch, drb_result: DrbResult) {
+        let Some(leaders) = self.epoch_leaders(drb_result) else {
+            return;
+        };
+        let da_committee = self.sample_da_committee(&drb_result);
+        self.epoch_leaders.insert(epoch, leaders);
+        self.epoch_da_committees.insert(epoch, da_committee);
+
+        if let Some(oldest_epoch) = epoch.u64().checked_sub(KEEP_PREVIOUS_RESULT_COUNT) {
+            let oldest_epoch = TYPES::Epoch::new(oldest_epoch);
+            self.epoch_leaders = self.epoch_leaders.split_off(&oldest_epoch);
+            self.epoch_da_committees = self.epoch_da_committees.split_off(&oldest_epoch);
+        }
+    }
+}
@@ -11,6 +11,6 @@ pub use event::{Event, EventType};
 pub use handle::SystemContextHandle;
 pub use hotshot_types::{
     message::Message,
-    signature_key::{BLSPrivKey, BLSPubKey},
+    signature_key::{BLSPrivKey, BLSPubKey, SchnorrPrivKey, SchnorrPubKey},
     traits::signature_key::SignatureKey,
 };
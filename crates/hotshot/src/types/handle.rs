@@ -6,35 +6,63 @@
 
 //! Provides an event-streaming handle for a [`SystemContext`] running in the background
 
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Arc,
+};
 
 use anyhow::{anyhow, Context, Ok, Result};
 use async_broadcast::{InactiveReceiver, Receiver, Sender};
 use async_lock::RwLock;
 use committable::{Commitment, Committable};
-use futures::Stream;
+use futures::{FutureExt, Stream};
 use hotshot_task::{
     dependency::{Dependency, EventDependency},
+    queue::QueueConfig,
+    supervisor::{StateFactory, TaskHealth},
     task::{ConsensusTaskRegistry, NetworkTaskRegistry, Task, TaskState},
 };
-use hotshot_task_impls::{events::HotShotEvent, helpers::broadcast_event};
+use hotshot_task_impls::{
+    events::HotShotEvent, helpers::broadcast_event, light_client::LightClientTaskState,
+    request::REQUEST_TIMEOUT,
+};
 use hotshot_types::{
     consensus::Consensus,
-    data::{Leaf2, QuorumProposalWrapper},
+    data::{vid_disperse::vid_storage_nodes, Leaf2, QuorumProposalWrapper, VidDisperseShare},
     error::HotShotError,
+    light_client::StateKeyPair,
     message::{Message, MessageKind, Proposal, RecipientList},
     request_response::ProposalRequestPayload,
+    signer::{SigningPurpose, SigningRequest},
     traits::{
-        consensus_api::ConsensusApi,
+        block_contents::{BlockHeader, BlockPayload},
         election::Membership,
-        network::{BroadcastDelay, ConnectedNetwork, Topic},
+        network::{BroadcastDelay, ConnectedNetwork, DataRequest, RequestKind, Topic},
         node_implementation::NodeType,
-        signature_key::SignatureKey,
     },
+    vid::{reaches_recovery_threshold, vid_recover},
+    vote::HasViewNumber,
 };
+use rand::{seq::SliceRandom, thread_rng};
+use sha2::{Digest, Sha256};
+use tokio::time::timeout;
 use tracing::instrument;
 
-use crate::{traits::NodeImplementation, types::Event, SystemContext, Versions};
+use crate::{
+    tasks::task_state::CreateTaskState, traits::NodeImplementation, types::Event, SystemContext,
+    Versions,
+};
+
+/// Short, human readable name of a task state type, used to label supervised tasks
+fn task_name<S>() -> &'static str {
+    let full_name = std::any::type_name::<S>();
+    let without_generics = full_name.split('<').next().unwrap_or(full_name);
+
+    without_generics
+        .rsplit("::")
+        .next()
+        .unwrap_or(without_generics)
+}
 
 /// Event streaming handle for a [`SystemContext`] instance running in the background
 ///
@@ -78,6 +106,8 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static, V: Versions>
     SystemContextHandle<TYPES, I, V>
 {
     /// Adds a hotshot consensus-related task to the `SystemContextHandle`.
+    ///
+    /// The task is supervised, so a panic is reported, but it cannot be restarted.
     pub fn add_task<S: TaskState<Event = HotShotEvent<TYPES>> + 'static>(&mut self, task_state: S) {
         let task = Task::new(
             task_state,
@@ -85,7 +115,85 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static, V: Versions>
             self.internal_event_stream.1.activate_cloned(),
         );
 
-        self.consensus_registry.run_task(task);
+        self.consensus_registry
+            .run_supervised_task(task_name::<S>(), task, None);
+    }
+
+    /// Creates a task state from this handle and adds it as a supervised task.
+    ///
+    /// If the task panics it is rebuilt through [`CreateTaskState::create_from`], subject to the
+    /// configured `task_restart_limit`. If `queue` is given, the task gets its own event queue
+    /// and only receives the events it subscribes to.
+    pub async fn add_restartable_task<S>(&mut self, queue: Option<QueueConfig>)
+    where
+        S: TaskState<Event = HotShotEvent<TYPES>> + CreateTaskState<TYPES, I, V> + 'static,
+    {
+        let task = Task::new(
+            S::create_from(self).await,
+            self.internal_event_stream.0.clone(),
+            self.internal_event_stream.1.activate_cloned(),
+        );
+
+        let detached = Arc::new(self.detached());
+        let factory: StateFactory<S> = Arc::new(move || {
+            let handle = Arc::clone(&detached);
+            async move { S::create_from(&handle).await }.boxed()
+        });
+
+        match queue {
+            Some(config) => {
+                self.consensus_registry.run_queued_task(
+                    task_name::<S>(),
+                    task,
+                    Some(factory),
+                    config,
+                );
+            }
+            None => {
+                self.consensus_registry
+                    .run_supervised_task(task_name::<S>(), task, Some(factory));
+            }
+        }
+    }
+
+    /// Starts signing the light client state of every decided leaf with `state_key_pair`.
+    ///
+    /// The signatures are gossiped to the other nodes, and an
+    /// [`EventType::LightClientStateSignatures`](hotshot_types::event::EventType) event is emitted
+    /// once the signers of a state reach the stake threshold. States are only signed if the
+    /// membership provides a [`Membership::light_client_stake_table`].
+    pub fn add_light_client_task(&mut self, state_key_pair: StateKeyPair) {
+        self.add_task(LightClientTaskState {
+            public_key: self.public_key(),
+            state_key_pair,
+            membership: Arc::clone(&self.memberships),
+            output_event_stream: self.output_event_stream.0.clone(),
+            aggregators: BTreeMap::new(),
+            pending_signatures: VecDeque::new(),
+            decided_epoch: None,
+            epoch_height: self.epoch_height,
+            id: self.hotshot.id,
+        });
+    }
+
+    /// A copy of this handle which does not own any tasks, used to rebuild task states.
+    fn detached(&self) -> Self {
+        Self {
+            output_event_stream: self.output_event_stream.clone(),
+            internal_event_stream: self.internal_event_stream.clone(),
+            consensus_registry: ConsensusTaskRegistry::new(),
+            network_registry: NetworkTaskRegistry::new(),
+            hotshot: Arc::clone(&self.hotshot),
+            storage: Arc::clone(&self.storage),
+            network: Arc::clone(&self.network),
+            memberships: Arc::clone(&self.memberships),
+            epoch_height: self.epoch_height,
+        }
+    }
+
+    /// Health of every supervised consensus task, keyed by task name
+    pub async fn task_health(&self) -> BTreeMap<String, TaskHealth> {
+        self.consensus_registry.task_health().await
     }
 
     /// obtains a stream to expose to the user
@@ -136,7 +244,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static, V: Versions>
     ///
     /// # Errors
     /// Errors if signing the request for proposal fails
-    pub fn request_proposal(
+    pub async fn request_proposal(
         &self,
         view: TYPES::View,
         leaf_commitment: Commitment<Leaf2<TYPES>>,
@@ -150,10 +258,15 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static, V: Versions>
         };
 
         // Finally, compute the signature for the payload.
-        let signature = TYPES::SignatureKey::sign(
-            self.private_key(),
-            signed_proposal_request.commit().as_ref(),
-        )?;
+        let signature = self
+            .hotshot
+            .signer
+            .sign(SigningRequest::new(
+                SigningPurpose::Request,
+                *view,
+                signed_proposal_request.commit(),
+            ))
+            .await?;
 
         let mem = Arc::clone(&self.memberships);
         let receiver = self.internal_event_stream.1.activate_cloned();
@@ -204,6 +317,157 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static, V: Versions>
         })
     }
 
+    /// Reconstruct the payload of the leaf proposed in `view` from the VID shares of its storage
+    /// nodes, for when the DA proposal is missing.
+    ///
+    /// Shares held locally are used first, the rest are requested from the other storage nodes
+    /// one at a time until the recovery threshold is reached. Every share is verified against
+    /// the payload commitment in the leaf header before it is used.
+    ///
+    /// # Errors
+    /// Errors if there is no leaf for `view`, or not enough valid shares could be collected
+    pub async fn reconstruct_payload(&self, view: TYPES::View) -> Result<TYPES::BlockPayload> {
+        let (leaf, payload, mut proposals) = {
+            let consensus = self.hotshot.consensus.read().await;
+            let leaf = consensus
+                .validated_state_map()
+                .get(&view)
+                .and_then(|view| view.view_inner.leaf_commitment())
+                .and_then(|commitment| consensus.saved_leaves().get(&commitment))
+                .cloned()
+                .context(format!("No leaf for view {view:?}"))?;
+            let payload = consensus.saved_payloads().get(&view).cloned();
+            let proposals: Vec<_> = consensus
+                .vid_shares()
+                .get(&view)
+                .map(|shares| shares.values().cloned().collect())
+                .unwrap_or_default();
+
+            (leaf, payload, proposals)
+        };
+        if let Some(payload) = payload {
+            return Ok((*payload).clone());
+        }
+
+        let header = leaf.block_header();
+        let commit = header.payload_commitment();
+        let vid_config = &self.hotshot.config.vid_config;
+        let epoch = leaf.epoch(self.epoch_height);
+        let mut recipients: Vec<_> = self
+            .memberships
+            .read()
+            .await
+            .committee_members(view, epoch)
+            .into_iter()
+            .filter(|key| *key != self.hotshot.public_key)
+            .collect();
+        recipients.shuffle(&mut thread_rng());
+
+        let receiver = self.internal_event_stream.1.activate_cloned();
+        let mut collected = Vec::new();
+        let mut common = None;
+        loop {
+            for proposal in proposals.drain(..) {
+                let share = proposal.data;
+                if share.payload_commitment() != commit {
+                    continue;
+                }
+                let storage_nodes = vid_storage_nodes::<TYPES>(
+                    &*self.memberships.read().await,
+                    view,
+                    share.target_epoch(),
+                );
+                if common
+                    .as_ref()
+                    .is_some_and(|(common, _)| common != share.vid_common_ref())
+                    || share.verify_share(&storage_nodes, vid_config).is_err()
+                {
+                    tracing::warn!("Invalid VID share for view {view:?}, ignoring it");
+                    continue;
+                }
+                common.get_or_insert_with(|| (share.vid_common_ref().clone(), storage_nodes.len()));
+                collected.push(share.vid_share_ref().clone());
+            }
+
+            if let Some((common, num_nodes)) = &common {
+                if reaches_recovery_threshold(&collected, common, *num_nodes, vid_config) {
+                    let bytes = vid_recover(&collected, common, &commit, *num_nodes, vid_config)
+                        .context("Failed to recover the payload from VID shares")?;
+                    return Ok(TYPES::BlockPayload::from_bytes(&bytes, header.metadata()));
+                }
+            }
+
+            let recipient = recipients.pop().context(format!(
+                "Not enough VID shares to reconstruct view {view:?}"
+            ))?;
+            if let Some(proposal) = self
+                .request_vid_share(view, recipient, receiver.clone())
+                .await?
+            {
+                proposals.push(proposal);
+            }
+        }
+    }
+
+    /// Request the VID share of `recipient` for `view` from `recipient` itself, waiting up to
+    /// [`REQUEST_TIMEOUT`] for the response.
+    ///
+    /// # Errors
+    /// Errors if signing the request fails
+    async fn request_vid_share(
+        &self,
+        view: TYPES::View,
+        recipient: TYPES::SignatureKey,
+        receiver: Receiver<Arc<HotShotEvent<TYPES>>>,
+    ) -> Result<Option<Proposal<TYPES, VidDisperseShare<TYPES>>>> {
+        let request = RequestKind::Vid(view, recipient.clone());
+        let signature = self
+            .hotshot
+            .signer
+            .sign(SigningRequest::new(
+                SigningPurpose::Request,
+                *view,
+                Sha256::digest(bincode::serialize(&request)?),
+            ))
+            .await?;
+
+        broadcast_event(
+            HotShotEvent::VidRequestSend(
+                DataRequest {
+                    request,
+                    view,
+                    signature,
+                },
+                self.hotshot.public_key.clone(),
+                recipient.clone(),
+            )
+            .into(),
+            &self.internal_event_stream.0,
+        )
+        .await;
+
+        let response = EventDependency::new(
+            receiver,
+            Box::new(move |event: &Arc<HotShotEvent<TYPES>>| {
+                matches!(
+                    event.as_ref(),
+                    HotShotEvent::VidResponseRecv(sender, proposal)
+                        if *sender == recipient
+                            && proposal.data.view_number() == view
+                            && *proposal.data.recipient_key() == recipient
+                )
+            }),
+        )
+        .completed();
+
+        if let std::result::Result::Ok(Some(event)) = timeout(REQUEST_TIMEOUT, response).await {
+            if let HotShotEvent::VidResponseRecv(_, proposal) = event.as_ref() {
+                return Ok(Some(proposal.clone()));
+            }
+        }
+        Ok(None)
+    }
+
     /// HACK so we can know the types when running tests...
     /// there are two cleaner solutions:
     /// - make the stream generic and in nodetypes or nodeimpelmentation
@@ -4,7 +4,7 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
-use std::{net::SocketAddr, time::Duration};
+use std::{net::SocketAddr, path::PathBuf, time::Duration};
 
 use clap::Parser;
 use futures::{Future, FutureExt};
@@ -145,6 +145,17 @@ pub struct ValidatorArgs {
     /// Allows for rejoining the network on a complete state loss
     #[arg(short, long)]
     pub network_config_file: Option<String>,
+    /// A keystore holding the keys of the validator, used instead of keys generated from its
+    /// node index
+    #[arg(long, env = "HOTSHOT_KEYSTORE", requires = "keystore_password_file")]
+    pub keystore: Option<PathBuf>,
+    /// A file containing the password of the keystore
+    #[arg(long, env = "HOTSHOT_KEYSTORE_PASSWORD_FILE")]
+    pub keystore_password_file: Option<PathBuf>,
+    /// A file the highest view the validator signed for is persisted to, so that it doesn't sign
+    /// twice for a view across restarts
+    #[arg(long, env = "HOTSHOT_SIGNING_GUARD")]
+    pub signing_guard: Option<PathBuf>,
 }
 
 /// arguments to run multiple validators
@@ -226,6 +237,9 @@ impl ValidatorArgs {
             network_config_file: multi_args
                 .network_config_file
                 .map(|s| format!("{s}-{node_index}")),
+            keystore: None,
+            keystore_password_file: None,
+            signing_guard: None,
         }
     }
 }
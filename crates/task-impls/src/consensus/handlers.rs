@@ -10,6 +10,7 @@ use async_broadcast::Sender;
 use chrono::Utc;
 use hotshot_types::{
     event::{Event, EventType},
+    signer::SigningPurpose,
     simple_vote::{HasEpoch, QuorumVote2, TimeoutData2, TimeoutVote2},
     traits::{
         election::Membership,
@@ -339,14 +340,14 @@ pub(crate) async fn handle_timeout<TYPES: NodeType, I: NodeImplementation<TYPES>
         )
     );
 
-    let vote = TimeoutVote2::create_signed_vote(
+    let vote = TimeoutVote2::create_signed_vote_with_signer(
         TimeoutData2::<TYPES> {
             view: view_number,
             epoch,
         },
         view_number,
-        &task_state.public_key,
-        &task_state.private_key,
+        &*task_state.signer,
+        SigningPurpose::TimeoutVote,
         &task_state.upgrade_lock,
     )
     .await
@@ -15,12 +15,10 @@ use hotshot_types::{
     consensus::OuterConsensus,
     event::Event,
     message::UpgradeLock,
+    signer::Signer,
     simple_certificate::{NextEpochQuorumCertificate2, QuorumCertificate2, TimeoutCertificate2},
     simple_vote::{NextEpochQuorumVote2, QuorumVote2, TimeoutVote2},
-    traits::{
-        node_implementation::{NodeImplementation, NodeType, Versions},
-        signature_key::SignatureKey,
-    },
+    traits::node_implementation::{NodeImplementation, NodeType, Versions},
     utils::option_epoch_from_block_number,
     vote::HasViewNumber,
 };
@@ -41,8 +39,8 @@ pub struct ConsensusTaskState<TYPES: NodeType, I: NodeImplementation<TYPES>, V:
     /// Our public key
     pub public_key: TYPES::SignatureKey,
 
-    /// Our Private Key
-    pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    /// Signs timeout votes with the key of this node
+    pub signer: Arc<dyn Signer<TYPES::SignatureKey>>,
 
     /// Immutable instance state
     pub instance_state: Arc<TYPES::InstanceState>,
@@ -187,6 +185,17 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TaskState
 {
     type Event = HotShotEvent<TYPES>;
 
+    fn subscribes_to(event: &Self::Event) -> bool {
+        matches!(
+            event,
+            HotShotEvent::QuorumVoteRecv(_)
+                | HotShotEvent::TimeoutVoteRecv(_)
+                | HotShotEvent::ViewChange(..)
+                | HotShotEvent::Timeout(..)
+                | HotShotEvent::Qc2Formed(_)
+        )
+    }
+
     async fn handle_event(
         &mut self,
         event: Arc<Self::Event>,
@@ -15,6 +15,7 @@ use hotshot_types::{
     data::{DaProposal2, PackedBundle},
     event::{Event, EventType},
     message::{Proposal, UpgradeLock},
+    signer::{Signer, SigningPurpose, SigningRequest},
     simple_certificate::DaCertificate2,
     simple_vote::{DaData2, DaVote2, HasEpoch},
     traits::{
@@ -27,10 +28,11 @@ use hotshot_types::{
         BlockPayload, EncodeBytes,
     },
     utils::EpochTransitionIndicator,
+    vid::VidConfig,
     vote::HasViewNumber,
 };
 use sha2::{Digest, Sha256};
-use tokio::{spawn, task::spawn_blocking};
+use tokio::spawn;
 use tracing::instrument;
 use utils::anytrace::*;
 
@@ -68,8 +70,8 @@ pub struct DaTaskState<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Version
     /// This Nodes public key
     pub public_key: TYPES::SignatureKey,
 
-    /// This Nodes private key
-    pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    /// Signs votes, proposals and VID shares with the key of this node
+    pub signer: Arc<dyn Signer<TYPES::SignatureKey>>,
 
     /// This state's ID
     pub id: u64,
@@ -79,6 +81,9 @@ pub struct DaTaskState<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Version
 
     /// Lock for a decided upgrade
     pub upgrade_lock: UpgradeLock<TYPES, V>,
+
+    /// VID parameters of the network
+    pub vid_config: VidConfig,
 }
 
 impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> DaTaskState<TYPES, I, V> {
@@ -182,9 +187,17 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> DaTaskState<TYP
                 let version = self.upgrade_lock.version_infallible(view_number).await;
 
                 let txns = Arc::clone(&proposal.data.encoded_transactions);
-                let payload_commitment =
-                    spawn_blocking(move || vid_commitment::<V>(&txns, num_nodes, version)).await;
-                let payload_commitment = payload_commitment.unwrap();
+                let vid_pool = self.consensus.read().await.vid_pool.clone();
+                let vid_config = self.vid_config;
+                let payload_commitment = vid_pool
+                    .encode(Some(*view_number), txns.len(), move || {
+                        vid_commitment::<V>(&txns, num_nodes, version, &vid_config)
+                    })
+                    .await
+                    .wrap()
+                    .context(|e| {
+                        warn!("Failed to compute the DA proposal's VID commitment: {e}")
+                    })?;
 
                 self.storage
                     .write()
@@ -194,14 +207,14 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> DaTaskState<TYP
                     .wrap()
                     .context(error!("Failed to append DA proposal to storage"))?;
                 // Generate and send vote
-                let vote = DaVote2::create_signed_vote(
+                let vote = DaVote2::create_signed_vote_with_signer(
                     DaData2 {
                         payload_commit: payload_commitment,
                         epoch: epoch_number,
                     },
                     view_number,
-                    &self.public_key,
-                    &self.private_key,
+                    &*self.signer,
+                    SigningPurpose::DaVote,
                     &self.upgrade_lock,
                 )
                 .await?;
@@ -234,10 +247,11 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> DaTaskState<TYP
                     let consensus =
                         OuterConsensus::new(Arc::clone(&self.consensus.inner_consensus));
                     let membership = Arc::clone(&self.membership);
-                    let pk = self.private_key.clone();
+                    let signer = Arc::clone(&self.signer);
                     let public_key = self.public_key.clone();
                     let chan = event_stream.clone();
                     let upgrade_lock = self.upgrade_lock.clone();
+                    let vid_config = self.vid_config;
                     let proposal_epoch = proposal.data.epoch();
                     let next_epoch = proposal_epoch.map(|epoch| epoch + 1);
 
@@ -257,8 +271,10 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> DaTaskState<TYP
                             view_number,
                             target_epoch,
                             membership,
-                            &pk,
+                            &*signer,
+                            SigningPurpose::VidDisperse,
                             &upgrade_lock,
+                            &vid_config,
                         )
                         .await;
                         if let Some(Some(vid_share)) = consensus
@@ -340,9 +356,14 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> DaTaskState<TYP
                 let encoded_transactions_hash = Sha256::digest(encoded_transactions);
 
                 // sign the encoded transactions as opposed to the VID commitment
-                let signature =
-                    TYPES::SignatureKey::sign(&self.private_key, &encoded_transactions_hash)
-                        .wrap()?;
+                let signature = self
+                    .signer
+                    .sign(SigningRequest::new(
+                        SigningPurpose::DaProposal,
+                        *view_number,
+                        encoded_transactions_hash,
+                    ))
+                    .await?;
 
                 let epoch = self.cur_epoch;
                 let leader = self.membership.read().await.leader(view_number, epoch)?;
@@ -401,6 +422,17 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TaskState
 {
     type Event = HotShotEvent<TYPES>;
 
+    fn subscribes_to(event: &Self::Event) -> bool {
+        matches!(
+            event,
+            HotShotEvent::DaProposalRecv(..)
+                | HotShotEvent::DaProposalValidated(..)
+                | HotShotEvent::DaVoteRecv(_)
+                | HotShotEvent::ViewChange(..)
+                | HotShotEvent::BlockRecv(_)
+        )
+    }
+
     async fn handle_event(
         &mut self,
         event: Arc<Self::Event>,
@@ -14,8 +14,9 @@ use hotshot_types::{
         DaProposal2, Leaf2, PackedBundle, QuorumProposal2, QuorumProposalWrapper, UpgradeProposal,
         VidDisperse, VidDisperseShare,
     },
+    light_client::StateSignatureRequestBody,
     message::Proposal,
-    request_response::ProposalRequestPayload,
+    request_response::{DrbResultRequestPayload, DrbResultResponse, ProposalRequestPayload},
     simple_certificate::{
         DaCertificate2, NextEpochQuorumCertificate2, QuorumCertificate, QuorumCertificate2,
         TimeoutCertificate, TimeoutCertificate2, UpgradeCertificate, ViewSyncCommitCertificate2,
@@ -26,13 +27,17 @@ use hotshot_types::{
         ViewSyncFinalizeVote2, ViewSyncPreCommitVote2,
     },
     traits::{
-        block_contents::BuilderFee, network::DataRequest, node_implementation::NodeType,
-        signature_key::SignatureKey, BlockPayload,
+        block_contents::BuilderFee,
+        network::DataRequest,
+        node_implementation::{ConsensusTime, NodeType},
+        signature_key::SignatureKey,
+        BlockPayload,
     },
     utils::BuilderCommitment,
     vid::VidCommitment,
     vote::HasViewNumber,
 };
+use serde::{Deserialize, Serialize};
 use vec1::Vec1;
 
 use crate::view_sync::ViewSyncPhase;
@@ -66,7 +71,8 @@ impl<TYPES: NodeType> Eq for ProposalMissing<TYPES> {}
 pub struct HotShotTaskCompleted;
 
 /// All of the possible events that can be passed between Sequencing `HotShot` tasks
-#[derive(Eq, PartialEq, Debug, Clone)]
+#[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "TYPES::AuctionResult: Serialize", deserialize = ""))]
 #[allow(clippy::large_enum_variant)]
 pub enum HotShotEvent<TYPES: NodeType> {
     /// Shutdown the task
@@ -125,6 +131,27 @@ pub enum HotShotEvent<TYPES: NodeType> {
     ),
     /// A quorum proposal was requested by a node for a view.
     QuorumProposalResponseRecv(Proposal<TYPES, QuorumProposalWrapper<TYPES>>),
+    /// The DRB result for an epoch is missing; request it from the network.
+    DrbResultRequestSend(
+        DrbResultRequestPayload<TYPES>,
+        <TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType,
+    ),
+    /// The DRB result for an epoch was requested by a node.
+    DrbResultRequestRecv(
+        DrbResultRequestPayload<TYPES>,
+        <TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType,
+    ),
+    /// Reply to a DRB result request, sent directly to the requester with their key.
+    DrbResultResponseSend(TYPES::SignatureKey, DrbResultResponse<TYPES>),
+    /// A DRB result was received in response to a request, not yet verified.
+    DrbResultResponseRecv(DrbResultResponse<TYPES>),
+    /// Leaves were decided, oldest first; emitted by the quorum vote task after the `Decide` event
+    LeavesDecided(Vec<Leaf2<TYPES>>),
+    /// Gossip our signature over the light client state of a decided leaf, along with our key;
+    /// emitted by the light client task
+    LightClientStateSignatureSend(StateSignatureRequestBody, TYPES::SignatureKey),
+    /// A signature over the light client state of a decided leaf was received, not yet verified
+    LightClientStateSignatureRecv(StateSignatureRequestBody),
     /// Send a DA proposal to the DA committee; emitted by the DA leader (which is the same node as the leader of view v + 1) in the DA task
     DaProposalSend(Proposal<TYPES, DaProposal2<TYPES>>, TYPES::SignatureKey),
     /// Send a DA vote to the DA leader; emitted by DA committee members in the DA task after seeing a valid DA proposal
@@ -328,6 +355,15 @@ impl<TYPES: NodeType> HotShotEvent<TYPES> {
             }
             HotShotEvent::QuorumProposalRequestSend(req, _)
             | HotShotEvent::QuorumProposalRequestRecv(req, _) => Some(req.view_number),
+            HotShotEvent::DrbResultRequestSend(req, _)
+            | HotShotEvent::DrbResultRequestRecv(req, _) => Some(req.view_number),
+            HotShotEvent::DrbResultResponseSend(_, response)
+            | HotShotEvent::DrbResultResponseRecv(response) => Some(response.view_number),
+            HotShotEvent::LeavesDecided(leaves) => leaves.last().map(Leaf2::view_number),
+            HotShotEvent::LightClientStateSignatureSend(body, _)
+            | HotShotEvent::LightClientStateSignatureRecv(body) => {
+                Some(TYPES::View::new(body.state.view_number as u64))
+            }
             HotShotEvent::ViewChange(view_number, _)
             | HotShotEvent::ViewSyncTimeout(view_number, _, _)
             | HotShotEvent::ViewSyncTrigger(view_number)
@@ -346,6 +382,46 @@ impl<TYPES: NodeType> HotShotEvent<TYPES> {
             }
         }
     }
+
+    /// Whether the event enters the task graph from outside of it: a message from the network,
+    /// a block from the builder or a timer firing, as opposed to an event produced by one task
+    /// for another.
+    ///
+    /// Given the same external inputs in the same order, a node's tasks are expected to produce
+    /// the same remaining events, which is what replaying a recording relies on.
+    #[must_use]
+    pub fn is_external_input(&self) -> bool {
+        matches!(
+            self,
+            HotShotEvent::QuorumProposalRecv(..)
+                | HotShotEvent::QuorumVoteRecv(_)
+                | HotShotEvent::TimeoutVoteRecv(_)
+                | HotShotEvent::DaProposalRecv(..)
+                | HotShotEvent::DaVoteRecv(_)
+                | HotShotEvent::DaCertificateRecv(_)
+                | HotShotEvent::QuorumProposalRequestRecv(..)
+                | HotShotEvent::QuorumProposalResponseRecv(_)
+                | HotShotEvent::DrbResultRequestRecv(..)
+                | HotShotEvent::DrbResultResponseRecv(_)
+                | HotShotEvent::LightClientStateSignatureRecv(_)
+                | HotShotEvent::ViewSyncPreCommitVoteRecv(_)
+                | HotShotEvent::ViewSyncCommitVoteRecv(_)
+                | HotShotEvent::ViewSyncFinalizeVoteRecv(_)
+                | HotShotEvent::ViewSyncPreCommitCertificateRecv(_)
+                | HotShotEvent::ViewSyncCommitCertificateRecv(_)
+                | HotShotEvent::ViewSyncFinalizeCertificateRecv(_)
+                | HotShotEvent::TransactionsRecv(_)
+                | HotShotEvent::BlockRecv(_)
+                | HotShotEvent::VidShareRecv(..)
+                | HotShotEvent::UpgradeProposalRecv(..)
+                | HotShotEvent::UpgradeVoteRecv(_)
+                | HotShotEvent::VidRequestRecv(..)
+                | HotShotEvent::VidResponseRecv(..)
+                | HotShotEvent::HighQcRecv(..)
+                | HotShotEvent::Timeout(..)
+                | HotShotEvent::ViewSyncTimeout(..)
+        )
+    }
 }
 
 impl<TYPES: NodeType> Display for HotShotEvent<TYPES> {
@@ -589,6 +665,33 @@ impl<TYPES: NodeType> Display for HotShotEvent<TYPES> {
                     proposal.data.view_number()
                 )
             }
+            HotShotEvent::DrbResultRequestSend(req, _) => {
+                write!(f, "DrbResultRequestSend(epoch={:?})", req.epoch)
+            }
+            HotShotEvent::DrbResultRequestRecv(req, _) => {
+                write!(f, "DrbResultRequestRecv(epoch={:?})", req.epoch)
+            }
+            HotShotEvent::DrbResultResponseSend(_, response) => {
+                write!(f, "DrbResultResponseSend(epoch={:?})", response.epoch)
+            }
+            HotShotEvent::DrbResultResponseRecv(response) => {
+                write!(f, "DrbResultResponseRecv(epoch={:?})", response.epoch)
+            }
+            HotShotEvent::LeavesDecided(leaves) => write!(
+                f,
+                "LeavesDecided(view_number={:?})",
+                leaves.last().map(Leaf2::view_number)
+            ),
+            HotShotEvent::LightClientStateSignatureSend(body, _) => write!(
+                f,
+                "LightClientStateSignatureSend(view_number={:?})",
+                body.state.view_number
+            ),
+            HotShotEvent::LightClientStateSignatureRecv(body) => write!(
+                f,
+                "LightClientStateSignatureRecv(view_number={:?})",
+                body.state.view_number
+            ),
             HotShotEvent::QuorumProposalPreliminarilyValidated(proposal) => {
                 write!(
                     f,
@@ -19,13 +19,13 @@ use hotshot_types::{
     event::{Event, EventType, LeafInfo},
     message::{Proposal, UpgradeLock},
     request_response::ProposalRequestPayload,
+    signer::{Signer, SigningPurpose, SigningRequest},
     simple_certificate::{QuorumCertificate2, UpgradeCertificate},
     simple_vote::HasEpoch,
     traits::{
         block_contents::BlockHeader,
         election::Membership,
         node_implementation::{ConsensusTime, NodeImplementation, NodeType, Versions},
-        signature_key::SignatureKey,
         BlockPayload, ValidatedState,
     },
     utils::{
@@ -50,7 +50,7 @@ pub(crate) async fn fetch_proposal<TYPES: NodeType, V: Versions>(
     membership: Arc<RwLock<TYPES::Membership>>,
     consensus: OuterConsensus<TYPES>,
     sender_public_key: TYPES::SignatureKey,
-    sender_private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    signer: Arc<dyn Signer<TYPES::SignatureKey>>,
     upgrade_lock: &UpgradeLock<TYPES, V>,
     epoch_height: u64,
 ) -> Result<(Leaf2<TYPES>, View<TYPES>)> {
@@ -62,12 +62,14 @@ pub(crate) async fn fetch_proposal<TYPES: NodeType, V: Versions>(
     };
 
     // Finally, compute the signature for the payload.
-    let signature = TYPES::SignatureKey::sign(
-        &sender_private_key,
-        signed_proposal_request.commit().as_ref(),
-    )
-    .wrap()
-    .context(error!("Failed to sign proposal. This should never happen."))?;
+    let signature = signer
+        .sign(SigningRequest::new(
+            SigningPurpose::Request,
+            *view_number,
+            signed_proposal_request.commit(),
+        ))
+        .await
+        .context(error!("Failed to sign proposal request"))?;
 
     // First, broadcast that we need a proposal to the current leader
     broadcast_event(
@@ -510,7 +512,7 @@ pub(crate) async fn parent_leaf_and_state<TYPES: NodeType, V: Versions>(
     event_receiver: &Receiver<Arc<HotShotEvent<TYPES>>>,
     membership: Arc<RwLock<TYPES::Membership>>,
     public_key: TYPES::SignatureKey,
-    private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    signer: Arc<dyn Signer<TYPES::SignatureKey>>,
     consensus: OuterConsensus<TYPES>,
     upgrade_lock: &UpgradeLock<TYPES, V>,
     parent_view_number: TYPES::View,
@@ -530,7 +532,7 @@ pub(crate) async fn parent_leaf_and_state<TYPES: NodeType, V: Versions>(
             membership,
             consensus.clone(),
             public_key.clone(),
-            private_key.clone(),
+            signer,
             upgrade_lock,
             epoch_height,
         )
@@ -61,3 +61,6 @@ pub mod quorum_proposal_recv;
 
 /// Task for storing and replaying all received tasks by a node
 pub mod rewind;
+
+/// Task for signing and aggregating the light client state of decided leaves
+pub mod light_client;
@@ -0,0 +1,340 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Arc,
+};
+
+use async_broadcast::{Receiver, Sender};
+use async_lock::RwLock;
+use async_trait::async_trait;
+use hotshot_task::task::TaskState;
+use hotshot_types::{
+    data::Leaf2,
+    event::{Event, EventType},
+    light_client::{
+        verify_state_signature, LightClientStakeTable, LightClientState, StateKeyPair,
+        StateSignatureRequestBody, StateSignaturesBundle,
+    },
+    traits::{
+        block_contents::BlockHeader,
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+    },
+};
+use utils::anytrace::*;
+
+use crate::{events::HotShotEvent, helpers::broadcast_event};
+
+/// Number of decided states whose signatures are collected at once, older ones are dropped
+const MAX_AGGREGATED_STATES: usize = 32;
+
+/// Number of verified signatures kept for states which aren't decided yet, older ones are dropped
+const MAX_PENDING_SIGNATURES: usize = 1024;
+
+/// Collects the signatures over a light client state until enough stake signed it
+#[derive(Clone, Debug)]
+pub struct StateSignatureAggregator {
+    /// The stake table the state is signed against
+    stake_table: LightClientStakeTable,
+
+    /// The state and the signatures collected so far
+    bundle: StateSignaturesBundle,
+}
+
+impl StateSignatureAggregator {
+    /// Start collecting signatures over `state`, certified against `stake_table`
+    #[must_use]
+    pub fn new(state: LightClientState, stake_table: LightClientStakeTable) -> Self {
+        Self {
+            stake_table,
+            bundle: StateSignaturesBundle {
+                state,
+                signatures: Default::default(),
+                accumulated_weight: Default::default(),
+            },
+        }
+    }
+
+    /// The stake table the state is signed against
+    #[must_use]
+    pub fn stake_table(&self) -> &LightClientStakeTable {
+        &self.stake_table
+    }
+
+    /// The state and the signatures collected so far
+    #[must_use]
+    pub fn bundle(&self) -> &StateSignaturesBundle {
+        &self.bundle
+    }
+
+    /// Whether the signers of the state reached the stake threshold
+    #[must_use]
+    pub fn is_certified(&self) -> bool {
+        self.bundle.accumulated_weight >= self.stake_table.threshold
+    }
+
+    /// Add a signature over the state, returning whether the state got certified with it.
+    ///
+    /// Signatures from keys which already signed are ignored.
+    ///
+    /// # Errors
+    /// If the signature is over another state, from a key without stake, or invalid
+    pub fn add_signature(&mut self, body: &StateSignatureRequestBody) -> Result<bool> {
+        ensure!(
+            body.state == self.bundle.state,
+            warn!(
+                "Signature is over state {:?}, expected {:?}",
+                body.state, self.bundle.state
+            )
+        );
+
+        let stake = self.stake_table.stake(&body.key);
+        ensure!(
+            !stake.is_zero(),
+            warn!("State key {:?} has no stake", body.key)
+        );
+
+        if self.bundle.signatures.contains_key(&body.key) {
+            return Ok(false);
+        }
+
+        ensure!(
+            verify_state_signature(
+                &body.key,
+                &body.state,
+                &self.stake_table.state,
+                &body.signature
+            ),
+            warn!("Invalid signature from state key {:?}", body.key)
+        );
+
+        let was_certified = self.is_certified();
+        self.bundle
+            .signatures
+            .insert(body.key.clone(), body.signature.clone());
+        self.bundle.accumulated_weight = self.bundle.accumulated_weight.saturating_add(stake);
+
+        Ok(!was_certified && self.is_certified())
+    }
+}
+
+/// Signs the light client state of decided leaves with the node's state key, gossips the
+/// signature, and aggregates the signatures of the other nodes until enough stake signed a state
+pub struct LightClientTaskState<TYPES: NodeType> {
+    /// Our public key
+    pub public_key: TYPES::SignatureKey,
+
+    /// The key we sign light client states with
+    pub state_key_pair: StateKeyPair,
+
+    /// Membership providing the stake table states are signed against
+    pub membership: Arc<RwLock<TYPES::Membership>>,
+
+    /// Output events to the application
+    pub output_event_stream: Sender<Event<TYPES>>,
+
+    /// Signatures collected for the states of the latest decided leaves
+    pub aggregators: BTreeMap<LightClientState, StateSignatureAggregator>,
+
+    /// Verified signatures received for states we haven't decided yet, oldest first
+    pub pending_signatures: VecDeque<StateSignatureRequestBody>,
+
+    /// The epoch of the newest decided leaf
+    pub decided_epoch: Option<TYPES::Epoch>,
+
+    /// Number of blocks in an epoch, zero means there are no epochs
+    pub epoch_height: u64,
+
+    /// This node's id
+    pub id: u64,
+}
+
+impl<TYPES: NodeType> LightClientTaskState<TYPES> {
+    /// The light client state of `leaf`
+    #[allow(clippy::cast_possible_truncation)]
+    fn light_client_state(leaf: &Leaf2<TYPES>) -> LightClientState {
+        LightClientState {
+            view_number: leaf.view_number().u64() as usize,
+            block_height: leaf.height() as usize,
+            block_comm_root: leaf.block_header().block_comm_root(),
+        }
+    }
+
+    /// Start collecting signatures for the states of `leaves` and sign them. Every decided state
+    /// is signed, since nodes may decide the same leaves in different batches.
+    async fn handle_leaves_decided(
+        &mut self,
+        leaves: &[Leaf2<TYPES>],
+        sender: &Sender<Arc<HotShotEvent<TYPES>>>,
+    ) {
+        let Some(newest_leaf) = leaves.last() else {
+            return;
+        };
+        self.decided_epoch = newest_leaf.epoch(self.epoch_height);
+        let key = self.state_key_pair.ver_key();
+
+        let membership_reader = self.membership.read().await;
+        let stake_tables: Vec<_> = leaves
+            .iter()
+            .map(|leaf| membership_reader.light_client_stake_table(leaf.epoch(self.epoch_height)))
+            .collect();
+        drop(membership_reader);
+
+        for (leaf, stake_table) in leaves.iter().zip(stake_tables) {
+            let Some(stake_table) = stake_table else {
+                tracing::debug!(
+                    "No light client stake table for the leaf of view {:?}",
+                    leaf.view_number()
+                );
+                continue;
+            };
+
+            let state = Self::light_client_state(leaf);
+            let signature = (!stake_table.stake(&key).is_zero())
+                .then(|| self.state_key_pair.sign_state(&state, &stake_table.state));
+            self.aggregators
+                .entry(state.clone())
+                .or_insert_with(|| StateSignatureAggregator::new(state.clone(), stake_table));
+
+            if let Some(signature) = signature {
+                let body = StateSignatureRequestBody {
+                    key: key.clone(),
+                    state,
+                    signature,
+                };
+
+                broadcast_event(
+                    Arc::new(HotShotEvent::LightClientStateSignatureSend(
+                        body.clone(),
+                        self.public_key.clone(),
+                    )),
+                    sender,
+                )
+                .await;
+                self.handle_signature(body).await;
+            }
+        }
+
+        while self.aggregators.len() > MAX_AGGREGATED_STATES {
+            self.aggregators.pop_first();
+        }
+
+        // Signatures for states newer than the ones we decided may still become useful, the rest
+        // is for states we'll never decide.
+        let newest_view = Self::light_client_state(newest_leaf).view_number;
+        let pending = std::mem::take(&mut self.pending_signatures);
+        for body in pending {
+            if self.aggregators.contains_key(&body.state) {
+                self.handle_signature(body).await;
+            } else if body.state.view_number > newest_view {
+                self.pending_signatures.push_back(body);
+            }
+        }
+    }
+
+    /// Add a signature to the aggregation of its state, emitting the bundle once the state gets
+    /// certified
+    async fn handle_signature(&mut self, body: StateSignatureRequestBody) {
+        let Some(aggregator) = self.aggregators.get_mut(&body.state) else {
+            self.buffer_signature(body).await;
+            return;
+        };
+
+        match aggregator.add_signature(&body) {
+            Ok(true) => {
+                tracing::debug!(
+                    "Light client state of view {} certified",
+                    body.state.view_number
+                );
+                broadcast_event(
+                    Event {
+                        view_number: TYPES::View::new(body.state.view_number as u64),
+                        event: EventType::LightClientStateSignatures {
+                            bundle: aggregator.bundle().clone(),
+                        },
+                    },
+                    &self.output_event_stream,
+                )
+                .await;
+            }
+            Ok(false) => {}
+            Err(e) => tracing::debug!("Dropping light client state signature: {}", e),
+        }
+    }
+
+    /// Keep a signature over a state we haven't decided yet, if it is valid against a stake table
+    /// the state may be certified with: the one of the newest decided epoch or of the next one.
+    /// Unverified signatures could be forged by anyone to evict the ones we'll need.
+    async fn buffer_signature(&mut self, body: StateSignatureRequestBody) {
+        if self.pending_signatures.contains(&body) {
+            return;
+        }
+
+        let membership_reader = self.membership.read().await;
+        let stake_tables = [
+            membership_reader.light_client_stake_table(self.decided_epoch),
+            membership_reader.light_client_stake_table(self.decided_epoch.map(|epoch| epoch + 1)),
+        ];
+        drop(membership_reader);
+
+        let valid = stake_tables.iter().flatten().any(|stake_table| {
+            !stake_table.stake(&body.key).is_zero()
+                && verify_state_signature(
+                    &body.key,
+                    &body.state,
+                    &stake_table.state,
+                    &body.signature,
+                )
+        });
+        if !valid {
+            tracing::debug!(
+                "Dropping unverifiable light client state signature from state key {:?}",
+                body.key
+            );
+            return;
+        }
+
+        if self.pending_signatures.len() >= MAX_PENDING_SIGNATURES {
+            self.pending_signatures.pop_front();
+        }
+        self.pending_signatures.push_back(body);
+    }
+}
+
+#[async_trait]
+impl<TYPES: NodeType> TaskState for LightClientTaskState<TYPES> {
+    type Event = HotShotEvent<TYPES>;
+
+    fn subscribes_to(event: &Self::Event) -> bool {
+        matches!(
+            event,
+            HotShotEvent::LeavesDecided(_) | HotShotEvent::LightClientStateSignatureRecv(_)
+        )
+    }
+
+    async fn handle_event(
+        &mut self,
+        event: Arc<Self::Event>,
+        sender: &Sender<Arc<Self::Event>>,
+        _receiver: &Receiver<Arc<Self::Event>>,
+    ) -> Result<()> {
+        match event.as_ref() {
+            HotShotEvent::LeavesDecided(leaves) => {
+                self.handle_leaves_decided(leaves, sender).await;
+            }
+            HotShotEvent::LightClientStateSignatureRecv(body) => {
+                self.handle_signature(body.clone()).await;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn cancel_subtasks(&mut self) {}
+}
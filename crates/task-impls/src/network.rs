@@ -308,6 +308,15 @@ impl<TYPES: NodeType, V: Versions> NetworkMessageTaskState<TYPES, V> {
                             HotShotEvent::UpgradeVoteRecv(message)
                         }
                         GeneralConsensusMessage::HighQc(qc) => HotShotEvent::HighQcRecv(qc, sender),
+                        GeneralConsensusMessage::DrbResultRequested(req, sig) => {
+                            HotShotEvent::DrbResultRequestRecv(req, sig)
+                        }
+                        GeneralConsensusMessage::DrbResultResponse(response) => {
+                            HotShotEvent::DrbResultResponseRecv(response)
+                        }
+                        GeneralConsensusMessage::LightClientStateSignature(body) => {
+                            HotShotEvent::LightClientStateSignatureRecv(body)
+                        }
                     },
                     SequencingMessage::Da(da_message) => match da_message {
                         DaConsensusMessage::DaProposal(proposal) => {
@@ -792,6 +801,27 @@ impl<
                     TransmitType::Direct(sender_key),
                 ))
             }
+            HotShotEvent::DrbResultRequestSend(req, signature) => Some((
+                req.key.clone(),
+                MessageKind::<TYPES>::from_consensus_message(SequencingMessage::General(
+                    GeneralConsensusMessage::DrbResultRequested(req.clone(), signature),
+                )),
+                TransmitType::Broadcast,
+            )),
+            HotShotEvent::DrbResultResponseSend(sender_key, response) => Some((
+                sender_key.clone(),
+                MessageKind::<TYPES>::from_consensus_message(SequencingMessage::General(
+                    GeneralConsensusMessage::DrbResultResponse(response),
+                )),
+                TransmitType::Direct(sender_key),
+            )),
+            HotShotEvent::LightClientStateSignatureSend(body, sender) => Some((
+                sender,
+                MessageKind::<TYPES>::from_consensus_message(SequencingMessage::General(
+                    GeneralConsensusMessage::LightClientStateSignature(body),
+                )),
+                TransmitType::Broadcast,
+            )),
             HotShotEvent::VidDisperseSend(proposal, sender) => {
                 self.handle_vid_disperse_proposal(proposal, &sender).await;
                 None
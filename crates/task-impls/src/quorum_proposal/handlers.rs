@@ -26,11 +26,9 @@ use hotshot_types::{
     consensus::{CommitmentAndMetadata, OuterConsensus},
     data::{Leaf2, QuorumProposal2, QuorumProposalWrapper, VidDisperse, ViewChangeEvidence2},
     message::Proposal,
+    signer::{Signer, SigningPurpose, SigningRequest},
     simple_certificate::{NextEpochQuorumCertificate2, QuorumCertificate2, UpgradeCertificate},
-    traits::{
-        block_contents::BlockHeader, election::Membership, node_implementation::NodeType,
-        signature_key::SignatureKey,
-    },
+    traits::{block_contents::BlockHeader, election::Membership, node_implementation::NodeType},
     utils::{is_last_block_in_epoch, option_epoch_from_block_number},
     vote::{Certificate, HasViewNumber},
 };
@@ -89,8 +87,8 @@ pub struct ProposalDependencyHandle<TYPES: NodeType, V: Versions> {
     /// Our public key
     pub public_key: TYPES::SignatureKey,
 
-    /// Our Private Key
-    pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    /// Signs proposals and requests with the key of this node
+    pub signer: Arc<dyn Signer<TYPES::SignatureKey>>,
 
     /// Shared consensus task state
     pub consensus: OuterConsensus<TYPES>,
@@ -274,7 +272,7 @@ impl<TYPES: NodeType, V: Versions> ProposalDependencyHandle<TYPES, V> {
             &self.receiver,
             Arc::clone(&self.membership),
             self.public_key.clone(),
-            self.private_key.clone(),
+            Arc::clone(&self.signer),
             OuterConsensus::new(Arc::clone(&self.consensus.inner_consensus)),
             &self.upgrade_lock,
             parent_qc.view_number(),
@@ -434,10 +432,15 @@ impl<TYPES: NodeType, V: Versions> ProposalDependencyHandle<TYPES, V> {
             "Proposed leaf parent does not equal high qc"
         );
 
-        let signature =
-            TYPES::SignatureKey::sign(&self.private_key, proposed_leaf.commit().as_ref())
-                .wrap()
-                .context(error!("Failed to compute proposed_leaf.commit()"))?;
+        let signature = self
+            .signer
+            .sign(SigningRequest::new(
+                SigningPurpose::QuorumProposal,
+                *proposed_leaf.view_number(),
+                proposed_leaf.commit(),
+            ))
+            .await
+            .context(error!("Failed to sign the proposed leaf"))?;
 
         let message = Proposal {
             data: proposal,
@@ -4,31 +4,33 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
-use std::{collections::BTreeMap, sync::Arc, time::Instant};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use async_broadcast::{Receiver, Sender};
 use async_lock::RwLock;
 use async_trait::async_trait;
 use either::Either;
 use hotshot_task::{
-    dependency::{AndDependency, EventDependency, OrDependency},
-    dependency_task::DependencyTask,
+    dependency::{AndDependency, Dependency, EventDependency, OrDependency},
+    dependency_task::{DependencyTask, DependencyTasks},
     task::TaskState,
 };
 use hotshot_types::{
     consensus::OuterConsensus,
     message::UpgradeLock,
+    signer::Signer,
     simple_certificate::{QuorumCertificate2, UpgradeCertificate},
     traits::{
         election::Membership,
         node_implementation::{ConsensusTime, NodeImplementation, NodeType, Versions},
-        signature_key::SignatureKey,
         storage::Storage,
     },
     utils::EpochTransitionIndicator,
     vote::{Certificate, HasViewNumber},
 };
-use tokio::task::JoinHandle;
 use tracing::instrument;
 use utils::anytrace::*;
 
@@ -46,7 +48,7 @@ pub struct QuorumProposalTaskState<TYPES: NodeType, I: NodeImplementation<TYPES>
     pub cur_epoch: Option<TYPES::Epoch>,
 
     /// Table for the in-progress proposal dependency tasks.
-    pub proposal_dependencies: BTreeMap<TYPES::View, JoinHandle<()>>,
+    pub proposal_dependencies: DependencyTasks<TYPES::View>,
 
     /// Immutable instance state
     pub instance_state: Arc<TYPES::InstanceState>,
@@ -57,8 +59,8 @@ pub struct QuorumProposalTaskState<TYPES: NodeType, I: NodeImplementation<TYPES>
     /// Our public key
     pub public_key: TYPES::SignatureKey,
 
-    /// Our Private Key
-    pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    /// Signs proposals and requests with the key of this node
+    pub signer: Arc<dyn Signer<TYPES::SignatureKey>>,
 
     /// View timeout from config.
     pub timeout: u64,
@@ -169,6 +171,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>
                 valid
             }),
         )
+        .with_name(format!("{dependency_type:?}"))
     }
 
     /// Creates the requisite dependencies for the Quorum Proposal task. It also handles any event forwarding.
@@ -312,12 +315,15 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>
         );
 
         ensure!(
-            !self.proposal_dependencies.contains_key(&view_number),
+            !self.proposal_dependencies.contains(&view_number),
             "Task already exists"
         );
 
-        let dependency_chain =
-            self.create_and_complete_dependencies(view_number, &event_receiver, event);
+        // Events for a view can arrive while the node is still in the previous one, so give up on
+        // the dependencies once both views would have timed out
+        let dependency_chain = self
+            .create_and_complete_dependencies(view_number, &event_receiver, event)
+            .with_timeout(Duration::from_millis(self.timeout.saturating_mul(2)));
 
         let dependency_task = DependencyTask::new(
             dependency_chain,
@@ -328,7 +334,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>
                 receiver: event_receiver,
                 membership: Arc::clone(&self.membership),
                 public_key: self.public_key.clone(),
-                private_key: self.private_key.clone(),
+                signer: Arc::clone(&self.signer),
                 instance_state: Arc::clone(&self.instance_state),
                 consensus: OuterConsensus::new(Arc::clone(&self.consensus.inner_consensus)),
                 timeout: self.timeout,
@@ -341,7 +347,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>
             },
         );
         self.proposal_dependencies
-            .insert(view_number, dependency_task.run());
+            .spawn(view_number, dependency_task);
 
         Ok(())
     }
@@ -358,10 +364,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>
 
             // Cancel the old dependency tasks.
             for view in (*self.latest_proposed_view + 1)..=(*new_view) {
-                if let Some(dependency) = self.proposal_dependencies.remove(&TYPES::View::new(view))
-                {
-                    dependency.abort();
-                }
+                self.proposal_dependencies.cancel(&TYPES::View::new(view));
             }
 
             self.latest_proposed_view = new_view;
@@ -549,6 +552,10 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>
                 self.cancel_tasks(keep_view);
             }
             HotShotEvent::Timeout(view, ..) => {
+                tracing::debug!(
+                    "Timed out in view {view:?} with pending proposal dependencies:\n{}",
+                    self.proposal_dependencies.dump()
+                );
                 let keep_view = TYPES::View::new(view.saturating_sub(1));
                 self.cancel_tasks(keep_view);
             }
@@ -605,11 +612,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>
 
     /// Cancel all tasks the consensus tasks has spawned before the given view
     pub fn cancel_tasks(&mut self, view: TYPES::View) {
-        let keep = self.proposal_dependencies.split_off(&view);
-        while let Some((_, task)) = self.proposal_dependencies.pop_first() {
-            task.abort();
-        }
-        self.proposal_dependencies = keep;
+        self.proposal_dependencies.cancel_before(&view);
     }
 }
 
@@ -629,8 +632,6 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TaskState
     }
 
     fn cancel_subtasks(&mut self) {
-        while let Some((_, handle)) = self.proposal_dependencies.pop_first() {
-            handle.abort();
-        }
+        self.proposal_dependencies.cancel_all();
     }
 }
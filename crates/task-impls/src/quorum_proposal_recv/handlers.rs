@@ -15,13 +15,13 @@ use hotshot_types::{
     consensus::OuterConsensus,
     data::{Leaf2, QuorumProposal, QuorumProposalWrapper},
     message::Proposal,
+    signer::Signer,
     simple_certificate::QuorumCertificate,
     simple_vote::HasEpoch,
     traits::{
         block_contents::BlockHeader,
         election::Membership,
         node_implementation::{ConsensusTime, NodeImplementation, NodeType},
-        signature_key::SignatureKey,
         storage::Storage,
         ValidatedState,
     },
@@ -104,7 +104,7 @@ fn spawn_fetch_proposal<TYPES: NodeType, V: Versions>(
     membership: Arc<RwLock<TYPES::Membership>>,
     consensus: OuterConsensus<TYPES>,
     sender_public_key: TYPES::SignatureKey,
-    sender_private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    signer: Arc<dyn Signer<TYPES::SignatureKey>>,
     upgrade_lock: UpgradeLock<TYPES, V>,
     epoch_height: u64,
 ) {
@@ -118,7 +118,7 @@ fn spawn_fetch_proposal<TYPES: NodeType, V: Versions>(
             membership,
             consensus,
             sender_public_key,
-            sender_private_key,
+            signer,
             &lock,
             epoch_height,
         )
@@ -244,7 +244,7 @@ pub(crate) async fn handle_quorum_proposal_recv<
             // This is because the key that we receive is for the prior leader, so the payload would be routed
             // incorrectly.
             validation_info.public_key.clone(),
-            validation_info.private_key.clone(),
+            Arc::clone(&validation_info.signer),
             validation_info.upgrade_lock.clone(),
             validation_info.epoch_height,
         );
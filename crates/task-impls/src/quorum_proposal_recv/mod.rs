@@ -19,11 +19,9 @@ use hotshot_types::{
     data::{EpochNumber, Leaf, ViewChangeEvidence2},
     event::Event,
     message::UpgradeLock,
+    signer::Signer,
     simple_certificate::UpgradeCertificate,
-    traits::{
-        node_implementation::{ConsensusTime, NodeImplementation, NodeType, Versions},
-        signature_key::SignatureKey,
-    },
+    traits::node_implementation::{ConsensusTime, NodeImplementation, NodeType, Versions},
     vote::{Certificate, HasViewNumber},
 };
 use tokio::task::JoinHandle;
@@ -45,8 +43,8 @@ pub struct QuorumProposalRecvTaskState<TYPES: NodeType, I: NodeImplementation<TY
     /// Our public key
     pub public_key: TYPES::SignatureKey,
 
-    /// Our Private Key
-    pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    /// Signs proposal requests with the key of this node
+    pub signer: Arc<dyn Signer<TYPES::SignatureKey>>,
 
     /// Reference to consensus. The replica will require a write lock on this.
     pub consensus: OuterConsensus<TYPES>,
@@ -92,8 +90,8 @@ pub(crate) struct ValidationInfo<TYPES: NodeType, I: NodeImplementation<TYPES>,
     /// Our public key
     pub(crate) public_key: TYPES::SignatureKey,
 
-    /// Our Private Key
-    pub(crate) private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    /// Signs proposal requests with the key of this node
+    pub(crate) signer: Arc<dyn Signer<TYPES::SignatureKey>>,
 
     /// Reference to consensus. The replica will require a write lock on this.
     pub(crate) consensus: OuterConsensus<TYPES>,
@@ -148,7 +146,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>
                 let validation_info = ValidationInfo::<TYPES, I, V> {
                     id: self.id,
                     public_key: self.public_key.clone(),
-                    private_key: self.private_key.clone(),
+                    signer: Arc::clone(&self.signer),
                     consensus: self.consensus.clone(),
                     membership: Arc::clone(&self.membership),
                     output_event_stream: self.output_event_stream.clone(),
@@ -4,7 +4,7 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
-use std::{collections::btree_map::Entry, sync::Arc};
+use std::sync::Arc;
 
 use async_broadcast::{InactiveReceiver, Sender};
 use async_lock::RwLock;
@@ -13,15 +13,17 @@ use committable::Committable;
 use hotshot_types::{
     consensus::OuterConsensus,
     data::{Leaf2, QuorumProposalWrapper, VidDisperseShare},
-    drb::{compute_drb_result, DrbResult},
+    drb::{drb_seed_input, DrbCheckpoint, DrbResult, DrbSeedProof, DRB_CHECKPOINT_INTERVAL},
     event::{Event, EventType},
     message::{convert_proposal, Proposal, UpgradeLock},
+    request_response::{DrbResultRequestPayload, DrbResultResponse},
+    signer::{Signer, SigningPurpose, SigningRequest},
+    simple_certificate::QuorumCertificate2,
     simple_vote::{HasEpoch, QuorumData2, QuorumVote2},
     traits::{
         block_contents::BlockHeader,
         election::Membership,
         node_implementation::{ConsensusTime, NodeImplementation, NodeType},
-        signature_key::SignatureKey,
         storage::Storage,
         ValidatedState,
     },
@@ -31,7 +33,7 @@ use hotshot_types::{
     },
     vote::HasViewNumber,
 };
-use tokio::spawn;
+use tokio::{spawn, task::spawn_blocking};
 use tracing::instrument;
 use utils::anytrace::*;
 use vbs::version::StaticVersionType;
@@ -46,6 +48,27 @@ use crate::{
     quorum_vote::Versions,
 };
 
+/// Store a DRB result in the shared `results` table, hand it to the membership for leader
+/// selection and persist it.
+async fn store_drb_result<TYPES: NodeType, S: Storage<TYPES>>(
+    consensus: &OuterConsensus<TYPES>,
+    membership: &RwLock<TYPES::Membership>,
+    storage: &RwLock<S>,
+    epoch: TYPES::Epoch,
+    result: DrbResult,
+) {
+    consensus
+        .write()
+        .await
+        .drb_seeds_and_results
+        .results
+        .insert(epoch, result);
+    membership.write().await.add_drb_result(epoch, result);
+    if let Err(e) = storage.read().await.add_drb_result(epoch, result).await {
+        tracing::warn!("Failed to store DRB result for epoch {epoch:?}: {e:#}");
+    }
+}
+
 /// Store the DRB result from the computation task to the shared `results` table.
 ///
 /// Returns the result if it exists.
@@ -84,11 +107,14 @@ async fn store_and_get_computed_drb_result<
 
     match computation.await {
         Ok(result) => {
-            let mut consensus_writer = task_state.consensus.write().await;
-            consensus_writer
-                .drb_seeds_and_results
-                .results
-                .insert(epoch_number, result);
+            store_drb_result(
+                &task_state.consensus,
+                &task_state.membership,
+                &task_state.storage,
+                epoch_number,
+                result,
+            )
+            .await;
             task_state.drb_computation = None;
             Ok(result)
         }
@@ -189,13 +215,14 @@ async fn start_drb_task<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versio
             if join_handle.is_finished() {
                 match join_handle.await {
                     Ok(result) => {
-                        task_state
-                            .consensus
-                            .write()
-                            .await
-                            .drb_seeds_and_results
-                            .results
-                            .insert(*task_epoch, result);
+                        store_drb_result(
+                            &task_state.consensus,
+                            &task_state.membership,
+                            &task_state.storage,
+                            *task_epoch,
+                            result,
+                        )
+                        .await;
                         task_state.drb_computation = None;
                     }
                     Err(e) => {
@@ -220,29 +247,111 @@ async fn start_drb_task<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versio
             return;
         }
 
-        if let Entry::Occupied(entry) = consensus_writer
+        // Resume the computation from a checkpoint saved before a restart, if there is one.
+        let checkpoint = match consensus_writer
             .drb_seeds_and_results
-            .seeds
-            .entry(new_epoch_number)
+            .checkpoints
+            .remove(&new_epoch_number)
         {
-            let drb_seed_input = *entry.get();
-            let new_drb_task = spawn(async move { compute_drb_result::<TYPES>(drb_seed_input) });
+            Some(checkpoint) => Some(checkpoint),
+            None => consensus_writer
+                .drb_seeds_and_results
+                .seeds
+                .remove(&new_epoch_number)
+                .map(DrbCheckpoint::new),
+        };
+        drop(consensus_writer);
+
+        if let Some(checkpoint) = checkpoint {
+            let new_drb_task = spawn(compute_drb_result_with_checkpoints(
+                new_epoch_number,
+                checkpoint,
+                task_state.drb_difficulty,
+                Arc::clone(&task_state.storage),
+            ));
             task_state.drb_computation = Some((new_epoch_number, new_drb_task));
-            entry.remove();
         }
     }
 }
 
+/// Compute the DRB result for `epoch` from `checkpoint`, saving a checkpoint to storage every
+/// [`DRB_CHECKPOINT_INTERVAL`] hashes.
+///
+/// The hashing runs on the blocking thread pool, one interval at a time, so it does not stall
+/// the async runtime, and aborting the task stops it after the current interval.
+async fn compute_drb_result_with_checkpoints<TYPES: NodeType, S: Storage<TYPES>>(
+    epoch: TYPES::Epoch,
+    mut checkpoint: DrbCheckpoint,
+    difficulty: u64,
+    storage: Arc<RwLock<S>>,
+) -> DrbResult {
+    loop {
+        let chunk = spawn_blocking(move || {
+            let result = checkpoint.advance(difficulty, DRB_CHECKPOINT_INTERVAL);
+            (checkpoint, result)
+        });
+        let (advanced, result) = match chunk.await {
+            Ok(advanced) => advanced,
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        };
+        if let Some(result) = result {
+            return result;
+        }
+        checkpoint = advanced;
+        if let Err(e) = storage
+            .read()
+            .await
+            .update_drb_checkpoint(epoch, checkpoint)
+            .await
+        {
+            tracing::warn!("Failed to store DRB checkpoint for epoch {epoch:?}: {e:#}");
+        }
+    }
+}
+
+/// Ask the other nodes for the DRB result of `epoch`, which we are missing.
+///
+/// Responses are handled by `handle_drb_result_response`.
+async fn request_drb_result<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
+    epoch: TYPES::Epoch,
+    view_number: TYPES::View,
+    task_state: &QuorumVoteTaskState<TYPES, I, V>,
+    event_sender: &Sender<Arc<HotShotEvent<TYPES>>>,
+) {
+    let request = DrbResultRequestPayload {
+        epoch,
+        view_number,
+        key: task_state.public_key.clone(),
+    };
+    let signing_request =
+        SigningRequest::new(SigningPurpose::Request, *view_number, request.commit());
+    match task_state.signer.sign(signing_request).await {
+        Ok(signature) => {
+            broadcast_event(
+                Arc::new(HotShotEvent::DrbResultRequestSend(request, signature)),
+                event_sender,
+            )
+            .await;
+        }
+        Err(e) => tracing::warn!("Failed to sign the DRB result request: {e}"),
+    }
+}
+
 /// Store the DRB seed two epochs in advance and the computed or received DRB result for next
 /// epoch.
 ///
 /// We store a combination of the following data.
 /// * The DRB seed two epochs in advance, if the third from the last block, i.e., the epoch root,
-///     is decided and we are in the quorum committee of the next epoch.
-/// * The computed result for the next epoch, if the third from the last block is decided.
+///     is decided and we are in the quorum committee of the next epoch. Along with it we keep
+///     `decided_leaf_qc`, the QC on the epoch root, to prove the seed to other nodes.
+/// * The computed result for the next epoch, if the third from the last block is decided. If we
+///     aren't computing it, e.g. because we restarted without its seed, we request it from the
+///     other nodes.
 /// * The received result for the next epoch, if the last block of the epoch is decided and we are
 ///     in the quorum committee of the committee of the next epoch.
 ///
+/// Seeds and results are also persisted, so they survive a restart.
+///
 /// Special cases:
 /// * Epoch 0: No DRB computation since we'll transition to epoch 1 immediately.
 /// * Epoch 1 and 2: No computed DRB result since when we first start the computation in epoch 1,
@@ -253,6 +362,8 @@ async fn start_drb_task<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versio
 async fn store_drb_seed_and_result<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions>(
     task_state: &mut QuorumVoteTaskState<TYPES, I, V>,
     decided_leaf: &Leaf2<TYPES>,
+    decided_leaf_qc: Option<QuorumCertificate2<TYPES>>,
+    event_sender: &Sender<Arc<HotShotEvent<TYPES>>>,
 ) -> Result<()> {
     if task_state.epoch_height == 0 {
         tracing::info!("Epoch height is 0, skipping DRB storage.");
@@ -276,35 +387,78 @@ async fn store_drb_seed_and_result<TYPES: NodeType, I: NodeImplementation<TYPES>
 
         // Store the DRB result for the next epoch, which will be used by the proposal task to
         // include in the proposal in the last block of this epoch.
-        store_and_get_computed_drb_result(current_epoch_number + 1, task_state).await?;
+        let next_epoch_number = current_epoch_number + 1;
+        if let Err(e) = store_and_get_computed_drb_result(next_epoch_number, task_state).await {
+            let computing = matches!(
+                &task_state.drb_computation,
+                Some((epoch, _)) if *epoch == next_epoch_number
+            );
+            if !computing
+                && task_state
+                    .membership
+                    .read()
+                    .await
+                    .has_stake(&task_state.public_key, Some(current_epoch_number))
+            {
+                request_drb_result(
+                    next_epoch_number,
+                    decided_leaf.view_number(),
+                    task_state,
+                    event_sender,
+                )
+                .await;
+            }
+            return Err(e);
+        }
 
         // Store the DRB seed input for the epoch after the next one.
-        let Ok(drb_seed_input_vec) = bincode::serialize(&decided_leaf.justify_qc().signatures)
-        else {
-            bail!("Failed to serialize the QC signature.");
+        let Some(drb_seed_input) = drb_seed_input(&decided_leaf.justify_qc()) else {
+            bail!("Failed to convert the QC signature into a DRB seed input.");
         };
-        let Ok(drb_seed_input) = drb_seed_input_vec.try_into() else {
-            bail!("Failed to convert the serialized QC signature into a DRB seed input.");
-        };
-        task_state
-            .consensus
-            .write()
-            .await
+        let seed_epoch_number = current_epoch_number + 2;
+        let seed_proof = decided_leaf_qc.map(|epoch_root_qc| DrbSeedProof {
+            epoch_root: decided_leaf.clone(),
+            epoch_root_qc,
+        });
+        let mut consensus_writer = task_state.consensus.write().await;
+        consensus_writer
             .drb_seeds_and_results
-            .store_seed(current_epoch_number + 2, drb_seed_input);
+            .store_seed(seed_epoch_number, drb_seed_input);
+        if let Some(seed_proof) = &seed_proof {
+            consensus_writer
+                .drb_seeds_and_results
+                .seed_proofs
+                .insert(seed_epoch_number, seed_proof.clone());
+        }
+        drop(consensus_writer);
+
+        let storage = task_state.storage.read().await;
+        storage
+            .add_drb_seed(seed_epoch_number, drb_seed_input)
+            .await
+            .wrap()
+            .context(warn!("Failed to store the DRB seed."))?;
+        if let Some(seed_proof) = seed_proof {
+            storage
+                .add_drb_seed_proof(seed_epoch_number, seed_proof)
+                .await
+                .wrap()
+                .context(warn!("Failed to store the DRB seed proof."))?;
+        }
     }
     // Skip storing the received result if this is not the last block.
     else if is_last_block_in_epoch(decided_block_number, task_state.epoch_height) {
         if let Some(result) = decided_leaf.next_drb_result {
             // We don't need to check value existence and consistency because it should be
             // impossible to decide on a block with different DRB results.
-            task_state
-                .consensus
-                .write()
-                .await
-                .drb_seeds_and_results
-                .results
-                .insert(current_epoch_number + 1, result);
+            store_drb_result(
+                &task_state.consensus,
+                &task_state.membership,
+                &task_state.storage,
+                current_epoch_number + 1,
+                result,
+            )
+            .await;
         } else {
             bail!("The last block of the epoch is decided but doesn't contain a DRB result.");
         }
@@ -312,6 +466,72 @@ async fn store_drb_seed_and_result<TYPES: NodeType, I: NodeImplementation<TYPES>
     Ok(())
 }
 
+/// Handles a `DrbResultResponseRecv` event.
+///
+/// If we are missing the result and the proof of its seed checks out, the result is verified by
+/// redoing the calculation on a blocking thread, then stored.
+pub(crate) async fn handle_drb_result_response<
+    TYPES: NodeType,
+    I: NodeImplementation<TYPES>,
+    V: Versions,
+>(
+    response: &DrbResultResponse<TYPES>,
+    task_state: &mut QuorumVoteTaskState<TYPES, I, V>,
+) -> Result<()> {
+    let epoch = response.epoch;
+    ensure!(
+        !task_state
+            .consensus
+            .read()
+            .await
+            .drb_seeds_and_results
+            .results
+            .contains_key(&epoch),
+        debug!("We already have the DRB result for epoch {:?}.", epoch)
+    );
+    if let Some((recovery_epoch, recovery)) = &task_state.drb_result_recovery {
+        ensure!(
+            *recovery_epoch != epoch || recovery.is_finished(),
+            debug!("Already verifying a DRB result for epoch {:?}.", epoch)
+        );
+    }
+
+    let membership_reader = task_state.membership.read().await;
+    let drb_seed_input = response
+        .proof
+        .verify(
+            epoch,
+            &membership_reader,
+            task_state.epoch_height,
+            &task_state.upgrade_lock,
+        )
+        .await?;
+    drop(membership_reader);
+
+    let consensus = OuterConsensus::new(Arc::clone(&task_state.consensus.inner_consensus));
+    let membership = Arc::clone(&task_state.membership);
+    let storage = Arc::clone(&task_state.storage);
+    let difficulty = task_state.drb_difficulty;
+    let result = response.result;
+    let recovery = spawn(async move {
+        let verification = spawn_blocking(move || {
+            hotshot_types::drb::verify_drb_result::<TYPES>(drb_seed_input, difficulty, &result)
+        });
+        match verification.await {
+            Ok(true) => {
+                store_drb_result(&consensus, &membership, &storage, epoch, result).await;
+            }
+            Ok(false) => tracing::warn!("Received an invalid DRB result for epoch {epoch:?}."),
+            Err(e) => tracing::error!("Error verifying the DRB result: {e:?}"),
+        }
+    });
+    if let Some((_, previous)) = task_state.drb_result_recovery.replace((epoch, recovery)) {
+        previous.abort();
+    }
+
+    Ok(())
+}
+
 /// Handles the `QuorumProposalValidated` event.
 #[instrument(skip_all, fields(id = task_state.id, view = *proposal.view_number()))]
 pub(crate) async fn handle_quorum_proposal_validated<
@@ -321,6 +541,7 @@ pub(crate) async fn handle_quorum_proposal_validated<
 >(
     proposal: &QuorumProposalWrapper<TYPES>,
     task_state: &mut QuorumVoteTaskState<TYPES, I, V>,
+    event_sender: &Sender<Arc<HotShotEvent<TYPES>>>,
 ) -> Result<()> {
     let version = task_state
         .upgrade_lock
@@ -418,6 +639,13 @@ pub(crate) async fn handle_quorum_proposal_validated<
         // We don't need to hold this while we broadcast
         drop(consensus_writer);
 
+        // The QC on the oldest decided leaf is the justify QC of the next one in the chain, or the
+        // decide QC if there's only one.
+        let oldest_decided_leaf_qc = match leaf_views.len() {
+            0 | 1 => new_decide_qc.clone(),
+            len => Some(leaf_views[len - 2].leaf.justify_qc()),
+        };
+
         // Send an update to everyone saying that we've reached a decide
         broadcast_event(
             Event {
@@ -434,10 +662,35 @@ pub(crate) async fn handle_quorum_proposal_validated<
         .await;
         tracing::debug!("Successfully sent decide event");
 
+        // Feed the decided leaves to the membership, oldest first.
+        let mut membership_writer = task_state.membership.write().await;
+        for leaf_info in leaf_views.iter().rev() {
+            membership_writer.add_decided_leaf(&leaf_info.leaf, task_state.epoch_height);
+        }
+        drop(membership_writer);
+
+        broadcast_event(
+            Arc::new(HotShotEvent::LeavesDecided(
+                leaf_views
+                    .iter()
+                    .rev()
+                    .map(|leaf_info| leaf_info.leaf.clone())
+                    .collect(),
+            )),
+            event_sender,
+        )
+        .await;
+
         if version >= V::Epochs::VERSION {
             // `leaf_views.last()` is never none if we've reached a new decide, so this is safe to
             // unwrap.
-            store_drb_seed_and_result(task_state, &leaf_views.last().unwrap().leaf).await?;
+            store_drb_seed_and_result(
+                task_state,
+                &leaf_views.last().unwrap().leaf,
+                oldest_decided_leaf_qc,
+                event_sender,
+            )
+            .await?;
         }
     }
 
@@ -457,7 +710,7 @@ pub(crate) async fn update_shared_state<
     receiver: InactiveReceiver<Arc<HotShotEvent<TYPES>>>,
     membership: Arc<RwLock<TYPES::Membership>>,
     public_key: TYPES::SignatureKey,
-    private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    signer: Arc<dyn Signer<TYPES::SignatureKey>>,
     upgrade_lock: UpgradeLock<TYPES, V>,
     view_number: TYPES::View,
     instance_state: Arc<TYPES::InstanceState>,
@@ -497,7 +750,7 @@ pub(crate) async fn update_shared_state<
                 Arc::clone(&membership),
                 OuterConsensus::new(Arc::clone(&consensus.inner_consensus)),
                 public_key.clone(),
-                private_key.clone(),
+                Arc::clone(&signer),
                 &upgrade_lock,
                 epoch_height,
             )
@@ -583,7 +836,7 @@ pub(crate) async fn submit_vote<TYPES: NodeType, I: NodeImplementation<TYPES>, V
     sender: Sender<Arc<HotShotEvent<TYPES>>>,
     membership: Arc<RwLock<TYPES::Membership>>,
     public_key: TYPES::SignatureKey,
-    private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    signer: Arc<dyn Signer<TYPES::SignatureKey>>,
     upgrade_lock: UpgradeLock<TYPES, V>,
     view_number: TYPES::View,
     storage: Arc<RwLock<I::Storage>>,
@@ -616,19 +869,19 @@ pub(crate) async fn submit_vote<TYPES: NodeType, I: NodeImplementation<TYPES>, V
     );
 
     // Create and send the vote.
-    let vote = QuorumVote2::<TYPES>::create_signed_vote(
+    let vote = QuorumVote2::<TYPES>::create_signed_vote_with_signer(
         QuorumData2 {
             leaf_commit: leaf.commit(),
             epoch: epoch_number,
         },
         view_number,
-        &public_key,
-        &private_key,
+        &*signer,
+        SigningPurpose::QuorumVote,
         &upgrade_lock,
     )
     .await
     .wrap()
-    .context(error!("Failed to sign vote. This should never happen."))?;
+    .context(error!("Failed to sign vote"))?;
     // Add to the storage.
     storage
         .write()
@@ -4,23 +4,24 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
-use std::{collections::BTreeMap, sync::Arc};
+use std::{sync::Arc, time::Duration};
 
 use async_broadcast::{InactiveReceiver, Receiver, Sender};
 use async_lock::RwLock;
 use async_trait::async_trait;
 use committable::Committable;
 use hotshot_task::{
-    dependency::{AndDependency, EventDependency},
-    dependency_task::{DependencyTask, HandleDepOutput},
+    dependency::{AndDependency, Dependency, EventDependency},
+    dependency_task::{DependencyTask, DependencyTasks, HandleDepOutput},
     task::TaskState,
 };
 use hotshot_types::{
     consensus::{ConsensusMetricsValue, OuterConsensus},
-    data::{Leaf2, QuorumProposalWrapper},
+    data::{vid_disperse::vid_storage_nodes, Leaf2, QuorumProposalWrapper},
     drb::DrbComputation,
     event::Event,
     message::{Proposal, UpgradeLock},
+    signer::Signer,
     simple_vote::HasEpoch,
     traits::{
         block_contents::BlockHeader,
@@ -30,6 +31,7 @@ use hotshot_types::{
         storage::Storage,
     },
     utils::{epoch_from_block_number, option_epoch_from_block_number},
+    vid::VidConfig,
     vote::{Certificate, HasViewNumber},
 };
 use tokio::task::JoinHandle;
@@ -40,7 +42,10 @@ use vbs::version::StaticVersionType;
 use crate::{
     events::HotShotEvent,
     helpers::broadcast_event,
-    quorum_vote::handlers::{handle_quorum_proposal_validated, submit_vote, update_shared_state},
+    quorum_vote::handlers::{
+        handle_drb_result_response, handle_quorum_proposal_validated, submit_vote,
+        update_shared_state,
+    },
 };
 
 /// Event handlers for `QuorumProposalValidated`.
@@ -62,8 +67,8 @@ pub struct VoteDependencyHandle<TYPES: NodeType, I: NodeImplementation<TYPES>, V
     /// Public key.
     pub public_key: TYPES::SignatureKey,
 
-    /// Private Key.
-    pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    /// Signs votes and requests with the key of this node.
+    pub signer: Arc<dyn Signer<TYPES::SignatureKey>>,
 
     /// Reference to consensus. The replica will require a write lock on this.
     pub consensus: OuterConsensus<TYPES>,
@@ -216,7 +221,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static, V: Versions> Handl
             self.receiver.clone(),
             Arc::clone(&self.membership),
             self.public_key.clone(),
-            self.private_key.clone(),
+            Arc::clone(&self.signer),
             self.upgrade_lock.clone(),
             self.view_number,
             Arc::clone(&self.instance_state),
@@ -252,7 +257,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static, V: Versions> Handl
             self.sender.clone(),
             Arc::clone(&self.membership),
             self.public_key.clone(),
-            self.private_key.clone(),
+            Arc::clone(&self.signer),
             self.upgrade_lock.clone(),
             self.view_number,
             Arc::clone(&self.storage),
@@ -275,8 +280,8 @@ pub struct QuorumVoteTaskState<TYPES: NodeType, I: NodeImplementation<TYPES>, V:
     /// Public key.
     pub public_key: TYPES::SignatureKey,
 
-    /// Private Key.
-    pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    /// Signs votes and requests with the key of this node.
+    pub signer: Arc<dyn Signer<TYPES::SignatureKey>>,
 
     /// Reference to consensus. The replica will require a write lock on this.
     pub consensus: OuterConsensus<TYPES>,
@@ -288,7 +293,7 @@ pub struct QuorumVoteTaskState<TYPES: NodeType, I: NodeImplementation<TYPES>, V:
     pub latest_voted_view: TYPES::View,
 
     /// Table for the in-progress dependency tasks.
-    pub vote_dependencies: BTreeMap<TYPES::View, JoinHandle<()>>,
+    pub vote_dependencies: DependencyTasks<TYPES::View>,
 
     /// The underlying network
     pub network: Arc<I::Network>,
@@ -299,6 +304,12 @@ pub struct QuorumVoteTaskState<TYPES: NodeType, I: NodeImplementation<TYPES>, V:
     /// In-progress DRB computation task.
     pub drb_computation: DrbComputation<TYPES>,
 
+    /// Number of hashes of a DRB computation
+    pub drb_difficulty: u64,
+
+    /// In-progress verification of a DRB result received from another node.
+    pub drb_result_recovery: Option<(TYPES::Epoch, JoinHandle<()>)>,
+
     /// Output events to application
     pub output_event_stream: async_broadcast::Sender<Event<TYPES>>,
 
@@ -316,6 +327,12 @@ pub struct QuorumVoteTaskState<TYPES: NodeType, I: NodeImplementation<TYPES>, V:
 
     /// Number of blocks in an epoch, zero means there are no epochs
     pub epoch_height: u64,
+
+    /// View timeout from config.
+    pub timeout: u64,
+
+    /// VID parameters of the network
+    pub vid_config: VidConfig,
 }
 
 impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> QuorumVoteTaskState<TYPES, I, V> {
@@ -367,6 +384,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> QuorumVoteTaskS
                 false
             }),
         )
+        .with_name(format!("{dependency_type:?}"))
     }
 
     /// Create and store an [`AndDependency`] combining [`EventDependency`]s associated with the
@@ -383,7 +401,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> QuorumVoteTaskS
             "Attempting to make dependency task for view {view_number:?} and event {event:?}"
         );
 
-        if self.vote_dependencies.contains_key(&view_number) {
+        if self.vote_dependencies.contains(&view_number) {
             return;
         }
 
@@ -403,13 +421,16 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> QuorumVoteTaskS
 
         let deps = vec![quorum_proposal_dependency, dac_dependency, vid_dependency];
 
-        let dependency_chain = AndDependency::from_deps(deps);
+        // Events for a view can arrive while the node is still in the previous one, so give up on
+        // the dependencies once both views would have timed out
+        let dependency_chain = AndDependency::from_deps(deps)
+            .with_timeout(Duration::from_millis(self.timeout.saturating_mul(2)));
 
         let dependency_task = DependencyTask::new(
             dependency_chain,
             VoteDependencyHandle::<TYPES, I, V> {
                 public_key: self.public_key.clone(),
-                private_key: self.private_key.clone(),
+                signer: Arc::clone(&self.signer),
                 consensus: OuterConsensus::new(Arc::clone(&self.consensus.inner_consensus)),
                 instance_state: Arc::clone(&self.instance_state),
                 membership: Arc::clone(&self.membership),
@@ -423,8 +444,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> QuorumVoteTaskS
                 consensus_metrics: Arc::clone(&self.consensus_metrics),
             },
         );
-        self.vote_dependencies
-            .insert(view_number, dependency_task.run());
+        self.vote_dependencies.spawn(view_number, dependency_task);
     }
 
     /// Update the latest voted view number.
@@ -439,8 +459,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> QuorumVoteTaskS
 
             // Cancel the old dependency tasks.
             for view in *self.latest_voted_view..(*new_view) {
-                if let Some(dependency) = self.vote_dependencies.remove(&TYPES::View::new(view)) {
-                    dependency.abort();
+                if self.vote_dependencies.cancel(&TYPES::View::new(view)) {
                     tracing::debug!("Vote dependency removed for view {:?}", view);
                 }
             }
@@ -477,7 +496,9 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> QuorumVoteTaskS
                 );
 
                 // Handle the event before creating the dependency task.
-                if let Err(e) = handle_quorum_proposal_validated(&proposal.data, self).await {
+                if let Err(e) =
+                    handle_quorum_proposal_validated(&proposal.data, self, &event_sender).await
+                {
                     tracing::debug!(
                         "Failed to handle QuorumProposalValidated event; error = {e:#}"
                     );
@@ -587,10 +608,21 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> QuorumVoteTaskS
                     "VID share was not sent by a DA member or the view leader."
                 );
 
-                let membership_total_nodes = membership_reader.total_nodes(target_epoch);
+                let storage_nodes =
+                    vid_storage_nodes::<TYPES>(&membership_reader, view, target_epoch);
                 drop(membership_reader);
 
-                if let Err(()) = share.data.verify_share(membership_total_nodes) {
+                let vid_pool = self.consensus.read().await.vid_pool.clone();
+                let share_data = share.data.clone();
+                let vid_config = self.vid_config;
+                let verified = vid_pool
+                    .verify(Some(*view), move || {
+                        share_data.verify_share(&storage_nodes, &vid_config)
+                    })
+                    .await
+                    .wrap()
+                    .context(|e| warn!("VID share was not verified: {e}"))?;
+                if let Err(()) = verified {
                     bail!("Failed to verify VID share");
                 }
 
@@ -616,14 +648,17 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> QuorumVoteTaskS
                     Arc::clone(&event),
                 );
             }
+            HotShotEvent::DrbResultResponseRecv(response) => {
+                handle_drb_result_response(response, self).await?;
+            }
             HotShotEvent::Timeout(view, ..) => {
+                tracing::debug!(
+                    "Timed out in view {view:?} with pending vote dependencies:\n{}",
+                    self.vote_dependencies.dump()
+                );
                 let view = TYPES::View::new(view.saturating_sub(1));
                 // cancel old tasks
-                let current_tasks = self.vote_dependencies.split_off(&view);
-                while let Some((_, task)) = self.vote_dependencies.pop_last() {
-                    task.abort();
-                }
-                self.vote_dependencies = current_tasks;
+                self.vote_dependencies.cancel_before(&view);
             }
             HotShotEvent::ViewChange(mut view, _) => {
                 view = TYPES::View::new(view.saturating_sub(1));
@@ -631,11 +666,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> QuorumVoteTaskS
                     tracing::debug!("view not updated");
                 }
                 // cancel old tasks
-                let current_tasks = self.vote_dependencies.split_off(&view);
-                while let Some((_, task)) = self.vote_dependencies.pop_last() {
-                    task.abort();
-                }
-                self.vote_dependencies = current_tasks;
+                self.vote_dependencies.cancel_before(&view);
             }
             _ => {}
         }
@@ -707,7 +738,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> QuorumVoteTaskS
             event_receiver.clone().deactivate(),
             Arc::clone(&self.membership),
             self.public_key.clone(),
-            self.private_key.clone(),
+            Arc::clone(&self.signer),
             self.upgrade_lock.clone(),
             proposal.data.view_number(),
             Arc::clone(&self.instance_state),
@@ -754,7 +785,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> QuorumVoteTaskS
             event_sender.clone(),
             Arc::clone(&self.membership),
             self.public_key.clone(),
-            self.private_key.clone(),
+            Arc::clone(&self.signer),
             self.upgrade_lock.clone(),
             proposal.data.view_number(),
             Arc::clone(&self.storage),
@@ -784,8 +815,6 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TaskState
     }
 
     fn cancel_subtasks(&mut self) {
-        while let Some((_, handle)) = self.vote_dependencies.pop_last() {
-            handle.abort();
-        }
+        self.vote_dependencies.cancel_all();
     }
 }
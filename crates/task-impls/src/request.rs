@@ -22,6 +22,7 @@ use hotshot_task::{
 };
 use hotshot_types::{
     consensus::OuterConsensus,
+    signer::{Signer, SigningPurpose, SigningRequest},
     simple_vote::HasEpoch,
     traits::{
         block_contents::BlockHeader,
@@ -73,8 +74,8 @@ pub struct NetworkRequestState<TYPES: NodeType, I: NodeImplementation<TYPES>> {
     /// This nodes public key
     pub public_key: TYPES::SignatureKey,
 
-    /// This nodes private/signing key, used to sign requests.
-    pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    /// Signs requests with the key of this node
+    pub signer: Arc<dyn Signer<TYPES::SignatureKey>>,
 
     /// The node's id
     pub id: u64,
@@ -182,7 +183,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> NetworkRequestState<TYPES, I
         let request = RequestKind::Vid(view, self.public_key.clone());
 
         // First sign the request for the VID shares.
-        if let Some(signature) = self.serialize_and_sign(&request) {
+        if let Some(signature) = self.serialize_and_sign(view, &request).await {
             self.create_vid_request_task(
                 request,
                 signature,
@@ -316,7 +317,12 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> NetworkRequestState<TYPES, I
         // Wait for a response
         let result = timeout(
             REQUEST_TIMEOUT,
-            Self::handle_event_dependency(receiver, da_committee_for_view.clone(), view),
+            Self::handle_event_dependency(
+                receiver,
+                da_committee_for_view.clone(),
+                public_key.clone(),
+                view,
+            ),
         )
         .await;
 
@@ -339,9 +345,13 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> NetworkRequestState<TYPES, I
 
     /// Create event dependency and wait for `VidResponseRecv` after we send out the request
     /// Returns an optional with `VidResponseRecv` if received, otherwise None
+    ///
+    /// Responses carrying the shares of other nodes, requested to reconstruct a payload, are
+    /// ignored.
     async fn handle_event_dependency(
         receiver: &Receiver<Arc<HotShotEvent<TYPES>>>,
         da_members_for_view: BTreeSet<<TYPES as NodeType>::SignatureKey>,
+        public_key: <TYPES as NodeType>::SignatureKey,
         view: TYPES::View,
     ) -> Option<Arc<HotShotEvent<TYPES>>> {
         EventDependency::new(
@@ -350,6 +360,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> NetworkRequestState<TYPES, I
                 let event = event.as_ref();
                 if let HotShotEvent::VidResponseRecv(sender_key, proposal) = event {
                     proposal.data.view_number() == view
+                        && *proposal.data.recipient_key() == public_key
                         && da_members_for_view.contains(sender_key)
                         && sender_key
                             .validate(&proposal.signature, proposal.data.payload_commitment_ref())
@@ -400,16 +411,23 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> NetworkRequestState<TYPES, I
     }
 
     /// Sign the serialized version of the request
-    fn serialize_and_sign(&self, request: &RequestKind<TYPES>) -> Option<Signature<TYPES>> {
+    async fn serialize_and_sign(
+        &self,
+        view: TYPES::View,
+        request: &RequestKind<TYPES>,
+    ) -> Option<Signature<TYPES>> {
         let Ok(data) = bincode::serialize(&request) else {
             tracing::error!("Failed to serialize request!");
             return None;
         };
-        let Ok(signature) = TYPES::SignatureKey::sign(&self.private_key, &Sha256::digest(data))
-        else {
-            tracing::error!("Failed to sign Data Request");
-            return None;
-        };
-        Some(signature)
+        let signing_request =
+            SigningRequest::new(SigningPurpose::Request, *view, Sha256::digest(data));
+        match self.signer.sign(signing_request).await {
+            Ok(signature) => Some(signature),
+            Err(e) => {
+                tracing::error!("Failed to sign Data Request: {}", e);
+                None
+            }
+        }
     }
 }
@@ -13,12 +13,15 @@ use hotshot_types::{
     consensus::{Consensus, LockedConsensusState, OuterConsensus},
     data::VidDisperseShare,
     message::{Proposal, UpgradeLock},
+    request_response::DrbResultResponse,
+    signer::{Signer, SigningPurpose},
     traits::{
         election::Membership,
-        network::DataRequest,
+        network::{DataRequest, RequestKind},
         node_implementation::{NodeType, Versions},
         signature_key::SignatureKey,
     },
+    vid::VidConfig,
 };
 use sha2::{Digest, Sha256};
 use tokio::{spawn, task::JoinHandle, time::sleep};
@@ -41,14 +44,17 @@ pub struct NetworkResponseState<TYPES: NodeType, V: Versions> {
     /// This replicas public key
     pub_key: TYPES::SignatureKey,
 
-    /// This replicas private key
-    private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    /// Signs the VID shares we calculate to answer requests
+    signer: Arc<dyn Signer<TYPES::SignatureKey>>,
 
     /// The node's id
     id: u64,
 
     /// Lock for a decided upgrade
     upgrade_lock: UpgradeLock<TYPES, V>,
+
+    /// VID parameters of the network
+    vid_config: VidConfig,
 }
 
 impl<TYPES: NodeType, V: Versions> NetworkResponseState<TYPES, V> {
@@ -57,17 +63,19 @@ impl<TYPES: NodeType, V: Versions> NetworkResponseState<TYPES, V> {
         consensus: LockedConsensusState<TYPES>,
         membership: Arc<RwLock<TYPES::Membership>>,
         pub_key: TYPES::SignatureKey,
-        private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+        signer: Arc<dyn Signer<TYPES::SignatureKey>>,
         id: u64,
         upgrade_lock: UpgradeLock<TYPES, V>,
+        vid_config: VidConfig,
     ) -> Self {
         Self {
             consensus,
             membership,
             pub_key,
-            private_key,
+            signer,
             id,
             upgrade_lock,
+            vid_config,
         }
     }
 
@@ -97,8 +105,14 @@ impl<TYPES: NodeType, V: Versions> NetworkResponseState<TYPES, V> {
                             if !valid_signature::<TYPES>(request, sender) {
                                 continue;
                             }
+                            // Serve the requester its own share, or ours so that it can
+                            // reconstruct the payload.
+                            let key = match &request.request {
+                                RequestKind::Vid(_, key) if *key == self.pub_key => key,
+                                _ => sender,
+                            };
                             if let Some(proposal) = self
-                                .get_or_calc_vid_share(request.view, target_epoch, sender)
+                                .get_or_calc_vid_share(request.view, target_epoch, key)
                                 .await
                             {
                                 broadcast_event(
@@ -117,7 +131,7 @@ impl<TYPES: NodeType, V: Versions> NetworkResponseState<TYPES, V> {
                             // Make sure that this request came from who we think it did
                             if !req.key.validate(signature, req.commit().as_ref()) {
                                 tracing::warn!("Invalid signature key on proposal request.");
-                                return;
+                                continue;
                             }
 
                             let quorum_proposal_result = self
@@ -139,6 +153,36 @@ impl<TYPES: NodeType, V: Versions> NetworkResponseState<TYPES, V> {
                                 .await;
                             }
                         }
+                        HotShotEvent::DrbResultRequestRecv(req, signature) => {
+                            // Make sure that this request came from who we think it did
+                            if !req.key.validate(signature, req.commit().as_ref()) {
+                                tracing::warn!("Invalid signature key on DRB result request.");
+                                continue;
+                            }
+
+                            let consensus_reader = self.consensus.read().await;
+                            let drb_seeds_and_results = &consensus_reader.drb_seeds_and_results;
+                            let response = drb_seeds_and_results
+                                .results
+                                .get(&req.epoch)
+                                .zip(drb_seeds_and_results.seed_proofs.get(&req.epoch))
+                                .map(|(result, proof)| DrbResultResponse {
+                                    view_number: req.view_number,
+                                    epoch: req.epoch,
+                                    result: *result,
+                                    proof: proof.clone(),
+                                });
+                            drop(consensus_reader);
+
+                            if let Some(response) = response {
+                                broadcast_event(
+                                    HotShotEvent::DrbResultResponseSend(req.key.clone(), response)
+                                        .into(),
+                                    &event_sender,
+                                )
+                                .await;
+                            }
+                        }
                         HotShotEvent::Shutdown => {
                             return;
                         }
@@ -176,8 +220,10 @@ impl<TYPES: NodeType, V: Versions> NetworkResponseState<TYPES, V> {
             view,
             target_epoch,
             Arc::clone(&self.membership),
-            &self.private_key,
+            &*self.signer,
+            SigningPurpose::Response,
             &self.upgrade_lock,
+            &self.vid_config,
         )
         .await
         .is_none()
@@ -189,8 +235,10 @@ impl<TYPES: NodeType, V: Versions> NetworkResponseState<TYPES, V> {
                 view,
                 target_epoch,
                 Arc::clone(&self.membership),
-                &self.private_key,
+                &*self.signer,
+                SigningPurpose::Response,
                 &self.upgrade_lock,
+                &self.vid_config,
             )
             .await?;
         }
@@ -4,30 +4,136 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
-use std::{fs::OpenOptions, io::Write, sync::Arc};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use async_broadcast::{Receiver, Sender};
 use async_trait::async_trait;
 use hotshot_task::task::TaskState;
-use hotshot_types::traits::node_implementation::NodeType;
-use utils::anytrace::Result;
+use hotshot_types::{traits::node_implementation::NodeType, HotShotConfig};
+use serde::{Deserialize, Serialize};
+use utils::anytrace::*;
 
 use crate::events::HotShotEvent;
 
+/// A single event captured by the `Rewind` task
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(bound(serialize = "TYPES::AuctionResult: Serialize", deserialize = ""))]
+pub struct RecordedEvent<TYPES: NodeType> {
+    /// Time since the recording started at which the event was received
+    pub offset: Duration,
+    /// The event itself
+    pub event: HotShotEvent<TYPES>,
+}
+
+/// Every event a node received, in order, in a form that can be replayed
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(bound(serialize = "TYPES::AuctionResult: Serialize", deserialize = ""))]
+pub struct EventRecording<TYPES: NodeType> {
+    /// The id of the recorded node
+    pub node_id: u64,
+    /// The configuration the recorded node was running with
+    pub config: HotShotConfig<TYPES::SignatureKey>,
+    /// The events, in the order they were received
+    pub events: Vec<RecordedEvent<TYPES>>,
+}
+
+impl<TYPES: NodeType> EventRecording<TYPES>
+where
+    TYPES::AuctionResult: Serialize,
+{
+    /// Write the recording to `path`
+    ///
+    /// # Errors
+    /// If the recording cannot be serialized or the file cannot be written
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let bytes = bincode::serialize(self)
+            .wrap()
+            .context(error!("Failed to serialize event recording"))?;
+
+        fs::write(path, bytes)
+            .wrap()
+            .context(error!("Failed to write event recording"))
+    }
+}
+
+impl<TYPES: NodeType> EventRecording<TYPES> {
+    /// Read a recording previously written with [`EventRecording::save`]
+    ///
+    /// # Errors
+    /// If the file cannot be read or does not contain a recording
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let bytes = fs::read(path)
+            .wrap()
+            .context(error!("Failed to read event recording"))?;
+
+        bincode::deserialize(&bytes)
+            .wrap()
+            .context(error!("Failed to deserialize event recording"))
+    }
+
+    /// The events which entered the node from the outside, see [`HotShotEvent::is_external_input`]
+    pub fn inputs(&self) -> impl Iterator<Item = &RecordedEvent<TYPES>> {
+        self.events
+            .iter()
+            .filter(|recorded| recorded.event.is_external_input())
+    }
+
+    /// The events produced by the node's own tasks in response to its inputs
+    pub fn outputs(&self) -> impl Iterator<Item = &RecordedEvent<TYPES>> {
+        self.events.iter().filter(|recorded| {
+            !recorded.event.is_external_input() && recorded.event != HotShotEvent::Shutdown
+        })
+    }
+}
+
 /// The task state for the `Rewind` task is used to capture all events received
 /// by a particular node, in the order they've been received.
 pub struct RewindTaskState<TYPES: NodeType> {
     /// All events received by this node since the beginning of time.
     pub events: Vec<Arc<HotShotEvent<TYPES>>>,
 
+    /// Time since `started` at which each event in `events` was received
+    pub received_at: Vec<Duration>,
+
+    /// When the task started recording
+    pub started: Instant,
+
     /// The id of this node
     pub id: u64,
+
+    /// The configuration this node is running with
+    pub config: HotShotConfig<TYPES::SignatureKey>,
 }
 
 impl<TYPES: NodeType> RewindTaskState<TYPES> {
     /// Handles all events, storing them to the private state
     pub fn handle(&mut self, event: &Arc<HotShotEvent<TYPES>>) {
         self.events.push(Arc::clone(event));
+        self.received_at.push(self.started.elapsed());
+    }
+
+    /// The events captured so far as a replayable recording
+    #[must_use]
+    pub fn recording(&self) -> EventRecording<TYPES> {
+        EventRecording {
+            node_id: self.id,
+            config: self.config.clone(),
+            events: self
+                .events
+                .iter()
+                .zip(&self.received_at)
+                .map(|(event, offset)| RecordedEvent {
+                    offset: *offset,
+                    event: event.as_ref().clone(),
+                })
+                .collect(),
+        }
     }
 }
 
@@ -47,6 +153,15 @@ impl<TYPES: NodeType> TaskState for RewindTaskState<TYPES> {
 
     fn cancel_subtasks(&mut self) {
         tracing::info!("Node ID {} Recording {} events", self.id, self.events.len());
+
+        #[cfg(feature = "rewind")]
+        {
+            let recording_filename = format!("rewind_{}.bin", self.id);
+            if let Err(e) = self.recording().save(&recording_filename) {
+                tracing::error!("Failed to write file {}; error = {}", recording_filename, e);
+            }
+        }
+
         let filename = format!("rewind_{}.log", self.id);
         let mut file = match OpenOptions::new()
             .write(true)
@@ -61,9 +176,10 @@ impl<TYPES: NodeType> TaskState for RewindTaskState<TYPES> {
             }
         };
 
-        for (event_number, event) in self.events.iter().enumerate() {
+        for (event_number, (event, offset)) in self.events.iter().zip(&self.received_at).enumerate()
+        {
             // We do not want to die here, so we log and move on capturing as many events as we can.
-            if let Err(e) = writeln!(file, "{event_number}: {event}") {
+            if let Err(e) = writeln!(file, "{event_number} (+{offset:?}): {event}") {
                 tracing::error!(
                     "Failed to write event number {event_number} and event {event}; error = {e}"
                 );
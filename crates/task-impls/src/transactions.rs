@@ -20,6 +20,7 @@ use hotshot_types::{
     data::{null_block, PackedBundle},
     event::{Event, EventType},
     message::UpgradeLock,
+    signer::{Signer, SigningPurpose, SigningRequest},
     traits::{
         auction_results_provider::AuctionResultsProvider,
         block_contents::{BuilderFee, EncodeBytes},
@@ -29,7 +30,7 @@ use hotshot_types::{
         BlockPayload,
     },
     utils::ViewInner,
-    vid::VidCommitment,
+    vid::{VidCommitment, VidConfig},
 };
 use tokio::time::{sleep, timeout};
 use tracing::instrument;
@@ -100,8 +101,8 @@ pub struct TransactionTaskState<TYPES: NodeType, I: NodeImplementation<TYPES>, V
     /// This Nodes Public Key
     pub public_key: TYPES::SignatureKey,
 
-    /// Our Private Key
-    pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    /// Signs builder requests with the key of this node
+    pub signer: Arc<dyn Signer<TYPES::SignatureKey>>,
 
     /// InstanceState
     pub instance_state: Arc<TYPES::InstanceState>,
@@ -120,6 +121,9 @@ pub struct TransactionTaskState<TYPES: NodeType, I: NodeImplementation<TYPES>, V
 
     /// Number of blocks in an epoch, zero means there are no epochs
     pub epoch_height: u64,
+
+    /// VID parameters of the network
+    pub vid_config: VidConfig,
 }
 
 impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TransactionTaskState<TYPES, I, V> {
@@ -213,9 +217,12 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TransactionTask
                 .add(1);
 
             let membership_total_nodes = self.membership.read().await.total_nodes(self.cur_epoch);
-            let Some(null_fee) =
-                null_block::builder_fee::<TYPES, V>(membership_total_nodes, version, *block_view)
-            else {
+            let Some(null_fee) = null_block::builder_fee::<TYPES, V>(
+                membership_total_nodes,
+                version,
+                *block_view,
+                &self.vid_config,
+            ) else {
                 tracing::error!("Failed to get null fee");
                 return None;
             };
@@ -356,9 +363,12 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TransactionTask
         version: Version,
     ) -> Option<PackedBundle<TYPES>> {
         let membership_total_nodes = self.membership.read().await.total_nodes(self.cur_epoch);
-        let Some(null_fee) =
-            null_block::builder_fee::<TYPES, V>(membership_total_nodes, version, *block_view)
-        else {
+        let Some(null_fee) = null_block::builder_fee::<TYPES, V>(
+            membership_total_nodes,
+            version,
+            *block_view,
+            &self.vid_config,
+        ) else {
             tracing::error!("Failed to calculate null block fee.");
             return None;
         };
@@ -576,10 +586,15 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TransactionTask
             }
         };
 
-        let parent_comm_sig = match <<TYPES as NodeType>::SignatureKey as SignatureKey>::sign(
-            &self.private_key,
-            parent_comm.as_ref(),
-        ) {
+        let parent_comm_sig = match self
+            .signer
+            .sign(SigningRequest::new(
+                SigningPurpose::Request,
+                *block_view,
+                parent_comm,
+            ))
+            .await
+        {
             Ok(sig) => sig,
             Err(err) => {
                 tracing::error!(%err, "Failed to sign block hash");
@@ -729,10 +744,15 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TransactionTask
                 continue;
             }
 
-            let request_signature = match <<TYPES as NodeType>::SignatureKey as SignatureKey>::sign(
-                &self.private_key,
-                block_info.block_hash.as_ref(),
-            ) {
+            let request_signature = match self
+                .signer
+                .sign(SigningRequest::new(
+                    SigningPurpose::Request,
+                    *view_number,
+                    &block_info.block_hash,
+                ))
+                .await
+            {
                 Ok(request_signature) => request_signature,
                 Err(err) => {
                     tracing::error!(%err, "Failed to sign block hash");
@@ -807,6 +827,13 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TaskState
 {
     type Event = HotShotEvent<TYPES>;
 
+    fn subscribes_to(event: &Self::Event) -> bool {
+        matches!(
+            event,
+            HotShotEvent::TransactionsRecv(_) | HotShotEvent::ViewChange(..)
+        )
+    }
+
     async fn handle_event(
         &mut self,
         event: Arc<Self::Event>,
@@ -15,12 +15,12 @@ use hotshot_types::{
     data::UpgradeProposal,
     event::{Event, EventType},
     message::{Proposal, UpgradeLock},
+    signer::{Signer, SigningPurpose, SigningRequest},
     simple_certificate::UpgradeCertificate,
     simple_vote::{UpgradeProposalData, UpgradeVote},
     traits::{
         election::Membership,
         node_implementation::{ConsensusTime, NodeType, Versions},
-        signature_key::SignatureKey,
     },
     utils::EpochTransitionIndicator,
     vote::HasViewNumber,
@@ -55,8 +55,8 @@ pub struct UpgradeTaskState<TYPES: NodeType, V: Versions> {
     /// This Nodes public key
     pub public_key: TYPES::SignatureKey,
 
-    /// This Nodes private key
-    pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    /// Signs votes and proposals with the key of this node
+    pub signer: Arc<dyn Signer<TYPES::SignatureKey>>,
 
     /// This state's ID
     pub id: u64,
@@ -202,11 +202,11 @@ impl<TYPES: NodeType, V: Versions> UpgradeTaskState<TYPES, V> {
                 .await;
 
                 // If everything is fine up to here, we generate and send a vote on the proposal.
-                let vote = UpgradeVote::create_signed_vote(
+                let vote = UpgradeVote::create_signed_vote_with_signer(
                     proposal.data.upgrade_proposal.clone(),
                     view,
-                    &self.public_key,
-                    &self.private_key,
+                    &*self.signer,
+                    SigningPurpose::UpgradeVote,
                     &self.upgrade_lock,
                 )
                 .await?;
@@ -297,11 +297,15 @@ impl<TYPES: NodeType, V: Versions> UpgradeTaskState<TYPES, V> {
                         ),
                     };
 
-                    let signature = TYPES::SignatureKey::sign(
-                        &self.private_key,
-                        upgrade_proposal_data.commit().as_ref(),
-                    )
-                    .expect("Failed to sign upgrade proposal commitment!");
+                    let signature = self
+                        .signer
+                        .sign(SigningRequest::new(
+                            SigningPurpose::UpgradeProposal,
+                            *upgrade_proposal.view_number,
+                            upgrade_proposal_data.commit(),
+                        ))
+                        .await
+                        .context(error!("Failed to sign upgrade proposal commitment!"))?;
 
                     tracing::warn!("Sending upgrade proposal:\n\n {:?}", upgrade_proposal);
 
@@ -332,6 +336,15 @@ impl<TYPES: NodeType, V: Versions> UpgradeTaskState<TYPES, V> {
 impl<TYPES: NodeType, V: Versions> TaskState for UpgradeTaskState<TYPES, V> {
     type Event = HotShotEvent<TYPES>;
 
+    fn subscribes_to(event: &Self::Event) -> bool {
+        matches!(
+            event,
+            HotShotEvent::UpgradeProposalRecv(..)
+                | HotShotEvent::UpgradeVoteRecv(_)
+                | HotShotEvent::ViewChange(..)
+        )
+    }
+
     async fn handle_event(
         &mut self,
         event: Arc<Self::Event>,
@@ -14,15 +14,16 @@ use hotshot_types::{
     consensus::OuterConsensus,
     data::{PackedBundle, VidDisperse, VidDisperseShare},
     message::{Proposal, UpgradeLock},
+    signer::{Signer, SigningPurpose, SigningRequest},
     simple_vote::HasEpoch,
     traits::{
         block_contents::BlockHeader,
         election::Membership,
         node_implementation::{NodeImplementation, NodeType, Versions},
-        signature_key::SignatureKey,
         BlockPayload,
     },
     utils::option_epoch_from_block_number,
+    vid::VidConfig,
 };
 use tracing::{debug, error, info, instrument};
 use utils::anytrace::Result;
@@ -52,8 +53,8 @@ pub struct VidTaskState<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versio
     /// This Nodes Public Key
     pub public_key: TYPES::SignatureKey,
 
-    /// Our Private Key
-    pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    /// Signs the VID disperse with the key of this node
+    pub signer: Arc<dyn Signer<TYPES::SignatureKey>>,
 
     /// This state's ID
     pub id: u64,
@@ -63,6 +64,9 @@ pub struct VidTaskState<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versio
 
     /// Number of blocks in an epoch, zero means there are no epochs
     pub epoch_height: u64,
+
+    /// VID parameters of the network
+    pub vid_config: VidConfig,
 }
 
 impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> VidTaskState<TYPES, I, V> {
@@ -100,6 +104,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> VidTaskState<TY
                     );
                     return None;
                 }
+                let vid_pool = self.consensus.read().await.vid_pool.clone();
                 let vid_disperse = VidDisperse::calculate_vid_disperse::<V>(
                     &payload,
                     &Arc::clone(&self.membership),
@@ -107,16 +112,33 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> VidTaskState<TY
                     epoch,
                     epoch,
                     &self.upgrade_lock,
+                    &vid_pool,
+                    &self.vid_config,
                 )
                 .await
                 .ok()?;
                 let payload_commitment = vid_disperse.payload_commitment();
+                // The disperse and each of its shares are signed over the payload commitment
+                let signature = match self
+                    .signer
+                    .sign(SigningRequest::new(
+                        SigningPurpose::VidDisperse,
+                        **view_number,
+                        payload_commitment.as_ref(),
+                    ))
+                    .await
+                {
+                    Ok(signature) => signature,
+                    Err(e) => {
+                        error!("VID: failed to sign dispersal payload: {}", e);
+                        return None;
+                    }
+                };
                 let shares = VidDisperseShare::from_vid_disperse(vid_disperse.clone());
                 let mut consensus_writer = self.consensus.write().await;
                 for share in shares {
-                    if let Some(share) = share.to_proposal(&self.private_key) {
-                        consensus_writer.update_vid_shares(*view_number, share);
-                    }
+                    consensus_writer
+                        .update_vid_shares(*view_number, share.to_proposal(signature.clone()));
                 }
                 drop(consensus_writer);
 
@@ -135,12 +157,6 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> VidTaskState<TY
                 .await;
 
                 let view_number = *view_number;
-                let Ok(signature) =
-                    TYPES::SignatureKey::sign(&self.private_key, payload_commitment.as_ref())
-                else {
-                    error!("VID: failed to sign dispersal payload");
-                    return None;
-                };
                 debug!(
                     "publishing VID disperse for view {} and epoch {:?}",
                     *view_number, epoch
@@ -204,6 +220,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> VidTaskState<TY
                     return None;
                 };
                 let payload = Arc::clone(payload);
+                let vid_pool = consensus_reader.vid_pool.clone();
                 drop(consensus_reader);
 
                 let next_epoch_vid_disperse = VidDisperse::calculate_vid_disperse::<V>(
@@ -213,15 +230,28 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> VidTaskState<TY
                     target_epoch,
                     sender_epoch,
                     &self.upgrade_lock,
+                    &vid_pool,
+                    &self.vid_config,
                 )
                 .await
                 .ok()?;
-                let Ok(next_epoch_signature) = TYPES::SignatureKey::sign(
-                    &self.private_key,
-                    next_epoch_vid_disperse.payload_commitment().as_ref(),
-                ) else {
-                    error!("VID: failed to sign dispersal payload for the next epoch");
-                    return None;
+                let next_epoch_signature = match self
+                    .signer
+                    .sign(SigningRequest::new(
+                        SigningPurpose::VidDisperse,
+                        *proposal_view_number,
+                        next_epoch_vid_disperse.payload_commitment().as_ref(),
+                    ))
+                    .await
+                {
+                    Ok(signature) => signature,
+                    Err(e) => {
+                        error!(
+                            "VID: failed to sign dispersal payload for the next epoch: {}",
+                            e
+                        );
+                        return None;
+                    }
                 };
                 debug!(
                     "publishing VID disperse for view {} and epoch {:?}",
@@ -256,6 +286,15 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, V: Versions> TaskState
 {
     type Event = HotShotEvent<TYPES>;
 
+    fn subscribes_to(event: &Self::Event) -> bool {
+        matches!(
+            event,
+            HotShotEvent::BlockRecv(_)
+                | HotShotEvent::ViewChange(..)
+                | HotShotEvent::QuorumProposalSend(..)
+        )
+    }
+
     async fn handle_event(
         &mut self,
         event: Arc<Self::Event>,
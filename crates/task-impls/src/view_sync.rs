@@ -17,6 +17,7 @@ use async_trait::async_trait;
 use hotshot_task::task::TaskState;
 use hotshot_types::{
     message::UpgradeLock,
+    signer::{Signer, SigningPurpose},
     simple_certificate::{
         ViewSyncCommitCertificate2, ViewSyncFinalizeCertificate2, ViewSyncPreCommitCertificate2,
     },
@@ -27,11 +28,11 @@ use hotshot_types::{
     traits::{
         election::Membership,
         node_implementation::{ConsensusTime, NodeType, Versions},
-        signature_key::SignatureKey,
     },
     utils::EpochTransitionIndicator,
     vote::{Certificate, HasViewNumber, Vote},
 };
+use serde::{Deserialize, Serialize};
 use tokio::{spawn, task::JoinHandle, time::sleep};
 use tracing::instrument;
 use utils::anytrace::*;
@@ -43,7 +44,7 @@ use crate::{
         create_vote_accumulator, AccumulatorInfo, HandleVoteEvent, VoteCollectionTaskState,
     },
 };
-#[derive(PartialEq, PartialOrd, Clone, Debug, Eq, Hash)]
+#[derive(PartialEq, PartialOrd, Clone, Debug, Eq, Hash, Serialize, Deserialize)]
 /// Phases of view sync
 pub enum ViewSyncPhase {
     /// No phase; before the protocol has begun
@@ -79,8 +80,8 @@ pub struct ViewSyncTaskState<TYPES: NodeType, V: Versions> {
     /// This Nodes Public Key
     pub public_key: TYPES::SignatureKey,
 
-    /// Our Private Key
-    pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    /// Signs votes with the key of this node
+    pub signer: Arc<dyn Signer<TYPES::SignatureKey>>,
 
     /// Our node id; for logging
     pub id: u64,
@@ -119,6 +120,21 @@ pub struct ViewSyncTaskState<TYPES: NodeType, V: Versions> {
 impl<TYPES: NodeType, V: Versions> TaskState for ViewSyncTaskState<TYPES, V> {
     type Event = HotShotEvent<TYPES>;
 
+    fn subscribes_to(event: &Self::Event) -> bool {
+        matches!(
+            event,
+            HotShotEvent::ViewSyncPreCommitCertificateRecv(_)
+                | HotShotEvent::ViewSyncCommitCertificateRecv(_)
+                | HotShotEvent::ViewSyncFinalizeCertificateRecv(_)
+                | HotShotEvent::ViewSyncTimeout(..)
+                | HotShotEvent::ViewSyncPreCommitVoteRecv(_)
+                | HotShotEvent::ViewSyncCommitVoteRecv(_)
+                | HotShotEvent::ViewSyncFinalizeVoteRecv(_)
+                | HotShotEvent::ViewChange(..)
+                | HotShotEvent::Timeout(..)
+        )
+    }
+
     async fn handle_event(
         &mut self,
         event: Arc<Self::Event>,
@@ -166,8 +182,8 @@ pub struct ViewSyncReplicaTaskState<TYPES: NodeType, V: Versions> {
     /// This Nodes Public Key
     pub public_key: TYPES::SignatureKey,
 
-    /// Our Private Key
-    pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    /// Signs votes with the key of this node
+    pub signer: Arc<dyn Signer<TYPES::SignatureKey>>,
 
     /// Lock for a decided upgrade
     pub upgrade_lock: UpgradeLock<TYPES, V>,
@@ -237,7 +253,7 @@ impl<TYPES: NodeType, V: Versions> ViewSyncTaskState<TYPES, V> {
             timeout_task: None,
             membership: Arc::clone(&self.membership),
             public_key: self.public_key.clone(),
-            private_key: self.private_key.clone(),
+            signer: Arc::clone(&self.signer),
             view_sync_timeout: self.view_sync_timeout,
             id: self.id,
             upgrade_lock: self.upgrade_lock.clone(),
@@ -582,15 +598,15 @@ impl<TYPES: NodeType, V: Versions> ViewSyncReplicaTaskState<TYPES, V> {
                     self.relay = certificate.data().relay;
                 }
 
-                let Ok(vote) = ViewSyncCommitVote2::<TYPES>::create_signed_vote(
+                let Ok(vote) = ViewSyncCommitVote2::<TYPES>::create_signed_vote_with_signer(
                     ViewSyncCommitData2 {
                         relay: certificate.data().relay,
                         round: self.next_view,
                         epoch: certificate.data().epoch,
                     },
                     self.next_view,
-                    &self.public_key,
-                    &self.private_key,
+                    &*self.signer,
+                    SigningPurpose::ViewSyncVote,
                     &self.upgrade_lock,
                 )
                 .await
@@ -676,15 +692,15 @@ impl<TYPES: NodeType, V: Versions> ViewSyncReplicaTaskState<TYPES, V> {
                     self.relay = certificate.data().relay;
                 }
 
-                let Ok(vote) = ViewSyncFinalizeVote2::<TYPES>::create_signed_vote(
+                let Ok(vote) = ViewSyncFinalizeVote2::<TYPES>::create_signed_vote_with_signer(
                     ViewSyncFinalizeData2 {
                         relay: certificate.data().relay,
                         round: self.next_view,
                         epoch: certificate.data().epoch,
                     },
                     self.next_view,
-                    &self.public_key,
-                    &self.private_key,
+                    &*self.signer,
+                    SigningPurpose::ViewSyncVote,
                     &self.upgrade_lock,
                 )
                 .await
@@ -802,15 +818,15 @@ impl<TYPES: NodeType, V: Versions> ViewSyncReplicaTaskState<TYPES, V> {
                 }
 
                 let epoch = self.cur_epoch;
-                let Ok(vote) = ViewSyncPreCommitVote2::<TYPES>::create_signed_vote(
+                let Ok(vote) = ViewSyncPreCommitVote2::<TYPES>::create_signed_vote_with_signer(
                     ViewSyncPreCommitData2 {
                         relay: 0,
                         round: view_number,
                         epoch,
                     },
                     view_number,
-                    &self.public_key,
-                    &self.private_key,
+                    &*self.signer,
+                    SigningPurpose::ViewSyncVote,
                     &self.upgrade_lock,
                 )
                 .await
@@ -858,18 +874,19 @@ impl<TYPES: NodeType, V: Versions> ViewSyncReplicaTaskState<TYPES, V> {
                     self.relay += 1;
                     match last_seen_certificate {
                         ViewSyncPhase::None | ViewSyncPhase::PreCommit | ViewSyncPhase::Commit => {
-                            let Ok(vote) = ViewSyncPreCommitVote2::<TYPES>::create_signed_vote(
-                                ViewSyncPreCommitData2 {
-                                    relay: self.relay,
-                                    round: self.next_view,
-                                    epoch: self.cur_epoch,
-                                },
-                                self.next_view,
-                                &self.public_key,
-                                &self.private_key,
-                                &self.upgrade_lock,
-                            )
-                            .await
+                            let Ok(vote) =
+                                ViewSyncPreCommitVote2::<TYPES>::create_signed_vote_with_signer(
+                                    ViewSyncPreCommitData2 {
+                                        relay: self.relay,
+                                        round: self.next_view,
+                                        epoch: self.cur_epoch,
+                                    },
+                                    self.next_view,
+                                    &*self.signer,
+                                    SigningPurpose::ViewSyncVote,
+                                    &self.upgrade_lock,
+                                )
+                                .await
                             else {
                                 tracing::error!("Failed to sign ViewSyncPreCommitData!");
                                 return None;
@@ -224,6 +224,7 @@ where
     let new_accumulator = VoteAccumulator {
         vote_outcomes: HashMap::new(),
         signers: HashMap::new(),
+        pending_votes: HashMap::new(),
         phantom: PhantomData,
         upgrade_lock,
     };
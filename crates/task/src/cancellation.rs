@@ -0,0 +1,62 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use tokio::sync::Notify;
+
+/// Shared state behind a [`CancellationToken`]
+#[derive(Debug, Default)]
+struct CancellationState {
+    /// Whether the token has been cancelled
+    cancelled: AtomicBool,
+    /// Wakes everyone waiting in [`CancellationToken::cancelled`]
+    notify: Notify,
+}
+
+/// A cheaply cloneable token used to cancel pending work.
+///
+/// All clones share the same state: cancelling one cancels them all.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    /// Shared state
+    state: Arc<CancellationState>,
+}
+
+impl CancellationToken {
+    /// Create a new, not yet cancelled, token
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the token, waking everything waiting on it
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+        self.state.notify.notify_waiters();
+    }
+
+    /// Whether the token has been cancelled
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Wait until the token is cancelled
+    pub async fn cancelled(&self) {
+        loop {
+            // Register for the wakeup before checking the flag, so a concurrent `cancel` is not missed.
+            let notified = self.state.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
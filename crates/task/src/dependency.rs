@@ -4,7 +4,15 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
-use std::future::Future;
+use std::{
+    fmt::{self, Display},
+    future::Future,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use async_broadcast::{Receiver, RecvError};
 use futures::{
@@ -13,10 +21,20 @@ use futures::{
     FutureExt,
 };
 
+use crate::cancellation::CancellationToken;
+
 /// Type which describes the idea of waiting for a dependency to complete
 pub trait Dependency<T> {
     /// Complete will wait until it gets some value `T` then return the value
     fn completed(self) -> impl Future<Output = Option<T>> + Send;
+    /// Describe this dependency and the dependencies it is built from, for debugging
+    fn graph(&self) -> DependencyGraph {
+        DependencyGraph::new(
+            std::any::type_name::<Self>(),
+            DependencyState::default(),
+            Vec::new(),
+        )
+    }
     /// Create an or dependency from this dependency and another
     fn or<D: Dependency<T> + Send + 'static>(self, dep: D) -> OrDependency<T>
     where
@@ -37,23 +55,157 @@ pub trait Dependency<T> {
         and.add_dep(dep);
         and
     }
+    /// Give up on this dependency if it has not completed within `timeout`
+    fn with_timeout(self, timeout: Duration) -> TimeoutDependency<T>
+    where
+        T: Send + 'static,
+        Self: Sized + Send + 'static,
+    {
+        TimeoutDependency::new(self, timeout)
+    }
+    /// Give up on this dependency as soon as `token` is cancelled
+    fn until_cancelled(self, token: CancellationToken) -> CancellableDependency<T>
+    where
+        T: Send + 'static,
+        Self: Sized + Send + 'static,
+    {
+        CancellableDependency::new(self, token)
+    }
+}
+
+/// Progress of a single node in a [`DependencyGraph`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DependencyStatus {
+    /// Still waiting
+    Pending,
+    /// Completed with a value
+    Completed,
+    /// Completed without a value, e.g. because its channel closed
+    Failed,
+    /// Gave up after its timeout elapsed
+    TimedOut,
+    /// Gave up because it was cancelled
+    Cancelled,
+}
+
+/// Status of a dependency, shared between the dependency and the graphs describing it
+#[derive(Clone, Debug, Default)]
+pub struct DependencyState(Arc<AtomicU8>);
+
+impl DependencyState {
+    /// Record the new status of the dependency
+    pub fn set(&self, status: DependencyStatus) {
+        let value = match status {
+            DependencyStatus::Pending => 0,
+            DependencyStatus::Completed => 1,
+            DependencyStatus::Failed => 2,
+            DependencyStatus::TimedOut => 3,
+            DependencyStatus::Cancelled => 4,
+        };
+        self.0.store(value, Ordering::Relaxed);
+    }
+
+    /// Record the outcome of a dependency which finished, with a value if `completed`
+    fn finish(&self, completed: bool) {
+        self.set(if completed {
+            DependencyStatus::Completed
+        } else {
+            DependencyStatus::Failed
+        });
+    }
+
+    /// The current status of the dependency
+    #[must_use]
+    pub fn get(&self) -> DependencyStatus {
+        match self.0.load(Ordering::Relaxed) {
+            1 => DependencyStatus::Completed,
+            2 => DependencyStatus::Failed,
+            3 => DependencyStatus::TimedOut,
+            4 => DependencyStatus::Cancelled,
+            _ => DependencyStatus::Pending,
+        }
+    }
+}
+
+/// A live description of a dependency and the dependencies it is built from.
+///
+/// The statuses are shared with the running dependencies, so a graph taken before a dependency
+/// task is spawned shows which parts are still pending later on.
+#[derive(Clone, Debug)]
+pub struct DependencyGraph {
+    /// Human readable description of the dependency
+    pub label: String,
+    /// Status of the dependency
+    state: DependencyState,
+    /// Dependencies this one is built from
+    pub children: Vec<DependencyGraph>,
+}
+
+impl DependencyGraph {
+    /// Create a new graph node
+    #[must_use]
+    pub fn new(label: impl Into<String>, state: DependencyState, children: Vec<Self>) -> Self {
+        Self {
+            label: label.into(),
+            state,
+            children,
+        }
+    }
+
+    /// The current status of the dependency
+    #[must_use]
+    pub fn status(&self) -> DependencyStatus {
+        self.state.get()
+    }
+
+    /// Write this node and its children, indented by `depth`
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        writeln!(
+            f,
+            "{:indent$}{} [{:?}]",
+            "",
+            self.label,
+            self.status(),
+            indent = depth * 2
+        )?;
+        for child in &self.children {
+            child.fmt_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for DependencyGraph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
 }
 
 /// Defines a dependency that completes when all of its deps complete
 pub struct AndDependency<T> {
     /// Dependencies being combined
     deps: Vec<BoxFuture<'static, Option<T>>>,
+    /// Graphs of the dependencies being combined
+    children: Vec<DependencyGraph>,
+    /// Status of this dependency
+    state: DependencyState,
 }
 impl<T: Clone + Send + Sync> Dependency<Vec<T>> for AndDependency<T> {
     /// Returns a vector of all of the results from it's dependencies.
     /// The results will be in a random order
     async fn completed(self) -> Option<Vec<T>> {
         let futures = FuturesUnordered::from_iter(self.deps);
-        futures
+        let result = futures
             .collect::<Vec<Option<T>>>()
             .await
             .into_iter()
-            .collect()
+            .collect();
+        self.state.finish(result.is_some());
+        result
+    }
+
+    fn graph(&self) -> DependencyGraph {
+        DependencyGraph::new("and", self.state.clone(), self.children.clone())
     }
 }
 
@@ -61,14 +213,19 @@ impl<T: Clone + Send + Sync + 'static> AndDependency<T> {
     /// Create from a vec of deps
     #[must_use]
     pub fn from_deps(deps: Vec<impl Dependency<T> + Send + 'static>) -> Self {
-        let mut pinned = vec![];
+        let mut and = Self {
+            deps: vec![],
+            children: vec![],
+            state: DependencyState::default(),
+        };
         for dep in deps {
-            pinned.push(dep.completed().boxed());
+            and.add_dep(dep);
         }
-        Self { deps: pinned }
+        and
     }
     /// Add another dependency
     pub fn add_dep(&mut self, dep: impl Dependency<T> + Send + 'static) {
+        self.children.push(dep.graph());
         self.deps.push(dep.completed().boxed());
     }
     /// Add multiple dependencies
@@ -76,6 +233,7 @@ impl<T: Clone + Send + Sync + 'static> AndDependency<T> {
         for dep in deps.deps {
             self.deps.push(dep);
         }
+        self.children.extend(deps.children);
     }
 }
 
@@ -83,6 +241,10 @@ impl<T: Clone + Send + Sync + 'static> AndDependency<T> {
 pub struct OrDependency<T> {
     /// Dependencies being combined
     deps: Vec<BoxFuture<'static, Option<T>>>,
+    /// Graphs of the dependencies being combined
+    children: Vec<DependencyGraph>,
+    /// Status of this dependency
+    state: DependencyState,
 }
 impl<T: Clone + Send + Sync> Dependency<T> for OrDependency<T> {
     /// Returns the value of the first completed dependency
@@ -91,31 +253,136 @@ impl<T: Clone + Send + Sync> Dependency<T> for OrDependency<T> {
         loop {
             if let Some(maybe) = futures.next().await {
                 if maybe.is_some() {
+                    self.state.set(DependencyStatus::Completed);
                     return maybe;
                 }
             } else {
+                self.state.set(DependencyStatus::Failed);
                 return None;
             }
         }
     }
+
+    fn graph(&self) -> DependencyGraph {
+        DependencyGraph::new("or", self.state.clone(), self.children.clone())
+    }
 }
 
 impl<T: Clone + Send + Sync + 'static> OrDependency<T> {
     /// Creat an `OrDependency` from a vec of dependencies
     #[must_use]
     pub fn from_deps(deps: Vec<impl Dependency<T> + Send + 'static>) -> Self {
-        let mut pinned = vec![];
+        let mut or = Self {
+            deps: vec![],
+            children: vec![],
+            state: DependencyState::default(),
+        };
         for dep in deps {
-            pinned.push(dep.completed().boxed());
+            or.add_dep(dep);
         }
-        Self { deps: pinned }
+        or
     }
     /// Add another dependency
     pub fn add_dep(&mut self, dep: impl Dependency<T> + Send + 'static) {
+        self.children.push(dep.graph());
         self.deps.push(dep.completed().boxed());
     }
 }
 
+/// A dependency which gives up, completing with `None`, if its inner dependency has not completed
+/// within a timeout
+pub struct TimeoutDependency<T> {
+    /// The dependency being waited on
+    dep: BoxFuture<'static, Option<T>>,
+    /// How long to wait for it
+    timeout: Duration,
+    /// Graph of the dependency being waited on
+    child: DependencyGraph,
+    /// Status of this dependency
+    state: DependencyState,
+}
+
+impl<T: Send + 'static> TimeoutDependency<T> {
+    /// Wait at most `timeout` for `dep` to complete
+    #[must_use]
+    pub fn new(dep: impl Dependency<T> + Send + 'static, timeout: Duration) -> Self {
+        Self {
+            child: dep.graph(),
+            dep: dep.completed().boxed(),
+            timeout,
+            state: DependencyState::default(),
+        }
+    }
+}
+
+impl<T: Send + 'static> Dependency<T> for TimeoutDependency<T> {
+    async fn completed(self) -> Option<T> {
+        if let Ok(result) = tokio::time::timeout(self.timeout, self.dep).await {
+            self.state.finish(result.is_some());
+            result
+        } else {
+            self.state.set(DependencyStatus::TimedOut);
+            None
+        }
+    }
+
+    fn graph(&self) -> DependencyGraph {
+        DependencyGraph::new(
+            format!("timeout after {:?}", self.timeout),
+            self.state.clone(),
+            vec![self.child.clone()],
+        )
+    }
+}
+
+/// A dependency which gives up, completing with `None`, once a [`CancellationToken`] is cancelled
+pub struct CancellableDependency<T> {
+    /// The dependency being waited on
+    dep: BoxFuture<'static, Option<T>>,
+    /// Token cancelling the wait
+    token: CancellationToken,
+    /// Graph of the dependency being waited on
+    child: DependencyGraph,
+    /// Status of this dependency
+    state: DependencyState,
+}
+
+impl<T: Send + 'static> CancellableDependency<T> {
+    /// Wait for `dep` until `token` is cancelled
+    #[must_use]
+    pub fn new(dep: impl Dependency<T> + Send + 'static, token: CancellationToken) -> Self {
+        Self {
+            child: dep.graph(),
+            dep: dep.completed().boxed(),
+            token,
+            state: DependencyState::default(),
+        }
+    }
+}
+
+impl<T: Send + 'static> Dependency<T> for CancellableDependency<T> {
+    async fn completed(self) -> Option<T> {
+        tokio::select! {
+            result = self.dep => {
+                self.state.finish(result.is_some());
+                result
+            }
+            () = self.token.cancelled() => {
+                self.state.set(DependencyStatus::Cancelled);
+                None
+            }
+        }
+    }
+
+    fn graph(&self) -> DependencyGraph {
+        DependencyGraph::new(
+            "until cancelled",
+            self.state.clone(),
+            vec![self.child.clone()],
+        )
+    }
+}
+
 /// A dependency that listens on a channel for an event
 /// that matches what some value it wants.
 pub struct EventDependency<T: Clone + Send + Sync> {
@@ -129,6 +396,12 @@ pub struct EventDependency<T: Clone + Send + Sync> {
     /// The potentially externally completed dependency. If the dependency was seeded from an event
     /// message, we can mark it as already done in lieu of other events still pending.
     completed_dependency: Option<T>,
+
+    /// Name of the dependency in its [`DependencyGraph`]
+    name: String,
+
+    /// Status of this dependency
+    state: DependencyState,
 }
 
 impl<T: Clone + Send + Sync + 'static> EventDependency<T> {
@@ -139,12 +412,22 @@ impl<T: Clone + Send + Sync + 'static> EventDependency<T> {
             event_rx: receiver,
             match_fn: Box::new(match_fn),
             completed_dependency: None,
+            name: "event".to_string(),
+            state: DependencyState::default(),
         }
     }
 
+    /// Name the dependency, for its [`DependencyGraph`]
+    #[must_use]
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
     /// Mark a dependency as completed.
     pub fn mark_as_completed(&mut self, dependency: T) {
         self.completed_dependency = Some(dependency);
+        self.state.set(DependencyStatus::Completed);
     }
 }
 
@@ -161,6 +444,7 @@ impl<T: Clone + Send + Sync + 'static> Dependency<T> for EventDependency<T> {
             match self.event_rx.recv_direct().await {
                 Ok(event) => {
                     if (self.match_fn)(&event) {
+                        self.state.set(DependencyStatus::Completed);
                         return Some(event);
                     }
                 }
@@ -168,25 +452,29 @@ impl<T: Clone + Send + Sync + 'static> Dependency<T> for EventDependency<T> {
                     tracing::error!("Dependency Task overloaded, skipping {} events", n);
                 }
                 Err(RecvError::Closed) => {
+                    self.state.set(DependencyStatus::Failed);
                     return None;
                 }
             }
         }
     }
+
+    fn graph(&self) -> DependencyGraph {
+        DependencyGraph::new(self.name.clone(), self.state.clone(), Vec::new())
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use async_broadcast::{broadcast, Receiver};
 
-    use super::{AndDependency, Dependency, EventDependency, OrDependency};
+    use super::{AndDependency, Dependency, DependencyStatus, EventDependency, OrDependency};
+    use crate::cancellation::CancellationToken;
 
     fn eq_dep(rx: Receiver<usize>, val: usize) -> EventDependency<usize> {
-        EventDependency {
-            event_rx: rx,
-            match_fn: Box::new(move |v| *v == val),
-            completed_dependency: None,
-        }
+        EventDependency::new(rx, Box::new(move |v| *v == val)).with_name(format!("eq {val}"))
     }
 
     #[tokio::test(flavor = "multi_thread")]
@@ -270,4 +558,39 @@ mod tests {
         let result = and1.completed().await;
         assert_eq!(result, Some(vec![4, 6, 4, 5]));
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn timeout_dep() {
+        let (tx, rx) = broadcast(10);
+
+        let timeout = eq_dep(rx.clone(), 5).with_timeout(Duration::from_millis(50));
+        let graph = timeout.graph();
+        tx.broadcast(4).await.unwrap();
+        assert_eq!(timeout.completed().await, None);
+        assert_eq!(graph.status(), DependencyStatus::TimedOut);
+        assert_eq!(graph.children[0].status(), DependencyStatus::Pending);
+
+        let timeout = eq_dep(rx, 6).with_timeout(Duration::from_secs(10));
+        tx.broadcast(6).await.unwrap();
+        assert_eq!(timeout.completed().await, Some(6));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn cancelled_dep() {
+        let (tx, rx) = broadcast::<usize>(10);
+        let token = CancellationToken::new();
+
+        let dep = eq_dep(rx, 5).and(eq_dep(tx.new_receiver(), 6));
+        let cancellable = dep.until_cancelled(token.clone());
+        let graph = cancellable.graph();
+        let handle = tokio::spawn(cancellable.completed());
+        token.cancel();
+
+        assert_eq!(handle.await.unwrap(), None);
+        assert_eq!(graph.status(), DependencyStatus::Cancelled);
+        assert_eq!(
+            graph.to_string(),
+            "until cancelled [Cancelled]\n  and [Pending]\n    eq 5 [Pending]\n    eq 6 [Pending]\n"
+        );
+    }
 }
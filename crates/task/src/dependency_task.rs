@@ -4,10 +4,15 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
+use std::{collections::BTreeMap, fmt::Debug};
+
 use futures::Future;
 use tokio::task::{spawn, JoinHandle};
 
-use crate::dependency::Dependency;
+use crate::{
+    cancellation::CancellationToken,
+    dependency::{Dependency, DependencyGraph},
+};
 
 /// Defines a type that can handle the result of a dependency
 pub trait HandleDepOutput: Send + Sized + Sync + 'static {
@@ -24,13 +29,32 @@ pub struct DependencyTask<D: Dependency<H::Output> + Send, H: HandleDepOutput +
     pub(crate) dep: D,
     /// Handles the results returned from `self.dep.completed().await`
     pub(crate) handle: H,
+    /// Token which stops the task, whether it is still waiting or already handling the result
+    pub(crate) cancellation: Option<CancellationToken>,
 }
 
 impl<D: Dependency<H::Output> + Send, H: HandleDepOutput + Send> DependencyTask<D, H> {
     /// Create a new `DependencyTask`
     #[must_use]
     pub fn new(dep: D, handle: H) -> Self {
-        Self { dep, handle }
+        Self {
+            dep,
+            handle,
+            cancellation: None,
+        }
+    }
+
+    /// Stop the task as soon as `token` is cancelled
+    #[must_use]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Live description of the dependency this task waits for
+    #[must_use]
+    pub fn graph(&self) -> DependencyGraph {
+        self.dep.graph()
     }
 }
 
@@ -40,14 +64,145 @@ impl<D: Dependency<H::Output> + Send + 'static, H: HandleDepOutput> DependencyTa
     where
         Self: Sized,
     {
+        let Self {
+            dep,
+            handle,
+            cancellation,
+        } = self;
+
         spawn(async move {
-            if let Some(completed) = self.dep.completed().await {
-                self.handle.handle_dep_result(completed).await;
+            let work = async move {
+                if let Some(completed) = dep.completed().await {
+                    handle.handle_dep_result(completed).await;
+                }
+            };
+
+            match cancellation {
+                Some(token) => {
+                    tokio::select! {
+                        biased;
+                        () = token.cancelled() => {}
+                        () = work => {}
+                    }
+                }
+                None => work.await,
             }
         })
     }
 }
 
+/// A spawned dependency task, kept by [`DependencyTasks`]
+struct SpawnedDependencyTask {
+    /// Handle of the spawned task
+    handle: JoinHandle<()>,
+    /// Token cancelling the task
+    cancellation: CancellationToken,
+    /// Live description of what the task waits for
+    graph: DependencyGraph,
+}
+
+/// Dependency tasks keyed by view (or any other ordered key), which can be cancelled one by one or
+/// all at once when the key moves on
+pub struct DependencyTasks<K> {
+    /// The spawned tasks
+    tasks: BTreeMap<K, SpawnedDependencyTask>,
+}
+
+impl<K: Ord + Debug> Default for DependencyTasks<K> {
+    fn default() -> Self {
+        Self {
+            tasks: BTreeMap::new(),
+        }
+    }
+}
+
+impl<K: Ord + Debug> DependencyTasks<K> {
+    /// Create an empty set of tasks
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a task was spawned for `key` and has not been cancelled since
+    #[must_use]
+    pub fn contains(&self, key: &K) -> bool {
+        self.tasks.contains_key(key)
+    }
+
+    /// Number of tasks which have not been cancelled
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Whether there are no tasks left
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Spawn `task` for `key`, cancelling the task previously spawned for the same key, if any.
+    ///
+    /// Tasks for lower keys which have already finished are forgotten, since the key has moved on.
+    pub fn spawn<D, H>(&mut self, key: K, task: DependencyTask<D, H>)
+    where
+        D: Dependency<H::Output> + Send + 'static,
+        H: HandleDepOutput,
+    {
+        self.tasks
+            .retain(|task_key, task| *task_key >= key || !task.handle.is_finished());
+
+        let cancellation = CancellationToken::new();
+        let graph = task.graph();
+        let handle = task.with_cancellation(cancellation.clone()).run();
+
+        let spawned = SpawnedDependencyTask {
+            handle,
+            cancellation,
+            graph,
+        };
+        if let Some(previous) = self.tasks.insert(key, spawned) {
+            previous.cancellation.cancel();
+        }
+    }
+
+    /// Cancel the task spawned for `key`. Returns whether there was one.
+    pub fn cancel(&mut self, key: &K) -> bool {
+        match self.tasks.remove(key) {
+            Some(task) => {
+                task.cancellation.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cancel every task whose key is strictly lower than `key`
+    pub fn cancel_before(&mut self, key: &K) {
+        let keep = self.tasks.split_off(key);
+        for task in std::mem::replace(&mut self.tasks, keep).into_values() {
+            task.cancellation.cancel();
+        }
+    }
+
+    /// Cancel all tasks
+    pub fn cancel_all(&mut self) {
+        for task in std::mem::take(&mut self.tasks).into_values() {
+            task.cancellation.cancel();
+        }
+    }
+
+    /// Dump the dependency graph of every task which has not finished yet
+    #[must_use]
+    pub fn dump(&self) -> String {
+        self.tasks
+            .iter()
+            .filter(|(_, task)| !task.handle.is_finished())
+            .map(|(key, task)| format!("{key:?}:\n{}", task.graph))
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod test {
 
@@ -91,7 +246,7 @@ mod test {
         let (res_tx, mut res_rx) = broadcast(10);
         let dep = eq_dep(rx, 2);
         let handle = DummyHandle { sender: res_tx };
-        let join_handle = DependencyTask { dep, handle }.run();
+        let join_handle = DependencyTask::new(dep, handle).run();
         tx.broadcast(2).await.unwrap();
         assert_eq!(res_rx.recv().await.unwrap(), TaskResult::Success(2));
 
@@ -109,7 +264,7 @@ mod test {
             let handle = DummyHandle {
                 sender: res_tx.clone(),
             };
-            handles.push(DependencyTask { dep, handle }.run());
+            handles.push(DependencyTask::new(dep, handle).run());
         }
         let tx2 = tx.clone();
         spawn(async move {
@@ -124,4 +279,64 @@ mod test {
         tx2.broadcast(100).await.unwrap();
         FuturesOrdered::from_iter(handles).collect::<Vec<_>>().await;
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn cancel_before_view() {
+        let (tx, rx) = broadcast(20);
+        let (res_tx, mut res_rx) = broadcast(20);
+
+        let mut tasks = DependencyTasks::new();
+        for i in 0..4 {
+            let dep = eq_dep(rx.clone(), i);
+            let handle = DummyHandle {
+                sender: res_tx.clone(),
+            };
+            tasks.spawn(i, DependencyTask::new(dep, handle));
+        }
+        assert_eq!(tasks.dump().matches("[Pending]").count(), 4);
+
+        tasks.cancel_before(&2);
+        assert!(!tasks.contains(&1));
+        assert!(tasks.contains(&2));
+        assert_eq!(tasks.len(), 2);
+
+        for i in 0..4 {
+            tx.broadcast(i).await.unwrap();
+        }
+        let mut results = vec![res_rx.recv().await.unwrap(), res_rx.recv().await.unwrap()];
+        results.sort_by_key(|TaskResult::Success(i)| *i);
+        assert_eq!(
+            results,
+            vec![TaskResult::Success(2), TaskResult::Success(3)]
+        );
+
+        tasks.cancel_all();
+        assert!(tasks.is_empty());
+        assert!(res_rx.try_recv().is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn prunes_finished_tasks_for_lower_keys() {
+        let (tx, rx) = broadcast(20);
+        let (res_tx, mut res_rx) = broadcast(20);
+
+        let mut tasks = DependencyTasks::new();
+        for i in 0..2 {
+            let handle = DummyHandle {
+                sender: res_tx.clone(),
+            };
+            tasks.spawn(i, DependencyTask::new(eq_dep(rx.clone(), i), handle));
+        }
+        tx.broadcast(0).await.unwrap();
+        assert_eq!(res_rx.recv().await.unwrap(), TaskResult::Success(0));
+        sleep(Duration::from_millis(50)).await;
+
+        // The finished task for key 0 is pruned, the pending one for key 1 is kept
+        let handle = DummyHandle { sender: res_tx };
+        tasks.spawn(2, DependencyTask::new(eq_dep(rx, 2), handle));
+        assert!(!tasks.contains(&0));
+        assert!(tasks.contains(&1));
+        assert_eq!(tasks.len(), 2);
+        tasks.cancel_all();
+    }
 }
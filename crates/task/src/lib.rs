@@ -6,9 +6,15 @@
 
 //! Task primitives for `HotShot`
 
+/// Cancellation tokens for dependencies and dependency tasks
+pub mod cancellation;
 /// Simple Dependency types
 pub mod dependency;
 /// Task which can uses dependencies
 pub mod dependency_task;
+/// Per-task filtered event queues
+pub mod queue;
+/// Supervision and restart of long running tasks
+pub mod supervisor;
 /// Basic task types
 pub mod task;
@@ -0,0 +1,237 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Per-task event queues.
+//!
+//! By default every task reads directly from the shared event stream. A task can instead be given
+//! its own bounded queue: a small forwarding task reads the shared stream, drops every event the
+//! task does not subscribe to (see [`TaskState::subscribes_to`]) and pushes the rest into the
+//! task's queue according to an [`OverflowPolicy`]. A slow task then only ever lags on its own
+//! queue, and never holds back the shared stream with events it would ignore anyway.
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use async_broadcast::{broadcast, InactiveReceiver, Receiver, RecvError, TrySendError};
+use tokio::task::{spawn, JoinHandle};
+
+use crate::task::{TaskEvent, TaskState};
+
+/// What to do with a new event when a task's queue is full
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Evict the oldest queued event to make room for the new one
+    DropOldest,
+    /// Wait until the task has made room. Only the forwarder for this task waits, other tasks
+    /// keep receiving events.
+    Block,
+    /// Log an error and discard the new event
+    Error,
+}
+
+/// Configuration of a per-task event queue
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QueueConfig {
+    /// Maximum number of events waiting to be handled by the task
+    pub capacity: usize,
+    /// What to do when the queue is full
+    pub overflow: OverflowPolicy,
+}
+
+/// Live statistics of a per-task event queue
+pub struct QueueStats<EVENT> {
+    /// Inactive handle on the queue, used to inspect its length
+    queue: InactiveReceiver<Arc<EVENT>>,
+    /// Number of events discarded because the queue was full
+    dropped: Arc<AtomicUsize>,
+}
+
+impl<EVENT> Clone for QueueStats<EVENT> {
+    fn clone(&self) -> Self {
+        Self {
+            queue: self.queue.clone(),
+            dropped: Arc::clone(&self.dropped),
+        }
+    }
+}
+
+impl<EVENT> QueueStats<EVENT> {
+    /// Number of events queued for the task but not yet handled
+    #[must_use]
+    pub fn lag(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Total number of events discarded because the queue was full
+    #[must_use]
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Spawn a forwarder which feeds the events `S` subscribes to from `shared` into a new queue.
+///
+/// Returns the receiving end of the queue, to be handed to the task, the queue's statistics and
+/// the forwarder's handle. The forwarder exits after forwarding the shutdown event, or when the
+/// shared stream closes.
+pub fn spawn_event_queue<S: TaskState + 'static>(
+    name: String,
+    mut shared: Receiver<Arc<S::Event>>,
+    config: QueueConfig,
+) -> (
+    Receiver<Arc<S::Event>>,
+    QueueStats<S::Event>,
+    JoinHandle<()>,
+) {
+    let (mut queue_tx, queue_rx) = broadcast(config.capacity);
+    queue_tx.set_overflow(config.overflow == OverflowPolicy::DropOldest);
+
+    let stats = QueueStats {
+        queue: queue_rx.clone().deactivate(),
+        dropped: Arc::new(AtomicUsize::new(0)),
+    };
+    let dropped = Arc::clone(&stats.dropped);
+
+    let handle = spawn(async move {
+        loop {
+            let event = match shared.recv_direct().await {
+                Ok(event) => event,
+                Err(RecvError::Closed) => return,
+                Err(RecvError::Overflowed(n)) => {
+                    tracing::error!("Event queue for {name} overflowed, skipping {n} events");
+                    dropped.fetch_add(usize::try_from(n).unwrap_or(usize::MAX), Ordering::Relaxed);
+                    continue;
+                }
+            };
+
+            if *event == S::Event::shutdown_event() {
+                let _ = queue_tx.broadcast_direct(event).await;
+                return;
+            }
+
+            if !S::subscribes_to(&event) {
+                continue;
+            }
+
+            match config.overflow {
+                OverflowPolicy::DropOldest => {
+                    if let Ok(Some(_)) = queue_tx.broadcast_direct(event).await {
+                        dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+                OverflowPolicy::Block => {
+                    if queue_tx.broadcast_direct(event).await.is_err() {
+                        return;
+                    }
+                }
+                OverflowPolicy::Error => match queue_tx.try_broadcast(event) {
+                    Ok(_) => {}
+                    Err(TrySendError::Full(_)) => {
+                        tracing::error!("Event queue for {name} is full, dropping event");
+                        dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(TrySendError::Inactive(_)) => {}
+                    Err(TrySendError::Closed(_)) => return,
+                },
+            }
+        }
+    });
+
+    (queue_rx, stats, handle)
+}
+
+#[cfg(test)]
+mod test {
+    use async_broadcast::Sender;
+    use async_trait::async_trait;
+    use utils::anytrace::Result;
+
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum TestEvent {
+        Shutdown,
+        Wanted(usize),
+        Ignored,
+    }
+
+    impl TaskEvent for TestEvent {
+        fn shutdown_event() -> Self {
+            TestEvent::Shutdown
+        }
+    }
+
+    struct FilteringState;
+
+    #[async_trait]
+    impl TaskState for FilteringState {
+        type Event = TestEvent;
+
+        fn subscribes_to(event: &TestEvent) -> bool {
+            matches!(event, TestEvent::Wanted(_))
+        }
+
+        fn cancel_subtasks(&mut self) {}
+
+        async fn handle_event(
+            &mut self,
+            _event: Arc<TestEvent>,
+            _sender: &Sender<Arc<TestEvent>>,
+            _receiver: &Receiver<Arc<TestEvent>>,
+        ) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn filters_and_drops_oldest() {
+        let (tx, rx) = broadcast(10);
+        let config = QueueConfig {
+            capacity: 2,
+            overflow: OverflowPolicy::DropOldest,
+        };
+        let (mut queue, stats, handle) =
+            spawn_event_queue::<FilteringState>("filtering".to_string(), rx, config);
+
+        for event in [
+            TestEvent::Wanted(1),
+            TestEvent::Ignored,
+            TestEvent::Wanted(2),
+            TestEvent::Wanted(3),
+            TestEvent::Shutdown,
+        ] {
+            tx.broadcast(Arc::new(event)).await.unwrap();
+        }
+        handle.await.unwrap();
+
+        assert_eq!(stats.dropped(), 1);
+        assert_eq!(*queue.recv().await.unwrap(), TestEvent::Wanted(3));
+        assert_eq!(*queue.recv().await.unwrap(), TestEvent::Shutdown);
+        assert_eq!(stats.lag(), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn error_policy_discards_new_events() {
+        let (tx, rx) = broadcast(10);
+        let config = QueueConfig {
+            capacity: 1,
+            overflow: OverflowPolicy::Error,
+        };
+        let (mut queue, stats, _handle) =
+            spawn_event_queue::<FilteringState>("filtering".to_string(), rx, config);
+
+        tx.broadcast(Arc::new(TestEvent::Wanted(1))).await.unwrap();
+        tx.broadcast(Arc::new(TestEvent::Wanted(2))).await.unwrap();
+
+        while stats.dropped() == 0 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(stats.dropped(), 1);
+        assert_eq!(*queue.recv().await.unwrap(), TestEvent::Wanted(1));
+    }
+}
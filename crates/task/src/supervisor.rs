@@ -0,0 +1,360 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Supervision of long running tasks.
+//!
+//! A supervised task runs each `handle_event` call in its own tokio task, so that a panic in the
+//! handler is caught by the supervisor instead of silently tearing down the whole event loop.
+//! The supervisor records the task's liveness, reports the failure through a [`FailureHook`] and,
+//! if a [`StateFactory`] was provided and the [`RestartPolicy`] allows it, continues the loop
+//! with a freshly constructed state.
+
+use std::{any::Any, collections::BTreeMap, sync::Arc};
+
+use futures::future::BoxFuture;
+use tokio::sync::RwLock;
+
+/// Liveness of a supervised task
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskLiveness {
+    /// The task loop is running and handling events
+    Running,
+    /// The task panicked and is being rebuilt from its state factory
+    Restarting,
+    /// The task loop returned, either because of a shutdown event or because the channel closed
+    Exited,
+    /// The task panicked and will not be restarted
+    Panicked,
+}
+
+/// Snapshot of the health of a single supervised task
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaskHealth {
+    /// Current liveness of the task
+    pub liveness: TaskLiveness,
+    /// Number of times the task has been restarted
+    pub restarts: usize,
+}
+
+/// Description of an unexpected task failure, handed to the [`FailureHook`]
+#[derive(Clone, Debug)]
+pub struct TaskFailure {
+    /// Name the task was registered under
+    pub task: String,
+    /// Human readable description of what went wrong
+    pub reason: String,
+    /// Whether the supervisor is going to restart the task
+    pub will_restart: bool,
+}
+
+/// How a supervisor reacts to a panicking task
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// Never restart, the task is reported and stays dead
+    #[default]
+    Never,
+    /// Restart the task up to the given number of times
+    Limited(usize),
+}
+
+impl RestartPolicy {
+    /// Whether a task that has already been restarted `restarts` times may be restarted again
+    #[must_use]
+    pub fn allows(&self, restarts: usize) -> bool {
+        match self {
+            Self::Never => false,
+            Self::Limited(max) => restarts < *max,
+        }
+    }
+}
+
+/// Builds a fresh task state, used to restart a task after it panicked
+pub type StateFactory<S> = Arc<dyn Fn() -> BoxFuture<'static, S> + Send + Sync>;
+
+/// Callback invoked whenever a supervised task fails
+pub type FailureHook = Arc<dyn Fn(TaskFailure) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// Shared map of task name to its latest health
+pub type TaskHealthMap = Arc<RwLock<BTreeMap<String, TaskHealth>>>;
+
+/// State shared between a registry and the supervised tasks it spawned
+#[derive(Clone, Default)]
+pub struct Supervisor {
+    /// Health of every supervised task, keyed by name
+    pub(crate) health: TaskHealthMap,
+    /// Restart policy applied to tasks which have a state factory
+    pub(crate) policy: RestartPolicy,
+    /// Optional hook to report failures
+    pub(crate) on_failure: Option<FailureHook>,
+}
+
+impl Supervisor {
+    /// Create a new supervisor with the given restart policy and failure hook
+    #[must_use]
+    pub fn new(policy: RestartPolicy, on_failure: Option<FailureHook>) -> Self {
+        Self {
+            health: TaskHealthMap::default(),
+            policy,
+            on_failure,
+        }
+    }
+
+    /// Record the liveness of the task `name`
+    pub(crate) async fn set(&self, name: &str, liveness: TaskLiveness, restarts: usize) {
+        self.health
+            .write()
+            .await
+            .insert(name.to_string(), TaskHealth { liveness, restarts });
+    }
+
+    /// Log the failure and hand it to the failure hook, if any
+    pub(crate) async fn report(&self, failure: TaskFailure) {
+        tracing::error!(
+            "Task {} failed: {} (restarting: {})",
+            failure.task,
+            failure.reason,
+            failure.will_restart
+        );
+
+        if let Some(hook) = &self.on_failure {
+            hook(failure).await;
+        }
+    }
+
+    /// A snapshot of the health of all supervised tasks
+    pub async fn health(&self) -> BTreeMap<String, TaskHealth> {
+        self.health.read().await.clone()
+    }
+
+    /// Number of supervised tasks currently running
+    pub async fn live_tasks(&self) -> usize {
+        self.health
+            .read()
+            .await
+            .values()
+            .filter(|health| health.liveness == TaskLiveness::Running)
+            .count()
+    }
+}
+
+/// Extract a readable message from a panic payload
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use async_broadcast::{broadcast, Receiver, Sender};
+    use async_trait::async_trait;
+    use futures::FutureExt;
+    use utils::anytrace::Result;
+
+    use super::*;
+    use crate::task::{ConsensusTaskRegistry, Task, TaskEvent, TaskState};
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum TestEvent {
+        Shutdown,
+        Panic,
+        Count,
+    }
+
+    impl TaskEvent for TestEvent {
+        fn shutdown_event() -> Self {
+            TestEvent::Shutdown
+        }
+    }
+
+    struct CountingState {
+        handled: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl TaskState for CountingState {
+        type Event = TestEvent;
+
+        fn cancel_subtasks(&mut self) {}
+
+        async fn handle_event(
+            &mut self,
+            event: Arc<TestEvent>,
+            _sender: &Sender<Arc<TestEvent>>,
+            _receiver: &Receiver<Arc<TestEvent>>,
+        ) -> Result<()> {
+            #[allow(clippy::panic)]
+            if *event == TestEvent::Panic {
+                panic!("test panic");
+            }
+            self.handled.fetch_add(1, Ordering::SeqCst);
+
+            Ok(())
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn restarts_panicked_task() {
+        let (tx, rx) = broadcast(10);
+        let handled = Arc::new(AtomicUsize::new(0));
+        let failures = Arc::new(AtomicUsize::new(0));
+
+        let mut registry = ConsensusTaskRegistry::new();
+        let hook_failures = Arc::clone(&failures);
+        registry.supervise(
+            RestartPolicy::Limited(1),
+            Some(Arc::new(move |failure: TaskFailure| {
+                assert!(failure.will_restart);
+                hook_failures.fetch_add(1, Ordering::SeqCst);
+                async {}.boxed()
+            })),
+        );
+
+        let factory_handled = Arc::clone(&handled);
+        let factory: StateFactory<CountingState> = Arc::new(move || {
+            let handled = Arc::clone(&factory_handled);
+            async move { CountingState { handled } }.boxed()
+        });
+        let state = CountingState {
+            handled: Arc::clone(&handled),
+        };
+        registry.run_supervised_task("counting", Task::new(state, tx.clone(), rx), Some(factory));
+
+        for event in [
+            TestEvent::Count,
+            TestEvent::Panic,
+            TestEvent::Count,
+            TestEvent::Shutdown,
+        ] {
+            tx.broadcast(Arc::new(event)).await.unwrap();
+        }
+        registry.shutdown().await;
+
+        assert_eq!(handled.load(Ordering::SeqCst), 2);
+        assert_eq!(failures.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            registry.task_health().await.get("counting"),
+            Some(&TaskHealth {
+                liveness: TaskLiveness::Exited,
+                restarts: 1
+            })
+        );
+    }
+
+    /// A state spawning a subtask for each event, each holding a clone of `token` until it is
+    /// cancelled
+    struct SpawningState {
+        token: Arc<()>,
+        subtasks: Vec<tokio::task::JoinHandle<()>>,
+    }
+
+    #[async_trait]
+    impl TaskState for SpawningState {
+        type Event = TestEvent;
+
+        fn cancel_subtasks(&mut self) {
+            for subtask in self.subtasks.drain(..) {
+                subtask.abort();
+            }
+        }
+
+        async fn handle_event(
+            &mut self,
+            event: Arc<TestEvent>,
+            _sender: &Sender<Arc<TestEvent>>,
+            _receiver: &Receiver<Arc<TestEvent>>,
+        ) -> Result<()> {
+            let token = Arc::clone(&self.token);
+            self.subtasks.push(tokio::spawn(async move {
+                let _token = token;
+                futures::future::pending::<()>().await;
+            }));
+            #[allow(clippy::panic)]
+            if *event == TestEvent::Panic {
+                panic!("test panic");
+            }
+
+            Ok(())
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn cancels_subtasks_of_panicked_state() {
+        let (tx, rx) = broadcast(10);
+        let token = Arc::new(());
+
+        let mut registry = ConsensusTaskRegistry::new();
+        registry.supervise(RestartPolicy::Limited(1), None);
+        let factory_token = Arc::clone(&token);
+        let factory: StateFactory<SpawningState> = Arc::new(move || {
+            let token = Arc::clone(&factory_token);
+            async move {
+                SpawningState {
+                    token,
+                    subtasks: Vec::new(),
+                }
+            }
+            .boxed()
+        });
+        let state = SpawningState {
+            token: Arc::clone(&token),
+            subtasks: Vec::new(),
+        };
+        registry.run_supervised_task("spawning", Task::new(state, tx.clone(), rx), Some(factory));
+
+        // Two subtasks of the first state, then one of the restarted state
+        for event in [TestEvent::Count, TestEvent::Panic, TestEvent::Count] {
+            tx.broadcast(Arc::new(event)).await.unwrap();
+        }
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while registry
+                .task_health()
+                .await
+                .get("spawning")
+                .map(|health| health.restarts)
+                != Some(1)
+                || Arc::strong_count(&token) != 4
+            {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("The subtasks of the panicked state were not cancelled");
+
+        // The token is held by the test, the factory, the restarted state and its subtask only
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(Arc::strong_count(&token), 4);
+
+        tx.broadcast(Arc::new(TestEvent::Shutdown)).await.unwrap();
+        registry.shutdown().await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reports_panic_without_factory() {
+        let (tx, rx) = broadcast(10);
+        let handled = Arc::new(AtomicUsize::new(0));
+
+        let mut registry = ConsensusTaskRegistry::new();
+        registry.supervise(RestartPolicy::Limited(1), None);
+        let state = CountingState {
+            handled: Arc::clone(&handled),
+        };
+        registry.run_supervised_task("counting", Task::new(state, tx.clone(), rx), None);
+
+        tx.broadcast(Arc::new(TestEvent::Panic)).await.unwrap();
+        registry.shutdown().await;
+
+        let health = registry.task_health().await;
+        assert_eq!(health["counting"].liveness, TaskLiveness::Panicked);
+        assert_eq!(registry.supervisor().live_tasks().await, 0);
+    }
+}
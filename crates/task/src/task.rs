@@ -4,7 +4,7 @@
 // You should have received a copy of the MIT License
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
-use std::sync::Arc;
+use std::{any::Any, collections::BTreeMap, sync::Arc};
 
 use async_broadcast::{Receiver, RecvError, Sender};
 use async_trait::async_trait;
@@ -12,6 +12,14 @@ use futures::future::try_join_all;
 use tokio::task::{spawn, JoinHandle};
 use utils::anytrace::Result;
 
+use crate::{
+    queue::{spawn_event_queue, QueueConfig, QueueStats},
+    supervisor::{
+        panic_message, FailureHook, RestartPolicy, StateFactory, Supervisor, TaskFailure,
+        TaskHealth, TaskLiveness,
+    },
+};
+
 /// Trait for events that long-running tasks handle
 pub trait TaskEvent: PartialEq {
     /// The shutdown signal for this event type
@@ -27,6 +35,17 @@ pub trait TaskState: Send {
     /// Type of event sent and received by the task
     type Event: TaskEvent + Clone + Send + Sync;
 
+    /// Whether this task wants to handle `event`.
+    ///
+    /// Only consulted when the task is given its own event queue, in which case events it does
+    /// not subscribe to are never delivered. The shutdown event is always delivered.
+    fn subscribes_to(_event: &Self::Event) -> bool
+    where
+        Self: Sized,
+    {
+        true
+    }
+
     /// Joins all subtasks.
     fn cancel_subtasks(&mut self);
 
@@ -97,6 +116,136 @@ impl<S: TaskState + Send + 'static> Task<S> {
             }
         })
     }
+
+    /// Spawn the task loop under `supervisor`, consuming self.
+    ///
+    /// Each event is handled in its own spawned task so that a panicking handler is detected.
+    /// When that happens the subtasks of the panicked state are cancelled, the failure is reported
+    /// and, if `factory` is provided and the supervisor's restart policy allows it, the loop
+    /// continues with a fresh state. Otherwise the panic is propagated through the returned
+    /// `JoinHandle`.
+    pub fn run_supervised(
+        self,
+        name: String,
+        factory: Option<StateFactory<S>>,
+        supervisor: Supervisor,
+    ) -> JoinHandle<Box<dyn TaskState<Event = S::Event>>> {
+        let Task {
+            mut state,
+            sender,
+            mut receiver,
+        } = self;
+
+        spawn(async move {
+            let mut restarts = 0;
+            supervisor.set(&name, TaskLiveness::Running, restarts).await;
+
+            loop {
+                match receiver.recv_direct().await {
+                    Ok(input) => {
+                        if *input == S::Event::shutdown_event() {
+                            state.cancel_subtasks();
+                            supervisor.set(&name, TaskLiveness::Exited, restarts).await;
+
+                            break Box::new(state) as Box<dyn TaskState<Event = S::Event>>;
+                        }
+
+                        let handler_sender = sender.clone();
+                        let handler_receiver = receiver.clone();
+                        let mut guarded = CancelOnDrop(Some(state));
+                        let handled = spawn(async move {
+                            let result = S::handle_event(
+                                guarded.state(),
+                                input,
+                                &handler_sender,
+                                &handler_receiver,
+                            )
+                            .await;
+                            (guarded.into_inner(), result)
+                        })
+                        .await;
+
+                        match handled {
+                            Ok((returned_state, result)) => {
+                                state = returned_state;
+                                let _ = result.inspect_err(|e| tracing::debug!("{e}"));
+                            }
+                            Err(join_error) => {
+                                let payload: Box<dyn Any + Send> = match join_error.try_into_panic()
+                                {
+                                    Ok(payload) => payload,
+                                    Err(join_error) => Box::new(join_error.to_string()),
+                                };
+                                let will_restart =
+                                    factory.is_some() && supervisor.policy.allows(restarts);
+
+                                supervisor
+                                    .report(TaskFailure {
+                                        task: name.clone(),
+                                        reason: panic_message(payload.as_ref()),
+                                        will_restart,
+                                    })
+                                    .await;
+
+                                let Some(factory) = factory.as_ref().filter(|_| will_restart)
+                                else {
+                                    supervisor
+                                        .set(&name, TaskLiveness::Panicked, restarts)
+                                        .await;
+                                    std::panic::resume_unwind(payload);
+                                };
+
+                                restarts += 1;
+                                supervisor
+                                    .set(&name, TaskLiveness::Restarting, restarts)
+                                    .await;
+                                state = factory().await;
+                                supervisor.set(&name, TaskLiveness::Running, restarts).await;
+                            }
+                        }
+                    }
+                    Err(RecvError::Closed) => {
+                        supervisor.set(&name, TaskLiveness::Exited, restarts).await;
+
+                        break Box::new(state) as Box<dyn TaskState<Event = S::Event>>;
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to receive from event stream Error: {}", e);
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Holds the state of a supervised task while one of its handlers runs.
+///
+/// If the handler panics, the state is dropped along with the handler, and its subtasks are
+/// cancelled rather than left running next to the subtasks of the restarted state.
+struct CancelOnDrop<S: TaskState>(Option<S>);
+
+impl<S: TaskState> CancelOnDrop<S> {
+    /// The guarded state
+    fn state(&mut self) -> &mut S {
+        self.0
+            .as_mut()
+            .expect("The state is only taken by `into_inner`")
+    }
+
+    /// Release the state without cancelling its subtasks
+    fn into_inner(mut self) -> S {
+        self.0
+            .take()
+            .expect("The state is only taken by `into_inner`")
+    }
+}
+
+impl<S: TaskState> Drop for CancelOnDrop<S> {
+    fn drop(&mut self) {
+        if let Some(state) = self.0.as_mut() {
+            state.cancel_subtasks();
+        }
+    }
 }
 
 #[derive(Default)]
@@ -104,6 +253,12 @@ impl<S: TaskState + Send + 'static> Task<S> {
 pub struct ConsensusTaskRegistry<EVENT> {
     /// Tasks this registry controls
     task_handles: Vec<JoinHandle<Box<dyn TaskState<Event = EVENT>>>>,
+    /// Supervisor shared by all tasks spawned through `run_supervised_task`
+    supervisor: Supervisor,
+    /// Forwarders feeding the per-task event queues
+    queue_handles: Vec<JoinHandle<()>>,
+    /// Statistics of the per-task event queues, keyed by task name
+    queue_stats: BTreeMap<String, QueueStats<EVENT>>,
 }
 
 impl<EVENT: Send + Sync + Clone + TaskEvent> ConsensusTaskRegistry<EVENT> {
@@ -112,8 +267,33 @@ impl<EVENT: Send + Sync + Clone + TaskEvent> ConsensusTaskRegistry<EVENT> {
     pub fn new() -> Self {
         ConsensusTaskRegistry {
             task_handles: vec![],
+            supervisor: Supervisor::default(),
+            queue_handles: vec![],
+            queue_stats: BTreeMap::new(),
         }
     }
+
+    /// Set the restart policy and failure hook used for supervised tasks.
+    ///
+    /// Only affects tasks spawned after this call.
+    pub fn supervise(&mut self, policy: RestartPolicy, on_failure: Option<FailureHook>) {
+        self.supervisor = Supervisor {
+            health: Arc::clone(&self.supervisor.health),
+            policy,
+            on_failure,
+        };
+    }
+
+    /// The supervisor shared by this registry's supervised tasks
+    #[must_use]
+    pub fn supervisor(&self) -> Supervisor {
+        self.supervisor.clone()
+    }
+
+    /// Health of every supervised task, keyed by the name it was registered under
+    pub async fn task_health(&self) -> BTreeMap<String, TaskHealth> {
+        self.supervisor.health().await
+    }
     /// Add a task to the registry
     pub fn register(&mut self, handle: JoinHandle<Box<dyn TaskState<Event = EVENT>>>) {
         self.task_handles.push(handle);
@@ -131,6 +311,10 @@ impl<EVENT: Send + Sync + Clone + TaskEvent> ConsensusTaskRegistry<EVENT> {
                 .await
                 .map(|mut task_state| task_state.cancel_subtasks());
         }
+
+        for handle in self.queue_handles.drain(..) {
+            handle.abort();
+        }
     }
     /// Take a task, run it, and register it
     pub fn run_task<S>(&mut self, task: Task<S>)
@@ -140,6 +324,52 @@ impl<EVENT: Send + Sync + Clone + TaskEvent> ConsensusTaskRegistry<EVENT> {
         self.register(task.run());
     }
 
+    /// Take a task, run it under this registry's supervisor, and register it.
+    ///
+    /// If `factory` is provided the task may be restarted with a fresh state after a panic,
+    /// according to the registry's restart policy.
+    pub fn run_supervised_task<S>(
+        &mut self,
+        name: impl Into<String>,
+        task: Task<S>,
+        factory: Option<StateFactory<S>>,
+    ) where
+        S: TaskState<Event = EVENT> + Send + 'static,
+    {
+        self.register(task.run_supervised(name.into(), factory, self.supervisor.clone()));
+    }
+
+    /// Take a task, give it its own filtered event queue, and run it under this registry's
+    /// supervisor.
+    ///
+    /// The task only receives the events it subscribes to through [`TaskState::subscribes_to`],
+    /// and the receiver passed to its `handle_event` is its own queue rather than the shared
+    /// event stream.
+    pub fn run_queued_task<S>(
+        &mut self,
+        name: impl Into<String>,
+        mut task: Task<S>,
+        factory: Option<StateFactory<S>>,
+        config: QueueConfig,
+    ) where
+        S: TaskState<Event = EVENT> + Send + 'static,
+    {
+        let name = name.into();
+        let (queue, stats, handle) =
+            spawn_event_queue::<S>(name.clone(), task.receiver.clone(), config);
+        task.receiver = queue;
+
+        self.queue_handles.push(handle);
+        self.queue_stats.insert(name.clone(), stats);
+        self.run_supervised_task(name, task, factory);
+    }
+
+    /// Statistics of every per-task event queue, keyed by task name
+    #[must_use]
+    pub fn queue_stats(&self) -> BTreeMap<String, QueueStats<EVENT>> {
+        self.queue_stats.clone()
+    }
+
     /// Wait for the results of all the tasks registered
     /// # Panics
     /// Panics if one of the tasks panicked
@@ -26,6 +26,7 @@ use hotshot_types::{
         node_implementation::{NodeType, Versions},
         signature_key::BuilderSignatureKey,
     },
+    vid::VidConfig,
 };
 use tide_disco::{method::ReadState, App, Url};
 use tokio::spawn;
@@ -191,6 +192,7 @@ where
         &block_payload.encode(),
         *num_storage_nodes.read_arc().await,
         version,
+        &VidConfig::default(),
     );
 
     // Get block size from the encoded payload
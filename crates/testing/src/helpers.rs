@@ -28,6 +28,8 @@ use hotshot_types::{
     consensus::ConsensusMetricsValue,
     data::{Leaf2, VidDisperse, VidDisperseShare},
     message::{Proposal, UpgradeLock},
+    signature_key::BLSPubKey,
+    signer::NodeKey,
     simple_certificate::DaCertificate2,
     simple_vote::{DaData2, DaVote2, SimpleVote, VersionedVoteData},
     traits::{
@@ -36,11 +38,10 @@ use hotshot_types::{
         node_implementation::{NodeType, Versions},
     },
     utils::{option_epoch_from_block_number, View, ViewInner},
-    vid::{advz_scheme, VidCommitment, VidProposal, VidSchemeType},
+    vid::{vid_disperse, VidCommitment, VidConfig, VidDispersal, VidProposal},
     vote::{Certificate, HasViewNumber, Vote},
     ValidatorConfig,
 };
-use jf_vid::VidScheme;
 use primitive_types::U256;
 use serde::Serialize;
 use vbs::version::Version;
@@ -120,7 +121,7 @@ pub async fn build_system_handle_from_launcher<
 
     SystemContext::init(
         public_key,
-        private_key,
+        NodeKey::Private(private_key),
         node_id,
         hotshot_config,
         memberships,
@@ -268,23 +269,36 @@ pub fn key_pair_for_id<TYPES: NodeType>(
     (private_key, public_key)
 }
 
-/// initialize VID
-/// # Panics
-/// if unable to create a [`VidSchemeType`]
-/// TODO(Chengyu): use this version information
+/// The config of validator `index`, with `stake`, generated from the same seed as
+/// [`key_pair_for_id`]
 #[must_use]
-pub async fn vid_scheme_from_view_number<TYPES: NodeType, V: Versions>(
+pub fn validator_config(index: u64, stake: u64) -> ValidatorConfig<BLSPubKey> {
+    ValidatorConfig::generated_from_seed_indexed([0u8; 32], index, stake, true)
+}
+
+/// Disperse `encoded_transactions` among the committee of `view_number`, with the VID scheme of
+/// `version`
+/// # Panics
+/// if the dispersal fails
+pub async fn vid_dispersal_from_view_number<TYPES: NodeType, V: Versions>(
     membership: &Arc<RwLock<TYPES::Membership>>,
     view_number: TYPES::View,
     epoch_number: Option<TYPES::Epoch>,
-    _version: Version,
-) -> VidSchemeType {
+    encoded_transactions: &[u8],
+    version: Version,
+) -> VidDispersal {
     let num_storage_nodes = membership
         .read()
         .await
         .committee_members(view_number, epoch_number)
         .len();
-    advz_scheme(num_storage_nodes)
+    vid_disperse::<V>(
+        encoded_transactions,
+        num_storage_nodes,
+        version,
+        &VidConfig::default(),
+    )
+    .unwrap()
 }
 
 pub async fn vid_payload_commitment<TYPES: NodeType, V: Versions>(
@@ -294,13 +308,17 @@ pub async fn vid_payload_commitment<TYPES: NodeType, V: Versions>(
     transactions: Vec<TestTransaction>,
     version: Version,
 ) -> VidCommitment {
-    let mut vid =
-        vid_scheme_from_view_number::<TYPES, V>(membership, view_number, epoch_number, version)
-            .await;
     let encoded_transactions = TestTransaction::encode(&transactions);
-    let vid_disperse = vid.disperse(&encoded_transactions).unwrap();
 
-    vid_disperse.commit
+    vid_dispersal_from_view_number::<TYPES, V>(
+        membership,
+        view_number,
+        epoch_number,
+        &encoded_transactions,
+        version,
+    )
+    .await
+    .commit
 }
 
 pub async fn da_payload_commitment<TYPES: NodeType, V: Versions>(
@@ -315,6 +333,7 @@ pub async fn da_payload_commitment<TYPES: NodeType, V: Versions>(
         &encoded_transactions,
         membership.read().await.total_nodes(epoch_number),
         version,
+        &VidConfig::default(),
     )
 }
 
@@ -323,12 +342,12 @@ pub async fn build_payload_commitment<TYPES: NodeType, V: Versions>(
     view: TYPES::View,
     epoch: Option<TYPES::Epoch>,
     version: Version,
-) -> <VidSchemeType as VidScheme>::Commit {
+) -> VidCommitment {
     // Make some empty encoded transactions, we just care about having a commitment handy for the
     // later calls. We need the VID commitment to be able to propose later.
-    let mut vid = vid_scheme_from_view_number::<TYPES, V>(membership, view, epoch, version).await;
-    let encoded_transactions = Vec::new();
-    vid.commit_only(&encoded_transactions).unwrap()
+    vid_dispersal_from_view_number::<TYPES, V>(membership, view, epoch, &[], version)
+        .await
+        .commit
 }
 
 /// TODO: <https://github.com/EspressoSystems/HotShot/issues/2821>
@@ -340,18 +359,23 @@ pub async fn build_vid_proposal<TYPES: NodeType, V: Versions>(
     private_key: &<TYPES::SignatureKey as SignatureKey>::PrivateKey,
     version: Version,
 ) -> VidProposal<TYPES> {
-    let mut vid =
-        vid_scheme_from_view_number::<TYPES, V>(membership, view_number, epoch_number, version)
-            .await;
     let encoded_transactions = TestTransaction::encode(&transactions);
 
     let vid_disperse = VidDisperse::from_membership(
         view_number,
-        vid.disperse(&encoded_transactions).unwrap(),
+        vid_dispersal_from_view_number::<TYPES, V>(
+            membership,
+            view_number,
+            epoch_number,
+            &encoded_transactions,
+            version,
+        )
+        .await,
         membership,
         epoch_number,
         epoch_number,
         None,
+        &VidConfig::default(),
     )
     .await;
 
@@ -360,7 +384,7 @@ pub async fn build_vid_proposal<TYPES: NodeType, V: Versions>(
             .expect("Failed to sign VID commitment");
     let vid_disperse_proposal = Proposal {
         data: vid_disperse.clone(),
-        signature,
+        signature: signature.clone(),
         _pd: PhantomData,
     };
 
@@ -368,11 +392,7 @@ pub async fn build_vid_proposal<TYPES: NodeType, V: Versions>(
         vid_disperse_proposal,
         VidDisperseShare::from_vid_disperse(vid_disperse)
             .into_iter()
-            .map(|vid_disperse| {
-                vid_disperse
-                    .to_proposal(private_key)
-                    .expect("Failed to sign payload commitment")
-            })
+            .map(|vid_disperse| vid_disperse.to_proposal(signature.clone()))
             .collect(),
     )
 }
@@ -393,6 +413,7 @@ pub async fn build_da_certificate<TYPES: NodeType, V: Versions>(
         &encoded_transactions,
         membership.read().await.total_nodes(epoch_number),
         upgrade_lock.version_infallible(view_number).await,
+        &VidConfig::default(),
     );
 
     let da_data = DaData2 {
@@ -48,6 +48,9 @@ pub mod predicates;
 /// scripting harness for tests
 pub mod script;
 
+/// deterministic replay of recorded event streams
+pub mod replay;
+
 /// view generator for tests
 pub mod view_generator;
 
@@ -15,7 +15,9 @@ use hotshot_types::{
         block_contents::BlockHeader,
         node_implementation::{NodeType, Versions},
     },
+    vid::VidConfig,
 };
+use vbs::version::StaticVersionType;
 
 use crate::predicates::{Predicate, PredicateResult};
 
@@ -217,7 +219,11 @@ where
         Arc::new(move |e: Arc<HotShotEvent<TYPES>>| match e.as_ref() {
             QuorumProposalSend(proposal, _) => {
                 Some(proposal.data.block_header().payload_commitment())
-                    == null_block::commitment::<V>(num_storage_nodes)
+                    == null_block::commitment::<V>(
+                        num_storage_nodes,
+                        V::Base::VERSION,
+                        &VidConfig::default(),
+                    )
             }
             _ => false,
         });
@@ -0,0 +1,286 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Deterministic replay of a node's recorded event stream.
+//!
+//! The `Rewind` task records every event a node handled into an [`EventRecording`]. Replaying
+//! feeds the recording's external inputs (network messages, builder responses and timeouts) into
+//! a fresh set of tasks, collects what those tasks produce and compares it with what the recorded
+//! node produced. An empty [`ReplayDiff`] means the incident reproduces locally.
+
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use async_broadcast::{Receiver, RecvError, Sender};
+use hotshot::{
+    traits::{NodeImplementation, TestableNodeImplementation},
+    types::SystemContextHandle,
+};
+use hotshot_example_types::{
+    auction_results_provider_types::TestAuctionResultsProvider, state_types::TestInstanceState,
+    storage_types::TestStorage,
+};
+use hotshot_task_impls::{
+    events::HotShotEvent,
+    rewind::{EventRecording, RecordedEvent},
+};
+use hotshot_types::{
+    traits::node_implementation::{NodeType, Versions},
+    HotShotConfig,
+};
+use tokio::{
+    spawn,
+    time::{sleep_until, timeout, Instant},
+};
+
+use crate::{helpers::build_system_handle_from_launcher, test_builder::TestDescription};
+
+/// How a recording is fed back into the task graph
+#[derive(Clone, Copy, Debug)]
+pub struct ReplayConfig {
+    /// Reproduce the recorded delays between inputs, instead of sending them back to back
+    pub pace: bool,
+    /// How long the task graph must stay silent after the last input before the replay ends
+    pub quiet_period: Duration,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            pace: true,
+            quiet_period: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Difference between the events a recorded node produced and the events produced on replay
+#[derive(Debug)]
+pub struct ReplayDiff<TYPES: NodeType> {
+    /// Recorded events which were not produced on replay
+    pub missing: Vec<HotShotEvent<TYPES>>,
+    /// Events produced on replay which were not recorded
+    pub unexpected: Vec<HotShotEvent<TYPES>>,
+}
+
+impl<TYPES: NodeType> ReplayDiff<TYPES> {
+    /// Compare the `recorded` outputs with the `replayed` ones, ignoring order
+    ///
+    /// The recorded events are indexed by view, so each replayed event is only compared with the
+    /// recorded events of its own view.
+    #[must_use]
+    pub fn between(
+        recorded: impl IntoIterator<Item = HotShotEvent<TYPES>>,
+        replayed: impl IntoIterator<Item = HotShotEvent<TYPES>>,
+    ) -> Self {
+        let mut by_view: BTreeMap<Option<TYPES::View>, Vec<HotShotEvent<TYPES>>> = BTreeMap::new();
+        for event in recorded {
+            by_view.entry(event.view_number()).or_default().push(event);
+        }
+
+        let mut unexpected = Vec::new();
+        for event in replayed {
+            let recorded = by_view.entry(event.view_number()).or_default();
+            match recorded.iter().position(|recorded| *recorded == event) {
+                Some(index) => {
+                    recorded.remove(index);
+                }
+                None => unexpected.push(event),
+            }
+        }
+
+        Self {
+            missing: by_view.into_values().flatten().collect(),
+            unexpected,
+        }
+    }
+
+    /// Whether the replay produced exactly the recorded events
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+/// Feed the external inputs of `recording` into the task graph behind `sender` and diff what the
+/// graph produces on `receiver` against the recorded outputs.
+///
+/// The replay ends once every input has been sent and the graph has been silent for
+/// `config.quiet_period`.
+pub async fn replay_into<TYPES: NodeType>(
+    recording: &EventRecording<TYPES>,
+    sender: &Sender<Arc<HotShotEvent<TYPES>>>,
+    mut receiver: Receiver<Arc<HotShotEvent<TYPES>>>,
+    config: ReplayConfig,
+) -> ReplayDiff<TYPES> {
+    let inputs: Vec<_> = recording.inputs().cloned().collect();
+    let sender = sender.clone();
+    let feeder = spawn(async move {
+        let start = Instant::now();
+        for input in inputs {
+            if config.pace {
+                sleep_until(start + input.offset).await;
+            }
+            if sender
+                .broadcast_direct(Arc::new(input.event))
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+    });
+
+    let mut replayed = Vec::new();
+    loop {
+        match timeout(config.quiet_period, receiver.recv_direct()).await {
+            Ok(Ok(event)) => {
+                if !event.is_external_input() && *event != HotShotEvent::Shutdown {
+                    replayed.push(event.as_ref().clone());
+                }
+            }
+            Ok(Err(RecvError::Overflowed(n))) => {
+                tracing::error!("Replay receiver overflowed, missed {n} events");
+            }
+            Ok(Err(RecvError::Closed)) => break,
+            Err(_) => {
+                if feeder.is_finished() {
+                    break;
+                }
+            }
+        }
+    }
+
+    ReplayDiff::between(
+        recording.outputs().map(|recorded| recorded.event.clone()),
+        replayed,
+    )
+}
+
+/// Build a test node configured by `configure`, whose view and view sync timers are stretched so
+/// that they do not fire while a run is recorded or replayed
+async fn build_replay_node<
+    TYPES: NodeType<InstanceState = TestInstanceState>,
+    I: NodeImplementation<
+            TYPES,
+            Storage = TestStorage<TYPES>,
+            AuctionResultsProvider = TestAuctionResultsProvider<TYPES>,
+        > + TestableNodeImplementation<TYPES>,
+    V: Versions,
+>(
+    node_id: u64,
+    configure: impl Fn(&mut HotShotConfig<TYPES::SignatureKey>) + 'static,
+) -> (
+    SystemContextHandle<TYPES, I, V>,
+    Sender<Arc<HotShotEvent<TYPES>>>,
+    Receiver<Arc<HotShotEvent<TYPES>>>,
+) {
+    let builder: TestDescription<TYPES, I, V> = TestDescription::default_multiple_rounds();
+    let launcher = builder
+        .gen_launcher()
+        .map_hotshot_config(move |hotshot_config| {
+            configure(hotshot_config);
+            hotshot_config.next_view_timeout = 3_600_000;
+            hotshot_config.view_sync_timeout = Duration::from_secs(3_600);
+        });
+
+    build_system_handle_from_launcher(node_id, &launcher).await
+}
+
+/// Record a run of a fresh test node fed with `inputs`.
+///
+/// Each input is sent once the node has been silent for `config.quiet_period`, so that the
+/// recording captures everything the node produced in response to the previous one. The node runs
+/// without epochs, and its configuration is recorded for [`replay_recording`].
+///
+/// # Panics
+/// if the node cannot be built
+pub async fn record_run<
+    TYPES: NodeType<InstanceState = TestInstanceState>,
+    I: NodeImplementation<
+            TYPES,
+            Storage = TestStorage<TYPES>,
+            AuctionResultsProvider = TestAuctionResultsProvider<TYPES>,
+        > + TestableNodeImplementation<TYPES>,
+    V: Versions,
+>(
+    node_id: u64,
+    inputs: Vec<HotShotEvent<TYPES>>,
+    config: ReplayConfig,
+) -> EventRecording<TYPES> {
+    let (mut handle, sender, mut receiver) =
+        build_replay_node::<TYPES, I, V>(node_id, |hotshot_config| {
+            hotshot_config.epoch_height = 0;
+        })
+        .await;
+    let hotshot_config = handle.hotshot.config.clone();
+    let start = Instant::now();
+    handle.hotshot.start_consensus().await;
+
+    let mut inputs = inputs.into_iter();
+    let mut events = Vec::new();
+    loop {
+        match timeout(config.quiet_period, receiver.recv_direct()).await {
+            Ok(Ok(event)) => events.push(RecordedEvent {
+                offset: start.elapsed(),
+                event: event.as_ref().clone(),
+            }),
+            Ok(Err(RecvError::Overflowed(n))) => {
+                tracing::error!("Recording receiver overflowed, missed {n} events");
+            }
+            Ok(Err(RecvError::Closed)) => break,
+            Err(_) => match inputs.next() {
+                Some(input) => {
+                    if sender.broadcast_direct(Arc::new(input)).await.is_err() {
+                        break;
+                    }
+                }
+                None => break,
+            },
+        }
+    }
+    handle.shut_down().await;
+
+    EventRecording {
+        node_id,
+        config: hotshot_config,
+        events,
+    }
+}
+
+/// Replay `recording` into a fresh test node with the recorded node's id and configuration.
+///
+/// The node keeps the builders of the test environment, since builder responses are replayed as
+/// inputs. Its view and view sync timeouts are stretched so that its own timers do not fire during
+/// the replay; recorded timeouts are replayed as inputs instead.
+///
+/// # Panics
+/// if the node cannot be built
+pub async fn replay_recording<
+    TYPES: NodeType<InstanceState = TestInstanceState>,
+    I: NodeImplementation<
+            TYPES,
+            Storage = TestStorage<TYPES>,
+            AuctionResultsProvider = TestAuctionResultsProvider<TYPES>,
+        > + TestableNodeImplementation<TYPES>,
+    V: Versions,
+>(
+    recording: &EventRecording<TYPES>,
+    config: ReplayConfig,
+) -> ReplayDiff<TYPES> {
+    let recorded_config = recording.config.clone();
+    let (mut handle, sender, receiver) =
+        build_replay_node::<TYPES, I, V>(recording.node_id, move |hotshot_config| {
+            let builder_urls = hotshot_config.builder_urls.clone();
+            *hotshot_config = recorded_config.clone();
+            hotshot_config.builder_urls = builder_urls;
+        })
+        .await;
+    handle.hotshot.start_consensus().await;
+
+    let diff = replay_into(recording, &sender, receiver, config).await;
+    handle.shut_down().await;
+
+    diff
+}
@@ -264,16 +264,22 @@ where
                                 let decided_upgrade_certificate =
                                     read_storage.decided_upgrade_certificate().await;
 
-                                let initializer = HotShotInitializer::<TYPES>::load(
-                                    TestInstanceState::new(self.async_delay_config.clone()),
-                                    self.epoch_height,
-                                    self.last_decided_leaf.clone(),
-                                    (start_view, start_epoch),
-                                    (high_qc, next_epoch_high_qc),
-                                    saved_proposals,
-                                    vid_shares,
-                                    decided_upgrade_certificate,
-                                );
+                                let initializer = HotShotInitializer {
+                                    drb_checkpoints: read_storage.drb_checkpoints_cloned().await,
+                                    drb_seeds: read_storage.drb_seeds_cloned().await,
+                                    drb_seed_proofs: read_storage.drb_seed_proofs_cloned().await,
+                                    drb_results: read_storage.drb_results_cloned().await,
+                                    ..HotShotInitializer::<TYPES>::load(
+                                        TestInstanceState::new(self.async_delay_config.clone()),
+                                        self.epoch_height,
+                                        self.last_decided_leaf.clone(),
+                                        (start_view, start_epoch),
+                                        (high_qc, next_epoch_high_qc),
+                                        saved_proposals,
+                                        vid_shares,
+                                        decided_upgrade_certificate,
+                                    )
+                                };
                                 // We assign node's public key and stake value rather than read from config file since it's a test
                                 let validator_config = ValidatorConfig::generated_from_seed_indexed(
                                     [0u8; 32],
@@ -20,7 +20,10 @@ use hotshot_example_types::{
 };
 use hotshot_types::{
     consensus::ConsensusMetricsValue,
+    drb::DIFFICULTY_LEVEL,
+    signer::NodeKey,
     traits::node_implementation::{NodeType, Versions},
+    vid::VidConfig,
     HotShotConfig, PeerConfig, ValidatorConfig,
 };
 use tide_disco::Url;
@@ -84,6 +87,9 @@ pub fn default_hotshot_config<TYPES: NodeType>(
         start_voting_time: u64::MAX,
         stop_voting_time: 0,
         epoch_height,
+        task_restart_limit: 0,
+        vid_config: VidConfig::default(),
+        drb_difficulty: DIFFICULTY_LEVEL,
     }
 }
 
@@ -292,7 +298,7 @@ pub async fn create_test_handle<
         Behaviour::Standard => {
             let hotshot = SystemContext::<TYPES, I, V>::new(
                 public_key,
-                private_key,
+                NodeKey::Private(private_key),
                 node_id,
                 config,
                 memberships,
@@ -31,6 +31,7 @@ use hotshot_types::{
     consensus::ConsensusMetricsValue,
     constants::EVENT_CHANNEL_SIZE,
     data::Leaf2,
+    signer::NodeKey,
     simple_certificate::QuorumCertificate2,
     traits::{
         election::Membership,
@@ -615,7 +616,7 @@ where
 
         SystemContext::new(
             public_key,
-            private_key,
+            NodeKey::Private(private_key),
             node_id,
             config,
             Arc::new(RwLock::new(memberships)),
@@ -653,7 +654,7 @@ where
 
         SystemContext::new_from_channels(
             public_key,
-            private_key,
+            NodeKey::Private(private_key),
             node_id,
             config,
             memberships,
@@ -474,7 +474,9 @@ impl TestView {
             },
             self.view_number,
             &handle.public_key(),
-            handle.private_key(),
+            handle
+                .private_key()
+                .expect("Test nodes hold their private key"),
             &handle.hotshot.upgrade_lock,
         )
         .await
@@ -490,7 +492,9 @@ impl TestView {
             data,
             self.view_number,
             &handle.public_key(),
-            handle.private_key(),
+            handle
+                .private_key()
+                .expect("Test nodes hold their private key"),
             &handle.hotshot.upgrade_lock,
         )
         .await
@@ -506,7 +510,9 @@ impl TestView {
             data,
             self.view_number,
             &handle.public_key(),
-            handle.private_key(),
+            handle
+                .private_key()
+                .expect("Test nodes hold their private key"),
             &handle.hotshot.upgrade_lock,
         )
         .await
@@ -0,0 +1,130 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
+
+use committable::Committable;
+use futures::StreamExt;
+use hotshot_example_types::node_types::{MemoryImpl, TestTypes, TestVersions};
+use hotshot_testing::{
+    helpers::{build_system_handle, key_pair_for_id},
+    view_generator::TestViewGenerator,
+};
+use hotshot_types::{
+    message::UpgradeLock,
+    signature_key::BLSPubKey,
+    simple_certificate::QuorumCertificate2,
+    simple_vote::{QuorumData2, QuorumVote2},
+    traits::{election::Membership, signature_key::SignatureKey},
+    vote::{Certificate, Vote, VoteAccumulator},
+};
+
+#[test]
+fn batch_validation_detects_an_invalid_signature() {
+    let data = b"batched data";
+    let (keys, mut signatures): (Vec<_>, Vec<_>) = (0..16)
+        .map(|i| {
+            let (key, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], i);
+            (key, BLSPubKey::sign(&private_key, data).unwrap())
+        })
+        .unzip();
+    assert!(BLSPubKey::batch_validate(&keys, &signatures, data));
+    assert!(!BLSPubKey::batch_validate(&keys[1..], &signatures, data));
+
+    // A valid signature over other data
+    let (_, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 3);
+    signatures[3] = BLSPubKey::sign(&private_key, b"other data").unwrap();
+    assert!(!BLSPubKey::batch_validate(&keys, &signatures, data));
+
+    // A valid signature from another key
+    signatures[3] = signatures[4].clone();
+    assert!(!BLSPubKey::batch_validate(&keys, &signatures, data));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn accumulator_drops_invalid_votes_and_forms_a_certificate() {
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(1)
+        .await
+        .0;
+    let membership = Arc::clone(&handle.hotshot.memberships);
+    let total_nodes = membership.read().await.total_nodes(None);
+    let upgrade_lock = UpgradeLock::<TestTypes, TestVersions>::new();
+
+    let leaf = TestViewGenerator::<TestVersions>::generate(Arc::clone(&membership))
+        .next()
+        .await
+        .unwrap()
+        .leaf;
+    let data = QuorumData2 {
+        leaf_commit: leaf.commit(),
+        epoch: None,
+    };
+
+    let mut votes = Vec::new();
+    for i in 0..total_nodes {
+        let (private_key, public_key) = key_pair_for_id::<TestTypes>(i as u64);
+        votes.push(
+            QuorumVote2::<TestTypes>::create_signed_vote(
+                data.clone(),
+                leaf.view_number(),
+                &public_key,
+                &private_key,
+                &upgrade_lock,
+            )
+            .await
+            .unwrap(),
+        );
+    }
+
+    let mut accumulator = VoteAccumulator::<
+        TestTypes,
+        QuorumVote2<TestTypes>,
+        QuorumCertificate2<TestTypes>,
+        TestVersions,
+    > {
+        vote_outcomes: HashMap::new(),
+        signers: HashMap::new(),
+        pending_votes: HashMap::new(),
+        phantom: PhantomData,
+        upgrade_lock: upgrade_lock.clone(),
+    };
+
+    // A vote for the first node with the signature of another doesn't prevent its real vote from
+    // counting
+    let mut forged = votes[0].clone();
+    forged.signature.1 = votes[1].signature();
+    assert!(accumulator
+        .accumulate(&forged, &membership, None)
+        .await
+        .is_none());
+
+    let mut cert = None;
+    for vote in &votes {
+        cert = accumulator.accumulate(vote, &membership, None).await;
+        if cert.is_some() {
+            break;
+        }
+    }
+    let cert = cert.expect("The votes should form a certificate");
+
+    let membership_reader = membership.read().await;
+    assert!(cert
+        .is_valid_cert(
+            membership_reader.stake_table(None),
+            membership_reader.success_threshold(None),
+            &upgrade_lock,
+        )
+        .await
+        .is_ok());
+    drop(membership_reader);
+
+    let (_, signers) = accumulator.vote_outcomes.values().next().unwrap();
+    assert_eq!(
+        signers[&votes[0].signing_key()].0,
+        votes[0].signature(),
+        "Only the real vote of the first node should be accumulated"
+    );
+}
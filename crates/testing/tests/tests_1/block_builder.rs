@@ -24,6 +24,7 @@ use hotshot_types::{
         block_contents::vid_commitment, node_implementation::NodeType, signature_key::SignatureKey,
         BlockPayload,
     },
+    vid::VidConfig,
 };
 use tide_disco::Url;
 use tokio::time::sleep;
@@ -67,7 +68,7 @@ async fn test_random_block_builder() {
         // Test getting blocks
         let blocks = client
             .available_blocks(
-                vid_commitment::<TestVersions>(&[], 1, version),
+                vid_commitment::<TestVersions>(&[], 1, version, &VidConfig::default()),
                 dummy_view_number,
                 pub_key,
                 &signature,
@@ -103,9 +104,7 @@ async fn test_random_block_builder() {
             &TestBlockPayload {
                 transactions: vec![TestTransaction::new(vec![0; 1])],
             },
-            &TestMetadata {
-                num_transactions: 1,
-            },
+            &TestMetadata::new(&[TestTransaction::new(vec![0; 1])]),
         );
 
     let result = client
@@ -28,6 +28,7 @@ use hotshot_types::{
         election::Membership,
         node_implementation::{ConsensusTime, Versions},
     },
+    vid::VidConfig,
 };
 use vbs::version::{StaticVersionType, Version};
 
@@ -50,6 +51,7 @@ async fn test_da_task() {
         &encoded_transactions,
         handle.hotshot.memberships.read().await.total_nodes(None),
         default_version,
+        &VidConfig::default(),
     );
 
     let mut generator = TestViewGenerator::<TestVersions>::generate(membership.clone());
@@ -102,15 +104,14 @@ async fn test_da_task() {
             ViewChange(ViewNumber::new(2), None),
             BlockRecv(PackedBundle::new(
                 encoded_transactions.clone(),
-                TestMetadata {
-                    num_transactions: transactions.len() as u64
-                },
+                TestMetadata::new(&transactions),
                 ViewNumber::new(2),
                 None,
                 vec1::vec1![null_block::builder_fee::<TestTypes, TestVersions>(
                     membership.read().await.total_nodes(None),
                     <TestVersions as Versions>::Base::VERSION,
                     *ViewNumber::new(2),
+                    &VidConfig::default(),
                 )
                 .unwrap()],
                 None,
@@ -159,6 +160,7 @@ async fn test_da_task_storage_failure() {
         &encoded_transactions,
         handle.hotshot.memberships.read().await.total_nodes(None),
         default_version,
+        &VidConfig::default(),
     );
 
     let mut generator = TestViewGenerator::<TestVersions>::generate(Arc::clone(&membership));
@@ -211,15 +213,14 @@ async fn test_da_task_storage_failure() {
             ViewChange(ViewNumber::new(2), None),
             BlockRecv(PackedBundle::new(
                 encoded_transactions.clone(),
-                TestMetadata {
-                    num_transactions: transactions.len() as u64
-                },
+                TestMetadata::new(&transactions),
                 ViewNumber::new(2),
                 None,
                 vec1::vec1![null_block::builder_fee::<TestTypes, TestVersions>(
                     membership.read().await.total_nodes(None),
                     <TestVersions as Versions>::Base::VERSION,
                     *ViewNumber::new(2),
+                    &VidConfig::default(),
                 )
                 .unwrap()],
                 None,
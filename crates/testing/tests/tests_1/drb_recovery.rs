@@ -0,0 +1,299 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{sync::Arc, time::Duration};
+
+use async_broadcast::broadcast;
+use async_lock::RwLock;
+use committable::Committable;
+use futures::StreamExt;
+use hotshot::tasks::task_state::CreateTaskState;
+use hotshot_example_types::node_types::{MemoryImpl, TestTypes, TestVersions};
+use hotshot_task::task::TaskState;
+use hotshot_task_impls::{
+    events::HotShotEvent,
+    quorum_vote::QuorumVoteTaskState,
+    response::{run_response_task, NetworkResponseState},
+};
+use hotshot_testing::{
+    helpers::build_system_handle,
+    view_generator::{TestView, TestViewGenerator},
+};
+use hotshot_types::{
+    consensus::Consensus,
+    data::{EpochNumber, ViewNumber},
+    drb::{compute_drb_result, drb_seed_input, DrbResult, DrbSeedProof},
+    message::UpgradeLock,
+    request_response::{DrbResultRequestPayload, DrbResultResponse},
+    signature_key::BLSPubKey,
+    traits::{
+        consensus_api::ConsensusApi,
+        node_implementation::{ConsensusTime, NodeType},
+        signature_key::SignatureKey,
+    },
+};
+use tokio::time::timeout;
+
+/// Epoch height at which the leaf of view 3 is the epoch root of epoch 1
+const EPOCH_HEIGHT: u64 = 5;
+
+/// DRB difficulty, low enough to recompute results quickly
+const DIFFICULTY: u64 = 100;
+
+/// The epoch seeded by the epoch root of epoch 1
+fn seeded_epoch() -> EpochNumber {
+    EpochNumber::new(3)
+}
+
+/// Views 1 to 4, where view 3 is the epoch root and view 4 carries a QC on it.
+async fn generate_views(
+    membership: Arc<RwLock<<TestTypes as NodeType>::Membership>>,
+) -> Vec<TestView> {
+    TestViewGenerator::<TestVersions>::generate(membership)
+        .take(4)
+        .collect()
+        .await
+}
+
+/// A valid proof of the seed of [`seeded_epoch`], and the DRB result computed from it.
+fn seed_proof_and_result(views: &[TestView]) -> (DrbSeedProof<TestTypes>, DrbResult) {
+    let proof = DrbSeedProof {
+        epoch_root: views[2].leaf.clone(),
+        epoch_root_qc: views[3].quorum_proposal.data.justify_qc().clone(),
+    };
+    let seed = drb_seed_input(&views[2].leaf.justify_qc()).unwrap();
+
+    (proof, compute_drb_result::<TestTypes>(seed, DIFFICULTY))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_drb_seed_depends_on_every_signer() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let views = generate_views(Arc::clone(&handle.hotshot.memberships)).await;
+    let qc = views[2].leaf.justify_qc();
+
+    // Same aggregated signature, only the last signer differs
+    let mut other_qc = qc.clone();
+    let (_, signers) = other_qc.signatures.as_mut().unwrap();
+    let last = signers.len() - 1;
+    let signed = signers[last];
+    signers.set(last, !signed);
+
+    assert_ne!(drb_seed_input(&qc), drb_seed_input(&other_qc));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_drb_seed_proof_verify() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let views = generate_views(Arc::clone(&handle.hotshot.memberships)).await;
+    let (proof, _) = seed_proof_and_result(&views);
+    let membership = handle.hotshot.memberships.read().await;
+    let upgrade_lock = UpgradeLock::<TestTypes, TestVersions>::new();
+
+    let seed = proof
+        .verify(seeded_epoch(), &membership, EPOCH_HEIGHT, &upgrade_lock)
+        .await
+        .unwrap();
+    assert_eq!(Some(seed), drb_seed_input(&views[2].leaf.justify_qc()));
+
+    // The root of epoch 1 does not seed any other epoch.
+    assert!(proof
+        .verify(seeded_epoch() + 1, &membership, EPOCH_HEIGHT, &upgrade_lock)
+        .await
+        .is_err());
+
+    // The leaf is not an epoch root at another epoch height.
+    assert!(proof
+        .verify(seeded_epoch(), &membership, EPOCH_HEIGHT + 1, &upgrade_lock)
+        .await
+        .is_err());
+
+    // A QC on another leaf does not prove the epoch root was decided.
+    let mut forged = proof.clone();
+    forged.epoch_root_qc = views[2].quorum_proposal.data.justify_qc().clone();
+    assert!(forged
+        .verify(seeded_epoch(), &membership, EPOCH_HEIGHT, &upgrade_lock)
+        .await
+        .is_err());
+
+    // Neither does a QC on the epoch root with someone else's signatures.
+    let mut forged = proof.clone();
+    forged
+        .epoch_root_qc
+        .signatures
+        .clone_from(&views[2].quorum_proposal.data.justify_qc().signatures);
+    assert!(forged
+        .verify(seeded_epoch(), &membership, EPOCH_HEIGHT, &upgrade_lock)
+        .await
+        .is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_drb_result_response() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let views = generate_views(Arc::clone(&handle.hotshot.memberships)).await;
+    let (proof, result) = seed_proof_and_result(&views);
+
+    let mut state =
+        QuorumVoteTaskState::<TestTypes, MemoryImpl, TestVersions>::create_from(&handle).await;
+    state.epoch_height = EPOCH_HEIGHT;
+    state.drb_difficulty = DIFFICULTY;
+    let (sender, receiver) = broadcast(16);
+    let response = |result, proof| {
+        Arc::new(HotShotEvent::DrbResultResponseRecv(DrbResultResponse {
+            view_number: ViewNumber::new(4),
+            epoch: seeded_epoch(),
+            result,
+            proof,
+        }))
+    };
+
+    // A result with a forged seed proof is not even recomputed.
+    let mut forged_proof = proof.clone();
+    forged_proof.epoch_root_qc = views[2].quorum_proposal.data.justify_qc().clone();
+    assert!(state
+        .handle_event(response(result, forged_proof), &sender, &receiver)
+        .await
+        .is_err());
+    assert!(state.drb_result_recovery.is_none());
+
+    // A forged result is recomputed and dropped.
+    let mut forged_result = result;
+    forged_result[0] ^= 1;
+    state
+        .handle_event(response(forged_result, proof.clone()), &sender, &receiver)
+        .await
+        .unwrap();
+    state.drb_result_recovery.take().unwrap().1.await.unwrap();
+    let stored = |consensus: &Consensus<TestTypes>| {
+        consensus
+            .drb_seeds_and_results
+            .results
+            .get(&seeded_epoch())
+            .copied()
+    };
+    assert_eq!(stored(&*handle.consensus().read().await), None);
+    assert!(!handle
+        .storage()
+        .read()
+        .await
+        .drb_results_cloned()
+        .await
+        .contains_key(&seeded_epoch()));
+
+    // A valid result is stored.
+    state
+        .handle_event(response(result, proof), &sender, &receiver)
+        .await
+        .unwrap();
+    state.drb_result_recovery.take().unwrap().1.await.unwrap();
+    assert_eq!(stored(&*handle.consensus().read().await), Some(result));
+    assert_eq!(
+        handle
+            .storage()
+            .read()
+            .await
+            .drb_results_cloned()
+            .await
+            .get(&seeded_epoch()),
+        Some(&result)
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_drb_result_request() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let views = generate_views(Arc::clone(&handle.hotshot.memberships)).await;
+    let (proof, result) = seed_proof_and_result(&views);
+
+    let consensus = handle.hotshot.consensus();
+    let mut consensus_writer = consensus.write().await;
+    consensus_writer
+        .drb_seeds_and_results
+        .results
+        .insert(seeded_epoch(), result);
+    consensus_writer
+        .drb_seeds_and_results
+        .seed_proofs
+        .insert(seeded_epoch(), proof.clone());
+    drop(consensus_writer);
+
+    let state = NetworkResponseState::<TestTypes, TestVersions>::new(
+        consensus,
+        Arc::clone(&handle.hotshot.memberships),
+        handle.public_key(),
+        Arc::clone(&handle.hotshot.signer),
+        handle.hotshot.id,
+        handle.hotshot.upgrade_lock.clone(),
+        handle.hotshot.config.vid_config,
+    );
+    let (request_sender, request_receiver) = broadcast(16);
+    let (response_sender, mut response_receiver) = broadcast(16);
+    let task = run_response_task(state, request_receiver, response_sender);
+
+    let (key, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 1);
+    let (_, forger_private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 2);
+    let signed_request = |epoch, private_key| {
+        let request = DrbResultRequestPayload {
+            epoch,
+            view_number: ViewNumber::new(4),
+            key: key.clone(),
+        };
+        let signature = BLSPubKey::sign(private_key, request.commit().as_ref()).unwrap();
+        Arc::new(HotShotEvent::DrbResultRequestRecv(request, signature))
+    };
+    let request = |epoch| signed_request(epoch, &private_key);
+
+    // A request not signed by its sender is dropped without stopping the task
+    request_sender
+        .broadcast(signed_request(seeded_epoch(), &forger_private_key))
+        .await
+        .unwrap();
+
+    // We have no result to serve for the next epoch, so the only response is for the seeded one.
+    request_sender
+        .broadcast(request(seeded_epoch() + 1))
+        .await
+        .unwrap();
+    request_sender
+        .broadcast(request(seeded_epoch()))
+        .await
+        .unwrap();
+    let response = timeout(Duration::from_secs(1), response_receiver.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        *response,
+        HotShotEvent::DrbResultResponseSend(
+            key,
+            DrbResultResponse {
+                view_number: ViewNumber::new(4),
+                epoch: seeded_epoch(),
+                result,
+                proof,
+            }
+        )
+    );
+
+    task.abort();
+}
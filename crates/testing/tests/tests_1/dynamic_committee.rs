@@ -0,0 +1,205 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use hotshot::traits::election::{
+    dynamic_committee::DynamicCommittee,
+    stake_source::{MockStakeSource, StakeUpdate},
+};
+use hotshot_example_types::{
+    node_types::{TestTypes, TestVersions},
+    state_types::{TestInstanceState, TestValidatedState},
+};
+use hotshot_testing::helpers::validator_config;
+use hotshot_types::{
+    data::{EpochNumber, Leaf2, ViewNumber},
+    traits::{election::Membership, node_implementation::ConsensusTime},
+};
+use primitive_types::U256;
+
+/// Add the epoch root of `epoch`, applying the callback like consensus does
+async fn add_epoch_root(
+    membership: &mut DynamicCommittee<TestTypes, MockStakeSource<TestTypes>>,
+    epoch: EpochNumber,
+) {
+    let block_header = Leaf2::<TestTypes>::genesis::<TestVersions>(
+        &TestValidatedState::default(),
+        &TestInstanceState::default(),
+    )
+    .await
+    .block_header()
+    .clone();
+
+    if let Some(callback) = membership.add_epoch_root(epoch, block_header).await {
+        callback(membership);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn committees_follow_stake_updates() {
+    let initial: Vec<_> = (0..4)
+        .map(|i| validator_config(i, 1).public_config())
+        .collect();
+    let joining = validator_config(4, 3).public_config();
+    let leaving = initial[0].stake_table_entry.stake_key.clone();
+    let joining_key = joining.stake_table_entry.stake_key.clone();
+
+    let source = MockStakeSource::default();
+    let mut membership =
+        DynamicCommittee::with_stake_source(initial.clone(), initial, source.clone());
+
+    source
+        .schedule(
+            EpochNumber::new(2),
+            vec![
+                StakeUpdate::Register {
+                    key: joining_key.clone(),
+                    state_ver_key: joining.state_ver_key,
+                    amount: U256::from(3),
+                },
+                StakeUpdate::Deregister {
+                    key: leaving.clone(),
+                },
+            ],
+        )
+        .await;
+    source
+        .schedule(
+            EpochNumber::new(3),
+            vec![StakeUpdate::SetStake {
+                key: joining_key.clone(),
+                amount: U256::from(6),
+            }],
+        )
+        .await;
+
+    // Before any epoch root, every epoch uses the initial committee
+    let (epoch_1, epoch_2, epoch_3) = (
+        Some(EpochNumber::new(1)),
+        Some(EpochNumber::new(2)),
+        Some(EpochNumber::new(3)),
+    );
+    assert_eq!(membership.total_nodes(epoch_2), 4);
+    assert!(membership.has_stake(&leaving, epoch_2));
+
+    add_epoch_root(&mut membership, EpochNumber::new(2)).await;
+    add_epoch_root(&mut membership, EpochNumber::new(3)).await;
+
+    // Epoch 1 keeps the initial committee
+    assert_eq!(membership.total_nodes(epoch_1), 4);
+    assert!(membership.has_stake(&leaving, epoch_1));
+    assert!(!membership.has_stake(&joining_key, epoch_1));
+    assert_eq!(membership.success_threshold(epoch_1).get(), 3);
+
+    // Epoch 2 has the new node and not the one that left
+    assert_eq!(membership.total_nodes(epoch_2), 4);
+    assert!(!membership.has_stake(&leaving, epoch_2));
+    assert!(membership.has_stake(&joining_key, epoch_2));
+    assert!(!membership.has_da_stake(&joining_key, epoch_2));
+    assert_eq!(membership.da_total_nodes(epoch_2), 3);
+    assert_eq!(membership.success_threshold(epoch_2).get(), 5);
+
+    // Epoch 3 has the updated stake, and later epochs keep it until their root is added
+    assert_eq!(
+        membership
+            .stake(&joining_key, epoch_3)
+            .unwrap()
+            .stake_amount,
+        U256::from(6)
+    );
+    assert_eq!(membership.success_threshold(epoch_3).get(), 7);
+    assert_eq!(
+        membership.stake_table(epoch_3),
+        membership.stake_table(Some(EpochNumber::new(4)))
+    );
+
+    // Leaders only come from the staked nodes of the epoch
+    for view in 0..8 {
+        assert_ne!(
+            membership
+                .lookup_leader(ViewNumber::new(view), epoch_2)
+                .unwrap(),
+            leaving
+        );
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn epoch_roots_are_applied_once() {
+    let initial: Vec<_> = (0..4)
+        .map(|i| validator_config(i, 1).public_config())
+        .collect();
+    let key = initial[0].stake_table_entry.stake_key.clone();
+
+    let source = MockStakeSource::default();
+    let mut membership =
+        DynamicCommittee::with_stake_source(initial.clone(), initial, source.clone());
+    source
+        .schedule(
+            EpochNumber::new(2),
+            vec![StakeUpdate::SetStake {
+                key: key.clone(),
+                amount: U256::from(5),
+            }],
+        )
+        .await;
+
+    add_epoch_root(&mut membership, EpochNumber::new(2)).await;
+    let stake_table = membership.stake_table(Some(EpochNumber::new(2)));
+
+    // Adding the same epoch root again, e.g. after a restart of the decide, changes nothing
+    source
+        .schedule(
+            EpochNumber::new(2),
+            vec![StakeUpdate::SetStake {
+                key,
+                amount: U256::from(9),
+            }],
+        )
+        .await;
+    add_epoch_root(&mut membership, EpochNumber::new(2)).await;
+    assert_eq!(
+        membership.stake_table(Some(EpochNumber::new(2))),
+        stake_table
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn failed_stake_updates_are_retried() {
+    let initial: Vec<_> = (0..4)
+        .map(|i| validator_config(i, 1).public_config())
+        .collect();
+    let key = initial[0].stake_table_entry.stake_key.clone();
+
+    let source = MockStakeSource::default();
+    let mut membership =
+        DynamicCommittee::with_stake_source(initial.clone(), initial, source.clone());
+    for epoch in [2, 3] {
+        source
+            .schedule(
+                EpochNumber::new(epoch),
+                vec![StakeUpdate::SetStake {
+                    key: key.clone(),
+                    amount: U256::from(epoch),
+                }],
+            )
+            .await;
+    }
+    let stake = |membership: &DynamicCommittee<_, _>, epoch| {
+        membership
+            .stake(&key, Some(EpochNumber::new(epoch)))
+            .unwrap()
+            .stake_amount
+    };
+
+    // An unavailable source is retried until it recovers, so no epoch's updates are skipped
+    source.fail_next(2);
+    add_epoch_root(&mut membership, EpochNumber::new(2)).await;
+    assert_eq!(stake(&membership, 2), U256::from(2));
+
+    source.fail_next(5);
+    add_epoch_root(&mut membership, EpochNumber::new(3)).await;
+    assert_eq!(stake(&membership, 3), U256::from(3));
+}
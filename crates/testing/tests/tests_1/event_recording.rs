@@ -0,0 +1,116 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{sync::Arc, time::Duration};
+
+use futures::StreamExt;
+use hotshot_example_types::node_types::{MemoryImpl, TestTypes, TestVersions};
+use hotshot_task_impls::{
+    events::HotShotEvent::{self, *},
+    rewind::{EventRecording, RecordedEvent},
+};
+use hotshot_testing::{
+    helpers::{build_system_handle, key_pair_for_id, vid_share},
+    replay::{record_run, replay_recording, ReplayConfig, ReplayDiff},
+    view_generator::TestViewGenerator,
+};
+use hotshot_types::{data::ViewNumber, traits::node_implementation::ConsensusTime};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_event_recording_round_trip() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let membership = Arc::clone(&handle.hotshot.memberships);
+    let mut generator = TestViewGenerator::<TestVersions>::generate(membership);
+    let view = generator.next().await.unwrap();
+
+    let events: Vec<HotShotEvent<TestTypes>> = vec![
+        ViewChange(ViewNumber::new(1), None),
+        QuorumProposalRecv(view.quorum_proposal.clone(), view.leader_public_key),
+        DaCertificateRecv(view.da_certificate.clone()),
+        QuorumProposalValidated(view.quorum_proposal.clone(), view.leaf.clone()),
+        Timeout(ViewNumber::new(2), None),
+        Shutdown,
+    ];
+    let mut config = handle.hotshot.config.clone();
+    config.epoch_height = 7;
+    let recording = EventRecording {
+        node_id: 2,
+        config,
+        events: events
+            .iter()
+            .zip(0..)
+            .map(|(event, i)| RecordedEvent {
+                offset: Duration::from_millis(i * 10),
+                event: event.clone(),
+            })
+            .collect(),
+    };
+
+    let path = std::env::temp_dir().join("hotshot_event_recording_round_trip.bin");
+    recording.save(&path).unwrap();
+    let loaded = EventRecording::<TestTypes>::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.node_id, recording.node_id);
+    assert_eq!(loaded.config.epoch_height, 7);
+    assert_eq!(loaded.events, recording.events);
+
+    let inputs: Vec<_> = loaded.inputs().map(|recorded| &recorded.event).collect();
+    assert_eq!(inputs, vec![&events[1], &events[2], &events[4]]);
+
+    let outputs: Vec<_> = loaded
+        .outputs()
+        .map(|recorded| recorded.event.clone())
+        .collect();
+    assert_eq!(outputs, vec![events[0].clone(), events[3].clone()]);
+
+    // Order does not matter, but every recorded output must be produced exactly once.
+    assert!(ReplayDiff::between(outputs.clone(), outputs.iter().rev().cloned()).is_empty());
+    let diff = ReplayDiff::between(outputs.clone(), vec![events[0].clone(), events[0].clone()]);
+    assert_eq!(diff.missing, vec![events[3].clone()]);
+    assert_eq!(diff.unexpected, vec![events[0].clone()]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_replayed_run_matches_recording() {
+    hotshot::helpers::initialize_logging();
+
+    // A node which does not lead any of the first views, so it only reacts to its inputs
+    let node_id = 5;
+    let (_, public_key) = key_pair_for_id::<TestTypes>(node_id);
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(0)
+        .await
+        .0;
+    let membership = Arc::clone(&handle.hotshot.memberships);
+    let mut generator = TestViewGenerator::<TestVersions>::generate(membership);
+    let view = generator.next().await.unwrap();
+
+    let inputs = vec![
+        DaCertificateRecv(view.da_certificate.clone()),
+        VidShareRecv(
+            view.vid_proposal.1,
+            vid_share(&view.vid_proposal.0, public_key),
+        ),
+        QuorumProposalRecv(view.quorum_proposal.clone(), view.leader_public_key),
+    ];
+    let config = ReplayConfig::default();
+    let recording =
+        record_run::<TestTypes, MemoryImpl, TestVersions>(node_id, inputs, config).await;
+    assert_eq!(recording.inputs().count(), 3);
+    assert!(recording
+        .outputs()
+        .any(|recorded| matches!(recorded.event, QuorumVoteSend(_))));
+
+    let diff = replay_recording::<TestTypes, MemoryImpl, TestVersions>(&recording, config).await;
+    assert!(
+        diff.is_empty(),
+        "Replay diverged from the recording: {diff:?}"
+    );
+}
@@ -0,0 +1,150 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use hotshot_types::{
+    keystore::{KeystoreError, KeystoreFile, ValidatorKeys},
+    signature_key::BLSPubKey,
+    validator_config::ValidatorConfigFile,
+    ValidatorConfig,
+};
+use tagged_base64::TaggedBase64;
+
+/// Base 2 logarithm of the scrypt cost of the keystores under test, low enough to keep the tests
+/// fast
+const SCRYPT_LOG_N: u8 = 4;
+
+#[test]
+fn keystore_decrypts_with_the_right_password_only() {
+    let keys = ValidatorKeys::<BLSPubKey>::generate();
+    let keystore = KeystoreFile::encrypt_with_scrypt_log_n(&keys, "password", SCRYPT_LOG_N);
+    assert_eq!(keystore.stake_table_key, keys.public_key());
+    assert_eq!(keystore.state_ver_key, keys.state_ver_key());
+    assert_eq!(keystore.libp2p_peer_id, keys.libp2p_peer_id());
+
+    let decrypted = keystore.decrypt("password").unwrap();
+    assert_eq!(decrypted.public_key(), keys.public_key());
+    assert_eq!(decrypted.state_ver_key(), keys.state_ver_key());
+    assert_eq!(decrypted.libp2p_peer_id(), keys.libp2p_peer_id());
+
+    assert!(matches!(
+        keystore.decrypt("wrong password"),
+        Err(KeystoreError::WrongPassword)
+    ));
+
+    // Tampering with the parameters or the ciphertext is detected
+    let mut tampered = keystore.clone();
+    tampered.crypto.kdf.params.n *= 2;
+    assert!(matches!(
+        tampered.decrypt("password"),
+        Err(KeystoreError::WrongPassword)
+    ));
+
+    // Parameters which would make us use more memory or time than the keystores we write are
+    // rejected before deriving the key
+    for (r, p) in [(1 << 20, 1), (8, 1 << 20)] {
+        let mut tampered = keystore.clone();
+        tampered.crypto.kdf.params.r = r;
+        tampered.crypto.kdf.params.p = p;
+        assert!(matches!(
+            tampered.decrypt("password"),
+            Err(KeystoreError::Malformed(_))
+        ));
+    }
+
+    let mut tampered = keystore.clone();
+    let mut ciphertext = TaggedBase64::parse(&tampered.crypto.cipher.message)
+        .unwrap()
+        .value();
+    ciphertext[0] ^= 1;
+    tampered.crypto.cipher.message = TaggedBase64::new("KEYSTORE_KEYS", &ciphertext)
+        .unwrap()
+        .to_string();
+    assert!(matches!(
+        tampered.decrypt("password"),
+        Err(KeystoreError::WrongPassword)
+    ));
+
+    // Public keys are authenticated along with the private keys
+    let mut tampered = keystore;
+    tampered.stake_table_key = ValidatorKeys::<BLSPubKey>::generate().public_key();
+    assert!(tampered.decrypt("password").is_err());
+}
+
+#[test]
+fn rotation_replaces_only_the_chosen_key() {
+    let keys = ValidatorKeys::<BLSPubKey>::generate();
+
+    let mut rotated = keys.clone();
+    rotated.rotate_state_key();
+    assert_eq!(rotated.public_key(), keys.public_key());
+    assert_ne!(rotated.state_ver_key(), keys.state_ver_key());
+    assert_eq!(rotated.libp2p_peer_id(), keys.libp2p_peer_id());
+
+    let mut rotated = keys.clone();
+    rotated.rotate_staking_key();
+    rotated.rotate_libp2p_key();
+    assert_ne!(rotated.public_key(), keys.public_key());
+    assert_eq!(rotated.state_ver_key(), keys.state_ver_key());
+    assert_ne!(rotated.libp2p_peer_id(), keys.libp2p_peer_id());
+
+    let peer = rotated.peer_config_keys(7, true);
+    assert_eq!(peer.stake_table_key, rotated.public_key());
+    assert_eq!(peer.state_ver_key, rotated.state_ver_key());
+    assert_eq!((peer.stake, peer.da), (7, true));
+}
+
+#[test]
+fn keystore_files_roundtrip_without_overwriting() {
+    let path = std::env::temp_dir().join(format!("hotshot-keystore-{}.toml", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let keys = ValidatorKeys::<BLSPubKey>::generate();
+    let keystore = KeystoreFile::encrypt_with_scrypt_log_n(&keys, "password", SCRYPT_LOG_N);
+    keystore.to_file(&path).unwrap();
+    assert!(keystore.to_file(&path).is_err());
+
+    let read = KeystoreFile::<BLSPubKey>::from_file(&path).unwrap();
+    assert_eq!(read, keystore);
+    assert_eq!(
+        read.decrypt("password").unwrap().public_key(),
+        keys.public_key()
+    );
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn validator_config_is_loaded_from_a_keystore() {
+    let dir = std::env::temp_dir().join(format!("hotshot-keystore-config-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let keystore_path = dir.join("keystore.toml");
+    let password_path = dir.join("password");
+
+    let keys = ValidatorKeys::<BLSPubKey>::generate();
+    KeystoreFile::encrypt_with_scrypt_log_n(&keys, "password", SCRYPT_LOG_N)
+        .to_file(&keystore_path)
+        .unwrap();
+    std::fs::write(&password_path, "password\n").unwrap();
+
+    let mut config_file = ValidatorConfigFile {
+        is_da: true,
+        keystore: Some(keystore_path),
+        ..ValidatorConfigFile::default()
+    };
+    assert!(matches!(
+        config_file.validator_config::<BLSPubKey>(),
+        Err(KeystoreError::MissingPassword)
+    ));
+
+    config_file.keystore_password_file = Some(password_path);
+    let config: ValidatorConfig<BLSPubKey> = config_file.validator_config().unwrap();
+    assert_eq!(config.public_key, keys.public_key());
+    assert_eq!(config.state_key_pair.ver_key(), keys.state_ver_key());
+    assert!(config.is_da);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,254 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use hotshot::traits::election::{
+    reputation_committee::{ReputationCommittee, ReputationConfig, ViewFailure},
+    static_committee::StaticCommittee,
+};
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_testing::helpers::validator_config;
+use hotshot_types::{
+    data::{EpochNumber, ViewNumber},
+    signature_key::BLSPubKey,
+    traits::{election::Membership, node_implementation::ConsensusTime},
+    ValidatorConfig,
+};
+
+/// Epoch height of the tests, the epoch roots are blocks 8, 18, 28...
+const EPOCH_HEIGHT: u64 = 10;
+
+/// A round robin committee of `num_nodes` nodes
+fn committee(
+    num_nodes: u64,
+) -> (
+    ReputationCommittee<TestTypes, StaticCommittee<TestTypes>>,
+    Vec<BLSPubKey>,
+) {
+    let validators: Vec<_> = (0..num_nodes).map(|i| validator_config(i, 1)).collect();
+    let peers: Vec<_> = validators
+        .iter()
+        .map(ValidatorConfig::public_config)
+        .collect();
+
+    let membership = ReputationCommittee::with_config(
+        StaticCommittee::new(peers.clone(), peers),
+        ReputationConfig { min_failures: 2 },
+    );
+    (
+        membership,
+        validators.into_iter().map(|v| v.public_key).collect(),
+    )
+}
+
+/// The tip of a decided chain
+struct Chain {
+    /// The view of the last decided leaf
+    last_view: u64,
+    /// The block number of the next decided leaf
+    next_block: u64,
+}
+
+impl Chain {
+    /// The chain at genesis
+    fn genesis() -> Self {
+        Self {
+            last_view: 0,
+            next_block: 1,
+        }
+    }
+}
+
+/// Decide every view in `views` except those in `failed`, each on top of the previous one
+fn decide_views(
+    membership: &mut ReputationCommittee<TestTypes, StaticCommittee<TestTypes>>,
+    views: impl Iterator<Item = u64>,
+    failed: &[u64],
+    failure: ViewFailure,
+    chain: &mut Chain,
+) {
+    for view in views.filter(|view| !failed.contains(view)) {
+        membership.add_decided_view(
+            ViewNumber::new(view),
+            ViewNumber::new(chain.last_view),
+            chain.next_block,
+            EPOCH_HEIGHT,
+            failure,
+        );
+        chain.last_view = view;
+        chain.next_block += 1;
+    }
+}
+
+/// The leader of `view` in `epoch`
+fn leader(
+    membership: &ReputationCommittee<TestTypes, StaticCommittee<TestTypes>>,
+    view: u64,
+    epoch: u64,
+) -> BLSPubKey {
+    membership
+        .lookup_leader(ViewNumber::new(view), Some(EpochNumber::new(epoch)))
+        .unwrap()
+}
+
+/// Decide up to the root of epoch 2, node 2 failing to lead all its views since the root of
+/// epoch 1. Returns the tip of the chain.
+fn fail_node_2(
+    membership: &mut ReputationCommittee<TestTypes, StaticCommittee<TestTypes>>,
+) -> Chain {
+    let mut chain = Chain::genesis();
+
+    // Views 1 to 8 are blocks 1 to 8, the root of epoch 1.
+    decide_views(membership, 1..9, &[], ViewFailure::Timeout, &mut chain);
+    // Node 2 leads views 10, 14 and 18, and none of them is decided. Node 3 misses view 19 too,
+    // which isn't enough to be demoted.
+    decide_views(
+        membership,
+        9..21,
+        &[10, 14, 18, 19],
+        ViewFailure::Timeout,
+        &mut chain,
+    );
+    // View 22 is block 18, the root of epoch 2.
+    decide_views(membership, 21..23, &[], ViewFailure::Timeout, &mut chain);
+    assert_eq!(chain.next_block, 19);
+
+    chain
+}
+
+#[test]
+fn unresponsive_leader_is_demoted_for_the_next_epoch() {
+    let (mut membership, keys) = committee(4);
+    let mut chain = fail_node_2(&mut membership);
+
+    // Node 2 is skipped in epoch 3, in favor of the next scheduled leader, but not in the current
+    // epoch whose leaders were already known.
+    assert!(membership.demoted_leaders(EpochNumber::new(2)).is_empty());
+    assert_eq!(
+        membership.demoted_leaders(EpochNumber::new(3)),
+        [keys[2].clone()].into_iter().collect()
+    );
+    for view in 30..40 {
+        assert_ne!(leader(&membership, view, 3), keys[2]);
+    }
+    assert_eq!(leader(&membership, 34, 3), keys[3]);
+    assert_eq!(leader(&membership, 34, 2), keys[2]);
+
+    // Demotions last a single epoch, node 2 is back in epoch 4.
+    decide_views(
+        &mut membership,
+        23..33,
+        &[],
+        ViewFailure::Timeout,
+        &mut chain,
+    );
+    assert_eq!(chain.next_block, 29);
+    assert!(membership.demoted_leaders(EpochNumber::new(4)).is_empty());
+    assert_eq!(leader(&membership, 42, 4), keys[2]);
+}
+
+#[test]
+fn failures_count_against_the_effective_leader() {
+    let (mut membership, keys) = committee(4);
+    let mut chain = fail_node_2(&mut membership);
+
+    // View 24 is block 20, the last of epoch 2, so the following views are in epoch 3 where node
+    // 3 leads the views scheduled for node 2.
+    decide_views(
+        &mut membership,
+        23..35,
+        &[26, 27, 30, 31],
+        ViewFailure::ViewSync,
+        &mut chain,
+    );
+    // View 36 is block 28, the root of epoch 3.
+    decide_views(
+        &mut membership,
+        35..37,
+        &[],
+        ViewFailure::ViewSync,
+        &mut chain,
+    );
+    assert_eq!(chain.next_block, 29);
+
+    assert_eq!(
+        membership.demoted_leaders(EpochNumber::new(4)),
+        [keys[3].clone()].into_iter().collect()
+    );
+}
+
+#[test]
+fn reputation_is_restored_after_a_restart() {
+    let path = std::env::temp_dir().join(format!(
+        "hotshot-leader-reputation-{}.bin",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    let (membership, keys) = committee(4);
+    let persistent = |membership: ReputationCommittee<_, _>| {
+        ReputationCommittee::persistent(
+            membership.inner().clone(),
+            ReputationConfig { min_failures: 2 },
+            &path,
+        )
+        .unwrap()
+    };
+    let mut membership = persistent(membership);
+    let mut chain = Chain::genesis();
+
+    // Node 2 fails view 10, after the root of epoch 1.
+    decide_views(
+        &mut membership,
+        1..15,
+        &[10],
+        ViewFailure::ViewSync,
+        &mut chain,
+    );
+
+    // The node restarts and fails view 18 too, which only demotes it if view 10 is remembered.
+    let mut membership = persistent(membership);
+    decide_views(
+        &mut membership,
+        15..25,
+        &[18, 22],
+        ViewFailure::ViewSync,
+        &mut chain,
+    );
+    assert_eq!(chain.next_block, 22);
+    assert_eq!(
+        membership.demoted_leaders(EpochNumber::new(3)),
+        [keys[2].clone()].into_iter().collect()
+    );
+
+    // So are the demotions.
+    let restarted = persistent(membership);
+    assert_eq!(
+        restarted.demoted_leaders(EpochNumber::new(3)),
+        [keys[2].clone()].into_iter().collect()
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn leaders_are_not_demoted_without_epochs() {
+    let (mut membership, keys) = committee(4);
+
+    let mut chain = Chain::genesis();
+    for view in (1..40).filter(|view| view % 4 != 2) {
+        membership.add_decided_view(
+            ViewNumber::new(view),
+            ViewNumber::new(chain.last_view),
+            view,
+            0,
+            ViewFailure::Timeout,
+        );
+        chain.last_view = view;
+    }
+    assert_eq!(
+        membership.lookup_leader(ViewNumber::new(42), None).unwrap(),
+        keys[2]
+    );
+}
@@ -0,0 +1,185 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::sync::Arc;
+
+use committable::Committable;
+use futures::StreamExt;
+use hotshot_example_types::node_types::{EpochsTestVersions, MemoryImpl, TestTypes, TestVersions};
+use hotshot_testing::{
+    helpers::{build_system_handle, validator_config},
+    view_generator::TestViewGenerator,
+};
+use hotshot_types::{
+    data::{EpochNumber, Leaf2},
+    leaf_verifier::{LeafVerifier, VerifierStakeTable},
+    message::UpgradeLock,
+    traits::{
+        block_contents::BlockHeader,
+        node_implementation::{ConsensusTime, Versions},
+    },
+};
+
+/// Epoch height of the chains signed with epoch versions, whose leaves are then all in epoch 1
+const EPOCH_HEIGHT: u64 = 100;
+
+/// The leaves of `count` consecutive views, signed by the committee of a 2 node system under the
+/// versions `V`, along with its stake table
+async fn leaves<V: Versions>(
+    count: usize,
+) -> (Vec<Leaf2<TestTypes>>, VerifierStakeTable<TestTypes>) {
+    let handle = build_system_handle::<TestTypes, MemoryImpl, V>(2).await.0;
+    let membership = Arc::clone(&handle.hotshot.memberships);
+    let stake_table = VerifierStakeTable::from_membership(&*membership.read().await, None);
+
+    let generator = TestViewGenerator::<V>::generate(membership);
+    let leaves = generator
+        .take(count)
+        .map(|view| view.leaf)
+        .collect::<Vec<_>>()
+        .await;
+
+    (leaves, stake_table)
+}
+
+/// A verifier trusting `stake_table`
+fn verifier(stake_table: VerifierStakeTable<TestTypes>) -> LeafVerifier<TestTypes, TestVersions> {
+    let mut verifier = LeafVerifier::new(0, UpgradeLock::new());
+    verifier.add_stake_table(None, stake_table).unwrap();
+    verifier
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn decided_leaves_are_verified() {
+    let (leaves, stake_table) = leaves::<TestVersions>(8).await;
+    let mut verifier = verifier(stake_table);
+
+    // Views 3, 4 and 5 are consecutive and view 5 is certified by the QC in view 6, so views 1
+    // to 3 are decided.
+    let headers = verifier
+        .verify(&leaves[..5], &leaves[5].justify_qc())
+        .await
+        .unwrap();
+    assert_eq!(
+        headers
+            .iter()
+            .map(BlockHeader::block_number)
+            .collect::<Vec<_>>(),
+        leaves[..3].iter().map(Leaf2::height).collect::<Vec<_>>()
+    );
+    assert_eq!(verifier.anchor(), Some(&leaves[2]));
+
+    // The next chain must extend the decided leaf
+    assert!(verifier
+        .verify(&leaves[4..7], &leaves[7].justify_qc())
+        .await
+        .is_err());
+    let headers = verifier
+        .verify(&leaves[3..7], &leaves[7].justify_qc())
+        .await
+        .unwrap();
+    assert_eq!(headers.len(), 2);
+    assert_eq!(verifier.anchor(), Some(&leaves[4]));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn invalid_chains_are_rejected() {
+    let (leaves, stake_table) = leaves::<TestVersions>(6).await;
+
+    // Not enough leaves for a decide
+    assert!(verifier(stake_table.clone())
+        .verify(&leaves[..2], &leaves[2].justify_qc())
+        .await
+        .is_err());
+
+    // A gap in the chain
+    let gapped = [leaves[0].clone(), leaves[2].clone(), leaves[3].clone()];
+    assert!(verifier(stake_table.clone())
+        .verify(&gapped, &leaves[4].justify_qc())
+        .await
+        .is_err());
+
+    // A QC over another leaf
+    assert!(verifier(stake_table.clone())
+        .verify(&leaves[..4], &leaves[3].justify_qc())
+        .await
+        .is_err());
+
+    // QCs signed by a stake table we don't trust
+    let other_stake_table = VerifierStakeTable::<TestTypes>::new(
+        (100..102)
+            .map(|i| validator_config(i, 1).public_config().stake_table_entry)
+            .collect(),
+    );
+    assert!(verifier(other_stake_table)
+        .verify(&leaves[..4], &leaves[4].justify_qc())
+        .await
+        .is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stake_tables_are_checked_against_their_commitment() {
+    let (leaves, stake_table) = leaves::<TestVersions>(5).await;
+    let mut verifier = LeafVerifier::<TestTypes, TestVersions>::new(0, UpgradeLock::new());
+    verifier
+        .add_stake_table_commitment(None, stake_table.commit())
+        .unwrap();
+
+    // Only the commitment is known, the QCs can't be checked yet
+    assert!(verifier
+        .verify(&leaves[..4], &leaves[4].justify_qc())
+        .await
+        .is_err());
+
+    let mut other_stake_table = stake_table.clone();
+    other_stake_table.threshold = other_stake_table.threshold.saturating_add(1);
+    assert!(verifier.add_stake_table(None, other_stake_table).is_err());
+
+    verifier.add_stake_table(None, stake_table).unwrap();
+    assert!(verifier
+        .verify(&leaves[..4], &leaves[4].justify_qc())
+        .await
+        .is_ok());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn epoch_versions_decide_with_two_chains() {
+    let (leaves, stake_table) = leaves::<EpochsTestVersions>(6).await;
+    let mut verifier =
+        LeafVerifier::<TestTypes, EpochsTestVersions>::new(EPOCH_HEIGHT, UpgradeLock::new());
+    verifier
+        .add_stake_table(Some(EpochNumber::new(1)), stake_table)
+        .unwrap();
+
+    // Views 2 and 3 are consecutive and view 3 is certified by the QC in view 4, so views 1 and 2
+    // are decided.
+    let headers = verifier
+        .verify(&leaves[..3], &leaves[3].justify_qc())
+        .await
+        .unwrap();
+    assert_eq!(
+        headers
+            .iter()
+            .map(BlockHeader::block_number)
+            .collect::<Vec<_>>(),
+        leaves[..2].iter().map(Leaf2::height).collect::<Vec<_>>()
+    );
+    assert_eq!(verifier.anchor(), Some(&leaves[1]));
+
+    // Two leaves of consecutive views are enough for a decide.
+    let headers = verifier
+        .verify(&leaves[2..4], &leaves[4].justify_qc())
+        .await
+        .unwrap();
+    assert_eq!(headers.len(), 1);
+    assert_eq!(verifier.anchor(), Some(&leaves[2]));
+
+    // But not a single leaf.
+    assert!(verifier
+        .verify(&leaves[3..4], &leaves[4].justify_qc())
+        .await
+        .is_err());
+}
@@ -0,0 +1,124 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use hotshot::traits::election::{
+    dynamic_committee::DynamicCommittee, stake_source::MockStakeSource,
+};
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_task_impls::light_client::StateSignatureAggregator;
+use hotshot_testing::helpers::validator_config;
+use hotshot_types::{
+    light_client::{
+        CircuitField, LightClientStakeTable, LightClientState, StakeTableState,
+        StateSignatureRequestBody,
+    },
+    signature_key::BLSPubKey,
+    traits::election::Membership,
+    ValidatorConfig,
+};
+use primitive_types::U256;
+
+/// The signature of `validator` over `state`
+fn sign(
+    validator: &ValidatorConfig<BLSPubKey>,
+    state: &LightClientState,
+    stake_table: &StakeTableState,
+) -> StateSignatureRequestBody {
+    StateSignatureRequestBody {
+        key: validator.state_key_pair.ver_key(),
+        state: state.clone(),
+        signature: validator.state_key_pair.sign_state(state, stake_table),
+    }
+}
+
+#[test]
+fn signatures_are_aggregated_until_the_threshold() {
+    let validators: Vec<_> = (0..4).map(|i| validator_config(i, 1)).collect();
+    let stake_table = LightClientStakeTable::new(
+        CircuitField::from(1u64),
+        CircuitField::from(2u64),
+        CircuitField::from(3u64),
+        validators
+            .iter()
+            .map(|v| (v.state_key_pair.ver_key(), U256::one()))
+            .collect(),
+    );
+    assert_eq!(stake_table.threshold, U256::from(3));
+
+    let state = LightClientState {
+        view_number: 7,
+        block_height: 5,
+        block_comm_root: CircuitField::from(42u64),
+    };
+    let other_state = LightClientState {
+        view_number: 8,
+        ..state.clone()
+    };
+    let st_state = stake_table.state;
+    let mut aggregator = StateSignatureAggregator::new(state.clone(), stake_table);
+
+    assert!(!aggregator
+        .add_signature(&sign(&validators[0], &state, &st_state))
+        .unwrap());
+    // A second signature from the same key doesn't count twice
+    assert!(!aggregator
+        .add_signature(&sign(&validators[0], &state, &st_state))
+        .unwrap());
+
+    // Signatures over another state, forged ones and ones from keys without stake are rejected
+    assert!(aggregator
+        .add_signature(&sign(&validators[1], &other_state, &st_state))
+        .is_err());
+    let mut forged = sign(&validators[1], &other_state, &st_state);
+    forged.state = state.clone();
+    assert!(aggregator.add_signature(&forged).is_err());
+    assert!(aggregator
+        .add_signature(&sign(&validator_config(10, 1), &state, &st_state))
+        .is_err());
+
+    assert!(!aggregator
+        .add_signature(&sign(&validators[1], &state, &st_state))
+        .unwrap());
+    assert!(!aggregator.is_certified());
+    assert!(aggregator
+        .add_signature(&sign(&validators[2], &state, &st_state))
+        .unwrap());
+    assert!(aggregator.is_certified());
+
+    // The state is only reported certified once
+    assert!(!aggregator
+        .add_signature(&sign(&validators[3], &state, &st_state))
+        .unwrap());
+    let bundle = aggregator.bundle();
+    assert_eq!(bundle.state, state);
+    assert_eq!(bundle.signatures.len(), 4);
+    assert_eq!(bundle.accumulated_weight, U256::from(4));
+}
+
+#[test]
+fn dynamic_committee_commits_to_its_light_client_stake_table() {
+    let validators: Vec<_> = (0..4).map(|i| validator_config(i, i + 1)).collect();
+    let peers: Vec<_> = validators
+        .iter()
+        .map(ValidatorConfig::public_config)
+        .collect();
+    let membership = DynamicCommittee::<TestTypes, MockStakeSource<TestTypes>>::with_stake_source(
+        peers.clone(),
+        peers,
+        MockStakeSource::default(),
+    );
+
+    let stake_table = membership.light_client_stake_table(None).unwrap();
+    for (i, validator) in (1u64..).zip(&validators) {
+        assert_eq!(
+            stake_table.stake(&validator.state_key_pair.ver_key()),
+            U256::from(i)
+        );
+    }
+    assert_eq!(stake_table.threshold, U256::from(7));
+    assert_eq!(stake_table.state.threshold, CircuitField::from(7u64));
+    assert_ne!(stake_table.state, StakeTableState::default());
+}
@@ -138,3 +138,88 @@ async fn test_certificate2_validity() {
 
     assert!(leaf2.parent_commitment() == parent_leaf2.commit());
 }
+
+#[test]
+// Checks that VID data before the AvidM upgrade keeps the encoding of the bare ADVZ types, while
+// AvidM data is tagged with its scheme.
+fn vid_data_keeps_advz_encoding_before_upgrade() {
+    use hotshot_example_types::node_types::TestVersions;
+    use hotshot_types::{
+        traits::node_implementation::Versions,
+        vid::{
+            vid_disperse, with_vid_encoding, VidCommitment, VidCommon, VidConfig, VidEncoding,
+            VidShare,
+        },
+    };
+    use vbs::version::StaticVersionType;
+
+    let base = <TestVersions as Versions>::Base::VERSION;
+    let avidm = <TestVersions as Versions>::AvidM::VERSION;
+    assert_eq!(VidEncoding::at::<TestVersions>(base), VidEncoding::Legacy);
+    assert_eq!(VidEncoding::at::<TestVersions>(avidm), VidEncoding::Tagged);
+
+    let payload = [7u8; 100];
+    let advz = vid_disperse::<TestVersions>(&payload, 4, base, &VidConfig::default()).unwrap();
+    let (VidCommitment::V0(commit), VidCommon::V0(common), VidShare::V0(share)) =
+        (advz.commit, &advz.common, &advz.shares[0])
+    else {
+        panic!("VID data before the upgrade should use ADVZ");
+    };
+
+    with_vid_encoding(VidEncoding::Legacy, || {
+        let bytes = bincode::serialize(&advz.commit).unwrap();
+        assert_eq!(bytes, bincode::serialize(&commit).unwrap());
+        assert_eq!(
+            bincode::deserialize::<VidCommitment>(&bytes).unwrap(),
+            advz.commit
+        );
+
+        let bytes = bincode::serialize(&advz.common).unwrap();
+        assert_eq!(bytes, bincode::serialize(common).unwrap());
+        assert_eq!(
+            bincode::deserialize::<VidCommon>(&bytes).unwrap(),
+            advz.common
+        );
+
+        let bytes = bincode::serialize(&advz.shares[0]).unwrap();
+        assert_eq!(bytes, bincode::serialize(share).unwrap());
+        assert_eq!(
+            bincode::deserialize::<VidShare>(&bytes).unwrap(),
+            advz.shares[0]
+        );
+    });
+
+    let avidm_dispersal =
+        vid_disperse::<TestVersions>(&payload, 4, avidm, &VidConfig::default()).unwrap();
+
+    // AvidM data cannot be sent before the upgrade.
+    with_vid_encoding(VidEncoding::Legacy, || {
+        assert!(bincode::serialize(&avidm_dispersal.commit).is_err());
+        assert!(bincode::serialize(&avidm_dispersal.shares[0]).is_err());
+    });
+
+    with_vid_encoding(VidEncoding::Tagged, || {
+        for commit in [advz.commit, avidm_dispersal.commit] {
+            let bytes = bincode::serialize(&commit).unwrap();
+            assert_eq!(
+                bincode::deserialize::<VidCommitment>(&bytes).unwrap(),
+                commit
+            );
+        }
+        let bytes = bincode::serialize(&avidm_dispersal.shares[0]).unwrap();
+        assert_eq!(
+            bincode::deserialize::<VidShare>(&bytes).unwrap(),
+            avidm_dispersal.shares[0]
+        );
+    });
+
+    // Human readable commitments are the tagged base64 strings of either scheme.
+    for commit in [advz.commit, avidm_dispersal.commit] {
+        let json = serde_json::to_string(&commit).unwrap();
+        assert_eq!(json, format!("\"{commit}\""));
+        assert_eq!(
+            serde_json::from_str::<VidCommitment>(&json).unwrap(),
+            commit
+        );
+    }
+}
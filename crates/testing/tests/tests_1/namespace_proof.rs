@@ -0,0 +1,148 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use hotshot_example_types::{
+    block_types::{TestBlockPayload, TestTransaction, TEST_NAMESPACE},
+    node_types::{TestTypes, TestVersions},
+    state_types::{TestInstanceState, TestValidatedState},
+};
+use hotshot_types::{
+    traits::{
+        block_contents::{
+            BlockPayload, EncodeBytes, NamespaceProof, NamespaceProofError, NamespacedBlockPayload,
+        },
+        node_implementation::Versions,
+    },
+    vid::{vid_disperse, VidConfig, VidError},
+};
+use vbs::version::StaticVersionType;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_namespace_proof_round_trip() {
+    let config = VidConfig::default();
+    let num_storage_nodes = 4;
+    let base = <TestVersions as Versions>::Base::VERSION;
+    let avidm = <TestVersions as Versions>::AvidM::VERSION;
+
+    let transactions = vec![
+        TestTransaction::new(vec![1, 2, 3]),
+        TestTransaction::new(vec![4; 40]),
+    ];
+    let (payload, metadata) = <TestBlockPayload as BlockPayload<TestTypes>>::from_transactions(
+        transactions.clone(),
+        &TestValidatedState::default(),
+        &TestInstanceState::default(),
+    )
+    .await
+    .unwrap();
+    let dispersal =
+        vid_disperse::<TestVersions>(&payload.encode(), num_storage_nodes, base, &config).unwrap();
+
+    let proof = <TestBlockPayload as NamespacedBlockPayload<TestTypes>>::namespace_proof(
+        &payload,
+        &metadata,
+        &TEST_NAMESPACE,
+        &dispersal.common,
+        num_storage_nodes,
+        &config,
+    )
+    .unwrap()
+    .expect("The test namespace is in the payload");
+    let verify = |proof: &NamespaceProof| {
+        <TestBlockPayload as NamespacedBlockPayload<TestTypes>>::verify_namespace(
+            &metadata,
+            &TEST_NAMESPACE,
+            proof,
+            &dispersal.commit,
+            &dispersal.common,
+            num_storage_nodes,
+            &config,
+        )
+    };
+    assert_eq!(verify(&proof).unwrap(), transactions);
+
+    // Tampered transactions are rejected.
+    let mut tampered = proof.clone();
+    let last = tampered.bytes.len() - 1;
+    tampered.bytes[last] ^= 1;
+    assert!(matches!(
+        verify(&tampered),
+        Err(NamespaceProofError::Vid(_))
+    ));
+
+    // So is a proof of another payload of the same length.
+    let other_transactions = vec![
+        TestTransaction::new(vec![3, 2, 1]),
+        TestTransaction::new(vec![5; 40]),
+    ];
+    let (other_payload, other_metadata) =
+        <TestBlockPayload as BlockPayload<TestTypes>>::from_transactions(
+            other_transactions,
+            &TestValidatedState::default(),
+            &TestInstanceState::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(other_metadata, metadata);
+    let other_dispersal =
+        vid_disperse::<TestVersions>(&other_payload.encode(), num_storage_nodes, base, &config)
+            .unwrap();
+    let mut forged = <TestBlockPayload as NamespacedBlockPayload<TestTypes>>::namespace_proof(
+        &other_payload,
+        &other_metadata,
+        &TEST_NAMESPACE,
+        &other_dispersal.common,
+        num_storage_nodes,
+        &config,
+    )
+    .unwrap()
+    .unwrap();
+    assert!(verify(&forged).is_err());
+    forged.bytes.clone_from(&proof.bytes);
+    assert!(verify(&forged).is_err());
+
+    // A namespace that is not in the payload has no proof.
+    let missing = TEST_NAMESPACE + 1;
+    assert!(
+        <TestBlockPayload as NamespacedBlockPayload<TestTypes>>::namespace_proof(
+            &payload,
+            &metadata,
+            &missing,
+            &dispersal.common,
+            num_storage_nodes,
+            &config,
+        )
+        .unwrap()
+        .is_none()
+    );
+    assert!(matches!(
+        <TestBlockPayload as NamespacedBlockPayload<TestTypes>>::verify_namespace(
+            &metadata,
+            &missing,
+            &proof,
+            &dispersal.commit,
+            &dispersal.common,
+            num_storage_nodes,
+            &config,
+        ),
+        Err(NamespaceProofError::MissingNamespace)
+    ));
+
+    // AvidM cannot prove payload ranges.
+    let avidm_dispersal =
+        vid_disperse::<TestVersions>(&payload.encode(), num_storage_nodes, avidm, &config).unwrap();
+    assert!(matches!(
+        <TestBlockPayload as NamespacedBlockPayload<TestTypes>>::namespace_proof(
+            &payload,
+            &metadata,
+            &TEST_NAMESPACE,
+            &avidm_dispersal.common,
+            num_storage_nodes,
+            &config,
+        ),
+        Err(VidError::RangeProofsUnsupported)
+    ));
+}
@@ -0,0 +1,37 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use hotshot_types::{
+    network::{NetworkConfig, NetworkConfigError},
+    signature_key::BLSPubKey,
+    vid::VidConfigError,
+};
+
+#[test]
+fn loading_a_config_with_an_invalid_vid_config_fails() {
+    let path = std::env::temp_dir()
+        .join(format!(
+            "hotshot-network-config-{}.json",
+            std::process::id()
+        ))
+        .to_string_lossy()
+        .into_owned();
+
+    let mut config = NetworkConfig::<BLSPubKey>::default();
+    config.to_file(path.clone()).unwrap();
+    assert!(NetworkConfig::<BLSPubKey>::from_file(path.clone()).is_ok());
+
+    config.config.vid_config.shares_per_node = 0;
+    config.to_file(path.clone()).unwrap();
+    assert!(matches!(
+        NetworkConfig::<BLSPubKey>::from_file(path.clone()),
+        Err(NetworkConfigError::InvalidVidConfig(
+            VidConfigError::NoShares
+        ))
+    ));
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -32,6 +32,7 @@ use hotshot_types::{
         node_implementation::{ConsensusTime, Versions},
     },
     utils::BuilderCommitment,
+    vid::VidConfig,
 };
 use sha2::Digest;
 use vec1::vec1;
@@ -103,6 +104,7 @@ async fn test_quorum_proposal_task_quorum_proposal_view_1() {
             .total_nodes(Some(EpochNumber::new(1))),
         <TestVersions as Versions>::Base::VERSION,
         *ViewNumber::new(1),
+        &VidConfig::default(),
     )
     .unwrap();
     drop(consensus_writer);
@@ -117,9 +119,7 @@ async fn test_quorum_proposal_task_quorum_proposal_view_1() {
             SendPayloadCommitmentAndMetadata(
                 payload_commitment,
                 builder_commitment,
-                TestMetadata {
-                    num_transactions: 0
-                },
+                TestMetadata::new(&[]),
                 ViewNumber::new(1),
                 vec1![builder_fee.clone()],
                 None,
@@ -198,6 +198,7 @@ async fn test_quorum_proposal_task_quorum_proposal_view_gt_1() {
             .total_nodes(Some(EpochNumber::new(1))),
         <TestVersions as Versions>::Base::VERSION,
         *ViewNumber::new(1),
+        &VidConfig::default(),
     )
     .unwrap();
 
@@ -220,9 +221,7 @@ async fn test_quorum_proposal_task_quorum_proposal_view_gt_1() {
                 )
                 .await,
                 builder_commitment.clone(),
-                TestMetadata {
-                    num_transactions: 0
-                },
+                TestMetadata::new(&[]),
                 ViewNumber::new(1),
                 vec1![builder_fee.clone()],
                 None,
@@ -392,9 +391,7 @@ async fn test_quorum_proposal_task_qc_timeout() {
         SendPayloadCommitmentAndMetadata(
             payload_commitment,
             builder_commitment,
-            TestMetadata {
-                num_transactions: 0
-            },
+            TestMetadata::new(&[]),
             ViewNumber::new(3),
             vec1![null_block::builder_fee::<TestTypes, TestVersions>(
                 membership
@@ -403,6 +400,7 @@ async fn test_quorum_proposal_task_qc_timeout() {
                     .total_nodes(Some(EpochNumber::new(1))),
                 <TestVersions as Versions>::Base::VERSION,
                 *ViewNumber::new(3),
+                &VidConfig::default(),
             )
             .unwrap()],
             None,
@@ -493,9 +491,7 @@ async fn test_quorum_proposal_task_view_sync() {
         SendPayloadCommitmentAndMetadata(
             payload_commitment,
             builder_commitment,
-            TestMetadata {
-                num_transactions: 0
-            },
+            TestMetadata::new(&[]),
             ViewNumber::new(2),
             vec1![null_block::builder_fee::<TestTypes, TestVersions>(
                 membership
@@ -504,6 +500,7 @@ async fn test_quorum_proposal_task_view_sync() {
                     .total_nodes(Some(EpochNumber::new(1))),
                 <TestVersions as Versions>::Base::VERSION,
                 *ViewNumber::new(2),
+                &VidConfig::default(),
             )
             .unwrap()],
             None,
@@ -574,6 +571,7 @@ async fn test_quorum_proposal_task_liveness_check() {
             .total_nodes(Some(EpochNumber::new(1))),
         <TestVersions as Versions>::Base::VERSION,
         *ViewNumber::new(1),
+        &VidConfig::default(),
     )
     .unwrap();
 
@@ -600,9 +598,7 @@ async fn test_quorum_proposal_task_liveness_check() {
                 )
                 .await,
                 builder_commitment.clone(),
-                TestMetadata {
-                    num_transactions: 0
-                },
+                TestMetadata::new(&[]),
                 ViewNumber::new(1),
                 vec1![builder_fee.clone()],
                 None,
@@ -0,0 +1,65 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::sync::Arc;
+
+use futures::StreamExt;
+use hotshot_example_types::{
+    block_types::{TestBlockPayload, TestTransaction},
+    node_types::{MemoryImpl, TestTypes, TestVersions},
+    state_types::TestValidatedState,
+};
+use hotshot_testing::{helpers::build_system_handle, view_generator::TestViewGenerator};
+use hotshot_types::{
+    data::{Leaf2, ViewNumber},
+    traits::node_implementation::ConsensusTime,
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn payload_is_reconstructed_from_vid_shares() {
+    hotshot::helpers::initialize_logging();
+
+    let handle = build_system_handle::<TestTypes, MemoryImpl, TestVersions>(2)
+        .await
+        .0;
+    let membership = Arc::clone(&handle.hotshot.memberships);
+    let mut generator = TestViewGenerator::<TestVersions>::generate(membership);
+
+    generator.next().await;
+    let transactions = vec![
+        TestTransaction::new(vec![1, 2, 3]),
+        TestTransaction::new(vec![4, 5]),
+    ];
+    generator.add_transactions(transactions.clone());
+    let view = generator.next().await.unwrap();
+
+    // The leaf is known but its payload isn't, only the VID shares of the storage nodes
+    let consensus = handle.hotshot.consensus();
+    let mut consensus_writer = consensus.write().await;
+    consensus_writer
+        .update_leaf(
+            Leaf2::from_quorum_proposal(&view.quorum_proposal.data),
+            Arc::new(TestValidatedState::default()),
+            None,
+        )
+        .unwrap();
+    for share in view.vid_proposal.0.clone() {
+        consensus_writer.update_vid_shares(view.view_number, share);
+    }
+    assert!(!consensus_writer
+        .saved_payloads()
+        .contains_key(&view.view_number));
+    drop(consensus_writer);
+
+    let payload = handle.reconstruct_payload(view.view_number).await.unwrap();
+    assert_eq!(payload, TestBlockPayload { transactions });
+
+    // Without a leaf there is no payload commitment to check the shares against
+    assert!(handle
+        .reconstruct_payload(ViewNumber::new(*view.view_number + 10))
+        .await
+        .is_err());
+}
@@ -0,0 +1,112 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use bitvec::bitvec;
+use hotshot_types::{
+    signature_key::{SchnorrPrivKey, SchnorrPubKey},
+    traits::signature_key::{PrivateSignatureKey, SignatureKey},
+    PeerConfig, ValidatorConfig,
+};
+use primitive_types::U256;
+use tagged_base64::TaggedBase64;
+
+/// Key pair `index` of a test committee
+fn key_pair(index: u64) -> (SchnorrPubKey, SchnorrPrivKey) {
+    SchnorrPubKey::generated_from_seed_indexed([0u8; 32], index)
+}
+
+#[test]
+fn signatures_are_validated() {
+    let data = b"signed data";
+    let (key, private_key) = key_pair(0);
+    let (other_key, other_private_key) = key_pair(1);
+    assert_eq!(SchnorrPubKey::from_private(&private_key), key);
+
+    let signature = SchnorrPubKey::sign(&private_key, data).unwrap();
+    assert!(key.validate(&signature, data));
+    assert!(!key.validate(&signature, b"other data"));
+    assert!(!other_key.validate(&signature, data));
+
+    // A QC isn't a valid vote signature
+    let other_signature = SchnorrPubKey::sign(&other_private_key, data).unwrap();
+    let stake_table = vec![key.stake_table_entry(1), other_key.stake_table_entry(1)];
+    let (qc_signature, _) = SchnorrPubKey::assemble(
+        &SchnorrPubKey::public_parameter(stake_table, U256::from(2)),
+        bitvec![1; 2].as_bitslice(),
+        &[signature, other_signature],
+    );
+    assert!(!key.validate(&qc_signature, data));
+}
+
+#[test]
+fn qcs_carry_the_signature_of_each_signer() {
+    let data = b"certified data";
+    let key_pairs: Vec<_> = (0..4).map(key_pair).collect();
+    let stake_table: Vec<_> = key_pairs
+        .iter()
+        .map(|(key, _)| key.stake_table_entry(1))
+        .collect();
+    let qc_pp = SchnorrPubKey::public_parameter(stake_table, U256::from(3));
+
+    // Signatures are collected in any order
+    let signers = bitvec![0, 1, 1, 1];
+    let signatures: Vec<_> = [3, 1, 2]
+        .iter()
+        .map(|&i| SchnorrPubKey::sign(&key_pairs[i].1, data).unwrap())
+        .collect();
+    let qc = SchnorrPubKey::assemble(&qc_pp, signers.as_bitslice(), &signatures);
+    assert!(SchnorrPubKey::check(&qc_pp, data, &qc).is_ok());
+    assert!(SchnorrPubKey::check(&qc_pp, b"other data", &qc).is_err());
+
+    // Not enough stake
+    let few_signers = bitvec![0, 1, 1, 0];
+    let qc = SchnorrPubKey::assemble(&qc_pp, few_signers.as_bitslice(), &signatures[1..]);
+    assert!(SchnorrPubKey::check(&qc_pp, data, &qc).is_err());
+
+    // Signers not matching the signatures
+    let qc = (
+        SchnorrPubKey::assemble(&qc_pp, signers.as_bitslice(), &signatures).0,
+        bitvec![1, 1, 1, 0],
+    );
+    assert!(SchnorrPubKey::check(&qc_pp, data, &qc).is_err());
+}
+
+#[test]
+fn keys_and_signatures_are_encoded() {
+    let (key, private_key) = key_pair(0);
+    assert_eq!(SchnorrPubKey::from_bytes(&key.to_bytes()).unwrap(), key);
+    assert_eq!(
+        SchnorrPubKey::try_from(&TaggedBase64::from(key.clone())).unwrap(),
+        key
+    );
+    assert_eq!(
+        SchnorrPrivKey::try_from(&private_key.to_tagged_base64().unwrap()).unwrap(),
+        private_key
+    );
+    assert_eq!(
+        <SchnorrPrivKey as PrivateSignatureKey>::from_bytes(&private_key.to_bytes()).unwrap(),
+        private_key
+    );
+
+    let signature = SchnorrPubKey::sign(&private_key, b"data").unwrap();
+    let encoded: TaggedBase64 = signature.clone().into();
+    assert_eq!(
+        <SchnorrPubKey as SignatureKey>::PureAssembledSignatureType::try_from(&encoded).unwrap(),
+        signature
+    );
+
+    // Keys of one kind aren't decoded as another
+    assert!(SchnorrPubKey::try_from(&encoded).is_err());
+
+    // Peer configs are exchanged through the orchestrator
+    let config =
+        ValidatorConfig::<SchnorrPubKey>::generated_from_seed_indexed([0u8; 32], 0, 1, true)
+            .public_config();
+    assert_eq!(
+        PeerConfig::from_bytes(&PeerConfig::to_bytes(&config)),
+        Some(config)
+    );
+}
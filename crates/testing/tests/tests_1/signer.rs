@@ -0,0 +1,234 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use committable::Committable;
+use hotshot_example_types::node_types::{TestTypes, TestVersions};
+use hotshot_types::{
+    data::ViewNumber,
+    message::UpgradeLock,
+    signature_key::{BLSPrivKey, BLSPubKey},
+    signer::{
+        serve_signer, DoubleSigningGuard, LocalSigner, NodeKey, RemoteSigner, SignEverything,
+        Signer, SigningPurpose, SigningRequest, DOUBLE_SIGNING_GUARD_VIEWS,
+    },
+    simple_vote::{TimeoutData2, TimeoutVote2, VersionedVoteData},
+    traits::{node_implementation::ConsensusTime, signature_key::SignatureKey},
+    vote::Vote,
+};
+use tokio::{net::UnixListener, spawn, time::timeout};
+use utils::anytrace::Result;
+
+/// The key pair of the signers under test
+fn key_pair() -> (BLSPubKey, BLSPrivKey) {
+    BLSPubKey::generated_from_seed_indexed([0u8; 32], 0)
+}
+
+/// A request for a quorum vote over `data` in `view`
+fn quorum_vote(view: u64, data: &[u8]) -> SigningRequest {
+    SigningRequest::new(SigningPurpose::QuorumVote, view, data)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn guard_refuses_to_sign_conflicting_votes() {
+    let (public_key, private_key) = key_pair();
+    let signer = LocalSigner::<BLSPubKey>::new(private_key, DoubleSigningGuard::default());
+    assert_eq!(*signer.public_key(), public_key);
+
+    let signature = signer.sign(quorum_vote(5, b"leaf")).await.unwrap();
+    assert!(public_key.validate(&signature, b"leaf"));
+
+    // Signing the same vote again is harmless, another vote in the same view isn't
+    assert!(signer.sign(quorum_vote(5, b"leaf")).await.is_ok());
+    assert!(signer.sign(quorum_vote(5, b"other leaf")).await.is_err());
+
+    // Other purposes and views are guarded separately
+    assert!(signer
+        .sign(SigningRequest::new(
+            SigningPurpose::DaVote,
+            5,
+            b"other leaf"
+        ))
+        .await
+        .is_ok());
+    assert!(signer.sign(quorum_vote(6, b"other leaf")).await.is_ok());
+    for purpose in [SigningPurpose::TimeoutVote, SigningPurpose::VidDisperse] {
+        for _ in 0..2 {
+            assert!(signer
+                .sign(SigningRequest::new(purpose, 5, rand::random::<[u8; 32]>()))
+                .await
+                .is_ok());
+        }
+    }
+
+    // Views we no longer remember can't be signed for
+    let view = 6 + DOUBLE_SIGNING_GUARD_VIEWS;
+    assert!(signer.sign(quorum_vote(view, b"leaf")).await.is_ok());
+    assert!(signer.sign(quorum_vote(5, b"third leaf")).await.is_err());
+    assert!(signer.sign(quorum_vote(6, b"other leaf")).await.is_ok());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn persistent_guard_refuses_views_signed_before_restarting() {
+    let dir = std::env::temp_dir().join(format!("hotshot-signing-guard-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("high_water_mark");
+    let (_, private_key) = key_pair();
+    let restart = || {
+        LocalSigner::<BLSPubKey>::new(
+            private_key.clone(),
+            DoubleSigningGuard::persistent(&path).unwrap(),
+        )
+    };
+
+    let signer = restart();
+    assert!(signer.sign(quorum_vote(5, b"leaf")).await.is_ok());
+    assert!(signer.sign(quorum_vote(7, b"leaf")).await.is_ok());
+    assert!(signer.sign(quorum_vote(6, b"leaf")).await.is_ok());
+    drop(signer);
+
+    // After restarting, the views signed for before are refused, even for the same data
+    let signer = restart();
+    assert!(signer.sign(quorum_vote(7, b"other leaf")).await.is_err());
+    assert!(signer.sign(quorum_vote(6, b"leaf")).await.is_err());
+    assert!(signer
+        .sign(SigningRequest::new(
+            SigningPurpose::TimeoutVote,
+            7,
+            b"timeout"
+        ))
+        .await
+        .is_ok());
+    assert!(signer.sign(quorum_vote(8, b"leaf")).await.is_ok());
+    drop(signer);
+
+    let signer = restart();
+    assert!(signer.sign(quorum_vote(8, b"leaf")).await.is_err());
+    assert!(signer.sign(quorum_vote(9, b"leaf")).await.is_ok());
+    drop(signer);
+
+    // A key guarded by the same file refuses them too
+    let signer = NodeKey::<BLSPubKey>::new(private_key.clone(), Some(path.clone()))
+        .into_signer()
+        .unwrap();
+    assert!(signer.sign(quorum_vote(9, b"other leaf")).await.is_err());
+    assert!(signer.sign(quorum_vote(10, b"leaf")).await.is_ok());
+    drop(signer);
+
+    // A corrupted high-water mark is rejected rather than ignored
+    std::fs::write(&path, "not a view").unwrap();
+    assert!(DoubleSigningGuard::persistent(&path).is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn remote_signer_signs_with_the_policy_of_the_remote_process() {
+    let path = std::env::temp_dir().join(format!("hotshot-signer-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+
+    let (public_key, private_key) = key_pair();
+    spawn(serve_signer(
+        listener,
+        Arc::new(LocalSigner::<BLSPubKey>::new(
+            private_key,
+            DoubleSigningGuard::default(),
+        )),
+    ));
+
+    let signer = RemoteSigner::<BLSPubKey>::connect(&path).await.unwrap();
+    assert_eq!(*signer.public_key(), public_key);
+
+    // Votes created through the signer carry valid signatures
+    let upgrade_lock = UpgradeLock::<TestTypes, TestVersions>::new();
+    let data = TimeoutData2::<TestTypes> {
+        view: ViewNumber::new(3),
+        epoch: None,
+    };
+    let vote = TimeoutVote2::<TestTypes>::create_signed_vote_with_signer(
+        data.clone(),
+        ViewNumber::new(3),
+        &signer,
+        SigningPurpose::TimeoutVote,
+        &upgrade_lock,
+    )
+    .await
+    .unwrap();
+    assert_eq!(vote.signing_key(), public_key);
+    let commit = VersionedVoteData::new(data, ViewNumber::new(3), &upgrade_lock)
+        .await
+        .unwrap()
+        .commit();
+    assert!(public_key.validate(&vote.signature(), commit.as_ref()));
+
+    // The remote guard refuses conflicting votes, without breaking the connection
+    assert!(signer.sign(quorum_vote(3, b"leaf")).await.is_ok());
+    assert!(signer.sign(quorum_vote(3, b"other leaf")).await.is_err());
+    assert!(signer.sign(quorum_vote(4, b"other leaf")).await.is_ok());
+
+    // Remote signers connecting later share the guard
+    let other_signer = RemoteSigner::<BLSPubKey>::connect(&path).await.unwrap();
+    assert!(other_signer
+        .sign(quorum_vote(4, b"third leaf"))
+        .await
+        .is_err());
+
+    let _ = std::fs::remove_file(&path);
+}
+
+/// A signer which never answers
+#[derive(Debug)]
+struct HangingSigner(BLSPubKey);
+
+#[async_trait]
+impl Signer<BLSPubKey> for HangingSigner {
+    fn public_key(&self) -> &BLSPubKey {
+        &self.0
+    }
+
+    async fn sign(
+        &self,
+        _request: SigningRequest,
+    ) -> Result<<BLSPubKey as SignatureKey>::PureAssembledSignatureType> {
+        std::future::pending().await
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn remote_signer_gives_up_on_a_hanging_signer() {
+    let path = std::env::temp_dir().join(format!(
+        "hotshot-hanging-signer-{}.sock",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+    let (public_key, _) = key_pair();
+    spawn(serve_signer(listener, Arc::new(HangingSigner(public_key))));
+
+    let signer = RemoteSigner::<BLSPubKey>::connect(&path).await.unwrap();
+    let result = timeout(
+        Duration::from_secs(30),
+        signer.sign(quorum_vote(3, b"leaf")),
+    )
+    .await
+    .expect("the remote signer should time out on its own");
+    assert!(result.is_err());
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn unguarded_signers_sign_everything() {
+    let (_, private_key) = key_pair();
+    let signer = LocalSigner::<BLSPubKey, SignEverything>::new(private_key, SignEverything);
+
+    assert!(signer.sign(quorum_vote(5, b"leaf")).await.is_ok());
+    assert!(signer.sign(quorum_vote(5, b"other leaf")).await.is_ok());
+}
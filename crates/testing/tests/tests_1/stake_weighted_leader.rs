@@ -0,0 +1,185 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::collections::BTreeMap;
+
+use hotshot::traits::election::stake_weighted_committee::{
+    DaSamplingConfig, StakeWeightedCommittee,
+};
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_testing::helpers::validator_config;
+use hotshot_types::{
+    data::{EpochNumber, ViewNumber},
+    signature_key::BLSPubKey,
+    traits::{
+        election::Membership, node_implementation::ConsensusTime,
+        signature_key::StakeTableEntryType,
+    },
+    ValidatorConfig,
+};
+use primitive_types::U256;
+
+/// Build a committee where node `i` has stake `stakes[i]`.
+fn committee(stakes: &[u64]) -> (StakeWeightedCommittee<TestTypes>, Vec<BLSPubKey>) {
+    let validators: Vec<_> = stakes
+        .iter()
+        .enumerate()
+        .map(|(i, stake)| validator_config(i as u64, *stake))
+        .collect();
+    let peers: Vec<_> = validators
+        .iter()
+        .map(ValidatorConfig::public_config)
+        .collect();
+
+    (
+        StakeWeightedCommittee::new(peers.clone(), peers),
+        validators.into_iter().map(|v| v.public_key).collect(),
+    )
+}
+
+#[test]
+#[allow(clippy::cast_precision_loss)]
+fn leader_distribution_matches_stake() {
+    let stakes = [1, 1, 2, 4, 8, 16];
+    let total_stake: u64 = stakes.iter().sum();
+    let (mut membership, keys) = committee(&stakes);
+    let epoch = EpochNumber::new(3);
+    membership.add_drb_result(epoch, [7u8; 32]);
+
+    let num_views = 60_000u64;
+    let mut counts: BTreeMap<BLSPubKey, u64> = BTreeMap::new();
+    for view in 0..num_views {
+        let leader = membership
+            .lookup_leader(ViewNumber::new(view), Some(epoch))
+            .unwrap();
+        *counts.entry(leader).or_default() += 1;
+    }
+
+    for (key, stake) in keys.iter().zip(stakes) {
+        let frequency = counts.get(key).copied().unwrap_or_default() as f64 / num_views as f64;
+        let share = stake as f64 / total_stake as f64;
+        // Over 5 standard deviations for 60,000 samples.
+        assert!(
+            (frequency - share).abs() < 0.012,
+            "frequency {frequency} doesn't match stake share {share}"
+        );
+    }
+}
+
+#[test]
+fn leaders_depend_on_drb_result() {
+    let (mut membership, _) = committee(&[1, 2, 3, 4, 5, 6, 7, 8]);
+    let (first_epoch, second_epoch) = (EpochNumber::new(3), EpochNumber::new(4));
+
+    // Leaders can't be picked before the DRB result of the epoch is known.
+    assert!(membership
+        .lookup_leader(ViewNumber::new(1), Some(first_epoch))
+        .is_err());
+
+    membership.add_drb_result(first_epoch, [1u8; 32]);
+    membership.add_drb_result(second_epoch, [2u8; 32]);
+
+    let leaders = |epoch| -> Vec<BLSPubKey> {
+        (0..32)
+            .map(|view| {
+                membership
+                    .lookup_leader(ViewNumber::new(view), Some(epoch))
+                    .unwrap()
+            })
+            .collect()
+    };
+    // Every node computes the same leaders from the same DRB result, and a different result
+    // reshuffles them.
+    assert_eq!(leaders(first_epoch), leaders(first_epoch));
+    assert_ne!(leaders(first_epoch), leaders(second_epoch));
+
+    // Leaders before epochs use the initial DRB result.
+    assert!(membership.lookup_leader(ViewNumber::new(1), None).is_ok());
+}
+
+#[test]
+fn da_committee_sampled_per_epoch() {
+    let stakes = [1, 1, 2, 4, 8, 16, 32, 64];
+    let peers: Vec<_> = stakes
+        .iter()
+        .enumerate()
+        .map(|(i, stake)| validator_config(i as u64, *stake).public_config())
+        .collect();
+    let mut membership = StakeWeightedCommittee::<TestTypes>::with_da_sampling(
+        peers.clone(),
+        peers[..2].to_vec(),
+        DaSamplingConfig {
+            committee_size: 3,
+            min_stake: U256::from(2),
+        },
+    );
+    let (first_epoch, second_epoch) = (EpochNumber::new(3), EpochNumber::new(4));
+    let view = ViewNumber::new(1);
+
+    // Nobody is on the DA committee of an epoch until its DRB result is known, like nobody leads
+    // its views.
+    assert_eq!(membership.da_total_nodes(Some(first_epoch)), 0);
+    assert!(membership.da_stake_table(Some(first_epoch)).is_empty());
+    assert!(membership
+        .da_committee_members(view, Some(first_epoch))
+        .is_empty());
+    assert!(!membership.has_da_stake(&peers[0].stake_table_entry.stake_key, Some(first_epoch)));
+    assert!(membership.lookup_leader(view, Some(first_epoch)).is_err());
+
+    membership.add_drb_result(first_epoch, [1u8; 32]);
+    membership.add_drb_result(second_epoch, [2u8; 32]);
+
+    for epoch in [first_epoch, second_epoch] {
+        let da_stake_table = membership.da_stake_table(Some(epoch));
+        assert_eq!(da_stake_table.len(), 3);
+        // Nodes below the minimum stake are never sampled.
+        assert!(da_stake_table
+            .iter()
+            .all(|entry| entry.stake() >= U256::from(2)));
+
+        // The threshold is computed from the sampled stake.
+        let sampled_stake: u64 = da_stake_table
+            .iter()
+            .map(|entry| entry.stake().as_u64())
+            .sum();
+        assert_eq!(
+            membership.da_success_threshold(Some(epoch)).get(),
+            sampled_stake * 2 / 3 + 1
+        );
+        for entry in &da_stake_table {
+            assert!(membership.has_da_stake(&entry.stake_key, Some(epoch)));
+        }
+    }
+    assert_eq!(membership.da_committee_members(view, None).len(), 2);
+}
+
+#[test]
+fn thresholds_near_u64_max() {
+    // More than two thirds and nine tenths of the total stake, computed without overflowing.
+    let (membership, _) = committee(&[u64::MAX - 2, 1]);
+    let total_stake = U256::from(u64::MAX - 1);
+    let quorum = (total_stake * 2 / 3 + 1).as_u64();
+    assert_eq!(membership.success_threshold(None).get(), quorum);
+    assert_eq!(membership.da_success_threshold(None).get(), quorum);
+    assert_eq!(
+        membership.failure_threshold(None).get(),
+        (total_stake / 3 + 1).as_u64()
+    );
+    assert_eq!(
+        membership.upgrade_threshold(None).get(),
+        (total_stake * 9 / 10).as_u64()
+    );
+
+    // Thresholds above `u64::MAX` saturate.
+    let (membership, _) = committee(&[u64::MAX, u64::MAX]);
+    assert_eq!(membership.success_threshold(None).get(), u64::MAX);
+    assert_eq!(membership.da_success_threshold(None).get(), u64::MAX);
+    assert_eq!(
+        membership.failure_threshold(None).get(),
+        (U256::from(u64::MAX) * 2 / 3 + 1).as_u64()
+    );
+    assert_eq!(membership.upgrade_threshold(None).get(), u64::MAX);
+}
@@ -8,8 +8,8 @@ use std::time::Duration;
 
 use hotshot_example_types::{
     node_types::{
-        Libp2pImpl, MemoryImpl, PushCdnImpl, TestConsecutiveLeaderTypes, TestTypes,
-        TestTypesRandomizedLeader, TestVersions,
+        Libp2pImpl, MemoryImpl, PushCdnImpl, TestConsecutiveLeaderTypes, TestSchnorrKeyTypes,
+        TestTypes, TestTypesRandomizedLeader, TestVersions,
     },
     testable_delay::{DelayConfig, DelayOptions, DelaySettings, SupportedTraitTypesForAsyncDelay},
 };
@@ -24,7 +24,7 @@ use hotshot_testing::{
 cross_tests!(
     TestName: test_success,
     Impls: [MemoryImpl, Libp2pImpl, PushCdnImpl],
-    Types: [TestTypes, TestTypesRandomizedLeader],
+    Types: [TestTypes, TestTypesRandomizedLeader, TestSchnorrKeyTypes],
     Versions: [TestVersions],
     Ignore: false,
     Metadata: {
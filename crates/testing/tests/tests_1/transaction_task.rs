@@ -13,6 +13,7 @@ use hotshot_types::{
         election::Membership,
         node_implementation::{ConsensusTime, Versions},
     },
+    vid::VidConfig,
 };
 use vbs::version::StaticVersionType;
 
@@ -45,9 +46,7 @@ async fn test_transaction_task_leader_two_views_in_a_row() {
     // current view
     let mut exp_packed_bundle = PackedBundle::new(
         vec![].into(),
-        TestMetadata {
-            num_transactions: 0,
-        },
+        TestMetadata::new(&[]),
         current_view,
         Some(EpochNumber::new(1)),
         vec1::vec1![
@@ -60,6 +59,7 @@ async fn test_transaction_task_leader_two_views_in_a_row() {
                     .total_nodes(Some(EpochNumber::new(0))),
                 <TestVersions as Versions>::Base::VERSION,
                 *ViewNumber::new(4),
+                &VidConfig::default(),
             )
             .unwrap()
         ],
@@ -38,6 +38,7 @@ use hotshot_types::{
         ValidatedState,
     },
     utils::BuilderCommitment,
+    vid::VidConfig,
     vote::HasViewNumber,
 };
 use sha2::Digest;
@@ -132,6 +133,7 @@ async fn test_upgrade_task_with_proposal() {
             .total_nodes(Some(EpochNumber::new(1))),
         <TestVersions as Versions>::Base::VERSION,
         *ViewNumber::new(1),
+        &VidConfig::default(),
     )
     .unwrap();
 
@@ -168,9 +170,7 @@ async fn test_upgrade_task_with_proposal() {
                 )
                 .await,
                 builder_commitment.clone(),
-                TestMetadata {
-                    num_transactions: 0
-                },
+                TestMetadata::new(&[]),
                 ViewNumber::new(1),
                 vec1![builder_fee.clone()],
                 None,
@@ -15,7 +15,7 @@ use hotshot_example_types::{
 use hotshot_macros::{run_test, test_scripts};
 use hotshot_task_impls::{events::HotShotEvent::*, vid::VidTaskState};
 use hotshot_testing::{
-    helpers::{build_system_handle, vid_scheme_from_view_number},
+    helpers::{build_system_handle, vid_dispersal_from_view_number},
     predicates::event::exact,
     script::{Expectations, InputOrder, TaskScript},
     serial,
@@ -28,8 +28,8 @@ use hotshot_types::{
         node_implementation::{ConsensusTime, NodeType, Versions},
         BlockPayload,
     },
+    vid::VidConfig,
 };
-use jf_vid::VidScheme;
 use vbs::version::{StaticVersionType, Version};
 use vec1::vec1;
 
@@ -49,13 +49,6 @@ async fn test_vid_task() {
 
     let default_version = Version { major: 0, minor: 0 };
 
-    let mut vid = vid_scheme_from_view_number::<TestTypes, TestVersions>(
-        &membership,
-        ViewNumber::new(0),
-        None,
-        default_version,
-    )
-    .await;
     let transactions = vec![TestTransaction::new(vec![0])];
 
     let (payload, metadata) = <TestBlockPayload as BlockPayload<TestTypes>>::from_transactions(
@@ -68,11 +61,18 @@ async fn test_vid_task() {
     let builder_commitment =
         <TestBlockPayload as BlockPayload<TestTypes>>::builder_commitment(&payload, &metadata);
     let encoded_transactions = Arc::from(TestTransaction::encode(&transactions));
-    let vid_disperse = vid.disperse(&encoded_transactions).unwrap();
+    let vid_disperse = vid_dispersal_from_view_number::<TestTypes, TestVersions>(
+        &membership,
+        ViewNumber::new(0),
+        None,
+        &encoded_transactions,
+        default_version,
+    )
+    .await;
     let payload_commitment = vid_disperse.commit;
 
     let signature = <TestTypes as NodeType>::SignatureKey::sign(
-        handle.private_key(),
+        handle.private_key().unwrap(),
         payload_commitment.as_ref(),
     )
     .expect("Failed to sign block payload!");
@@ -80,6 +80,7 @@ async fn test_vid_task() {
         encoded_transactions: encoded_transactions.clone(),
         metadata: TestMetadata {
             num_transactions: encoded_transactions.len() as u64,
+            payload_byte_len: encoded_transactions.len() as u64,
         },
         view_number: ViewNumber::new(2),
     };
@@ -96,6 +97,7 @@ async fn test_vid_task() {
         None,
         None,
         None,
+        &VidConfig::default(),
     )
     .await;
 
@@ -110,15 +112,14 @@ async fn test_vid_task() {
             ViewChange(ViewNumber::new(2), None),
             BlockRecv(PackedBundle::new(
                 encoded_transactions.clone(),
-                TestMetadata {
-                    num_transactions: transactions.len() as u64
-                },
+                TestMetadata::new(&transactions),
                 ViewNumber::new(2),
                 None,
                 vec1::vec1![null_block::builder_fee::<TestTypes, TestVersions>(
                     membership.read().await.total_nodes(None),
                     <TestVersions as Versions>::Base::VERSION,
                     *ViewNumber::new(2),
+                    &VidConfig::default(),
                 )
                 .unwrap()],
                 None,
@@ -132,14 +133,13 @@ async fn test_vid_task() {
             exact(SendPayloadCommitmentAndMetadata(
                 payload_commitment,
                 builder_commitment,
-                TestMetadata {
-                    num_transactions: transactions.len() as u64,
-                },
+                TestMetadata::new(&transactions),
                 ViewNumber::new(2),
                 vec1![null_block::builder_fee::<TestTypes, TestVersions>(
                     membership.read().await.total_nodes(None),
                     <TestVersions as Versions>::Base::VERSION,
                     *ViewNumber::new(2),
+                    &VidConfig::default(),
                 )
                 .unwrap()],
                 None,
@@ -16,7 +16,7 @@ use hotshot_testing::{
 use hotshot_types::{
     consensus::OuterConsensus,
     data::{Leaf2, ViewNumber},
-    traits::{consensus_api::ConsensusApi, node_implementation::ConsensusTime},
+    traits::node_implementation::ConsensusTime,
 };
 use itertools::Itertools;
 use tokio::time::timeout;
@@ -86,7 +86,7 @@ async fn test_vote_dependency_handle() {
         let vote_dependency_handle_state =
             VoteDependencyHandle::<TestTypes, MemoryImpl, TestVersions> {
                 public_key: handle.public_key(),
-                private_key: handle.private_key().clone(),
+                signer: Arc::clone(&handle.hotshot.signer),
                 consensus: OuterConsensus::new(consensus.clone()),
                 consensus_metrics: Arc::clone(&consensus.read().await.metrics),
                 instance_state: handle.hotshot.instance_state(),
@@ -0,0 +1,67 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+use std::time::Duration;
+
+use hotshot_example_types::node_types::{
+    AvidMTestVersions, AvidMUpgradeTestVersions, MemoryImpl, PushCdnImpl, TestTwoStakeTablesTypes,
+    TestTypes,
+};
+use hotshot_macros::cross_tests;
+use hotshot_testing::{
+    block_builder::SimpleBuilderImplementation,
+    completion_task::{CompletionTaskDescription, TimeBasedCompletionTaskDescription},
+    test_builder::TestDescription,
+};
+
+cross_tests!(
+    TestName: test_success_with_avidm,
+    Impls: [MemoryImpl, PushCdnImpl],
+    Types: [TestTypes, TestTwoStakeTablesTypes],
+    Versions: [AvidMTestVersions],
+    Ignore: false,
+    Metadata: {
+        let mut metadata = TestDescription {
+            // allow more time to pass in CI
+            completion_task_description: CompletionTaskDescription::TimeBasedCompletionTaskBuilder(
+                                             TimeBasedCompletionTaskDescription {
+                                                 duration: Duration::from_secs(60),
+                                             },
+                                         ),
+            ..TestDescription::default()
+        };
+
+        metadata.test_config.epoch_height = 10;
+
+        metadata
+    },
+);
+
+cross_tests!(
+    TestName: test_avidm_upgrade,
+    Impls: [MemoryImpl],
+    Types: [TestTypes],
+    Versions: [AvidMUpgradeTestVersions],
+    Ignore: false,
+    Metadata: {
+        let mut metadata = TestDescription {
+            // allow more time to pass in CI
+            completion_task_description: CompletionTaskDescription::TimeBasedCompletionTaskBuilder(
+                                             TimeBasedCompletionTaskDescription {
+                                                 duration: Duration::from_secs(120),
+                                             },
+                                         ),
+            upgrade_view: Some(5),
+            ..TestDescription::default()
+        };
+
+        // Keep going well past the switch from ADVZ to AvidM
+        metadata.overall_safety_properties.num_successful_views = 50;
+        metadata.test_config.epoch_height = 10;
+
+        metadata
+    },
+);
@@ -10,7 +10,7 @@ use hotshot_example_types::{
     node_types::{
         CombinedImpl, EpochUpgradeTestVersions, EpochsTestVersions, Libp2pImpl, MemoryImpl,
         PushCdnImpl, RandomOverlapQuorumFilterConfig, StableQuorumFilterConfig,
-        TestConsecutiveLeaderTypes, TestTwoStakeTablesTypes, TestTypes,
+        TestConsecutiveLeaderTypes, TestSchnorrKeyTypes, TestTwoStakeTablesTypes, TestTypes,
         TestTypesRandomizedCommitteeMembers, TestTypesRandomizedLeader,
     },
     testable_delay::{DelayConfig, DelayOptions, DelaySettings, SupportedTraitTypesForAsyncDelay},
@@ -29,7 +29,7 @@ use hotshot_types::{data::ViewNumber, traits::node_implementation::ConsensusTime
 cross_tests!(
     TestName: test_success_with_epochs,
     Impls: [Libp2pImpl, PushCdnImpl, CombinedImpl],
-    Types: [TestTypes, TestTypesRandomizedLeader, TestTwoStakeTablesTypes],
+    Types: [TestTypes, TestTypesRandomizedLeader, TestTwoStakeTablesTypes, TestSchnorrKeyTypes],
     Versions: [EpochsTestVersions],
     Ignore: false,
     Metadata: {
@@ -0,0 +1,55 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Compares the throughput of verifying vote signatures one by one and in a batch.
+//!
+//! Run with `cargo bench -p hotshot-types --bench batch_verification`.
+
+use std::time::{Duration, Instant};
+
+use hotshot_types::{signature_key::BLSPubKey, traits::signature_key::SignatureKey};
+
+/// Committee sizes to verify the votes of
+const COMMITTEE_SIZES: [u64; 4] = [10, 100, 200, 500];
+
+/// Number of times each verification is repeated
+const ITERATIONS: u32 = 5;
+
+/// The average time `verify` takes over [`ITERATIONS`] runs
+fn time(mut verify: impl FnMut() -> bool) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        assert!(verify());
+    }
+    start.elapsed() / ITERATIONS
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn main() {
+    let data = b"vote commitment";
+    for size in COMMITTEE_SIZES {
+        let (keys, signatures): (Vec<_>, Vec<_>) = (0..size)
+            .map(|i| {
+                let (key, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], i);
+                let signature = BLSPubKey::sign(&private_key, data).expect("Signing failed");
+                (key, signature)
+            })
+            .unzip();
+
+        let individual = time(|| {
+            keys.iter()
+                .zip(&signatures)
+                .all(|(key, signature)| key.validate(signature, data))
+        });
+        let batch = time(|| BLSPubKey::batch_validate(&keys, &signatures, data));
+
+        println!(
+            "{size} votes: individual {individual:?} ({:.0} votes/s), batch {batch:?} ({:.0} votes/s)",
+            size as f64 / individual.as_secs_f64(),
+            size as f64 / batch.as_secs_f64(),
+        );
+    }
+}
@@ -26,19 +26,22 @@ use crate::{
     error::HotShotError,
     event::{HotShotAction, LeafInfo},
     message::{Proposal, UpgradeLock},
+    signer::{Signer, SigningPurpose, SigningRequest},
     simple_certificate::{DaCertificate2, NextEpochQuorumCertificate2, QuorumCertificate2},
     traits::{
         block_contents::BuilderFee,
-        metrics::{Counter, Gauge, Histogram, Metrics, NoMetrics},
+        metrics::{
+            Counter, CounterFamily, Gauge, GaugeFamily, Histogram, HistogramFamily, Metrics,
+            NoMetrics,
+        },
         node_implementation::{ConsensusTime, NodeType, Versions},
-        signature_key::SignatureKey,
         BlockPayload, ValidatedState,
     },
     utils::{
         epoch_from_block_number, is_last_block_in_epoch, option_epoch_from_block_number,
         BuilderCommitment, LeafCommitment, StateAndDelta, Terminator,
     },
-    vid::VidCommitment,
+    vid::{pool::VidComputePool, VidCommitment, VidConfig},
     vote::{Certificate, HasViewNumber},
 };
 
@@ -330,6 +333,9 @@ pub struct Consensus<TYPES: NodeType> {
 
     /// Tables for the DRB seeds and results.
     pub drb_seeds_and_results: DrbSeedsAndResults<TYPES>,
+
+    /// Pool running VID encoding and verification off the async runtime
+    pub vid_pool: VidComputePool,
 }
 
 /// Contains several `ConsensusMetrics` that we're interested in from the consensus interfaces
@@ -365,6 +371,20 @@ pub struct ConsensusMetricsValue {
     pub number_of_empty_blocks_proposed: Box<dyn Counter>,
     /// Number of events in the hotshot event queue
     pub internal_event_queue_len: Box<dyn Gauge>,
+    /// Number of supervised tasks which are currently running
+    pub live_tasks: Box<dyn Gauge>,
+    /// Number of times a supervised task panicked
+    pub number_of_task_failures: Box<dyn Counter>,
+    /// Number of times a supervised task was restarted after a panic
+    pub number_of_task_restarts: Box<dyn Counter>,
+    /// Number of events waiting in each task's own event queue, labelled by task
+    pub task_event_lag: Box<dyn GaugeFamily>,
+    /// Number of events dropped from each task's own event queue, labelled by task
+    pub task_events_dropped: Box<dyn CounterFamily>,
+    /// Time taken to encode a payload for VID, labelled by payload size range
+    pub vid_encode_duration: Box<dyn HistogramFamily>,
+    /// Number of VID jobs abandoned because their view was abandoned
+    pub vid_jobs_cancelled: Box<dyn Counter>,
 }
 
 impl ConsensusMetricsValue {
@@ -396,6 +416,22 @@ impl ConsensusMetricsValue {
                 .create_counter(String::from("number_of_empty_blocks_proposed"), None),
             internal_event_queue_len: metrics
                 .create_gauge(String::from("internal_event_queue_len"), None),
+            live_tasks: metrics.create_gauge(String::from("live_tasks"), None),
+            number_of_task_failures: metrics
+                .create_counter(String::from("number_of_task_failures"), None),
+            number_of_task_restarts: metrics
+                .create_counter(String::from("number_of_task_restarts"), None),
+            task_event_lag: metrics
+                .gauge_family(String::from("task_event_lag"), vec![String::from("task")]),
+            task_events_dropped: metrics.counter_family(
+                String::from("task_events_dropped"),
+                vec![String::from("task")],
+            ),
+            vid_encode_duration: metrics.histogram_family(
+                String::from("vid_encode_duration"),
+                vec![String::from("payload_size")],
+            ),
+            vid_jobs_cancelled: metrics.create_counter(String::from("vid_jobs_cancelled"), None),
         }
     }
 }
@@ -425,6 +461,7 @@ impl<TYPES: NodeType> Consensus<TYPES> {
         metrics: Arc<ConsensusMetricsValue>,
         epoch_height: u64,
     ) -> Self {
+        let vid_pool = VidComputePool::with_available_parallelism(Arc::clone(&metrics));
         Consensus {
             validated_state_map,
             vid_shares: vid_shares.unwrap_or_default(),
@@ -442,6 +479,7 @@ impl<TYPES: NodeType> Consensus<TYPES> {
             metrics,
             epoch_height,
             drb_seeds_and_results: DrbSeedsAndResults::new(),
+            vid_pool,
         }
     }
 
@@ -516,6 +554,7 @@ impl<TYPES: NodeType> Consensus<TYPES> {
             debug!("New view isn't newer than the current view.")
         );
         self.cur_view = view_number;
+        self.vid_pool.abandon_views_before(*view_number);
         Ok(())
     }
 
@@ -949,16 +988,21 @@ impl<TYPES: NodeType> Consensus<TYPES> {
     /// Associated helper function:
     /// Takes `LockedConsensusState` which will be updated; locks it for read and write accordingly.
     /// Calculates `VidDisperse` based on the view, the txns and the membership,
-    /// and updates `vid_shares` map with the signed `VidDisperseShare` proposals.
+    /// and updates `vid_shares` map with the `VidDisperseShare` proposals, signed by `signer`
+    /// for `purpose`.
+    /// The calculation runs on the VID pool and is not cancelled if the view is abandoned.
     /// Returned `Option` indicates whether the update has actually happened or not.
+    #[allow(clippy::too_many_arguments)]
     #[instrument(skip_all, target = "Consensus", fields(view = *view))]
     pub async fn calculate_and_update_vid<V: Versions>(
         consensus: OuterConsensus<TYPES>,
         view: <TYPES as NodeType>::View,
         target_epoch: Option<<TYPES as NodeType>::Epoch>,
         membership: Arc<RwLock<TYPES::Membership>>,
-        private_key: &<TYPES::SignatureKey as SignatureKey>::PrivateKey,
+        signer: &dyn Signer<TYPES::SignatureKey>,
+        purpose: SigningPurpose,
         upgrade_lock: &UpgradeLock<TYPES, V>,
+        vid_config: &VidConfig,
     ) -> Option<()> {
         let payload = Arc::clone(consensus.read().await.saved_payloads().get(&view)?);
         let epoch = consensus
@@ -968,6 +1012,7 @@ impl<TYPES: NodeType> Consensus<TYPES> {
             .get(&view)?
             .view_inner
             .epoch()?;
+        let vid_pool = consensus.read().await.vid_pool.without_cancellation();
 
         let vid = VidDisperse::calculate_vid_disperse::<V>(
             payload.as_ref(),
@@ -976,16 +1021,25 @@ impl<TYPES: NodeType> Consensus<TYPES> {
             target_epoch,
             epoch,
             upgrade_lock,
+            &vid_pool,
+            vid_config,
         )
         .await
         .ok()?;
 
+        // Every share carries the payload commitment of the disperse, so one signature does
+        let signature = signer
+            .sign(SigningRequest::new(
+                purpose,
+                *view,
+                vid.payload_commitment().as_ref(),
+            ))
+            .await
+            .ok()?;
         let shares = VidDisperseShare::from_vid_disperse(vid);
         let mut consensus_writer = consensus.write().await;
         for share in shares {
-            if let Some(prop) = share.to_proposal(private_key) {
-                consensus_writer.update_vid_shares(view, prop);
-            }
+            consensus_writer.update_vid_shares(view, share.to_proposal(signature.clone()));
         }
 
         Some(())
@@ -40,6 +40,9 @@ pub const REQUEST_DATA_DELAY: u64 = 5000;
 /// Default channel size for consensus event sharing
 pub const EVENT_CHANNEL_SIZE: usize = 100_000;
 
+/// Default capacity of the event queue of a task that has its own queue
+pub const TASK_EVENT_QUEUE_SIZE: usize = 10_000;
+
 /// Default channel size for HotShot -> application communication
 pub const EXTERNAL_EVENT_CHANNEL_SIZE: usize = 100_000;
 
@@ -59,7 +62,7 @@ pub const TEST_UPGRADE_CONSTANTS: UpgradeConstants = UpgradeConstants {
     finish_offset: 20,
 };
 
-/// For `STAKE_TABLE_CAPACITY=200`, the light client prover (a.k.a. `hotshot-state-prover`)
+/// For a stake table capacity of 200, the light client prover (a.k.a. `hotshot-state-prover`)
 /// would need to generate proof for a circuit of slightly below 2^20 gates.
 /// Thus we need to support this upperbounded degree in our Structured Reference String (SRS),
 /// the `+2` is just an artifact from the jellyfish's Plonk proof system.
@@ -19,7 +19,7 @@ use std::{
 use async_lock::RwLock;
 use bincode::Options;
 use committable::{Commitment, CommitmentBoundsArkless, Committable, RawCommitmentBuilder};
-use jf_vid::VidDisperse as JfVidDisperse;
+use primitive_types::U256;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -49,7 +49,7 @@ use crate::{
         BlockPayload,
     },
     utils::{bincode_opts, genesis_epoch_from_version, option_epoch_from_block_number},
-    vid::{VidCommitment, VidCommon, VidSchemeType},
+    vid::{pool::VidComputePool, VidCommitment, VidCommon, VidConfig, VidDispersal, VidShare},
     vote::{Certificate, HasViewNumber},
 };
 
@@ -215,7 +215,7 @@ pub mod vid_disperse;
 pub enum VidDisperse<TYPES: NodeType> {
     /// Disperse type for first VID version
     V0(vid_disperse::ADVZDisperse<TYPES>),
-    /// Place holder for VID upgrade
+    /// Disperse type after the epoch upgrade
     V1(vid_disperse::ADVZDisperse<TYPES>),
 }
 
@@ -247,11 +247,12 @@ impl<TYPES: NodeType> VidDisperse<TYPES> {
     /// Allows for more complex stake table functionality
     pub async fn from_membership(
         view_number: TYPES::View,
-        vid_disperse: JfVidDisperse<VidSchemeType>,
+        vid_disperse: VidDispersal,
         membership: &Arc<RwLock<TYPES::Membership>>,
         target_epoch: Option<TYPES::Epoch>,
         data_epoch: Option<TYPES::Epoch>,
         data_epoch_payload_commitment: Option<VidCommitment>,
+        vid_config: &VidConfig,
     ) -> Self {
         Self::V0(
             ADVZDisperse::from_membership(
@@ -261,6 +262,7 @@ impl<TYPES: NodeType> VidDisperse<TYPES> {
                 target_epoch,
                 data_epoch,
                 data_epoch_payload_commitment,
+                vid_config,
             )
             .await,
         )
@@ -269,6 +271,8 @@ impl<TYPES: NodeType> VidDisperse<TYPES> {
     /// Calculate the vid disperse information from the payload given a view, epoch and membership,
     /// If the sender epoch is missing, it means it's the same as the target epoch.
     ///
+    /// The VID scheme is the one of the protocol version at `view`.
+    ///
     /// # Errors
     /// Returns an error if the disperse or commitment calculation fails
     #[allow(clippy::panic)]
@@ -278,14 +282,35 @@ impl<TYPES: NodeType> VidDisperse<TYPES> {
         view: TYPES::View,
         target_epoch: Option<TYPES::Epoch>,
         data_epoch: Option<TYPES::Epoch>,
-        _upgrade_lock: &UpgradeLock<TYPES, V>,
+        upgrade_lock: &UpgradeLock<TYPES, V>,
+        pool: &VidComputePool,
+        vid_config: &VidConfig,
     ) -> Result<Self> {
-        ADVZDisperse::calculate_vid_disperse(payload, membership, view, target_epoch, data_epoch)
-            .await
-            .map(|result| match data_epoch {
-                None => Self::V0(result),
-                Some(_) => Self::V1(result),
-            })
+        let version = upgrade_lock.version_infallible(view).await;
+
+        ADVZDisperse::calculate_vid_disperse::<V>(
+            payload,
+            membership,
+            view,
+            target_epoch,
+            data_epoch,
+            version,
+            pool,
+            vid_config,
+        )
+        .await
+        .map(|result| match data_epoch {
+            None => Self::V0(result),
+            Some(_) => Self::V1(result),
+        })
+    }
+
+    /// Return a reference to the internal VID share
+    pub fn vid_share_ref(&self) -> &VidShare {
+        match self {
+            Self::V0(share) => &share.share,
+            Self::V1(share) => &share.share,
+        }
     }
 
     /// Return a reference to the internal VidCommon field.
@@ -326,7 +351,7 @@ impl<TYPES: NodeType> VidDisperse<TYPES> {
 pub enum VidDisperseShare<TYPES: NodeType> {
     /// VID disperse share type for first version VID
     V0(vid_disperse::ADVZDisperseShare<TYPES>),
-    /// VID disperse share type after epoch upgrade and VID upgrade
+    /// VID disperse share type after the epoch upgrade
     V1(vid_disperse::VidDisperseShare2<TYPES>),
 }
 
@@ -349,24 +374,17 @@ impl<TYPES: NodeType> VidDisperseShare<TYPES> {
         }
     }
 
-    /// Consume `self` and return a `Proposal`
+    /// Consume `self` and return a `Proposal` with `signature`, the signature of the payload
+    /// commitment of the share
     pub fn to_proposal(
         self,
-        private_key: &<TYPES::SignatureKey as SignatureKey>::PrivateKey,
-    ) -> Option<Proposal<TYPES, Self>> {
-        let payload_commitment_ref: &[u8] = match &self {
-            Self::V0(share) => share.payload_commitment.as_ref(),
-            Self::V1(share) => share.payload_commitment.as_ref(),
-        };
-        let Ok(signature) = TYPES::SignatureKey::sign(private_key, payload_commitment_ref) else {
-            tracing::error!("VID: failed to sign dispersal share payload");
-            return None;
-        };
-        Some(Proposal {
+        signature: <TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType,
+    ) -> Proposal<TYPES, Self> {
+        Proposal {
             signature,
             _pd: PhantomData,
             data: self,
-        })
+        }
     }
 
     /// Split a VID share proposal into a proposal for each recipient.
@@ -442,14 +460,19 @@ impl<TYPES: NodeType> VidDisperseShare<TYPES> {
         }
     }
 
-    /// Internally verify the share given necessary information
+    /// Internally verify the share given the storage nodes it was dispersed among, as listed by
+    /// [`vid_disperse::vid_storage_nodes`]
     ///
     /// # Errors
     #[allow(clippy::result_unit_err)]
-    pub fn verify_share(&self, total_nodes: usize) -> std::result::Result<(), ()> {
+    pub fn verify_share(
+        &self,
+        storage_nodes: &[(TYPES::SignatureKey, U256)],
+        vid_config: &VidConfig,
+    ) -> std::result::Result<(), ()> {
         match self {
-            Self::V0(share) => share.verify_share(total_nodes),
-            Self::V1(share) => share.verify_share(total_nodes),
+            Self::V0(share) => share.verify_share(storage_nodes, vid_config),
+            Self::V1(share) => share.verify_share(storage_nodes, vid_config),
         }
     }
 
@@ -845,6 +868,10 @@ pub enum BlockError {
     /// The payload commitment does not match the block header's payload commitment
     #[error("Inconsistent payload commitment")]
     InconsistentPayloadCommitment,
+
+    /// The encoded payload, or a part of it, does not decode
+    #[error("Invalid block payload: {0}")]
+    InvalidBlockPayload(String),
 }
 
 /// Additional functions required to use a [`Leaf`] with hotshot-testing.
@@ -959,6 +986,7 @@ impl<TYPES: NodeType> Leaf2<TYPES> {
             &payload_bytes,
             GENESIS_VID_NUM_STORAGE_NODES,
             genesis_version,
+            &VidConfig::default(),
         );
 
         let block_header = TYPES::BlockHeader::genesis(
@@ -1044,9 +1072,10 @@ impl<TYPES: NodeType> Leaf2<TYPES> {
         block_payload: TYPES::BlockPayload,
         num_storage_nodes: usize,
         version: Version,
+        vid_config: &VidConfig,
     ) -> std::result::Result<(), BlockError> {
         let encoded_txns = block_payload.encode();
-        let commitment = vid_commitment::<V>(&encoded_txns, num_storage_nodes, version);
+        let commitment = vid_commitment::<V>(&encoded_txns, num_storage_nodes, version, vid_config);
         if commitment != self.block_header.payload_commitment() {
             return Err(BlockError::InconsistentPayloadCommitment);
         }
@@ -1344,6 +1373,7 @@ impl<TYPES: NodeType> Leaf<TYPES> {
             &payload_bytes,
             GENESIS_VID_NUM_STORAGE_NODES,
             genesis_version,
+            &VidConfig::default(),
         );
 
         let block_header = TYPES::BlockHeader::genesis(
@@ -1417,9 +1447,10 @@ impl<TYPES: NodeType> Leaf<TYPES> {
         block_payload: TYPES::BlockPayload,
         num_storage_nodes: usize,
         version: Version,
+        vid_config: &VidConfig,
     ) -> std::result::Result<(), BlockError> {
         let encoded_txns = block_payload.encode();
-        let commitment = vid_commitment::<V>(&encoded_txns, num_storage_nodes, version);
+        let commitment = vid_commitment::<V>(&encoded_txns, num_storage_nodes, version, vid_config);
         if commitment != self.block_header.payload_commitment() {
             return Err(BlockError::InconsistentPayloadCommitment);
         }
@@ -1634,7 +1665,6 @@ impl<TYPES: NodeType> Leaf<TYPES> {
 pub mod null_block {
     #![allow(missing_docs)]
 
-    use jf_vid::VidScheme;
     use vbs::version::StaticVersionType;
 
     use crate::{
@@ -1644,25 +1674,20 @@ pub mod null_block {
             signature_key::BuilderSignatureKey,
             BlockPayload,
         },
-        vid::{advz_scheme, VidCommitment},
+        vid::{vid_commit, VidCommitment, VidConfig},
     };
 
     /// The commitment for a null block payload.
     ///
-    /// Note: the commitment depends on the network (via `num_storage_nodes`),
-    /// and may change (albeit rarely) during execution.
-    ///
-    /// We memoize the result to avoid having to recalculate it.
-    // TODO(Chengyu): fix it. Empty commitment must be computed at every upgrade.
-    // #[memoize(SharedCache, Capacity: 10)]
+    /// Note: the commitment depends on the network (via `num_storage_nodes` and `vid_config`)
+    /// and on the VID scheme of `version`, and may change (albeit rarely) during execution.
     #[must_use]
-    pub fn commitment<V: Versions>(num_storage_nodes: usize) -> Option<VidCommitment> {
-        let vid_result = advz_scheme(num_storage_nodes).commit_only(Vec::new());
-
-        match vid_result {
-            Ok(r) => Some(r),
-            Err(_) => None,
-        }
+    pub fn commitment<V: Versions>(
+        num_storage_nodes: usize,
+        version: vbs::version::Version,
+        vid_config: &VidConfig,
+    ) -> Option<VidCommitment> {
+        vid_commit::<V>(&[], num_storage_nodes, version, vid_config).ok()
     }
 
     /// Builder fee data for a null block payload
@@ -1671,6 +1696,7 @@ pub mod null_block {
         num_storage_nodes: usize,
         version: vbs::version::Version,
         view_number: u64,
+        vid_config: &VidConfig,
     ) -> Option<BuilderFee<TYPES>> {
         /// Arbitrary fee amount, this block doesn't actually come from a builder
         const FEE_AMOUNT: u64 = 0;
@@ -1701,7 +1727,7 @@ pub mod null_block {
                 &priv_key,
                 FEE_AMOUNT,
                 &null_block_metadata,
-                &commitment::<V>(num_storage_nodes)?,
+                &commitment::<V>(num_storage_nodes, version, vid_config)?,
             ) {
                 Ok(sig) => Some(BuilderFee {
                     fee_amount: FEE_AMOUNT,
@@ -1715,7 +1741,8 @@ pub mod null_block {
 }
 
 /// A packed bundle constructed from a sequence of bundles.
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "TYPES::AuctionResult: Serialize", deserialize = ""))]
 pub struct PackedBundle<TYPES: NodeType> {
     /// The combined transactions as bytes.
     pub encoded_transactions: Arc<[u8]>,
@@ -9,26 +9,31 @@
 use std::{collections::BTreeMap, fmt::Debug, hash::Hash, marker::PhantomData, sync::Arc};
 
 use async_lock::RwLock;
-use jf_vid::{VidDisperse as JfVidDisperse, VidScheme};
+use primitive_types::U256;
 use serde::{Deserialize, Serialize};
-use tokio::task::spawn_blocking;
 use utils::anytrace::*;
+use vbs::version::Version;
 
 use crate::{
     impl_has_epoch,
     message::Proposal,
     simple_vote::HasEpoch,
     traits::{
-        block_contents::EncodeBytes, election::Membership, node_implementation::NodeType,
-        signature_key::SignatureKey,
+        block_contents::EncodeBytes,
+        election::Membership,
+        node_implementation::{NodeType, Versions},
+        signature_key::{SignatureKey, StakeTableEntryType},
+    },
+    vid::{
+        pool::VidComputePool, verify_vid_share, vid_commit, vid_disperse, VidCommitment, VidCommon,
+        VidConfig, VidDispersal, VidShare,
     },
-    vid::{advz_scheme, VidCommitment, VidCommon, VidSchemeType, VidShare},
     vote::HasViewNumber,
 };
 
 impl_has_epoch!(ADVZDisperse<TYPES>, VidDisperseShare2<TYPES>);
 
-/// ADVZ dispersal data
+/// VID dispersal data. Despite the name, the shares may be of any scheme, see [`VidShare`].
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
 pub struct ADVZDisperse<TYPES: NodeType> {
     /// The view number for which this VID data is intended
@@ -47,6 +52,44 @@ pub struct ADVZDisperse<TYPES: NodeType> {
     pub common: VidCommon,
 }
 
+/// The storage nodes of `epoch` in the order VID shares are assigned to them, with their stakes
+#[must_use]
+pub fn vid_storage_nodes<TYPES: NodeType>(
+    membership: &TYPES::Membership,
+    view_number: TYPES::View,
+    epoch: Option<TYPES::Epoch>,
+) -> Vec<(TYPES::SignatureKey, U256)> {
+    membership
+        .committee_members(view_number, epoch)
+        .into_iter()
+        .map(|node| {
+            let stake = membership
+                .stake(&node, epoch)
+                .map_or_else(U256::zero, |entry| entry.stake());
+            (node, stake)
+        })
+        .collect()
+}
+
+/// Verify the VID share of `recipient_key` against `commit`, given the storage nodes the
+/// payload was dispersed among as listed by [`vid_storage_nodes`]
+fn verify_recipient_share<K: PartialEq>(
+    share: &VidShare,
+    common: &VidCommon,
+    commit: &VidCommitment,
+    recipient_key: &K,
+    storage_nodes: &[(K, U256)],
+    config: &VidConfig,
+) -> std::result::Result<(), ()> {
+    let recipient = storage_nodes
+        .iter()
+        .position(|(node, _)| node == recipient_key)
+        .ok_or(())?;
+    let stakes: Vec<U256> = storage_nodes.iter().map(|(_, stake)| *stake).collect();
+
+    verify_vid_share(share, common, commit, &stakes, recipient, config).map_err(|_| ())
+}
+
 impl<TYPES: NodeType> HasViewNumber<TYPES> for ADVZDisperse<TYPES> {
     fn view_number(&self) -> TYPES::View {
         self.view_number
@@ -59,18 +102,21 @@ impl<TYPES: NodeType> ADVZDisperse<TYPES> {
     /// Allows for more complex stake table functionality
     pub async fn from_membership(
         view_number: TYPES::View,
-        mut vid_disperse: JfVidDisperse<VidSchemeType>,
+        mut vid_disperse: VidDispersal,
         membership: &Arc<RwLock<TYPES::Membership>>,
         target_epoch: Option<TYPES::Epoch>,
         data_epoch: Option<TYPES::Epoch>,
         data_epoch_payload_commitment: Option<VidCommitment>,
+        vid_config: &VidConfig,
     ) -> Self {
-        let shares = membership
-            .read()
-            .await
-            .committee_members(view_number, target_epoch)
-            .iter()
-            .map(|node| (node.clone(), vid_disperse.shares.remove(0)))
+        let storage_nodes =
+            vid_storage_nodes::<TYPES>(&*membership.read().await, view_number, target_epoch);
+        let stakes: Vec<_> = storage_nodes.iter().map(|(_, stake)| *stake).collect();
+        vid_disperse.weight_by_stake(vid_config, &stakes);
+
+        let shares = storage_nodes
+            .into_iter()
+            .map(|(node, _)| (node, vid_disperse.shares.remove(0)))
             .collect();
 
         Self {
@@ -87,26 +133,36 @@ impl<TYPES: NodeType> ADVZDisperse<TYPES> {
     /// Calculate the vid disperse information from the payload given a view, epoch and membership,
     /// If the sender epoch is missing, it means it's the same as the target epoch.
     ///
+    /// The payload is dispersed with the VID scheme of `version`. The encoding runs on `pool`, and
+    /// is abandoned along with `view`.
+    ///
     /// # Errors
     /// Returns an error if the disperse or commitment calculation fails
     #[allow(clippy::panic)]
-    pub async fn calculate_vid_disperse(
+    pub async fn calculate_vid_disperse<V: Versions>(
         payload: &TYPES::BlockPayload,
         membership: &Arc<RwLock<TYPES::Membership>>,
         view: TYPES::View,
         target_epoch: Option<TYPES::Epoch>,
         data_epoch: Option<TYPES::Epoch>,
+        version: Version,
+        pool: &VidComputePool,
+        vid_config: &VidConfig,
     ) -> Result<Self> {
         let num_nodes = membership.read().await.total_nodes(target_epoch);
 
         let txns = payload.encode();
         let txns_clone = Arc::clone(&txns);
         let num_txns = txns.len();
+        let config = *vid_config;
 
-        let vid_disperse = spawn_blocking(move || advz_scheme(num_nodes).disperse(&txns_clone))
+        let dispersal = pool
+            .encode(Some(*view), num_txns, move || {
+                vid_disperse::<V>(&txns_clone, num_nodes, version, &config)
+            })
             .await
             .wrap()
-            .context(error!("Join error"))?
+            .context(|err| error!("VID disperse was not computed: {}", err))?
             .wrap()
             .context(|err| error!("Failed to calculate VID disperse. Error: {}", err))?;
 
@@ -116,10 +172,10 @@ impl<TYPES: NodeType> ADVZDisperse<TYPES> {
             let num_nodes = membership.read().await.total_nodes(data_epoch);
 
             Some(
-              spawn_blocking(move || advz_scheme(num_nodes).commit_only(&txns))
+              pool.encode(Some(*view), num_txns, move || vid_commit::<V>(&txns, num_nodes, version, &config))
                 .await
                 .wrap()
-                .context(error!("Join error"))?
+                .context(|err| error!("VID commitment was not computed: {}", err))?
                 .wrap()
                 .context(|err| error!("Failed to calculate VID commitment with (num_storage_nodes, payload_byte_len) = ({}, {}). Error: {}", num_nodes, num_txns, err))?
             )
@@ -127,18 +183,19 @@ impl<TYPES: NodeType> ADVZDisperse<TYPES> {
 
         Ok(Self::from_membership(
             view,
-            vid_disperse,
+            dispersal,
             membership,
             target_epoch,
             data_epoch,
             payload_commitment,
+            vid_config,
         )
         .await)
     }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
-/// ADVZ share and associated metadata for a single node
+/// VID share and associated metadata for a single node, without epoch information
 pub struct ADVZDisperseShare<TYPES: NodeType> {
     /// The view number for which this VID data is intended
     pub view_number: TYPES::View,
@@ -174,22 +231,17 @@ impl<TYPES: NodeType> ADVZDisperseShare<TYPES> {
             .collect()
     }
 
-    /// Consume `self` and return a `Proposal`
+    /// Consume `self` and return a `Proposal` with `signature`, the signature of the payload
+    /// commitment of the share
     pub fn to_proposal(
         self,
-        private_key: &<TYPES::SignatureKey as SignatureKey>::PrivateKey,
-    ) -> Option<Proposal<TYPES, Self>> {
-        let Ok(signature) =
-            TYPES::SignatureKey::sign(private_key, self.payload_commitment.as_ref())
-        else {
-            tracing::error!("VID: failed to sign dispersal share payload");
-            return None;
-        };
-        Some(Proposal {
+        signature: <TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType,
+    ) -> Proposal<TYPES, Self> {
+        Proposal {
             signature,
             _pd: PhantomData,
             data: self,
-        })
+        }
     }
 
     /// Create `VidDisperse` out of an iterator to `VidDisperseShare`s
@@ -243,15 +295,25 @@ impl<TYPES: NodeType> ADVZDisperseShare<TYPES> {
             .collect()
     }
 
-    /// Internally verify the share given necessary information
+    /// Internally verify the share given the storage nodes it was dispersed among, as listed by
+    /// [`vid_storage_nodes`]
     ///
     /// # Errors
     /// Verification fail
     #[allow(clippy::result_unit_err)]
-    pub fn verify_share(&self, total_nodes: usize) -> std::result::Result<(), ()> {
-        advz_scheme(total_nodes)
-            .verify_share(&self.share, &self.common, &self.payload_commitment)
-            .unwrap_or(Err(()))
+    pub fn verify_share(
+        &self,
+        storage_nodes: &[(TYPES::SignatureKey, U256)],
+        vid_config: &VidConfig,
+    ) -> std::result::Result<(), ()> {
+        verify_recipient_share(
+            &self.share,
+            &self.common,
+            &self.payload_commitment,
+            &self.recipient_key,
+            storage_nodes,
+            vid_config,
+        )
     }
 }
 
@@ -301,22 +363,17 @@ impl<TYPES: NodeType> VidDisperseShare2<TYPES> {
             .collect()
     }
 
-    /// Consume `self` and return a `Proposal`
+    /// Consume `self` and return a `Proposal` with `signature`, the signature of the payload
+    /// commitment of the share
     pub fn to_proposal(
         self,
-        private_key: &<TYPES::SignatureKey as SignatureKey>::PrivateKey,
-    ) -> Option<Proposal<TYPES, Self>> {
-        let Ok(signature) =
-            TYPES::SignatureKey::sign(private_key, self.payload_commitment.as_ref())
-        else {
-            tracing::error!("VID: failed to sign dispersal share payload");
-            return None;
-        };
-        Some(Proposal {
+        signature: <TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType,
+    ) -> Proposal<TYPES, Self> {
+        Proposal {
             signature,
             _pd: PhantomData,
             data: self,
-        })
+        }
     }
 
     /// Create `VidDisperse` out of an iterator to `VidDisperseShare`s
@@ -373,14 +430,24 @@ impl<TYPES: NodeType> VidDisperseShare2<TYPES> {
             .collect()
     }
 
-    /// Internally verify the share given necessary information
+    /// Internally verify the share given the storage nodes it was dispersed among, as listed by
+    /// [`vid_storage_nodes`]
     ///
     /// # Errors
     #[allow(clippy::result_unit_err)]
-    pub fn verify_share(&self, total_nodes: usize) -> std::result::Result<(), ()> {
-        advz_scheme(total_nodes)
-            .verify_share(&self.share, &self.common, &self.payload_commitment)
-            .unwrap_or(Err(()))
+    pub fn verify_share(
+        &self,
+        storage_nodes: &[(TYPES::SignatureKey, U256)],
+        vid_config: &VidConfig,
+    ) -> std::result::Result<(), ()> {
+        verify_recipient_share(
+            &self.share,
+            &self.common,
+            &self.payload_commitment,
+            &self.recipient_key,
+            storage_nodes,
+            vid_config,
+        )
     }
 }
 
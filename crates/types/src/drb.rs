@@ -5,37 +5,46 @@
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
 use std::{
-    collections::BTreeMap,
-    hash::{DefaultHasher, Hash, Hasher},
+    collections::{BTreeMap, BTreeSet},
+    time::{Duration, Instant},
 };
 
+use committable::Committable;
+use primitive_types::U256;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use tokio::task::JoinHandle;
+use utils::anytrace::*;
 
-use crate::traits::{
-    node_implementation::{ConsensusTime, NodeType},
-    signature_key::SignatureKey,
+use crate::{
+    data::Leaf2,
+    message::UpgradeLock,
+    simple_certificate::QuorumCertificate2,
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType, Versions},
+        signature_key::{SignatureKey, StakeTableEntryType},
+    },
+    utils::{epoch_from_block_number, is_epoch_root},
+    vote::Certificate,
 };
 
-// TODO: Add the following consts once we bench the hash time.
-// <https://github.com/EspressoSystems/HotShot/issues/3880>
-// /// Highest number of hashes that a hardware can complete in a second.
-// const `HASHES_PER_SECOND`
-// /// Time a DRB calculation will take, in terms of number of views.
-// const `DRB_CALCULATION_NUM_VIEW`: u64 = 300;
+/// Time a DRB calculation should take, in terms of number of views.
+pub const DRB_CALCULATION_NUM_VIEW: u64 = 300;
 
-// TODO: Replace this with an accurate number calculated by `fn difficulty_level()` once we bench
-// the hash time.
-// <https://github.com/EspressoSystems/HotShot/issues/3880>
-/// Arbitrary number of times the hash function will be repeatedly called.
-const DIFFICULTY_LEVEL: u64 = 10;
+/// Default number of times the hash function will be repeatedly called, used when no difficulty
+/// is configured. Small enough for tests, far too small for a real network.
+pub const DIFFICULTY_LEVEL: u64 = 10;
+
+/// Number of hashes between two checkpoints of a DRB calculation.
+pub const DRB_CHECKPOINT_INTERVAL: u64 = 1 << 20;
 
 /// DRB seed input for epoch 1 and 2.
 pub const INITIAL_DRB_SEED_INPUT: [u8; 32] = [0; 32];
 /// DRB result for epoch 1 and 2.
 pub const INITIAL_DRB_RESULT: [u8; 32] = [0; 32];
 
-/// Alias for DRB seed input for `compute_drb_result`, serialized from the QC signature.
+/// Alias for DRB seed input for `compute_drb_result`, derived from the QC signatures.
 pub type DrbSeedInput = [u8; 32];
 
 /// Alias for DRB result from `compute_drb_result`.
@@ -44,57 +53,386 @@ pub type DrbResult = [u8; 32];
 /// Number of previous results and seeds to keep
 pub const KEEP_PREVIOUS_RESULT_COUNT: u64 = 8;
 
-// TODO: Use `HASHES_PER_SECOND` * `VIEW_TIMEOUT` * `DRB_CALCULATION_NUM_VIEW` to calculate this
-// once we bench the hash time.
-// <https://github.com/EspressoSystems/HotShot/issues/3880>
 /// Difficulty level of the DRB calculation.
 ///
-/// Represents the number of times the hash function will be repeatedly called.
+/// Represents the number of times the hash function will be repeatedly called, so that the
+/// calculation takes about [`DRB_CALCULATION_NUM_VIEW`] views of `view_timeout` each on hardware
+/// completing `hashes_per_second` hashes, e.g. as measured by [`measure_hashes_per_second`].
+#[must_use]
+pub fn difficulty_level(hashes_per_second: u64, view_timeout: Duration) -> u64 {
+    let hashes = u128::from(hashes_per_second)
+        * view_timeout.as_millis()
+        * u128::from(DRB_CALCULATION_NUM_VIEW)
+        / 1000;
+
+    u64::try_from(hashes).unwrap_or(u64::MAX).max(1)
+}
+
+/// Measure how many DRB hashes this machine completes in a second, hashing for `duration`.
 #[must_use]
-pub fn difficulty_level() -> u64 {
-    unimplemented!("Use an arbitrary `DIFFICULTY_LEVEL` for now before we bench the hash time.");
+pub fn measure_hashes_per_second(duration: Duration) -> u64 {
+    let mut checkpoint = DrbCheckpoint::new(INITIAL_DRB_SEED_INPUT);
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        checkpoint.advance(u64::MAX, 1 << 12);
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    let hashes_per_second = (checkpoint.iterations as f64 / elapsed) as u64;
+    hashes_per_second
+}
+
+/// Progress of a DRB calculation, which can be persisted to resume the calculation after a
+/// restart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DrbCheckpoint {
+    /// Seed the calculation started from
+    pub seed: DrbSeedInput,
+    /// Number of hashes done so far
+    pub iterations: u64,
+    /// Hash after `iterations` hashes
+    pub hash: [u8; 32],
+}
+
+impl DrbCheckpoint {
+    /// Start a calculation from `seed`.
+    #[must_use]
+    pub fn new(seed: DrbSeedInput) -> Self {
+        Self {
+            seed,
+            iterations: 0,
+            hash: seed,
+        }
+    }
+
+    /// Do up to `max_iterations` more hashes of a calculation with `difficulty`.
+    ///
+    /// Returns the DRB result once the calculation is complete.
+    pub fn advance(&mut self, difficulty: u64, max_iterations: u64) -> Option<DrbResult> {
+        let stop = difficulty.min(self.iterations.saturating_add(max_iterations));
+        while self.iterations < stop {
+            self.hash = Sha256::digest(self.hash).into();
+            self.iterations += 1;
+        }
+
+        self.result(difficulty)
+    }
+
+    /// The DRB result, if the calculation with `difficulty` is complete.
+    #[must_use]
+    pub fn result(&self, difficulty: u64) -> Option<DrbResult> {
+        (self.iterations == difficulty).then_some(self.hash)
+    }
 }
 
 /// Compute the DRB result for the leader rotation.
 ///
-/// This is to be started two epochs in advance and spawned in a non-blocking thread.
+/// This is to be started two epochs in advance and spawned in a non-blocking thread. Use
+/// [`DrbCheckpoint`] instead to be able to resume the calculation after a restart.
 ///
 /// # Arguments
-/// * `drb_seed_input` - Serialized QC signature.
+/// * `drb_seed_input` - Seed derived from the QC signatures, see [`drb_seed_input`].
+/// * `difficulty` - Number of hashes, see [`difficulty_level`].
+#[must_use]
+pub fn compute_drb_result<TYPES: NodeType>(
+    drb_seed_input: DrbSeedInput,
+    difficulty: u64,
+) -> DrbResult {
+    let mut checkpoint = DrbCheckpoint::new(drb_seed_input);
+    checkpoint.advance(difficulty, difficulty);
+    checkpoint.hash
+}
+
+/// Check a DRB result received from another node, e.g. the `next_drb_result` of a proposal, by
+/// redoing the calculation.
+///
+/// The calculation is inherently sequential, so this takes as long as computing the result.
+#[must_use]
+pub fn verify_drb_result<TYPES: NodeType>(
+    drb_seed_input: DrbSeedInput,
+    difficulty: u64,
+    drb_result: &DrbResult,
+) -> bool {
+    compute_drb_result::<TYPES>(drb_seed_input, difficulty) == *drb_result
+}
+
+/// Domain separator for the DRB seed, keeping it independent from other hashes of QC signatures.
+const DRB_SEED_DOMAIN: &[u8] = b"DRB seed";
+
+/// Derive the DRB seed input from the signatures of `qc`, the justify QC of an epoch root.
+///
+/// The seed is the hash of all the serialized signatures, so that it depends on every signer.
+/// Returns `None` if the signatures can't be serialized.
 #[must_use]
-pub fn compute_drb_result<TYPES: NodeType>(drb_seed_input: DrbSeedInput) -> DrbResult {
-    let mut hash = drb_seed_input.to_vec();
-    for _iter in 0..DIFFICULTY_LEVEL {
-        // TODO: This may be optimized to avoid memcopies after we bench the hash time.
-        // <https://github.com/EspressoSystems/HotShot/issues/3880>
-        hash = Sha256::digest(hash).to_vec();
+pub fn drb_seed_input<TYPES: NodeType>(qc: &QuorumCertificate2<TYPES>) -> Option<DrbSeedInput> {
+    let serialized = bincode::serialize(&qc.signatures).ok()?;
+    let hash = Sha256::new()
+        .chain_update(DRB_SEED_DOMAIN)
+        .chain_update(serialized)
+        .finalize();
+
+    Some(hash.into())
+}
+
+/// Proof that a DRB seed was taken from the quorum's signatures on the epoch root two epochs
+/// before the one the seed is for, so that a result received from a peer can be checked.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(bound(deserialize = ""))]
+pub struct DrbSeedProof<TYPES: NodeType> {
+    /// The decided epoch root, whose justify QC the seed is derived from
+    pub epoch_root: Leaf2<TYPES>,
+
+    /// A QC on `epoch_root`
+    pub epoch_root_qc: QuorumCertificate2<TYPES>,
+}
+
+impl<TYPES: NodeType> DrbSeedProof<TYPES> {
+    /// Check that this proves the seed for `epoch` and return the seed.
+    ///
+    /// # Errors
+    /// Errors if the leaf isn't the epoch root two epochs before `epoch`, or either QC is
+    /// invalid for the stake table of its epoch.
+    pub async fn verify<V: Versions>(
+        &self,
+        epoch: TYPES::Epoch,
+        membership: &TYPES::Membership,
+        epoch_height: u64,
+        upgrade_lock: &UpgradeLock<TYPES, V>,
+    ) -> Result<DrbSeedInput> {
+        let height = self.epoch_root.height();
+        ensure!(
+            is_epoch_root(height, epoch_height),
+            warn!("Block {} is not an epoch root.", height)
+        );
+
+        let root_epoch = TYPES::Epoch::new(epoch_from_block_number(height, epoch_height));
+        ensure!(
+            root_epoch + 2 == epoch,
+            warn!(
+                "The epoch root of epoch {:?} doesn't seed epoch {:?}.",
+                root_epoch, epoch
+            )
+        );
+        ensure!(
+            self.epoch_root_qc.data.leaf_commit == self.epoch_root.commit(),
+            warn!("The QC is not on the epoch root.")
+        );
+
+        let justify_qc = self.epoch_root.justify_qc();
+        for qc in [&self.epoch_root_qc, &justify_qc] {
+            qc.is_valid_cert(
+                membership.stake_table(qc.data.epoch),
+                membership.success_threshold(qc.data.epoch),
+                upgrade_lock,
+            )
+            .await
+            .context(|e| warn!("Invalid QC for view {:?}: {}", qc.view_number, e))?;
+        }
+
+        drb_seed_input(&justify_qc).context(warn!("The justify QC has no usable signature."))
     }
+}
+
+/// Table for sampling stake table entries with probability proportional to their stake, in
+/// constant time per sample.
+///
+/// Built with Vose's alias method on exact integer weights: each of the `n` columns holds
+/// `total_stake` of weight, split between the column's own entry and at most one alias, so the
+/// table reproduces the stake distribution exactly.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StakeAliasTable {
+    /// Total stake of all entries
+    total_stake: U256,
+
+    /// Weight of each column which belongs to the column's own entry, the rest belongs to its alias
+    thresholds: Vec<U256>,
 
-    // Convert the hash to the DRB result.
-    let mut drb_result = [0u8; 32];
-    drb_result.copy_from_slice(&hash);
-    drb_result
+    /// Entry taking the rest of each column
+    aliases: Vec<usize>,
 }
 
-/// Use the DRB result to get the leader.
+impl StakeAliasTable {
+    /// Build the table for entries with the given `stakes`.
+    ///
+    /// Returns `None` if the total stake is zero.
+    #[must_use]
+    pub fn new(stakes: &[U256]) -> Option<Self> {
+        let total_stake = stakes
+            .iter()
+            .fold(U256::zero(), |total, stake| total + *stake);
+        if total_stake.is_zero() {
+            return None;
+        }
+
+        let num_entries = U256::from(stakes.len());
+        let mut weights: Vec<U256> = stakes.iter().map(|stake| *stake * num_entries).collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..stakes.len()).partition(|&index| weights[index] < total_stake);
+
+        let mut thresholds = vec![total_stake; stakes.len()];
+        let mut aliases: Vec<usize> = (0..stakes.len()).collect();
+        while let (Some(&underfull), Some(&overfull)) = (small.last(), large.last()) {
+            small.pop();
+            // Fill up the underfull column with the weight of an overfull entry.
+            thresholds[underfull] = weights[underfull];
+            aliases[underfull] = overfull;
+            weights[overfull] = weights[overfull] + weights[underfull] - total_stake;
+            if weights[overfull] < total_stake {
+                large.pop();
+                small.push(overfull);
+            }
+        }
+        // Any remaining columns hold exactly `total_stake` of their own entry's weight, as the
+        // weights always sum up to `n * total_stake`.
+
+        Some(Self {
+            total_stake,
+            thresholds,
+            aliases,
+        })
+    }
+
+    /// Number of entries in the table.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.aliases.len()
+    }
+
+    /// Whether the table has no entries, which is never the case for a table built by `new`.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+
+    /// Index of the entry picked by uniformly distributed `randomness`.
+    #[must_use]
+    pub fn sample(&self, randomness: U256) -> usize {
+        let num_entries = U256::from(self.len());
+        let column = (randomness % num_entries).as_usize();
+        let point = (randomness / num_entries) % self.total_stake;
+
+        if point < self.thresholds[column] {
+            column
+        } else {
+            self.aliases[column]
+        }
+    }
+
+    /// Total weight of entry `index` over all columns, which is `n` times its stake.
+    #[must_use]
+    pub fn weight(&self, index: usize) -> U256 {
+        self.thresholds.iter().zip(&self.aliases).enumerate().fold(
+            U256::zero(),
+            |weight, (column, (threshold, alias))| {
+                let own = if column == index {
+                    *threshold
+                } else {
+                    U256::zero()
+                };
+                let aliased = if *alias == index {
+                    self.total_stake - threshold
+                } else {
+                    U256::zero()
+                };
+                weight + own + aliased
+            },
+        )
+    }
+}
+
+/// Randomness for picking the leader of `view_number` in an epoch with `drb_result`.
+#[must_use]
+pub fn leader_randomness(view_number: u64, drb_result: &DrbResult) -> U256 {
+    let hash = Sha256::new()
+        .chain_update(drb_result)
+        .chain_update(view_number.to_le_bytes())
+        .finalize();
+
+    U256::from_big_endian(&hash)
+}
+
+/// Use the DRB result to get the leader, with probability proportional to stake.
 ///
-/// The DRB result is the output of a spawned `compute_drb_result` call.
+/// The DRB result is the output of a spawned `compute_drb_result` call. This builds the alias
+/// table for `stake_table` on every call, keep a [`StakeAliasTable`] per epoch instead to pick
+/// the leaders of many views.
+///
+/// Returns `None` if there is no stake in `stake_table`.
 #[must_use]
 pub fn leader<TYPES: NodeType>(
-    view_number: usize,
+    view_number: u64,
     stake_table: &[<TYPES::SignatureKey as SignatureKey>::StakeTableEntry],
     drb_result: DrbResult,
-) -> TYPES::SignatureKey {
-    let mut hasher = DefaultHasher::new();
-    drb_result.hash(&mut hasher);
-    view_number.hash(&mut hasher);
-    #[allow(clippy::cast_possible_truncation)]
-    // TODO: Use the total stake rather than `len()` and update the indexing after switching to
-    // a weighted stake table.
-    // <https://github.com/EspressoSystems/HotShot/issues/3898>
-    let index = (hasher.finish() as usize) % stake_table.len();
-    let entry = stake_table[index].clone();
-    TYPES::SignatureKey::public_key(&entry)
+) -> Option<TYPES::SignatureKey> {
+    let stakes: Vec<U256> = stake_table.iter().map(StakeTableEntryType::stake).collect();
+    let table = StakeAliasTable::new(&stakes)?;
+    let index = table.sample(leader_randomness(view_number, &drb_result));
+
+    Some(TYPES::SignatureKey::public_key(&stake_table[index]))
+}
+
+/// Domain separator for the randomness of DA committee sampling, keeping it independent from
+/// leader selection.
+const DA_COMMITTEE_DOMAIN: &[u8] = b"DA committee";
+
+/// Number of draws per seat after which sampling a committee gives up, and fills the remaining
+/// seats with the highest stakes.
+const MAX_DRAWS_PER_SEAT: usize = 32;
+
+/// Randomness for the `draw`-th sample of the DA committee in an epoch with `drb_result`.
+#[must_use]
+pub fn da_committee_randomness(draw: u64, drb_result: &DrbResult) -> U256 {
+    let hash = Sha256::new()
+        .chain_update(DA_COMMITTEE_DOMAIN)
+        .chain_update(drb_result)
+        .chain_update(draw.to_le_bytes())
+        .finalize();
+
+    U256::from_big_endian(&hash)
+}
+
+/// Use the DRB result to sample `size` distinct entries of `stakes` for a committee, each draw
+/// picking an entry with probability proportional to stake.
+///
+/// Entries without stake are never picked, and if there are at most `size` entries with stake,
+/// all of them are. Returns the sorted indices of the picked entries.
+#[must_use]
+pub fn sample_committee(stakes: &[U256], size: usize, drb_result: &DrbResult) -> Vec<usize> {
+    let staked: Vec<usize> = (0..stakes.len())
+        .filter(|&index| !stakes[index].is_zero())
+        .collect();
+    if staked.len() <= size {
+        return staked;
+    }
+    let Some(table) = StakeAliasTable::new(stakes) else {
+        return Vec::new();
+    };
+
+    let mut picked = BTreeSet::new();
+    let mut draw = 0;
+    while picked.len() < size && draw < size.saturating_mul(MAX_DRAWS_PER_SEAT) {
+        picked.insert(table.sample(da_committee_randomness(draw as u64, drb_result)));
+        draw += 1;
+    }
+
+    // Very unbalanced stakes can keep drawing the same entries, give the remaining seats to the
+    // highest stakes.
+    if picked.len() < size {
+        let mut remaining: Vec<usize> = staked
+            .into_iter()
+            .filter(|index| !picked.contains(index))
+            .collect();
+        remaining.sort_by(|a, b| stakes[*b].cmp(&stakes[*a]).then(a.cmp(b)));
+        let missing = size - picked.len();
+        picked.extend(remaining.into_iter().take(missing));
+    }
+
+    picked.into_iter().collect()
 }
 
 /// Alias for in-progress DRB computation task, if there's any.
@@ -108,6 +446,12 @@ pub struct DrbSeedsAndResults<TYPES: NodeType> {
 
     /// Stored results from computations
     pub results: BTreeMap<TYPES::Epoch, DrbResult>,
+
+    /// Progress of interrupted computations, to be resumed
+    pub checkpoints: BTreeMap<TYPES::Epoch, DrbCheckpoint>,
+
+    /// Proofs of the stored seeds, served to peers missing the corresponding results
+    pub seed_proofs: BTreeMap<TYPES::Epoch, DrbSeedProof<TYPES>>,
 }
 
 impl<TYPES: NodeType> DrbSeedsAndResults<TYPES> {
@@ -123,6 +467,8 @@ impl<TYPES: NodeType> DrbSeedsAndResults<TYPES> {
                 (TYPES::Epoch::new(1), INITIAL_DRB_RESULT),
                 (TYPES::Epoch::new(2), INITIAL_DRB_RESULT),
             ]),
+            checkpoints: BTreeMap::new(),
+            seed_proofs: BTreeMap::new(),
         }
     }
 
@@ -142,9 +488,11 @@ impl<TYPES: NodeType> DrbSeedsAndResults<TYPES> {
 
         // Remove result entries older than EPOCH
         self.results = self.results.split_off(&retain_epoch);
+        self.seed_proofs = self.seed_proofs.split_off(&retain_epoch);
 
         // Remove result entries older than EPOCH+1
         self.seeds = self.seeds.split_off(&(retain_epoch + 1));
+        self.checkpoints = self.checkpoints.split_off(&(retain_epoch + 1));
     }
 }
 
@@ -153,3 +501,121 @@ impl<TYPES: NodeType> Default for DrbSeedsAndResults<TYPES> {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checkpointed_calculation_matches() {
+        let seed = [7u8; 32];
+        let difficulty = 1000;
+
+        let mut checkpoint = DrbCheckpoint::new(seed);
+        let mut result = None;
+        while result.is_none() {
+            result = checkpoint.advance(difficulty, 64);
+            // Persisting and reloading the checkpoint does not change the outcome.
+            checkpoint = bincode::deserialize(&bincode::serialize(&checkpoint).unwrap()).unwrap();
+        }
+
+        let mut hash = seed;
+        for _ in 0..difficulty {
+            hash = Sha256::digest(hash).into();
+        }
+        assert_eq!(result, Some(hash));
+        assert_eq!(checkpoint.result(difficulty), Some(hash));
+        assert_eq!(checkpoint.result(difficulty + 1), None);
+    }
+
+    #[test]
+    fn difficulty_from_hash_rate() {
+        assert_eq!(
+            difficulty_level(1_000_000, Duration::from_secs(2)),
+            2_000_000 * DRB_CALCULATION_NUM_VIEW
+        );
+        assert_eq!(difficulty_level(0, Duration::from_secs(2)), 1);
+        assert_eq!(
+            difficulty_level(u64::MAX, Duration::from_secs(10)),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn alias_table_matches_stake() {
+        let stakes: Vec<U256> = [1u64, 2, 3, 4, 10, 0, 7, 1]
+            .into_iter()
+            .map(U256::from)
+            .collect();
+        let table = StakeAliasTable::new(&stakes).unwrap();
+
+        // The table holds exactly `n` times each entry's stake.
+        for (index, stake) in stakes.iter().enumerate() {
+            assert_eq!(table.weight(index), *stake * U256::from(stakes.len()));
+        }
+
+        assert!(StakeAliasTable::new(&[U256::zero(), U256::zero()]).is_none());
+        assert!(StakeAliasTable::new(&[]).is_none());
+    }
+
+    #[test]
+    fn leader_distribution_matches_stake() {
+        let stakes: Vec<u64> = vec![1, 2, 3, 4, 10, 0, 7, 1];
+        let total_stake: u64 = stakes.iter().sum();
+        let table =
+            StakeAliasTable::new(&stakes.iter().copied().map(U256::from).collect::<Vec<_>>())
+                .unwrap();
+
+        let drb_result = [3u8; 32];
+        let num_views = 100_000u64;
+        let mut counts = vec![0u64; stakes.len()];
+        for view_number in 0..num_views {
+            counts[table.sample(leader_randomness(view_number, &drb_result))] += 1;
+        }
+
+        for (count, stake) in counts.iter().zip(&stakes) {
+            #[allow(clippy::cast_precision_loss)]
+            let (frequency, share) = (
+                *count as f64 / num_views as f64,
+                *stake as f64 / total_stake as f64,
+            );
+            // Well over 5 standard deviations for 100,000 samples.
+            assert!(
+                (frequency - share).abs() < 0.01,
+                "frequency {frequency} doesn't match stake share {share}"
+            );
+        }
+        assert_eq!(counts[5], 0);
+    }
+
+    #[test]
+    fn committee_sampling() {
+        let stakes: Vec<U256> = [1u64, 100, 1, 0, 1, 1, 50, 1]
+            .into_iter()
+            .map(U256::from)
+            .collect();
+
+        let committee = sample_committee(&stakes, 3, &[5u8; 32]);
+        assert_eq!(committee.len(), 3);
+        assert!(!committee.contains(&3));
+        assert!(committee.windows(2).all(|pair| pair[0] < pair[1]));
+        // Every node samples the same committee from the same DRB result.
+        assert_eq!(committee, sample_committee(&stakes, 3, &[5u8; 32]));
+
+        // Entries with more stake are picked far more often.
+        let mut counts = vec![0u64; stakes.len()];
+        for drb in 0..=255u8 {
+            for index in sample_committee(&stakes, 2, &[drb; 32]) {
+                counts[index] += 1;
+            }
+        }
+        assert!(counts[1] > 200 && counts[6] > 200);
+        assert_eq!(counts[3], 0);
+
+        // Everyone with stake is picked when there aren't more than seats.
+        assert_eq!(
+            sample_committee(&stakes, 7, &[5u8; 32]),
+            vec![0, 1, 2, 4, 5, 6, 7]
+        );
+    }
+}
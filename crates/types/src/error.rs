@@ -23,6 +23,10 @@ pub enum HotShotError<TYPES: NodeType> {
     #[error("Invalid state: {0}")]
     InvalidState(String),
 
+    /// The configuration of the node is invalid
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+
     /// Leaf was not present in storage
     #[error("Missing leaf with commitment: {0}")]
     MissingLeaf(Commitment<Leaf2<TYPES>>),
@@ -43,6 +47,15 @@ pub enum HotShotError<TYPES: NodeType> {
         /// The state that the round was in when it timed out
         state: RoundTimedoutState,
     },
+
+    /// A long running task panicked or stopped unexpectedly
+    #[error("Task {task} failed: {reason}")]
+    TaskFailed {
+        /// The name of the task that failed
+        task: String,
+        /// Description of the failure
+        reason: String,
+    },
 }
 
 /// Contains information about what the state of the hotshot-consensus was when a round timed out
@@ -13,6 +13,7 @@ use serde::{Deserialize, Serialize};
 use crate::{
     data::{DaProposal2, Leaf2, QuorumProposalWrapper, UpgradeProposal, VidDisperseShare},
     error::HotShotError,
+    light_client::StateSignaturesBundle,
     message::Proposal,
     simple_certificate::QuorumCertificate2,
     traits::{node_implementation::NodeType, ValidatedState},
@@ -180,6 +181,12 @@ pub enum EventType<TYPES: NodeType> {
         /// Serialized data of the message
         data: Vec<u8>,
     },
+
+    /// Enough stake signed the light client state of a decided leaf to certify it
+    LightClientStateSignatures {
+        /// The state and the signatures collected for it
+        bundle: StateSignaturesBundle,
+    },
 }
 #[derive(Debug, Serialize, Deserialize, Clone, Copy)]
 /// A list of actions that we track for nodes
@@ -10,8 +10,12 @@ use url::Url;
 use vec1::Vec1;
 
 use crate::{
-    constants::REQUEST_DATA_DELAY, traits::signature_key::SignatureKey,
-    upgrade_config::UpgradeConfig, HotShotConfig, PeerConfig, ValidatorConfig,
+    constants::REQUEST_DATA_DELAY,
+    drb::{difficulty_level, DIFFICULTY_LEVEL},
+    traits::signature_key::SignatureKey,
+    upgrade_config::UpgradeConfig,
+    vid::VidConfig,
+    HotShotConfig, PeerConfig, ValidatorConfig,
 };
 
 /// Default builder URL, used as placeholder
@@ -55,6 +59,19 @@ pub struct HotShotConfigFile<KEY: SignatureKey> {
     pub upgrade: UpgradeConfig,
     /// Number of blocks in an epoch, zero means there are no epochs
     pub epoch_height: u64,
+    /// Number of times a panicked consensus task is restarted, zero disables restarts
+    #[serde(default)]
+    pub task_restart_limit: usize,
+    /// VID erasure code rate and share assignment
+    #[serde(default)]
+    pub vid_config: VidConfig,
+    /// Number of hashes of the DRB calculation. Derived from `drb_hashes_per_second` if unset.
+    #[serde(default)]
+    pub drb_difficulty: Option<u64>,
+    /// Measured DRB hashes per second of the slowest expected node, see
+    /// [`measure_hashes_per_second`](crate::drb::measure_hashes_per_second)
+    #[serde(default)]
+    pub drb_hashes_per_second: Option<u64>,
 }
 
 impl<KEY: SignatureKey> From<HotShotConfigFile<KEY>> for HotShotConfig<KEY> {
@@ -83,6 +100,17 @@ impl<KEY: SignatureKey> From<HotShotConfigFile<KEY>> for HotShotConfig<KEY> {
             start_voting_time: val.upgrade.start_voting_time,
             stop_voting_time: val.upgrade.stop_voting_time,
             epoch_height: val.epoch_height,
+            task_restart_limit: val.task_restart_limit,
+            vid_config: val.vid_config,
+            drb_difficulty: val.drb_difficulty.unwrap_or_else(|| {
+                val.drb_hashes_per_second
+                    .map_or(DIFFICULTY_LEVEL, |hashes_per_second| {
+                        difficulty_level(
+                            hashes_per_second,
+                            Duration::from_millis(val.next_view_timeout),
+                        )
+                    })
+            }),
         }
     }
 }
@@ -128,6 +156,10 @@ impl<KEY: SignatureKey> HotShotConfigFile<KEY> {
             builder_urls: default_builder_urls(),
             upgrade: UpgradeConfig::default(),
             epoch_height: 0,
+            task_restart_limit: 0,
+            vid_config: VidConfig::default(),
+            drb_difficulty: None,
+            drb_hashes_per_second: None,
         }
     }
 }
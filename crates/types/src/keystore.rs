@@ -0,0 +1,541 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Password encrypted files holding the keys of a validator.
+//!
+//! A keystore holds the staking key, the state (light client) key and the libp2p key of a
+//! validator. The public keys are stored in the clear, so the public keys of a committee can be
+//! collected without any password, while the private keys are encrypted with AES-256-GCM under a
+//! key derived from the password with scrypt. The encryption parameters are laid out like the
+//! `crypto` module of EIP-2335 keystores, the authentication tag of the cipher standing in for
+//! their checksum.
+
+use std::{fmt::Debug, fs, io::Write, path::Path};
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use libp2p_identity::{ed25519, Keypair, PeerId};
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use tagged_base64::TaggedBase64;
+use thiserror::Error;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::{
+    light_client::{StateKeyPair, StateSignKey, StateVerKey},
+    network::PeerConfigKeys,
+    traits::signature_key::{PrivateSignatureKey, SignatureKey},
+    ValidatorConfig,
+};
+
+/// Version of the keystore format written by this module
+pub const KEYSTORE_VERSION: u32 = 2;
+
+/// Base 2 logarithm of the scrypt cost of new keystores, as recommended by EIP-2335
+pub const DEFAULT_SCRYPT_LOG_N: u8 = 18;
+
+/// Largest base 2 logarithm of the scrypt cost we accept, bounding the memory a keystore can make
+/// us use to 1 GiB
+const MAX_SCRYPT_LOG_N: u32 = 20;
+
+/// scrypt block size of keystores, with [`MAX_SCRYPT_LOG_N`] bounding the memory scrypt uses
+const SCRYPT_R: u32 = 8;
+
+/// scrypt parallelism of keystores, with [`MAX_SCRYPT_LOG_N`] bounding the time scrypt takes
+const SCRYPT_P: u32 = 1;
+
+/// Length of the key derived from the password
+const KEY_LEN: usize = 32;
+
+/// Length of the AES-GCM nonce
+const IV_LEN: usize = 12;
+
+/// Name of the key derivation function
+const KDF_FUNCTION: &str = "scrypt";
+
+/// Name of the cipher
+const CIPHER_FUNCTION: &str = "aes-256-gcm";
+
+/// Tag of the encoded salt
+const SALT_TAG: &str = "KEYSTORE_SALT";
+
+/// Tag of the encoded nonce
+const NONCE_TAG: &str = "KEYSTORE_NONCE";
+
+/// Tag of the encoded private keys
+const CIPHERTEXT_TAG: &str = "KEYSTORE_KEYS";
+
+/// Error reading or writing a keystore
+#[derive(Error, Debug)]
+pub enum KeystoreError {
+    /// Failed to read or write the keystore file
+    #[error("Failed to access the keystore file: {0}")]
+    Io(#[from] std::io::Error),
+    /// The keystore file is malformed
+    #[error("Malformed keystore: {0}")]
+    Malformed(String),
+    /// The keystore was written by an unknown version
+    #[error("Unsupported keystore version {0}")]
+    UnsupportedVersion(u32),
+    /// The password is wrong or the keystore was tampered with
+    #[error("Wrong password or corrupted keystore")]
+    WrongPassword,
+    /// No password was given for the keystore
+    #[error("No password given for the keystore")]
+    MissingPassword,
+}
+
+/// The private keys of a validator. The libp2p secret is wiped from memory when the keys are
+/// dropped.
+#[derive(Clone)]
+pub struct ValidatorKeys<KEY: SignatureKey> {
+    /// The staking key, signing consensus messages
+    pub private_key: KEY::PrivateKey,
+    /// The state key pair, signing light client states
+    pub state_key_pair: StateKeyPair,
+    /// The secret of the ed25519 libp2p key
+    libp2p_secret: [u8; 32],
+}
+
+impl<KEY: SignatureKey> Debug for ValidatorKeys<KEY> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ValidatorKeys")
+            .field("public_key", &self.public_key())
+            .field("state_ver_key", &self.state_ver_key())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<KEY: SignatureKey> ValidatorKeys<KEY> {
+    /// Generate random keys
+    #[must_use]
+    pub fn generate() -> Self {
+        let mut keys = Self {
+            private_key: KEY::generated_from_seed_indexed(rand::random(), 0).1,
+            state_key_pair: StateKeyPair::generate(),
+            libp2p_secret: [0u8; 32],
+        };
+        keys.rotate_libp2p_key();
+        keys
+    }
+
+    /// Replace the staking key with a random one
+    pub fn rotate_staking_key(&mut self) {
+        self.private_key = KEY::generated_from_seed_indexed(rand::random(), 0).1;
+    }
+
+    /// Replace the state key with a random one
+    pub fn rotate_state_key(&mut self) {
+        self.state_key_pair = StateKeyPair::generate();
+    }
+
+    /// Replace the libp2p key with a random one
+    pub fn rotate_libp2p_key(&mut self) {
+        rand::thread_rng().fill_bytes(&mut self.libp2p_secret);
+    }
+
+    /// The public staking key
+    #[must_use]
+    pub fn public_key(&self) -> KEY {
+        KEY::from_private(&self.private_key)
+    }
+
+    /// The public state key
+    #[must_use]
+    pub fn state_ver_key(&self) -> StateVerKey {
+        self.state_key_pair.ver_key()
+    }
+
+    /// The libp2p key pair
+    #[must_use]
+    pub fn libp2p_keypair(&self) -> Keypair {
+        // Any 32 bytes are a valid ed25519 secret
+        let mut secret = self.libp2p_secret;
+        let secret = ed25519::SecretKey::try_from_bytes(&mut secret)
+            .expect("32 bytes are a valid ed25519 secret");
+        ed25519::Keypair::from(secret).into()
+    }
+
+    /// The libp2p peer ID
+    #[must_use]
+    pub fn libp2p_peer_id(&self) -> PeerId {
+        self.libp2p_keypair().public().to_peer_id()
+    }
+
+    /// The public keys of a node with `stake`, as listed in a
+    /// [`PublicKeysFile`](crate::network::PublicKeysFile)
+    #[must_use]
+    pub fn peer_config_keys(&self, stake: u64, da: bool) -> PeerConfigKeys<KEY> {
+        PeerConfigKeys {
+            stake_table_key: self.public_key(),
+            state_ver_key: self.state_ver_key(),
+            stake,
+            da,
+        }
+    }
+
+    /// The configuration of a validator with these keys
+    #[must_use]
+    pub fn validator_config(&self, stake_value: u64, is_da: bool) -> ValidatorConfig<KEY> {
+        ValidatorConfig {
+            public_key: self.public_key(),
+            private_key: self.private_key.clone(),
+            stake_value,
+            state_key_pair: self.state_key_pair.clone(),
+            is_da,
+            signing_guard: None,
+        }
+    }
+}
+
+impl<KEY: SignatureKey> Drop for ValidatorKeys<KEY> {
+    fn drop(&mut self) {
+        self.libp2p_secret.zeroize();
+    }
+}
+
+/// The private keys as encrypted in a keystore
+#[derive(Serialize, Deserialize)]
+struct KeystoreSecrets {
+    /// The serialized staking key
+    staking_key: Vec<u8>,
+    /// The serialized state signing key
+    state_key: Vec<u8>,
+    /// The secret of the libp2p key
+    libp2p_secret: [u8; 32],
+}
+
+impl Drop for KeystoreSecrets {
+    fn drop(&mut self) {
+        self.staking_key.zeroize();
+        self.state_key.zeroize();
+        self.libp2p_secret.zeroize();
+    }
+}
+
+/// Parameters of the scrypt key derivation
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScryptParams {
+    /// Length of the derived key
+    pub dklen: u32,
+    /// CPU and memory cost, a power of two
+    pub n: u32,
+    /// Block size
+    pub r: u32,
+    /// Parallelism
+    pub p: u32,
+    /// Salt of the derivation
+    pub salt: String,
+}
+
+/// How the encryption key is derived from the password
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfModule {
+    /// The key derivation function, always `scrypt`
+    pub function: String,
+    /// Parameters of the key derivation
+    pub params: ScryptParams,
+}
+
+/// Parameters of the cipher
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CipherParams {
+    /// Nonce of the encryption
+    pub iv: String,
+}
+
+/// How the private keys are encrypted
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CipherModule {
+    /// The cipher, always `aes-256-gcm`
+    pub function: String,
+    /// Parameters of the cipher
+    pub params: CipherParams,
+    /// The encrypted private keys, followed by their authentication tag
+    pub message: String,
+}
+
+/// The encrypted private keys of a keystore, with the parameters needed to decrypt them
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeystoreCrypto {
+    /// How the encryption key is derived from the password
+    pub kdf: KdfModule,
+    /// How the private keys are encrypted
+    pub cipher: CipherModule,
+}
+
+/// A keystore file
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(deserialize = ""))]
+pub struct KeystoreFile<KEY: SignatureKey> {
+    /// Version of the keystore format
+    pub version: u32,
+    /// The public staking key
+    pub stake_table_key: KEY,
+    /// The public state key
+    pub state_ver_key: StateVerKey,
+    /// The libp2p peer ID
+    pub libp2p_peer_id: PeerId,
+    /// The encrypted private keys
+    pub crypto: KeystoreCrypto,
+}
+
+/// Derive the encryption key from `password` as described by `kdf`
+fn derive_key(password: &str, kdf: &KdfModule) -> Result<Zeroizing<[u8; KEY_LEN]>, KeystoreError> {
+    if kdf.function != KDF_FUNCTION {
+        return Err(KeystoreError::Malformed(format!(
+            "unsupported key derivation function {}",
+            kdf.function
+        )));
+    }
+    let ScryptParams {
+        dklen,
+        n,
+        r,
+        p,
+        salt,
+    } = &kdf.params;
+    if usize::try_from(*dklen).ok() != Some(KEY_LEN) {
+        return Err(KeystoreError::Malformed(format!(
+            "unsupported derived key length {dklen}"
+        )));
+    }
+    if !n.is_power_of_two() || n.trailing_zeros() > MAX_SCRYPT_LOG_N {
+        return Err(KeystoreError::Malformed(format!(
+            "unsupported scrypt cost {n}"
+        )));
+    }
+    if *r != SCRYPT_R || *p != SCRYPT_P {
+        return Err(KeystoreError::Malformed(format!(
+            "unsupported scrypt block size {r} and parallelism {p}"
+        )));
+    }
+
+    let log_n = u8::try_from(n.trailing_zeros()).expect("The scrypt cost was bounded above");
+    let params = scrypt::Params::new(log_n, *r, *p, KEY_LEN)
+        .map_err(|e| KeystoreError::Malformed(format!("scrypt parameters: {e}")))?;
+    let salt = decode(salt, SALT_TAG)?;
+
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    scrypt::scrypt(password.as_bytes(), &salt, &params, &mut key[..])
+        .map_err(|e| KeystoreError::Malformed(format!("scrypt: {e}")))?;
+    Ok(key)
+}
+
+/// Decode bytes encoded as tagged base64 with `tag`
+fn decode(encoded: &str, tag: &str) -> Result<Vec<u8>, KeystoreError> {
+    let decoded = TaggedBase64::parse(encoded)
+        .map_err(|e| KeystoreError::Malformed(format!("{tag}: {e}")))?;
+    if decoded.tag() != tag {
+        return Err(KeystoreError::Malformed(format!(
+            "expected a {tag}, found a {}",
+            decoded.tag()
+        )));
+    }
+    Ok(decoded.value())
+}
+
+/// Encode `bytes` as tagged base64 with `tag`
+fn encode(bytes: &[u8], tag: &str) -> String {
+    TaggedBase64::new(tag, bytes)
+        .expect("Keystore tags are valid")
+        .to_string()
+}
+
+/// Read a password from the first line of the file at `path`
+///
+/// # Errors
+/// If the file can't be read
+pub fn read_password_file(path: impl AsRef<Path>) -> Result<Zeroizing<String>, KeystoreError> {
+    let contents = Zeroizing::new(fs::read_to_string(path)?);
+    Ok(Zeroizing::new(
+        contents.lines().next().unwrap_or_default().to_string(),
+    ))
+}
+
+impl<KEY: SignatureKey> KeystoreFile<KEY> {
+    /// Encrypt `keys` with `password`
+    #[must_use]
+    pub fn encrypt(keys: &ValidatorKeys<KEY>, password: &str) -> Self {
+        Self::encrypt_with_scrypt_log_n(keys, password, DEFAULT_SCRYPT_LOG_N)
+    }
+
+    /// Encrypt `keys` with `password`, deriving the encryption key with a scrypt cost of
+    /// `2^scrypt_log_n`
+    ///
+    /// # Panics
+    /// If the scrypt cost is too high or the state key can't be serialized
+    #[must_use]
+    pub fn encrypt_with_scrypt_log_n(
+        keys: &ValidatorKeys<KEY>,
+        password: &str,
+        scrypt_log_n: u8,
+    ) -> Self {
+        let mut state_key = Vec::new();
+        keys.state_key_pair
+            .sign_key_ref()
+            .serialize_compressed(&mut state_key)
+            .expect("Serialization should not fail.");
+        let secrets = KeystoreSecrets {
+            staking_key: keys.private_key.to_bytes(),
+            state_key,
+            libp2p_secret: keys.libp2p_secret,
+        };
+        let plaintext =
+            Zeroizing::new(bincode::serialize(&secrets).expect("Serialization should not fail."));
+
+        let mut rng = rand::thread_rng();
+        let salt: [u8; 32] = rng.gen();
+        let iv: [u8; IV_LEN] = rng.gen();
+        let mut keystore = Self {
+            version: KEYSTORE_VERSION,
+            stake_table_key: keys.public_key(),
+            state_ver_key: keys.state_ver_key(),
+            libp2p_peer_id: keys.libp2p_peer_id(),
+            crypto: KeystoreCrypto {
+                kdf: KdfModule {
+                    function: KDF_FUNCTION.to_string(),
+                    params: ScryptParams {
+                        dklen: KEY_LEN.try_into().expect("The key length fits in 32 bits"),
+                        n: 1u32
+                            .checked_shl(scrypt_log_n.into())
+                            .expect("The scrypt cost fits in 32 bits"),
+                        r: SCRYPT_R,
+                        p: SCRYPT_P,
+                        salt: encode(&salt, SALT_TAG),
+                    },
+                },
+                cipher: CipherModule {
+                    function: CIPHER_FUNCTION.to_string(),
+                    params: CipherParams {
+                        iv: encode(&iv, NONCE_TAG),
+                    },
+                    message: String::new(),
+                },
+            },
+        };
+
+        let key = derive_key(password, &keystore.crypto.kdf).expect("Valid scrypt parameters");
+        let ciphertext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key[..]))
+            .encrypt(
+                Nonce::from_slice(&iv),
+                Payload {
+                    msg: &plaintext,
+                    aad: &keystore
+                        .associated_data()
+                        .expect("Serialization should not fail."),
+                },
+            )
+            .expect("Encryption should not fail.");
+        keystore.crypto.cipher.message = encode(&ciphertext, CIPHERTEXT_TAG);
+
+        keystore
+    }
+
+    /// The data authenticated along with the private keys: everything but the ciphertext, so
+    /// neither the public keys nor the encryption parameters can be swapped
+    fn associated_data(&self) -> Result<Vec<u8>, KeystoreError> {
+        bincode::serialize(&(
+            self.version,
+            &self.stake_table_key,
+            &self.state_ver_key,
+            &self.libp2p_peer_id,
+            &self.crypto.kdf,
+            &self.crypto.cipher.function,
+            &self.crypto.cipher.params,
+        ))
+        .map_err(|e| KeystoreError::Malformed(e.to_string()))
+    }
+
+    /// Decrypt the keys with `password`
+    ///
+    /// # Errors
+    /// If the password is wrong, or the keystore is malformed or doesn't match its public keys
+    pub fn decrypt(&self, password: &str) -> Result<ValidatorKeys<KEY>, KeystoreError> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(KeystoreError::UnsupportedVersion(self.version));
+        }
+        if self.crypto.cipher.function != CIPHER_FUNCTION {
+            return Err(KeystoreError::Malformed(format!(
+                "unsupported cipher {}",
+                self.crypto.cipher.function
+            )));
+        }
+
+        let iv = decode(&self.crypto.cipher.params.iv, NONCE_TAG)?;
+        if iv.len() != IV_LEN {
+            return Err(KeystoreError::Malformed("invalid nonce".to_string()));
+        }
+        let ciphertext = decode(&self.crypto.cipher.message, CIPHERTEXT_TAG)?;
+
+        let key = derive_key(password, &self.crypto.kdf)?;
+        let plaintext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key[..]))
+            .decrypt(
+                Nonce::from_slice(&iv),
+                Payload {
+                    msg: &ciphertext,
+                    aad: &self.associated_data()?,
+                },
+            )
+            .map(Zeroizing::new)
+            .map_err(|_| KeystoreError::WrongPassword)?;
+
+        let secrets: KeystoreSecrets = bincode::deserialize(&plaintext)
+            .map_err(|e| KeystoreError::Malformed(format!("private keys: {e}")))?;
+        let private_key = KEY::PrivateKey::from_bytes(&secrets.staking_key)
+            .map_err(|e| KeystoreError::Malformed(format!("staking key: {e}")))?;
+        let state_sign_key = StateSignKey::deserialize_compressed(&*secrets.state_key)
+            .map_err(|e| KeystoreError::Malformed(format!("state key: {e}")))?;
+        let keys = ValidatorKeys {
+            private_key,
+            state_key_pair: StateKeyPair::from_sign_key(state_sign_key),
+            libp2p_secret: secrets.libp2p_secret,
+        };
+
+        if keys.public_key() != self.stake_table_key
+            || keys.state_ver_key() != self.state_ver_key
+            || keys.libp2p_peer_id() != self.libp2p_peer_id
+        {
+            return Err(KeystoreError::Malformed(
+                "the public keys don't match the private keys".to_string(),
+            ));
+        }
+
+        Ok(keys)
+    }
+
+    /// Read a keystore from the file at `path`
+    ///
+    /// # Errors
+    /// If the file can't be read or isn't a keystore
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, KeystoreError> {
+        let contents = fs::read_to_string(path)?;
+        toml::from_str(&contents).map_err(|e| KeystoreError::Malformed(e.to_string()))
+    }
+
+    /// Write the keystore to the file at `path`, only readable by the current user on Unix.
+    /// Existing files are not overwritten.
+    ///
+    /// # Errors
+    /// If the file exists or can't be written
+    pub fn to_file(&self, path: impl AsRef<Path>) -> Result<(), KeystoreError> {
+        let contents =
+            toml::to_string_pretty(self).map_err(|e| KeystoreError::Malformed(e.to_string()))?;
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let mut file = options.open(path)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        Ok(())
+    }
+}
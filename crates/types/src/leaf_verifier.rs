@@ -0,0 +1,383 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Verification of decided leaves which doesn't trust the node serving them.
+//!
+//! A [`LeafVerifier`] is given the stake table of each epoch, or a commitment to it, and checks
+//! chains of [`Leaf2`]s along with the [`QuorumCertificate2`] over their last leaf: that each
+//! leaf extends the previous one, that every QC is signed by enough stake of its epoch's stake
+//! table, and that the chain satisfies the decide rule of the version it's in: the 3-chain rule of
+//! HotStuff before epochs, and the 2-chain rule of HotStuff 2 with epochs. It returns the headers
+//! of the leaves the chain decides, which light clients and bridges can then rely on.
+
+use std::{collections::BTreeMap, num::NonZeroU64};
+
+use committable::{Commitment, Committable, RawCommitmentBuilder};
+use primitive_types::U256;
+use serde::{Deserialize, Serialize};
+use utils::anytrace::*;
+
+use crate::{
+    data::Leaf2,
+    message::UpgradeLock,
+    simple_certificate::QuorumCertificate2,
+    traits::{
+        election::Membership,
+        node_implementation::{NodeType, Versions},
+        signature_key::{SignatureKey, StakeTableEntryType},
+    },
+    vote::{Certificate, HasViewNumber},
+};
+
+/// The stake table quorum certificates of an epoch are checked against
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct VerifierStakeTable<TYPES: NodeType> {
+    /// The stake table entries, in the order signers are indexed in the QCs
+    pub entries: Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry>,
+    /// The stake the signers of a QC must reach
+    pub threshold: NonZeroU64,
+}
+
+impl<TYPES: NodeType> VerifierStakeTable<TYPES> {
+    /// A stake table with the success threshold of consensus: more than two thirds of the total
+    /// stake
+    #[must_use]
+    pub fn new(entries: Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry>) -> Self {
+        let total_stake = entries.iter().fold(U256::zero(), |total, entry| {
+            total.saturating_add(entry.stake())
+        });
+        let threshold = (total_stake.saturating_mul(U256::from(2)) / 3 + 1)
+            .min(U256::from(u64::MAX))
+            .as_u64();
+
+        Self {
+            entries,
+            threshold: NonZeroU64::new(threshold).unwrap_or(NonZeroU64::MIN),
+        }
+    }
+
+    /// The stake table and success threshold `membership` uses in `epoch`
+    #[must_use]
+    pub fn from_membership(membership: &TYPES::Membership, epoch: Option<TYPES::Epoch>) -> Self {
+        Self {
+            entries: membership.stake_table(epoch),
+            threshold: membership.success_threshold(epoch),
+        }
+    }
+}
+
+impl<TYPES: NodeType> Committable for VerifierStakeTable<TYPES> {
+    /// Commits to the key and stake of each entry, which can't fail unlike serializing the entry
+    fn commit(&self) -> Commitment<Self> {
+        let mut builder = RawCommitmentBuilder::new("Verifier stake table commitment")
+            .u64_field("threshold", self.threshold.get())
+            .u64_field("entries", self.entries.len() as u64);
+        for entry in &self.entries {
+            let mut stake = [0u8; 32];
+            entry.stake().to_little_endian(&mut stake);
+            builder = builder
+                .var_size_bytes(&entry.public_key().to_bytes())
+                .fixed_size_bytes(&stake);
+        }
+        builder.finalize()
+    }
+}
+
+/// What the verifier knows about the stake table of an epoch
+#[derive(Clone, Debug)]
+enum EpochStakeTable<TYPES: NodeType> {
+    /// The stake table itself
+    Known(VerifierStakeTable<TYPES>),
+    /// Only a commitment to it, the stake table must be provided before verifying QCs of the epoch
+    Committed(Commitment<VerifierStakeTable<TYPES>>),
+}
+
+/// Verifies chains of decided leaves against trusted stake tables
+#[derive(Clone, Debug)]
+pub struct LeafVerifier<TYPES: NodeType, V: Versions> {
+    /// Number of blocks in an epoch, zero means there are no epochs
+    epoch_height: u64,
+
+    /// The stake table of each epoch, `None` being the stake table used without epochs
+    stake_tables: BTreeMap<Option<TYPES::Epoch>, EpochStakeTable<TYPES>>,
+
+    /// Lock for the versions the QCs are signed under, updated with decided upgrade certificates
+    upgrade_lock: UpgradeLock<TYPES, V>,
+
+    /// The latest leaf we verified as decided
+    anchor: Option<Leaf2<TYPES>>,
+}
+
+impl<TYPES: NodeType, V: Versions> LeafVerifier<TYPES, V> {
+    /// Create a verifier for a chain with epochs of `epoch_height` blocks, the QCs being signed
+    /// under the versions of `upgrade_lock`
+    #[must_use]
+    pub fn new(epoch_height: u64, upgrade_lock: UpgradeLock<TYPES, V>) -> Self {
+        Self {
+            epoch_height,
+            stake_tables: BTreeMap::new(),
+            upgrade_lock,
+            anchor: None,
+        }
+    }
+
+    /// The latest leaf verified as decided
+    #[must_use]
+    pub fn anchor(&self) -> Option<&Leaf2<TYPES>> {
+        self.anchor.as_ref()
+    }
+
+    /// Trust the stake table of `epoch`.
+    ///
+    /// # Errors
+    /// If a different stake table or a commitment to a different one was trusted for the epoch
+    pub fn add_stake_table(
+        &mut self,
+        epoch: Option<TYPES::Epoch>,
+        stake_table: VerifierStakeTable<TYPES>,
+    ) -> Result<()> {
+        match self.stake_tables.get(&epoch) {
+            Some(EpochStakeTable::Known(known)) => {
+                ensure!(
+                    *known == stake_table,
+                    warn!(
+                        "Another stake table is already trusted for epoch {:?}",
+                        epoch
+                    )
+                );
+            }
+            Some(EpochStakeTable::Committed(commitment)) => {
+                ensure!(
+                    *commitment == stake_table.commit(),
+                    warn!(
+                        "Stake table doesn't match the commitment trusted for epoch {:?}",
+                        epoch
+                    )
+                );
+            }
+            None => {}
+        }
+
+        self.stake_tables
+            .insert(epoch, EpochStakeTable::Known(stake_table));
+        Ok(())
+    }
+
+    /// Trust a commitment to the stake table of `epoch`. QCs of the epoch can only be verified
+    /// once a stake table matching it is added with [`LeafVerifier::add_stake_table`].
+    ///
+    /// # Errors
+    /// If a stake table or a commitment not matching `commitment` was trusted for the epoch
+    pub fn add_stake_table_commitment(
+        &mut self,
+        epoch: Option<TYPES::Epoch>,
+        commitment: Commitment<VerifierStakeTable<TYPES>>,
+    ) -> Result<()> {
+        match self.stake_tables.get(&epoch) {
+            Some(EpochStakeTable::Known(known)) => {
+                ensure!(
+                    known.commit() == commitment,
+                    warn!(
+                        "Commitment doesn't match the stake table trusted for epoch {:?}",
+                        epoch
+                    )
+                );
+            }
+            Some(EpochStakeTable::Committed(trusted)) => {
+                ensure!(
+                    *trusted == commitment,
+                    warn!(
+                        "Another commitment is already trusted for epoch {:?}",
+                        epoch
+                    )
+                );
+            }
+            None => {
+                self.stake_tables
+                    .insert(epoch, EpochStakeTable::Committed(commitment));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that `qc` is a valid QC over `leaf`, signed by the stake table of the leaf's epoch
+    async fn verify_qc(&self, qc: &QuorumCertificate2<TYPES>, leaf: &Leaf2<TYPES>) -> Result<()> {
+        ensure!(
+            qc.data.leaf_commit == leaf.commit(),
+            warn!(
+                "QC for view {:?} doesn't certify the leaf of view {:?}",
+                qc.view_number(),
+                leaf.view_number()
+            )
+        );
+        ensure!(
+            qc.view_number() == leaf.view_number(),
+            warn!(
+                "QC is for view {:?}, the leaf for view {:?}",
+                qc.view_number(),
+                leaf.view_number()
+            )
+        );
+
+        let epoch = leaf.epoch(self.epoch_height);
+        ensure!(
+            qc.data.epoch == epoch,
+            warn!(
+                "QC is for epoch {:?}, but the leaf of height {} is in epoch {:?}",
+                qc.data.epoch,
+                leaf.height(),
+                epoch
+            )
+        );
+
+        let stake_table = match self.stake_tables.get(&epoch) {
+            Some(EpochStakeTable::Known(stake_table)) => stake_table,
+            Some(EpochStakeTable::Committed(_)) => {
+                bail!(warn!(
+                    "Only a commitment to the stake table of epoch {:?} is known",
+                    epoch
+                ));
+            }
+            None => {
+                bail!(warn!("No stake table is trusted for epoch {:?}", epoch));
+            }
+        };
+
+        qc.is_valid_cert(
+            stake_table.entries.clone(),
+            stake_table.threshold,
+            &self.upgrade_lock,
+        )
+        .await
+        .context(warn!(
+            "Invalid QC for the leaf of view {:?}",
+            leaf.view_number()
+        ))
+    }
+
+    /// Verify a chain of `leaves`, oldest first, and `qc` over its last leaf, returning the
+    /// headers of the leaves it decides, oldest first.
+    ///
+    /// Like consensus, the decide rule is picked by the version of the last leaf's view. Before
+    /// epochs, the chain decides every leaf up to the first of its last three leaves, which must
+    /// have consecutive views. With epochs, it decides every leaf up to the first of its last two
+    /// leaves, which must have consecutive views. If a leaf was already verified as decided, the
+    /// chain must extend it.
+    ///
+    /// # Errors
+    /// If the chain is broken, any QC is invalid, epochs don't follow the block heights, or the
+    /// chain doesn't end in enough leaves of consecutive views for its decide rule.
+    pub async fn verify(
+        &mut self,
+        leaves: &[Leaf2<TYPES>],
+        qc: &QuorumCertificate2<TYPES>,
+    ) -> Result<Vec<TYPES::BlockHeader>> {
+        let last_view = leaves
+            .last()
+            .context(warn!("A decide takes at least one leaf"))?
+            .view_number();
+        let chain_len = if self.upgrade_lock.epochs_enabled(last_view).await {
+            2
+        } else {
+            3
+        };
+        ensure!(
+            leaves.len() >= chain_len,
+            warn!(
+                "A decide takes at least {} leaves, got {}",
+                chain_len,
+                leaves.len()
+            )
+        );
+
+        if let Some(anchor) = &self.anchor {
+            ensure!(
+                leaves[0].parent_commitment() == anchor.commit(),
+                warn!(
+                    "The chain doesn't extend the decided leaf of view {:?}",
+                    anchor.view_number()
+                )
+            );
+            self.verify_qc(&leaves[0].justify_qc(), anchor).await?;
+        }
+
+        for pair in leaves.windows(2) {
+            let (parent, child) = (&pair[0], &pair[1]);
+            ensure!(
+                child.parent_commitment() == parent.commit(),
+                warn!(
+                    "The leaf of view {:?} doesn't extend the leaf of view {:?}",
+                    child.view_number(),
+                    parent.view_number()
+                )
+            );
+            ensure!(
+                child.view_number() > parent.view_number() && child.height() == parent.height() + 1,
+                warn!(
+                    "The leaf of view {:?} doesn't follow the leaf of view {:?}",
+                    child.view_number(),
+                    parent.view_number()
+                )
+            );
+
+            // Epochs change at block heights, one at a time
+            let (parent_epoch, child_epoch) = (
+                parent.epoch(self.epoch_height),
+                child.epoch(self.epoch_height),
+            );
+            ensure!(
+                parent_epoch == child_epoch
+                    || parent_epoch
+                        .zip(child_epoch)
+                        .is_some_and(|(parent, child)| *child == *parent + 1),
+                warn!(
+                    "Invalid epoch transition from {:?} to {:?}",
+                    parent_epoch, child_epoch
+                )
+            );
+
+            self.verify_qc(&child.justify_qc(), parent).await?;
+        }
+
+        let last = leaves.len() - 1;
+        self.verify_qc(qc, &leaves[last]).await?;
+
+        let first = leaves.len() - chain_len;
+        let chain = &leaves[first..];
+        ensure!(
+            chain
+                .windows(2)
+                .all(|pair| pair[0].view_number() + 1 == pair[1].view_number()),
+            warn!(
+                "The last {} leaves have views {:?}, which aren't consecutive",
+                chain_len,
+                chain
+                    .iter()
+                    .map(Leaf2::view_number)
+                    .collect::<Vec<_>>()
+            )
+        );
+
+        // Like consensus, only take upgrade certificates decided before their deadline
+        let decided = &leaves[..=first];
+        let decided_view = chain[0].view_number();
+        for leaf in decided {
+            if let Some(cert) = leaf.upgrade_certificate() {
+                if cert.data.decide_by >= decided_view {
+                    *self.upgrade_lock.decided_upgrade_certificate.write().await = Some(cert);
+                }
+            }
+        }
+        self.anchor = decided.last().cloned();
+
+        Ok(decided
+            .iter()
+            .map(|leaf| leaf.block_header().clone())
+            .collect())
+    }
+}
@@ -5,11 +5,12 @@
 // along with the HotShot repository. If not, see <https://mit-license.org/>.
 
 //! Types and Traits for the `HotShot` consensus module
-use std::{fmt::Debug, future::Future, num::NonZeroUsize, pin::Pin, time::Duration};
+use std::{fmt::Debug, future::Future, num::NonZeroUsize, path::PathBuf, pin::Pin, time::Duration};
 
 use bincode::Options;
 use displaydoc::Display;
 use light_client::StateVerKey;
+use signer::NodeKey;
 use tracing::error;
 use traits::signature_key::SignatureKey;
 use url::Url;
@@ -26,6 +27,8 @@ pub mod error;
 pub mod event;
 /// Holds the configuration file specification for a HotShot node.
 pub mod hotshot_config_file;
+pub mod keystore;
+pub mod leaf_verifier;
 pub mod light_client;
 pub mod message;
 
@@ -34,6 +37,7 @@ pub mod network;
 pub mod qc;
 pub mod request_response;
 pub mod signature_key;
+pub mod signer;
 pub mod simple_certificate;
 pub mod simple_vote;
 pub mod stake_table;
@@ -78,6 +82,9 @@ pub struct ValidatorConfig<KEY: SignatureKey> {
     pub state_key_pair: light_client::StateKeyPair,
     /// Whether or not this validator is DA
     pub is_da: bool,
+    /// Where the highest view the validator signed for is persisted, so that it doesn't sign
+    /// twice for a view across restarts
+    pub signing_guard: Option<PathBuf>,
 }
 
 impl<KEY: SignatureKey> ValidatorConfig<KEY> {
@@ -97,9 +104,16 @@ impl<KEY: SignatureKey> ValidatorConfig<KEY> {
             stake_value,
             state_key_pair: state_key_pairs,
             is_da,
+            signing_guard: None,
         }
     }
 
+    /// The key the validator signs with, guarded by its persisted signing guard if it has one
+    #[must_use]
+    pub fn node_key(&self) -> NodeKey<KEY> {
+        NodeKey::new(self.private_key.clone(), self.signing_guard.clone())
+    }
+
     /// get the public config of the validator
     pub fn public_config(&self) -> PeerConfig<KEY> {
         PeerConfig {
@@ -208,6 +222,20 @@ pub struct HotShotConfig<KEY: SignatureKey> {
     pub stop_voting_time: u64,
     /// Number of blocks in an epoch, zero means there are no epochs
     pub epoch_height: u64,
+    /// Number of times a panicked consensus task is restarted with a fresh state, zero disables restarts
+    #[serde(default)]
+    pub task_restart_limit: usize,
+    /// Erasure code rate and share assignment of VID, which must be the same on every node
+    #[serde(default)]
+    pub vid_config: vid::VidConfig,
+    /// Number of hashes of the DRB calculation, see [`drb::difficulty_level`]
+    #[serde(default = "default_drb_difficulty")]
+    pub drb_difficulty: u64,
+}
+
+/// Default for [`HotShotConfig::drb_difficulty`]
+fn default_drb_difficulty() -> u64 {
+    drb::DIFFICULTY_LEVEL
 }
 
 impl<KEY: SignatureKey> HotShotConfig<KEY> {
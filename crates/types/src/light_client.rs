@@ -11,7 +11,7 @@ use std::collections::HashMap;
 use ark_ed_on_bn254::EdwardsConfig as Config;
 use ark_ff::PrimeField;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use jf_signature::schnorr;
+use jf_signature::{constants::CS_ID_SCHNORR, schnorr};
 use primitive_types::U256;
 use rand::SeedableRng;
 use rand_chacha::ChaCha20Rng;
@@ -40,7 +40,17 @@ pub type PublicInput = GenericPublicInput<CircuitField>;
 pub struct StateKeyPair(pub schnorr::KeyPair<Config>);
 
 /// Request body to send to the state relay server
-#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[derive(
+    Clone,
+    Debug,
+    CanonicalSerialize,
+    CanonicalDeserialize,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+)]
 pub struct StateSignatureRequestBody {
     /// The public key associated with this request
     pub key: StateVerKey,
@@ -61,6 +71,55 @@ pub struct StateSignaturesBundle {
     pub accumulated_weight: U256,
 }
 
+/// The stake table a light client state is signed against: its commitments, and the stake of
+/// each state key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LightClientStakeTable {
+    /// Commitments to the stake table, including the signing threshold
+    pub state: StakeTableState,
+    /// Stake of each state key
+    pub stakes: HashMap<StateVerKey, U256>,
+    /// Stake the signers of a state must reach for it to be certified
+    pub threshold: U256,
+}
+
+impl LightClientStakeTable {
+    /// Build the stake table from the commitments to its columns and the stake of each state
+    /// key, with a threshold of more than two thirds of the total stake
+    #[must_use]
+    pub fn new(
+        bls_key_comm: CircuitField,
+        schnorr_key_comm: CircuitField,
+        amount_comm: CircuitField,
+        stakes: HashMap<StateVerKey, U256>,
+    ) -> Self {
+        let total_stake = stakes
+            .values()
+            .fold(U256::zero(), |total, stake| total.saturating_add(*stake));
+        let threshold = total_stake.saturating_mul(U256::from(2)) / 3 + 1;
+
+        let mut threshold_bytes = [0u8; 32];
+        threshold.to_little_endian(&mut threshold_bytes);
+
+        Self {
+            state: StakeTableState {
+                bls_key_comm,
+                schnorr_key_comm,
+                amount_comm,
+                threshold: CircuitField::from_le_bytes_mod_order(&threshold_bytes),
+            },
+            stakes,
+            threshold,
+        }
+    }
+
+    /// Stake of `key`, zero if it isn't in the stake table
+    #[must_use]
+    pub fn stake(&self, key: &StateVerKey) -> U256 {
+        self.stakes.get(key).copied().unwrap_or_default()
+    }
+}
+
 /// A light client state
 #[tagged("LIGHT_CLIENT_STATE")]
 #[derive(
@@ -168,6 +227,32 @@ impl StateKeyPair {
     }
 }
 
+impl StateKeyPair {
+    /// Sign the light client `state`, to be certified against the stake table `stake_table`
+    #[must_use]
+    pub fn sign_state(
+        &self,
+        state: &LightClientState,
+        stake_table: &StakeTableState,
+    ) -> StateSignature {
+        let input = PublicInput::new(state.clone(), *stake_table);
+        self.0.sign(input.as_ref(), CS_ID_SCHNORR)
+    }
+}
+
+/// Check that `signature` is `key`'s signature over the light client `state`, certified against
+/// the stake table `stake_table`
+#[must_use]
+pub fn verify_state_signature(
+    key: &StateVerKey,
+    state: &LightClientState,
+    stake_table: &StakeTableState,
+    signature: &StateSignature,
+) -> bool {
+    let input = PublicInput::new(state.clone(), *stake_table);
+    key.verify(input.as_ref(), signature, CS_ID_SCHNORR).is_ok()
+}
+
 impl From<schnorr::KeyPair<Config>> for StateKeyPair {
     fn from(value: schnorr::KeyPair<Config>) -> Self {
         StateKeyPair(value)
@@ -30,7 +30,8 @@ use crate::{
         DaProposal, DaProposal2, Leaf, Leaf2, QuorumProposal, QuorumProposal2,
         QuorumProposalWrapper, UpgradeProposal,
     },
-    request_response::ProposalRequestPayload,
+    light_client::StateSignatureRequestBody,
+    request_response::{DrbResultRequestPayload, DrbResultResponse, ProposalRequestPayload},
     simple_certificate::{
         DaCertificate, DaCertificate2, QuorumCertificate2, UpgradeCertificate,
         ViewSyncCommitCertificate, ViewSyncCommitCertificate2, ViewSyncFinalizeCertificate,
@@ -49,6 +50,7 @@ use crate::{
         signature_key::SignatureKey,
     },
     utils::{mnemonic, option_epoch_from_block_number},
+    vid::{with_vid_encoding, VidEncoding},
     vote::HasViewNumber,
 };
 
@@ -262,6 +264,18 @@ pub enum GeneralConsensusMessage<TYPES: NodeType> {
 
     /// Message with a Timeout vote
     TimeoutVote2(TimeoutVote2<TYPES>),
+
+    /// A peer node needs the DRB result for an epoch.
+    DrbResultRequested(
+        DrbResultRequestPayload<TYPES>,
+        <TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType,
+    ),
+
+    /// A replica has responded with a DRB result and the proof of its seed.
+    DrbResultResponse(DrbResultResponse<TYPES>),
+
+    /// A replica's signature over the light client state of a decided leaf.
+    LightClientStateSignature(StateSignatureRequestBody),
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Hash, Eq)]
@@ -325,6 +339,11 @@ impl<TYPES: NodeType> SequencingMessage<TYPES> {
                         p.data.view_number()
                     }
                     GeneralConsensusMessage::ProposalRequested(req, _) => req.view_number,
+                    GeneralConsensusMessage::DrbResultRequested(req, _) => req.view_number,
+                    GeneralConsensusMessage::DrbResultResponse(response) => response.view_number,
+                    GeneralConsensusMessage::LightClientStateSignature(body) => {
+                        TYPES::View::new(body.state.view_number as u64)
+                    }
                     GeneralConsensusMessage::ProposalResponse(proposal) => {
                         proposal.data.view_number()
                     }
@@ -408,7 +427,10 @@ impl<TYPES: NodeType> SequencingMessage<TYPES> {
                         // this should match replica upon receipt
                         p.data.epoch()
                     }
-                    GeneralConsensusMessage::ProposalRequested(_, _) => None,
+                    GeneralConsensusMessage::ProposalRequested(_, _)
+                    | GeneralConsensusMessage::DrbResultRequested(_, _)
+                    | GeneralConsensusMessage::DrbResultResponse(_)
+                    | GeneralConsensusMessage::LightClientStateSignature(_) => None,
                     GeneralConsensusMessage::ProposalResponse(proposal) => proposal.data.epoch(),
                     GeneralConsensusMessage::ProposalResponse2(proposal) => proposal.data.epoch(),
                     GeneralConsensusMessage::Vote(vote_message) => vote_message.epoch(),
@@ -692,14 +714,20 @@ impl<TYPES: NodeType, V: Versions> UpgradeLock<TYPES, V> {
 
         let version = self.version(view).await?;
 
-        let serialized_message = match version {
-            // Associated constants cannot be used in pattern matches, so we do this trick instead.
-            v if v == V::Base::VERSION => Serializer::<V::Base>::serialize(&message),
-            v if v == V::Upgrade::VERSION => Serializer::<V::Upgrade>::serialize(&message),
-            v => {
-                bail!("Attempted to serialize with version {}, which is incompatible. This should be impossible.", v);
+        ensure!(
+            version == V::Base::VERSION || version == V::Upgrade::VERSION,
+            "Attempted to serialize with version {}, which is incompatible. This should be impossible.",
+            version
+        );
+
+        // VID data keeps the encoding of the version it is sent with.
+        let serialized_message = with_vid_encoding(VidEncoding::at::<V>(version), || {
+            if version == V::Base::VERSION {
+                Serializer::<V::Base>::serialize(&message)
+            } else {
+                Serializer::<V::Upgrade>::serialize(&message)
             }
-        };
+        });
 
         serialized_message
             .wrap()
@@ -720,15 +748,22 @@ impl<TYPES: NodeType, V: Versions> UpgradeLock<TYPES, V> {
             .context(info!("Failed to read message version!"))?
             .0;
 
-        let deserialized_message: M = match actual_version {
-            v if v == V::Base::VERSION => Serializer::<V::Base>::deserialize(message),
-            v if v == V::Upgrade::VERSION => Serializer::<V::Upgrade>::deserialize(message),
-            v => {
-                bail!("Cannot deserialize message with stated version {}", v);
-            }
-        }
-        .wrap()
-        .context(info!("Failed to deserialize message!"))?;
+        ensure!(
+            actual_version == V::Base::VERSION || actual_version == V::Upgrade::VERSION,
+            "Cannot deserialize message with stated version {}",
+            actual_version
+        );
+
+        let deserialized_message: M =
+            with_vid_encoding(VidEncoding::at::<V>(actual_version), || {
+                if actual_version == V::Base::VERSION {
+                    Serializer::<V::Base>::deserialize(message)
+                } else {
+                    Serializer::<V::Upgrade>::deserialize(message)
+                }
+            })
+            .wrap()
+            .context(info!("Failed to deserialize message!"))?;
 
         let view = deserialized_message.view_number();
 
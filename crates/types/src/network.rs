@@ -21,6 +21,7 @@ use crate::{
     hotshot_config_file::HotShotConfigFile,
     light_client::StateVerKey,
     traits::signature_key::SignatureKey,
+    vid::VidConfigError,
     HotShotConfig, ValidatorConfig,
 };
 
@@ -56,6 +57,9 @@ pub enum NetworkConfigError {
     /// Failed to recursively create path to NetworkConfig
     #[error("Failed to recursively create path to NetworkConfig")]
     FailedToCreatePath(std::io::Error),
+    /// The loaded NetworkConfig has an invalid VID config
+    #[error("Invalid VID config in NetworkConfig: {0}")]
+    InvalidVidConfig(VidConfigError),
 }
 
 #[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize, Default, ValueEnum)]
@@ -188,7 +192,7 @@ impl<K: SignatureKey> NetworkConfig<K> {
     ///
     /// # Errors
     ///
-    /// This function will return an error if the file cannot be read or if the contents cannot be deserialized into a `NetworkConfig`.
+    /// This function will return an error if the file cannot be read, if the contents cannot be deserialized into a `NetworkConfig`, or if its VID config is invalid.
     ///
     /// # Examples
     ///
@@ -213,10 +217,17 @@ impl<K: SignatureKey> NetworkConfig<K> {
         };
 
         // deserialize
-        match serde_json::from_slice(&data) {
-            Ok(data) => Ok(data),
-            Err(e) => Err(NetworkConfigError::DeserializeError(e)),
-        }
+        let config: Self = match serde_json::from_slice(&data) {
+            Ok(data) => data,
+            Err(e) => return Err(NetworkConfigError::DeserializeError(e)),
+        };
+
+        config
+            .config
+            .vid_config
+            .validate()
+            .map_err(NetworkConfigError::InvalidVidConfig)?;
+        Ok(config)
     }
 
     /// Serializes the `NetworkConfig` and writes it to a file.
@@ -10,7 +10,10 @@
 use committable::{Committable, RawCommitmentBuilder};
 use serde::{Deserialize, Serialize};
 
-use crate::traits::{node_implementation::NodeType, signature_key::SignatureKey};
+use crate::{
+    drb::{DrbResult, DrbSeedProof},
+    traits::{node_implementation::NodeType, signature_key::SignatureKey},
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
 /// A signed request for a proposal.
@@ -31,3 +34,45 @@ impl<TYPES: NodeType> Committable for ProposalRequestPayload<TYPES> {
             .finalize()
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+/// A signed request for the DRB result of an epoch.
+pub struct DrbResultRequestPayload<TYPES: NodeType> {
+    /// The epoch that we're requesting the DRB result for.
+    pub epoch: TYPES::Epoch,
+
+    /// Our current view, which the response is sent for so that both sides agree on the
+    /// message version.
+    pub view_number: TYPES::View,
+
+    /// Our public key. The ensures that the recipient can reply to
+    /// us directly.
+    pub key: TYPES::SignatureKey,
+}
+
+impl<TYPES: NodeType> Committable for DrbResultRequestPayload<TYPES> {
+    fn commit(&self) -> committable::Commitment<Self> {
+        RawCommitmentBuilder::new("signed DRB result request commitment")
+            .u64_field("epoch", *self.epoch)
+            .u64_field("view number", *self.view_number)
+            .var_size_bytes(&self.key.to_bytes())
+            .finalize()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[serde(bound(deserialize = ""))]
+/// A DRB result sent in response to a [`DrbResultRequestPayload`].
+pub struct DrbResultResponse<TYPES: NodeType> {
+    /// The view of the request.
+    pub view_number: TYPES::View,
+
+    /// The epoch the result is for.
+    pub epoch: TYPES::Epoch,
+
+    /// The DRB result.
+    pub result: DrbResult,
+
+    /// Proof of the seed the result was computed from.
+    pub proof: DrbSeedProof<TYPES>,
+}
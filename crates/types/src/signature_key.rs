@@ -6,16 +6,26 @@
 
 //! Types and structs for the hotshot signature keys
 
-use ark_serialize::SerializationError;
+use std::{
+    cmp::Ordering,
+    fmt::{self, Debug, Display, Formatter},
+    hash::{Hash, Hasher},
+};
+
+use ark_bn254::{Fr, G1Projective, G2Projective};
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
 use bitvec::{slice::BitSlice, vec::BitVec};
 use digest::generic_array::GenericArray;
 use jf_signature::{
-    bls_over_bn254::{BLSOverBN254CurveSignatureScheme, KeyPair, SignKey, VerKey},
-    SignatureError, SignatureScheme,
+    bls_over_bn254::{BLSOverBN254CurveSignatureScheme, KeyPair, SignKey, Signature, VerKey},
+    schnorr, SignatureError, SignatureScheme,
 };
 use primitive_types::U256;
-use rand::SeedableRng;
+use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
+use tagged_base64::{TaggedBase64, Tb64Error};
 use tracing::instrument;
 
 use crate::{
@@ -34,6 +44,13 @@ pub type BLSPubKey = VerKey;
 /// Public parameters for BLS signature scheme
 pub type BLSPublicParam = ();
 
+/// Reinterpret a BLS key or signature as the curve point it wraps, or the other way around
+fn convert_point<FROM: CanonicalSerialize, TO: CanonicalDeserialize>(from: &FROM) -> Option<TO> {
+    let mut bytes = Vec::new();
+    from.serialize_uncompressed(&mut bytes).ok()?;
+    TO::deserialize_uncompressed_unchecked(&*bytes).ok()
+}
+
 impl PrivateSignatureKey for BLSPrivKey {
     fn to_bytes(&self) -> Vec<u8> {
         self.to_bytes()
@@ -64,6 +81,43 @@ impl SignatureKey for BLSPubKey {
         BLSOverBN254CurveSignatureScheme::verify(&(), self, data, signature).is_ok()
     }
 
+    fn batch_validate(
+        keys: &[Self],
+        signatures: &[Self::PureAssembledSignatureType],
+        data: &[u8],
+    ) -> bool {
+        if keys.len() != signatures.len() {
+            return false;
+        }
+
+        // Check a random linear combination of the signatures against the same combination of
+        // the keys: as all signatures are over the same message, it is valid if each of them is,
+        // and an invalid one only cancels out with negligible probability.
+        let mut rng = rand::thread_rng();
+        let mut combined_key = G2Projective::default();
+        let mut combined_signature = G1Projective::default();
+        for (key, signature) in keys.iter().zip(signatures) {
+            let (Some(key), Some(signature)) = (
+                convert_point::<_, G2Projective>(key),
+                convert_point::<_, G1Projective>(signature),
+            ) else {
+                return false;
+            };
+            let coefficient = Fr::from(rng.gen::<u128>());
+            combined_key += key * coefficient;
+            combined_signature += signature * coefficient;
+        }
+
+        let (Some(combined_key), Some(combined_signature)) = (
+            convert_point::<_, VerKey>(&combined_key),
+            convert_point::<_, Signature>(&combined_signature),
+        ) else {
+            return false;
+        };
+        BLSOverBN254CurveSignatureScheme::verify(&(), &combined_key, data, &combined_signature)
+            .is_ok()
+    }
+
     fn sign(
         sk: &Self::PrivateKey,
         data: &[u8],
@@ -185,3 +239,324 @@ impl BuilderSignatureKey for BuilderKey {
         (kp.ver_key(), kp.sign_key_ref().clone())
     }
 }
+
+/// Domain separator of the Schnorr signatures of [`SchnorrPubKey`]s
+const SCHNORR_CS_ID: &str = "HotShot Schnorr signature key";
+
+/// Tag of [`SchnorrPrivKey`]s encoded in tagged base64
+const SCHNORR_PRIV_KEY_TAG: &str = "SCHNORR_PRIV_KEY";
+
+/// Tag of [`SchnorrPubKey`]s encoded in tagged base64
+const SCHNORR_PUB_KEY_TAG: &str = "SCHNORR_PUB_KEY";
+
+/// Tag of [`SchnorrSignatures`] encoded in tagged base64
+const SCHNORR_SIGNATURES_TAG: &str = "SCHNORR_SIGS";
+
+/// Curve of the Schnorr signature keys, the twisted Edwards curve embedded in BN254
+type SchnorrConfig = ark_ed_on_bn254::EdwardsConfig;
+
+/// The field elements `data` is signed as with a Schnorr key: its length, then its chunks of 31
+/// bytes, which always fit in a field element
+fn schnorr_message(data: &[u8]) -> Vec<ark_ed_on_bn254::Fq> {
+    std::iter::once(ark_ed_on_bn254::Fq::from(data.len() as u64))
+        .chain(
+            data.chunks(31)
+                .map(ark_ed_on_bn254::Fq::from_le_bytes_mod_order),
+        )
+        .collect()
+}
+
+/// Schnorr private key used to sign a message
+#[derive(Clone)]
+pub struct SchnorrPrivKey(schnorr::SignKey<ark_ed_on_bn254::Fr>);
+
+impl SchnorrPrivKey {
+    /// The key pair of this private key
+    fn key_pair(&self) -> schnorr::KeyPair<SchnorrConfig> {
+        schnorr::KeyPair::from(self.0.clone())
+    }
+}
+
+impl Debug for SchnorrPrivKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("SchnorrPrivKey(..)")
+    }
+}
+
+impl PartialEq for SchnorrPrivKey {
+    fn eq(&self, other: &Self) -> bool {
+        PrivateSignatureKey::to_bytes(self) == PrivateSignatureKey::to_bytes(other)
+    }
+}
+
+impl Eq for SchnorrPrivKey {}
+
+impl Hash for SchnorrPrivKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        PrivateSignatureKey::to_bytes(self).hash(state);
+    }
+}
+
+impl TryFrom<&TaggedBase64> for SchnorrPrivKey {
+    type Error = Tb64Error;
+
+    fn try_from(value: &TaggedBase64) -> Result<Self, Self::Error> {
+        if value.tag() != SCHNORR_PRIV_KEY_TAG {
+            return Err(Tb64Error::InvalidTag);
+        }
+        CanonicalDeserialize::deserialize_compressed(&*value.value())
+            .map(Self)
+            .map_err(|_| Tb64Error::InvalidData)
+    }
+}
+
+impl PrivateSignatureKey for SchnorrPrivKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        self.0
+            .serialize_compressed(&mut buf)
+            .expect("Serialization should not fail.");
+        buf
+    }
+
+    fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self(CanonicalDeserialize::deserialize_compressed(bytes)?))
+    }
+
+    fn to_tagged_base64(&self) -> Result<TaggedBase64, Tb64Error> {
+        TaggedBase64::new(SCHNORR_PRIV_KEY_TAG, &PrivateSignatureKey::to_bytes(self))
+    }
+}
+
+/// Schnorr public key, an alternative to [`BLSPubKey`] whose signatures can't be aggregated: a
+/// QC carries the signature of each signer
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SchnorrPubKey(schnorr::VerKey<SchnorrConfig>);
+
+impl PartialOrd for SchnorrPubKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SchnorrPubKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        SignatureKey::to_bytes(self).cmp(&SignatureKey::to_bytes(other))
+    }
+}
+
+impl From<SchnorrPubKey> for TaggedBase64 {
+    fn from(key: SchnorrPubKey) -> Self {
+        TaggedBase64::new(SCHNORR_PUB_KEY_TAG, &SignatureKey::to_bytes(&key))
+            .expect("The tag is valid")
+    }
+}
+
+impl TryFrom<&TaggedBase64> for SchnorrPubKey {
+    type Error = Tb64Error;
+
+    fn try_from(value: &TaggedBase64) -> Result<Self, Self::Error> {
+        if value.tag() != SCHNORR_PUB_KEY_TAG {
+            return Err(Tb64Error::InvalidTag);
+        }
+        SignatureKey::from_bytes(&value.value()).map_err(|_| Tb64Error::InvalidData)
+    }
+}
+
+impl Display for SchnorrPubKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", TaggedBase64::from(self.clone()))
+    }
+}
+
+/// Schnorr signatures by [`SchnorrPubKey`]s along with the key of each signer, a single one for
+/// a vote and one per signer for a QC
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SchnorrSignatures(Vec<(SchnorrPubKey, schnorr::Signature<SchnorrConfig>)>);
+
+impl From<SchnorrSignatures> for TaggedBase64 {
+    fn from(signatures: SchnorrSignatures) -> Self {
+        TaggedBase64::new(
+            SCHNORR_SIGNATURES_TAG,
+            &bincode::serialize(&signatures).expect("Serialization should not fail."),
+        )
+        .expect("The tag is valid")
+    }
+}
+
+impl TryFrom<&TaggedBase64> for SchnorrSignatures {
+    type Error = Tb64Error;
+
+    fn try_from(value: &TaggedBase64) -> Result<Self, Self::Error> {
+        if value.tag() != SCHNORR_SIGNATURES_TAG {
+            return Err(Tb64Error::InvalidTag);
+        }
+        bincode::deserialize(&value.value()).map_err(|_| Tb64Error::InvalidData)
+    }
+}
+
+impl SignatureKey for SchnorrPubKey {
+    type PrivateKey = SchnorrPrivKey;
+    type StakeTableEntry = StakeTableEntry<SchnorrPubKey>;
+    type QcParams = QcParams<SchnorrPubKey, ()>;
+    type PureAssembledSignatureType = SchnorrSignatures;
+    type QcType = (Self::PureAssembledSignatureType, BitVec);
+    type SignError = SignatureError;
+
+    fn validate(&self, signature: &Self::PureAssembledSignatureType, data: &[u8]) -> bool {
+        match &signature.0[..] {
+            [(key, signature)] => {
+                key == self
+                    && self
+                        .0
+                        .verify(&schnorr_message(data), signature, SCHNORR_CS_ID)
+                        .is_ok()
+            }
+            _ => false,
+        }
+    }
+
+    fn sign(
+        private_key: &Self::PrivateKey,
+        data: &[u8],
+    ) -> Result<Self::PureAssembledSignatureType, Self::SignError> {
+        let key_pair = private_key.key_pair();
+        let signature = key_pair.sign(&schnorr_message(data), SCHNORR_CS_ID);
+        Ok(SchnorrSignatures(vec![(
+            Self(key_pair.ver_key()),
+            signature,
+        )]))
+    }
+
+    fn from_private(private_key: &Self::PrivateKey) -> Self {
+        Self(private_key.key_pair().ver_key())
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        self.0
+            .serialize_compressed(&mut buf)
+            .expect("Serialization should not fail.");
+        buf
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        CanonicalDeserialize::deserialize_compressed(bytes).map(Self)
+    }
+
+    fn generated_from_seed_indexed(seed: [u8; 32], index: u64) -> (Self, Self::PrivateKey) {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&seed);
+        hasher.update(&index.to_le_bytes());
+        let new_seed = *hasher.finalize().as_bytes();
+        let kp = schnorr::KeyPair::<SchnorrConfig>::generate(&mut ChaCha20Rng::from_seed(new_seed));
+        (Self(kp.ver_key()), SchnorrPrivKey(kp.sign_key()))
+    }
+
+    fn stake_table_entry(&self, stake: u64) -> Self::StakeTableEntry {
+        StakeTableEntry {
+            stake_key: self.clone(),
+            stake_amount: U256::from(stake),
+        }
+    }
+
+    fn public_key(entry: &Self::StakeTableEntry) -> Self {
+        entry.stake_key.clone()
+    }
+
+    fn public_parameter(
+        stake_entries: Vec<Self::StakeTableEntry>,
+        threshold: U256,
+    ) -> Self::QcParams {
+        QcParams {
+            stake_entries,
+            threshold,
+            agg_sig_pp: (),
+        }
+    }
+
+    fn check(
+        real_qc_pp: &Self::QcParams,
+        data: &[u8],
+        qc: &Self::QcType,
+    ) -> Result<(), SignatureError> {
+        let (signatures, signers) = qc;
+        if signers.len() != real_qc_pp.stake_entries.len() {
+            return Err(SignatureError::ParameterError(format!(
+                "signers bit vector len {} != the number of stake entries {}",
+                signers.len(),
+                real_qc_pp.stake_entries.len(),
+            )));
+        }
+
+        let signer_entries: Vec<_> = real_qc_pp
+            .stake_entries
+            .iter()
+            .zip(signers.iter())
+            .filter_map(|(entry, signed)| signed.then_some(entry))
+            .collect();
+        if signer_entries.len() != signatures.0.len() {
+            return Err(SignatureError::ParameterError(format!(
+                "the number of signers {} != the number of signatures {}",
+                signer_entries.len(),
+                signatures.0.len(),
+            )));
+        }
+
+        let total_weight = signer_entries
+            .iter()
+            .fold(U256::zero(), |acc, entry| acc + entry.stake_amount);
+        if total_weight < real_qc_pp.threshold {
+            return Err(SignatureError::ParameterError(format!(
+                "total_weight {} less than threshold {}",
+                total_weight, real_qc_pp.threshold,
+            )));
+        }
+
+        // Signatures are in the order of the signers in the stake table
+        let message = schnorr_message(data);
+        for (entry, (key, signature)) in signer_entries.into_iter().zip(&signatures.0) {
+            if entry.stake_key != *key {
+                return Err(SignatureError::VerificationError(format!(
+                    "signature from {key} where {} signed",
+                    entry.stake_key
+                )));
+            }
+            key.0.verify(&message, signature, SCHNORR_CS_ID)?;
+        }
+
+        Ok(())
+    }
+
+    fn sig_proof(signature: &Self::QcType) -> (Self::PureAssembledSignatureType, BitVec) {
+        signature.clone()
+    }
+
+    fn assemble(
+        real_qc_pp: &Self::QcParams,
+        signers: &BitSlice,
+        sigs: &[Self::PureAssembledSignatureType],
+    ) -> Self::QcType {
+        // Sort the signatures in the order of the signers in the stake table, for `check` to
+        // match them with their key
+        let position = |key: &SchnorrPubKey| {
+            real_qc_pp
+                .stake_entries
+                .iter()
+                .position(|entry| entry.stake_key == *key)
+        };
+        let mut signatures: Vec<_> = sigs
+            .iter()
+            .flat_map(|signatures| signatures.0.iter().cloned())
+            .collect();
+        signatures.sort_by_key(|(key, _)| position(key));
+
+        (SchnorrSignatures(signatures), signers.to_bitvec())
+    }
+
+    fn genesis_proposer_pk() -> Self {
+        let kp =
+            schnorr::KeyPair::<SchnorrConfig>::generate(&mut ChaCha20Rng::from_seed([0u8; 32]));
+        Self(kp.ver_key())
+    }
+}
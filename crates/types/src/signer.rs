@@ -0,0 +1,652 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Signing of consensus messages with the validator key.
+//!
+//! Votes, proposals and requests are signed through a [`Signer`] instead of with the private key
+//! directly, so the key can live outside of the node: [`LocalSigner`] holds it in process, while
+//! [`RemoteSigner`] asks a separate process, served by [`serve_signer`], over a Unix socket.
+//! Either way a [`SigningPolicy`] decides what may be signed, [`DoubleSigningGuard`] refusing
+//! to sign conflicting votes or proposals for a view.
+
+#[cfg(unix)]
+use std::time::Duration;
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use async_lock::Mutex;
+use async_trait::async_trait;
+#[cfg(unix)]
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{UnixListener, UnixStream},
+    time::timeout,
+};
+use utils::anytrace::*;
+
+use crate::traits::signature_key::SignatureKey;
+#[cfg(unix)]
+use crate::utils::bincode_opts;
+
+/// Number of views before the highest signed one the [`DoubleSigningGuard`] remembers
+pub const DOUBLE_SIGNING_GUARD_VIEWS: u64 = 1000;
+
+/// Largest message exchanged with a remote signer
+#[cfg(unix)]
+const MAX_SIGNER_MESSAGE_SIZE: u32 = 1 << 20;
+
+/// How long a remote signer has to answer a request
+#[cfg(unix)]
+const REMOTE_SIGNER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What a signature is requested for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum SigningPurpose {
+    /// A vote for a quorum proposal
+    QuorumVote,
+    /// A vote for a DA proposal
+    DaVote,
+    /// A timeout vote
+    TimeoutVote,
+    /// A view sync vote
+    ViewSyncVote,
+    /// A vote for an upgrade proposal
+    UpgradeVote,
+    /// A quorum proposal
+    QuorumProposal,
+    /// A DA proposal
+    DaProposal,
+    /// An upgrade proposal
+    UpgradeProposal,
+    /// A request to other nodes or to builders
+    Request,
+    /// The VID disperse of a block we propose
+    VidDisperse,
+    /// VID shares computed to answer the requests of other nodes
+    Response,
+}
+
+impl SigningPurpose {
+    /// Whether signing different data for this purpose twice in a view is equivocating
+    #[must_use]
+    pub fn is_exclusive(self) -> bool {
+        matches!(
+            self,
+            Self::QuorumVote | Self::DaVote | Self::QuorumProposal | Self::DaProposal
+        )
+    }
+}
+
+/// A request for a signature over `data`
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SigningRequest {
+    /// What the signature is for
+    pub purpose: SigningPurpose,
+    /// The view the signature is for
+    pub view: u64,
+    /// The data to sign, usually a commitment
+    pub data: Vec<u8>,
+}
+
+impl SigningRequest {
+    /// A request to sign `data` for `purpose` in `view`
+    #[must_use]
+    pub fn new(purpose: SigningPurpose, view: u64, data: impl AsRef<[u8]>) -> Self {
+        Self {
+            purpose,
+            view,
+            data: data.as_ref().to_vec(),
+        }
+    }
+}
+
+/// Decides which requests a signer signs
+pub trait SigningPolicy: Send + Sync + 'static {
+    /// Check whether `request` may be signed, recording it as signed if so.
+    ///
+    /// # Errors
+    /// If the request must not be signed
+    fn check(&mut self, request: &SigningRequest) -> Result<()>;
+}
+
+/// Policy signing every request
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SignEverything;
+
+impl SigningPolicy for SignEverything {
+    fn check(&mut self, _request: &SigningRequest) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Policy refusing to sign different data for the same exclusive purpose in a view, e.g. two
+/// quorum votes for different leaves, which would let byzantine leaders fork the chain with our
+/// stake. Requests older than the last [`DOUBLE_SIGNING_GUARD_VIEWS`] views are refused, as they
+/// can't be checked anymore.
+///
+/// A guard created with [`DoubleSigningGuard::persistent`] writes the highest view it signed for
+/// to a file before signing, and after a restart refuses the views up to it, whose signatures it
+/// no longer knows.
+#[derive(Clone, Debug, Default)]
+pub struct DoubleSigningGuard {
+    /// The data signed for each exclusive purpose and view
+    signed: BTreeMap<(u64, SigningPurpose), Vec<u8>>,
+
+    /// The highest view we signed for
+    highest_view: u64,
+
+    /// File the highest view we signed for is persisted to
+    path: Option<PathBuf>,
+
+    /// The highest view persisted to `path`
+    persisted_view: Option<u64>,
+
+    /// The highest view we signed for before the last restart
+    restart_view: Option<u64>,
+}
+
+impl DoubleSigningGuard {
+    /// A guard persisting the highest view it signed for to `path`, refusing the views up to
+    /// the one already persisted there.
+    ///
+    /// # Errors
+    /// If the file exists but can't be read or doesn't hold a view
+    pub fn persistent(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let restart_view = match fs::read_to_string(&path) {
+            Ok(contents) => Some(
+                contents
+                    .trim()
+                    .parse::<u64>()
+                    .wrap()
+                    .context(error!("Signing high-water mark at {:?} is malformed", path))?,
+            ),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => bail!(error!(
+                "Failed to read the signing high-water mark at {:?}: {}",
+                path, e
+            )),
+        };
+
+        Ok(Self {
+            signed: BTreeMap::new(),
+            highest_view: restart_view.unwrap_or(0),
+            path: Some(path),
+            persisted_view: restart_view,
+            restart_view,
+        })
+    }
+
+    /// Persist `view` as the highest view we signed for, if we persist at all.
+    ///
+    /// The view is written to a temporary file which then replaces the previous one, so a crash
+    /// leaves either the old or the new view behind.
+    fn persist(&mut self, view: u64) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if self
+            .persisted_view
+            .is_some_and(|persisted| persisted >= view)
+        {
+            return Ok(());
+        }
+
+        let tmp_path = path.with_extension("tmp");
+        let write = || -> std::io::Result<()> {
+            let mut file = OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&tmp_path)?;
+            file.write_all(view.to_string().as_bytes())?;
+            file.sync_all()?;
+            fs::rename(&tmp_path, path)
+        };
+        write().wrap().context(error!(
+            "Failed to persist the signing high-water mark to {:?}",
+            path
+        ))?;
+
+        self.persisted_view = Some(view);
+        Ok(())
+    }
+}
+
+impl SigningPolicy for DoubleSigningGuard {
+    fn check(&mut self, request: &SigningRequest) -> Result<()> {
+        if !request.purpose.is_exclusive() {
+            return Ok(());
+        }
+
+        if let Some(restart_view) = self.restart_view {
+            ensure!(
+                request.view > restart_view,
+                warn!(
+                    "Refusing to sign a {:?} for view {}, we signed up to view {} before restarting",
+                    request.purpose, request.view, restart_view
+                )
+            );
+        }
+
+        let oldest_view = self.highest_view.saturating_sub(DOUBLE_SIGNING_GUARD_VIEWS);
+        ensure!(
+            request.view >= oldest_view,
+            warn!(
+                "Refusing to sign a {:?} for view {}, older than the guarded views",
+                request.purpose, request.view
+            )
+        );
+
+        if let Some(signed) = self.signed.get(&(request.view, request.purpose)) {
+            ensure!(
+                *signed == request.data,
+                warn!(
+                    "Refusing to sign a second {:?} for view {}",
+                    request.purpose, request.view
+                )
+            );
+            return Ok(());
+        }
+
+        // The view must be persisted before we sign for it, or a crash could let us sign for it
+        // again after restarting
+        self.persist(request.view)?;
+        self.signed
+            .insert((request.view, request.purpose), request.data.clone());
+
+        if request.view > self.highest_view {
+            self.highest_view = request.view;
+            let oldest_view = self.highest_view.saturating_sub(DOUBLE_SIGNING_GUARD_VIEWS);
+            self.signed = self
+                .signed
+                .split_off(&(oldest_view, SigningPurpose::QuorumVote));
+        }
+
+        Ok(())
+    }
+}
+
+/// Signs consensus messages with a validator key
+#[async_trait]
+pub trait Signer<KEY: SignatureKey>: Send + Sync + 'static {
+    /// The public key of the signatures
+    fn public_key(&self) -> &KEY;
+
+    /// Sign the data of `request`.
+    ///
+    /// # Errors
+    /// If the signing policy refuses the request or signing fails
+    async fn sign(&self, request: SigningRequest) -> Result<KEY::PureAssembledSignatureType>;
+}
+
+/// Signer holding the private key in process
+pub struct LocalSigner<KEY: SignatureKey, POLICY: SigningPolicy = DoubleSigningGuard> {
+    /// The public key of the signatures
+    public_key: KEY,
+
+    /// The key we sign with
+    private_key: KEY::PrivateKey,
+
+    /// Decides which requests we sign
+    policy: Mutex<POLICY>,
+}
+
+impl<KEY: SignatureKey, POLICY: SigningPolicy> LocalSigner<KEY, POLICY> {
+    /// A signer signing with `private_key` the requests `policy` allows
+    #[must_use]
+    pub fn new(private_key: KEY::PrivateKey, policy: POLICY) -> Self {
+        Self {
+            public_key: KEY::from_private(&private_key),
+            private_key,
+            policy: Mutex::new(policy),
+        }
+    }
+}
+
+impl<KEY: SignatureKey, POLICY: SigningPolicy> Debug for LocalSigner<KEY, POLICY> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalSigner")
+            .field("public_key", &self.public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<KEY: SignatureKey, POLICY: SigningPolicy> Signer<KEY> for LocalSigner<KEY, POLICY> {
+    fn public_key(&self) -> &KEY {
+        &self.public_key
+    }
+
+    async fn sign(&self, request: SigningRequest) -> Result<KEY::PureAssembledSignatureType> {
+        self.policy.lock().await.check(&request)?;
+        KEY::sign(&self.private_key, &request.data)
+            .wrap()
+            .context(error!("Failed to sign a {:?}", request.purpose))
+    }
+}
+
+/// The key a node signs with
+pub enum NodeKey<KEY: SignatureKey> {
+    /// The private key, held in process and signed with through a [`LocalSigner`] guarded by an
+    /// in-memory [`DoubleSigningGuard`]
+    Private(KEY::PrivateKey),
+    /// The private key, held in process and signed with through a [`LocalSigner`] whose
+    /// [`DoubleSigningGuard`] persists the highest signed view to `guard_path`
+    Guarded {
+        /// The private key
+        private_key: KEY::PrivateKey,
+        /// Where the guard persists the highest signed view, see
+        /// [`DoubleSigningGuard::persistent`]
+        guard_path: PathBuf,
+    },
+    /// A signer holding the key elsewhere, e.g. a [`RemoteSigner`]
+    Signer(Arc<dyn Signer<KEY>>),
+}
+
+impl<KEY: SignatureKey> NodeKey<KEY> {
+    /// The private key, guarded by a persistent [`DoubleSigningGuard`] if `guard_path` is set
+    #[must_use]
+    pub fn new(private_key: KEY::PrivateKey, guard_path: Option<PathBuf>) -> Self {
+        match guard_path {
+            Some(guard_path) => Self::Guarded {
+                private_key,
+                guard_path,
+            },
+            None => Self::Private(private_key),
+        }
+    }
+
+    /// The private key, if it is held in process
+    #[must_use]
+    pub fn private_key(&self) -> Option<&KEY::PrivateKey> {
+        match self {
+            Self::Private(private_key) | Self::Guarded { private_key, .. } => Some(private_key),
+            Self::Signer(_) => None,
+        }
+    }
+
+    /// The signer to sign with. A private key gets a new [`LocalSigner`], with a guard of its
+    /// own, so a node should only turn its key into a signer once.
+    ///
+    /// # Errors
+    /// If the persisted guard of a [`NodeKey::Guarded`] key can't be read
+    pub fn into_signer(self) -> Result<Arc<dyn Signer<KEY>>> {
+        match self {
+            Self::Private(private_key) => Ok(Arc::new(LocalSigner::<KEY>::new(
+                private_key,
+                DoubleSigningGuard::default(),
+            ))),
+            Self::Guarded {
+                private_key,
+                guard_path,
+            } => Ok(Arc::new(LocalSigner::<KEY>::new(
+                private_key,
+                DoubleSigningGuard::persistent(guard_path)?,
+            ))),
+            Self::Signer(signer) => Ok(signer),
+        }
+    }
+}
+
+impl<KEY: SignatureKey> Clone for NodeKey<KEY> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Private(private_key) => Self::Private(private_key.clone()),
+            Self::Guarded {
+                private_key,
+                guard_path,
+            } => Self::Guarded {
+                private_key: private_key.clone(),
+                guard_path: guard_path.clone(),
+            },
+            Self::Signer(signer) => Self::Signer(Arc::clone(signer)),
+        }
+    }
+}
+
+impl<KEY: SignatureKey> Debug for NodeKey<KEY> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Private(_) => f.write_str("NodeKey::Private(..)"),
+            Self::Guarded { guard_path, .. } => f
+                .debug_struct("NodeKey::Guarded")
+                .field("guard_path", guard_path)
+                .finish_non_exhaustive(),
+            Self::Signer(signer) => f
+                .debug_tuple("NodeKey::Signer")
+                .field(signer.public_key())
+                .finish(),
+        }
+    }
+}
+
+/// A message to a remote signer
+#[cfg(unix)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum SignerRequest {
+    /// Ask for the public key of the signatures
+    PublicKey,
+    /// Ask for a signature
+    Sign(SigningRequest),
+}
+
+/// A message from a remote signer
+#[cfg(unix)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+enum SignerResponse<KEY: SignatureKey> {
+    /// The public key of the signatures
+    PublicKey(KEY),
+    /// The requested signature
+    Signature(KEY::PureAssembledSignatureType),
+    /// The request was refused, for the given reason
+    Refused(String),
+}
+
+/// Write `message` to `stream`, prefixed by its length
+#[cfg(unix)]
+async fn write_message<T: Serialize>(
+    stream: &mut (impl AsyncWrite + Unpin),
+    message: &T,
+) -> Result<()> {
+    let bytes = bincode_opts()
+        .serialize(message)
+        .wrap()
+        .context(error!("Failed to serialize a signer message"))?;
+    let len = u32::try_from(bytes.len())
+        .ok()
+        .filter(|len| *len <= MAX_SIGNER_MESSAGE_SIZE)
+        .context(warn!(
+            "Signer message of {} bytes is too large",
+            bytes.len()
+        ))?;
+
+    stream
+        .write_all(&len.to_be_bytes())
+        .await
+        .wrap()
+        .context(warn!("Failed to write a signer message"))?;
+    stream
+        .write_all(&bytes)
+        .await
+        .wrap()
+        .context(warn!("Failed to write a signer message"))
+}
+
+/// Read a message written by [`write_message`] from `stream`
+#[cfg(unix)]
+async fn read_message<T: for<'a> Deserialize<'a>>(
+    stream: &mut (impl AsyncRead + Unpin),
+) -> Result<T> {
+    let mut len = [0u8; 4];
+    stream
+        .read_exact(&mut len)
+        .await
+        .wrap()
+        .context(warn!("Failed to read a signer message"))?;
+    let len = u32::from_be_bytes(len);
+    ensure!(
+        len <= MAX_SIGNER_MESSAGE_SIZE,
+        warn!("Signer message of {} bytes is too large", len)
+    );
+
+    let mut bytes = vec![0u8; len as usize];
+    stream
+        .read_exact(&mut bytes)
+        .await
+        .wrap()
+        .context(warn!("Failed to read a signer message"))?;
+    bincode_opts()
+        .deserialize(&bytes)
+        .wrap()
+        .context(warn!("Failed to deserialize a signer message"))
+}
+
+/// Signer asking a remote signer, served by [`serve_signer`] on a Unix socket, for signatures
+#[cfg(unix)]
+pub struct RemoteSigner<KEY: SignatureKey> {
+    /// The public key of the signatures
+    public_key: KEY,
+
+    /// Path of the socket the remote signer listens on
+    path: PathBuf,
+
+    /// Connection to the remote signer, reopened after failures
+    connection: Mutex<Option<UnixStream>>,
+}
+
+#[cfg(unix)]
+impl<KEY: SignatureKey> RemoteSigner<KEY> {
+    /// Connect to the remote signer listening on `path`.
+    ///
+    /// # Errors
+    /// If we can't connect to the signer or it doesn't tell its public key
+    pub async fn connect(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let mut stream = UnixStream::connect(&path).await.wrap().context(warn!(
+            "Failed to connect to the remote signer at {:?}",
+            path
+        ))?;
+
+        write_message(&mut stream, &SignerRequest::PublicKey).await?;
+        let SignerResponse::<KEY>::PublicKey(public_key) = read_message(&mut stream).await? else {
+            bail!(warn!(
+                "Remote signer at {:?} didn't send its public key",
+                path
+            ));
+        };
+
+        Ok(Self {
+            public_key,
+            path,
+            connection: Mutex::new(Some(stream)),
+        })
+    }
+}
+
+#[cfg(unix)]
+#[async_trait]
+impl<KEY: SignatureKey> Signer<KEY> for RemoteSigner<KEY> {
+    fn public_key(&self) -> &KEY {
+        &self.public_key
+    }
+
+    async fn sign(&self, request: SigningRequest) -> Result<KEY::PureAssembledSignatureType> {
+        let mut connection = self.connection.lock().await;
+        let mut stream = match connection.take() {
+            Some(stream) => stream,
+            None => UnixStream::connect(&self.path).await.wrap().context(warn!(
+                "Failed to reconnect to the remote signer at {:?}",
+                self.path
+            ))?,
+        };
+
+        // The connection is only kept if the exchange succeeds, so a failed one can't leave a
+        // stale response behind
+        let purpose = request.purpose;
+        let exchange = async {
+            write_message(&mut stream, &SignerRequest::Sign(request)).await?;
+            read_message(&mut stream).await
+        };
+        let response = timeout(REMOTE_SIGNER_TIMEOUT, exchange)
+            .await
+            .wrap()
+            .context(warn!(
+                "Remote signer at {:?} didn't answer within {:?}",
+                self.path, REMOTE_SIGNER_TIMEOUT
+            ))??;
+        *connection = Some(stream);
+
+        match response {
+            SignerResponse::<KEY>::Signature(signature) => Ok(signature),
+            SignerResponse::Refused(reason) => {
+                bail!(warn!(
+                    "Remote signer refused to sign a {:?}: {}",
+                    purpose, reason
+                ));
+            }
+            SignerResponse::PublicKey(_) => {
+                bail!(warn!(
+                    "Remote signer sent a public key instead of a signature"
+                ));
+            }
+        }
+    }
+}
+
+/// Answer the requests of a [`RemoteSigner`] on `stream` with `signer`
+#[cfg(unix)]
+async fn serve_connection<KEY: SignatureKey, SIGNER: Signer<KEY>>(
+    mut stream: UnixStream,
+    signer: Arc<SIGNER>,
+) -> Result<()> {
+    loop {
+        let response = match read_message(&mut stream).await? {
+            SignerRequest::PublicKey => SignerResponse::PublicKey(signer.public_key().clone()),
+            SignerRequest::Sign(request) => match signer.sign(request).await {
+                Ok(signature) => SignerResponse::Signature(signature),
+                Err(e) => SignerResponse::Refused(e.to_string()),
+            },
+        };
+        write_message(&mut stream, &response).await?;
+    }
+}
+
+/// Serve the requests of [`RemoteSigner`]s connecting to `listener` with `signer`, which usually
+/// is a [`LocalSigner`] running in a separate, hardened process.
+///
+/// # Errors
+/// If accepting a connection fails
+#[cfg(unix)]
+pub async fn serve_signer<KEY: SignatureKey, SIGNER: Signer<KEY>>(
+    listener: UnixListener,
+    signer: Arc<SIGNER>,
+) -> Result<()> {
+    loop {
+        let (stream, _) = listener
+            .accept()
+            .await
+            .wrap()
+            .context(error!("Failed to accept a remote signer connection"))?;
+
+        let signer = Arc::clone(&signer);
+        tokio::spawn(async move {
+            if let Err(e) = serve_connection(stream, signer).await {
+                tracing::debug!("Remote signer connection closed: {}", e);
+            }
+        });
+    }
+}
@@ -21,6 +21,7 @@ use vbs::version::Version;
 use crate::{
     data::{Leaf, Leaf2},
     message::UpgradeLock,
+    signer::{Signer, SigningPurpose, SigningRequest},
     traits::{
         node_implementation::{NodeType, Versions},
         signature_key::SignatureKey,
@@ -276,6 +277,35 @@ impl<TYPES: NodeType, DATA: Voteable<TYPES> + 'static> SimpleVote<TYPES, DATA> {
             view_number: view,
         })
     }
+
+    /// Creates a simple vote signed by `signer` for `purpose`
+    /// # Errors
+    /// If the signer refuses or fails to sign the data
+    pub async fn create_signed_vote_with_signer<V: Versions>(
+        data: DATA,
+        view: TYPES::View,
+        signer: &dyn Signer<TYPES::SignatureKey>,
+        purpose: SigningPurpose,
+        upgrade_lock: &UpgradeLock<TYPES, V>,
+    ) -> Result<Self> {
+        let commit = VersionedVoteData::new(data.clone(), view, upgrade_lock)
+            .await?
+            .commit();
+
+        let signature = (
+            signer.public_key().clone(),
+            signer
+                .sign(SigningRequest::new(purpose, *view, commit))
+                .await
+                .context(error!("Failed to sign vote"))?,
+        );
+
+        Ok(Self {
+            signature,
+            data,
+            view_number: view,
+        })
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Hash, Eq)]
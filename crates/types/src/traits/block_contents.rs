@@ -14,25 +14,30 @@ use std::{
     fmt::{Debug, Display},
     future::Future,
     hash::Hash,
+    ops::Range,
     sync::Arc,
 };
 
+use ark_ff::PrimeField;
 use async_trait::async_trait;
 use committable::{Commitment, Committable};
-use jf_vid::VidScheme;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use vbs::version::Version;
 
 use super::signature_key::BuilderSignatureKey;
 use crate::{
     data::Leaf2,
+    light_client::CircuitField,
     traits::{
         node_implementation::{NodeType, Versions},
         states::InstanceState,
         ValidatedState,
     },
     utils::BuilderCommitment,
-    vid::{advz_scheme, VidCommitment, VidCommon, VidSchemeType},
+    vid::{
+        payload_range_proof, verify_payload_range, vid_commit, LargeRangeProofType, VidCommitment,
+        VidCommon, VidConfig, VidError,
+    },
 };
 
 /// Trait for structures that need to be unambiguously encoded as bytes.
@@ -134,6 +139,196 @@ pub trait BlockPayload<TYPES: NodeType>:
     ) -> impl 'a + Iterator<Item = Self::Transaction>;
 }
 
+/// Byte ranges of the namespaces of a [`NamespacedBlockPayload`] within its encoding
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NamespaceTable<N> {
+    /// Namespaces and their byte ranges, in payload order
+    entries: Vec<(N, Range<usize>)>,
+}
+
+impl<N: Eq> NamespaceTable<N> {
+    /// Create a table from namespaces and their byte ranges in the encoded payload.
+    ///
+    /// # Errors
+    /// If a namespace appears twice, or the ranges are not contiguous from the start of the
+    /// payload
+    pub fn new(entries: Vec<(N, Range<usize>)>) -> Result<Self, NamespaceTableError> {
+        let mut end = 0;
+        for (i, (namespace, range)) in entries.iter().enumerate() {
+            if range.start != end || range.end < range.start {
+                return Err(NamespaceTableError::NotContiguous(i));
+            }
+            if entries[..i].iter().any(|(other, _)| other == namespace) {
+                return Err(NamespaceTableError::Duplicate(i));
+            }
+            end = range.end;
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Byte range of `namespace` in the encoded payload, if it is present
+    pub fn range(&self, namespace: &N) -> Option<Range<usize>> {
+        self.entries
+            .iter()
+            .find(|(other, _)| other == namespace)
+            .map(|(_, range)| range.clone())
+    }
+
+    /// Namespaces of the payload, in payload order
+    pub fn namespaces(&self) -> impl Iterator<Item = &N> {
+        self.entries.iter().map(|(namespace, _)| namespace)
+    }
+
+    /// Number of bytes covered by the table
+    #[must_use]
+    pub fn byte_len(&self) -> usize {
+        self.entries.last().map_or(0, |(_, range)| range.end)
+    }
+}
+
+/// Reasons a [`NamespaceTable`] is malformed
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum NamespaceTableError {
+    /// The namespace at this position appears earlier in the table
+    #[error("namespace {0} of the table is a duplicate")]
+    Duplicate(usize),
+    /// The range at this position does not start where the previous one ends
+    #[error("namespace {0} of the table does not follow the previous one")]
+    NotContiguous(usize),
+}
+
+/// The transactions of a single namespace, with a proof that they are part of a payload
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespaceProof {
+    /// Encoded transactions of the namespace
+    pub bytes: Vec<u8>,
+    /// Proof that `bytes` are the namespace's range of the payload
+    pub proof: LargeRangeProofType,
+}
+
+/// A [`BlockPayload`] whose transactions are grouped into namespaces, e.g. one per rollup, each
+/// laid out as a contiguous byte range of the encoded payload.
+///
+/// The namespace table is derived from the metadata alone, which is committed to by the block
+/// header, so a light client can fetch and verify the transactions of a single namespace without
+/// downloading the rest of the payload.
+///
+/// Namespace proofs are payload range proofs, which only ADVZ supports. For payloads dispersed
+/// with AvidM, i.e. from [`Versions::AvidM`](crate::traits::node_implementation::Versions::AvidM)
+/// on, [`namespace_proof`](Self::namespace_proof) and
+/// [`verify_namespace`](Self::verify_namespace) fail with [`VidError::RangeProofsUnsupported`];
+/// such clients have to fetch the whole payload and use
+/// [`namespace_transactions`](Self::namespace_transactions).
+pub trait NamespacedBlockPayload<TYPES: NodeType>: BlockPayload<TYPES> {
+    /// Identifier of a namespace
+    type NamespaceId: Clone + Debug + Eq + Hash + Send + Sync + Serialize + DeserializeOwned;
+
+    /// Namespace table of the payload described by `metadata`.
+    fn namespace_table(metadata: &Self::Metadata) -> NamespaceTable<Self::NamespaceId>;
+
+    /// Decode the transactions of `namespace` from its bytes.
+    ///
+    /// # Errors
+    /// If `bytes` is not a valid encoding of the namespace's transactions
+    fn decode_namespace(
+        namespace: &Self::NamespaceId,
+        bytes: &[u8],
+    ) -> Result<Vec<Self::Transaction>, Self::Error>;
+
+    /// The transactions of `namespace`, empty if it is not in the payload.
+    ///
+    /// # Errors
+    /// If the namespace is not encoded correctly
+    fn namespace_transactions(
+        &self,
+        metadata: &Self::Metadata,
+        namespace: &Self::NamespaceId,
+    ) -> Result<Vec<Self::Transaction>, Self::Error> {
+        let Some(range) = Self::namespace_table(metadata).range(namespace) else {
+            return Ok(Vec::new());
+        };
+        let encoded = self.encode();
+        match encoded.get(range) {
+            Some(bytes) => Self::decode_namespace(namespace, bytes),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// The bytes of `namespace` with a proof against the payload commitment, or `None` if the
+    /// namespace is not in the payload.
+    ///
+    /// # Errors
+    /// If the VID scheme of `common` cannot prove payload ranges
+    fn namespace_proof(
+        &self,
+        metadata: &Self::Metadata,
+        namespace: &Self::NamespaceId,
+        common: &VidCommon,
+        num_storage_nodes: usize,
+        config: &VidConfig,
+    ) -> Result<Option<NamespaceProof>, VidError> {
+        let Some(range) = Self::namespace_table(metadata).range(namespace) else {
+            return Ok(None);
+        };
+        let encoded = self.encode();
+        let Some(bytes) = encoded.get(range.clone()) else {
+            return Ok(None);
+        };
+        let proof = payload_range_proof(&encoded, range, common, num_storage_nodes, config)?;
+
+        Ok(Some(NamespaceProof {
+            bytes: bytes.to_vec(),
+            proof,
+        }))
+    }
+
+    /// Verify a [`NamespaceProof`] of `namespace` against the payload commitment and metadata
+    /// of a block header, and decode its transactions.
+    ///
+    /// # Errors
+    /// If the namespace is not in the table, the proof is invalid, or the transactions do not
+    /// decode
+    fn verify_namespace(
+        metadata: &Self::Metadata,
+        namespace: &Self::NamespaceId,
+        proof: &NamespaceProof,
+        commit: &VidCommitment,
+        common: &VidCommon,
+        num_storage_nodes: usize,
+        config: &VidConfig,
+    ) -> Result<Vec<Self::Transaction>, NamespaceProofError<Self::Error>> {
+        let range = Self::namespace_table(metadata)
+            .range(namespace)
+            .ok_or(NamespaceProofError::MissingNamespace)?;
+        verify_payload_range(
+            &proof.bytes,
+            range,
+            &proof.proof,
+            commit,
+            common,
+            num_storage_nodes,
+            config,
+        )?;
+
+        Self::decode_namespace(namespace, &proof.bytes).map_err(NamespaceProofError::Decode)
+    }
+}
+
+/// Reasons a [`NamespaceProof`] is rejected
+#[derive(Debug, thiserror::Error)]
+pub enum NamespaceProofError<E: Error> {
+    /// The namespace is not in the payload's namespace table
+    #[error("namespace is not in the payload")]
+    MissingNamespace,
+    /// The range proof does not verify
+    #[error("invalid namespace proof: {0}")]
+    Vid(#[from] VidError),
+    /// The namespace's transactions do not decode
+    #[error("invalid namespace encoding: {0}")]
+    Decode(E),
+}
+
 /// extra functions required on block to be usable by hotshot-testing
 pub trait TestableBlock<TYPES: NodeType>: BlockPayload<TYPES> + Debug {
     /// generate a genesis block
@@ -143,9 +338,8 @@ pub trait TestableBlock<TYPES: NodeType>: BlockPayload<TYPES> + Debug {
     fn txn_count(&self) -> u64;
 }
 
-/// Compute the VID payload commitment.
+/// Compute the VID payload commitment with the VID scheme of `version`.
 /// TODO(Gus) delete this function?
-/// TODO(Chengyu): use the version information
 /// # Panics
 /// If the VID computation fails.
 #[must_use]
@@ -153,16 +347,18 @@ pub trait TestableBlock<TYPES: NodeType>: BlockPayload<TYPES> + Debug {
 pub fn vid_commitment<V: Versions>(
     encoded_transactions: &[u8],
     num_storage_nodes: usize,
-    _version: Version,
-) -> <VidSchemeType as VidScheme>::Commit {
+    version: Version,
+    config: &VidConfig,
+) -> VidCommitment {
     let encoded_tx_len = encoded_transactions.len();
-    advz_scheme(num_storage_nodes).commit_only(encoded_transactions).unwrap_or_else(|err| panic!("VidScheme::commit_only failure:(num_storage_nodes,payload_byte_len)=({num_storage_nodes},{encoded_tx_len}) error: {err}"))
+    vid_commit::<V>(encoded_transactions, num_storage_nodes, version, config).unwrap_or_else(|err| panic!("VID commitment failure:(num_storage_nodes,payload_byte_len)=({num_storage_nodes},{encoded_tx_len}) error: {err}"))
 }
 
 /// The number of storage nodes to use when computing the genesis VID commitment.
 ///
 /// The number of storage nodes for the genesis VID commitment is arbitrary, since we don't actually
-/// do dispersal for the genesis block. For simplicity and performance, we use 1.
+/// do dispersal for the genesis block. For simplicity and performance, we use 1. For the same
+/// reason, the genesis VID commitment uses the default [`VidConfig`] rather than the network's.
 pub const GENESIS_VID_NUM_STORAGE_NODES: usize = 1;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -238,4 +434,44 @@ pub trait BlockHeader<TYPES: NodeType>:
 
     /// Get the results of the auction for this Header. Only used in post-marketplace versions
     fn get_auction_results(&self) -> Option<TYPES::AuctionResult>;
+
+    /// Get the root of the block commitment tree the light client state commits to.
+    ///
+    /// Defaults to the header's commitment reduced into the light client's circuit field.
+    fn block_comm_root(&self) -> CircuitField {
+        CircuitField::from_le_bytes_mod_order(self.commit().as_ref())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn namespace_table() {
+        let table = NamespaceTable::new(vec![(7u32, 0..10), (3, 10..10), (5, 10..25)]).unwrap();
+        assert_eq!(table.range(&7), Some(0..10));
+        assert_eq!(table.range(&3), Some(10..10));
+        assert_eq!(table.range(&5), Some(10..25));
+        assert_eq!(table.range(&1), None);
+        assert_eq!(
+            table.namespaces().copied().collect::<Vec<_>>(),
+            vec![7, 3, 5]
+        );
+        assert_eq!(table.byte_len(), 25);
+
+        assert_eq!(
+            NamespaceTable::new(vec![(7u32, 0..10), (7, 10..12)]),
+            Err(NamespaceTableError::Duplicate(1))
+        );
+        assert_eq!(
+            NamespaceTable::new(vec![(7u32, 0..10), (3, 11..12)]),
+            Err(NamespaceTableError::NotContiguous(1))
+        );
+        assert_eq!(
+            NamespaceTable::new(vec![(7u32, 1..10)]),
+            Err(NamespaceTableError::NotContiguous(0))
+        );
+        assert_eq!(NamespaceTable::<u32>::new(vec![]).unwrap().byte_len(), 0);
+    }
 }
@@ -31,8 +31,9 @@ pub trait ConsensusApi<TYPES: NodeType, I: NodeImplementation<TYPES>>: Send + Sy
     /// Get a reference to the public key.
     fn public_key(&self) -> &TYPES::SignatureKey;
 
-    /// Get a reference to the private key.
-    fn private_key(&self) -> &<TYPES::SignatureKey as SignatureKey>::PrivateKey;
+    /// Get a reference to the private key, unless the node signs through a signer holding the
+    /// key elsewhere.
+    fn private_key(&self) -> Option<&<TYPES::SignatureKey as SignatureKey>::PrivateKey>;
 
     /// Notify the system of an event within `hotshot-consensus`.
     async fn send_event(&self, event: Event<TYPES>);
@@ -11,7 +11,10 @@ use async_trait::async_trait;
 use utils::anytrace::Result;
 
 use super::node_implementation::NodeType;
-use crate::{traits::signature_key::SignatureKey, PeerConfig};
+use crate::{
+    data::Leaf2, drb::DrbResult, light_client::LightClientStakeTable,
+    traits::signature_key::SignatureKey, PeerConfig,
+};
 
 #[async_trait]
 /// A protocol for determining membership in and participating in a committee.
@@ -19,7 +22,10 @@ pub trait Membership<TYPES: NodeType>: Debug + Send + Sync {
     /// The error type returned by methods like `lookup_leader`.
     type Error: std::fmt::Display;
 
-    /// Create a committee
+    /// Create a committee.
+    ///
+    /// Implementations sampling the DA committee of each epoch may only use
+    /// `da_committee_members` until they're able to sample it.
     fn new(
         // Note: eligible_leaders is currently a hack because the DA leader == the quorum leader
         // but they should not have voting power.
@@ -146,6 +152,24 @@ pub trait Membership<TYPES: NodeType>: Debug + Send + Sync {
         None
     }
 
+    /// Handles notifications that the DRB result for `epoch` is known, for implementations which
+    /// randomize leader selection with it. Is called under a write lock to the Membership.
+    fn add_drb_result(&mut self, _epoch: TYPES::Epoch, _drb_result: DrbResult) {}
+
+    /// Handles notifications that `leaf` was decided, for implementations which adapt to the
+    /// history of the chain. Leaves are passed in increasing view order and are the same on every
+    /// node. Is called under a write lock to the Membership.
+    fn add_decided_leaf(&mut self, _leaf: &Leaf2<TYPES>, _epoch_height: u64) {}
+
+    /// The stake table light client states of `epoch` are signed against, for implementations
+    /// which commit to their stake table.
+    fn light_client_stake_table(
+        &self,
+        _epoch: Option<TYPES::Epoch>,
+    ) -> Option<LightClientStakeTable> {
+        None
+    }
+
     #[allow(clippy::type_complexity)]
     /// Called after add_epoch_root runs and any callback has been invoked.
     /// Causes a read lock to be reacquired for this functionality.
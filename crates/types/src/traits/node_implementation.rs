@@ -193,6 +193,23 @@ pub trait ConsensusTime:
     fn u64(&self) -> u64;
 }
 
+/// What the `rewind` feature needs from [`NodeType::AuctionResult`] to save the events it records.
+///
+/// Without the feature this holds for every type, so nodes which don't record their events don't
+/// have to serialize their auction results.
+#[cfg(feature = "rewind")]
+pub trait Recordable: Serialize {}
+
+#[cfg(feature = "rewind")]
+impl<T: Serialize> Recordable for T {}
+
+/// What the `rewind` feature needs from [`NodeType::AuctionResult`]; nothing without the feature
+#[cfg(not(feature = "rewind"))]
+pub trait Recordable {}
+
+#[cfg(not(feature = "rewind"))]
+impl<T> Recordable for T {}
+
 /// Trait with all the type definitions that are used in the current hotshot setup.
 pub trait NodeType:
     Clone
@@ -222,6 +239,7 @@ pub trait NodeType:
     /// run, for a particular view.
     type AuctionResult: Debug
         + HasUrls
+        + Recordable
         + DeserializeOwned
         + Default
         + PartialEq
@@ -276,4 +294,7 @@ pub trait Versions: Clone + Copy + Debug + Send + Sync + 'static {
 
     /// The version at which to switch over to epochs logic
     type Epochs: StaticVersionType;
+
+    /// The version at which to switch the VID scheme from ADVZ to AvidM
+    type AvidM: StaticVersionType;
 }
@@ -18,7 +18,6 @@ use ark_serialize::SerializationError;
 use bitvec::prelude::*;
 use committable::Committable;
 use jf_signature::SignatureError;
-use jf_vid::VidScheme;
 use primitive_types::U256;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use tagged_base64::{TaggedBase64, Tb64Error};
@@ -26,7 +25,7 @@ use tagged_base64::{TaggedBase64, Tb64Error};
 use super::EncodeBytes;
 use crate::{
     bundle::Bundle, traits::node_implementation::NodeType, utils::BuilderCommitment,
-    vid::VidSchemeType,
+    vid::VidCommitment,
 };
 
 /// Type representing stake table entries in a `StakeTable`
@@ -122,6 +121,21 @@ pub trait SignatureKey:
     /// Validate a signature
     fn validate(&self, signature: &Self::PureAssembledSignatureType, data: &[u8]) -> bool;
 
+    /// Validate the signatures of `keys` over the same `data` at once, returning whether all of
+    /// them are valid. Implementations may be faster than validating each signature, but don't
+    /// tell which signature is invalid.
+    fn batch_validate(
+        keys: &[Self],
+        signatures: &[Self::PureAssembledSignatureType],
+        data: &[u8],
+    ) -> bool {
+        keys.len() == signatures.len()
+            && keys
+                .iter()
+                .zip(signatures)
+                .all(|(key, signature)| key.validate(signature, data))
+    }
+
     /// Produce a signature
     /// # Errors
     /// If unable to sign the data with the key
@@ -223,7 +237,7 @@ pub trait BuilderSignatureKey:
         signature: &Self::BuilderSignature,
         fee_amount: u64,
         metadata: &Metadata,
-        vid_commitment: &<VidSchemeType as VidScheme>::Commit,
+        vid_commitment: &VidCommitment,
     ) -> bool {
         self.validate_builder_signature(
             signature,
@@ -288,7 +302,7 @@ pub trait BuilderSignatureKey:
         private_key: &Self::BuilderPrivateKey,
         fee_amount: u64,
         metadata: &Metadata,
-        vid_commitment: &<VidSchemeType as VidScheme>::Commit,
+        vid_commitment: &VidCommitment,
     ) -> Result<Self::BuilderSignature, Self::SignError> {
         Self::sign_builder_message(
             private_key,
@@ -348,7 +362,7 @@ pub trait BuilderSignatureKey:
 fn aggregate_fee_data<Metadata: EncodeBytes>(
     fee_amount: u64,
     metadata: &Metadata,
-    vid_commitment: &<VidSchemeType as VidScheme>::Commit,
+    vid_commitment: &VidCommitment,
 ) -> Vec<u8> {
     let mut fee_info = Vec::new();
     fee_info.extend_from_slice(fee_amount.to_be_bytes().as_ref());
@@ -200,6 +200,65 @@ pub trait StakeTableScheme {
     fn try_iter(&self, version: SnapshotVersion) -> Result<Self::IntoIter, StakeTableError>;
 }
 
+/// Delegation of stake to the keys of a [`StakeTableScheme`].
+///
+/// Delegators lock their own amounts with a registered key, whose stake as returned by the
+/// [`StakeTableScheme`] queries is then its voting stake: the stake of the key itself plus
+/// all the stake delegated to it. Undelegated stake stops counting right away, and can only be
+/// withdrawn after an unbonding period measured in epochs.
+pub trait DelegationScheme: StakeTableScheme {
+    /// type for the delegators
+    type Delegator: Clone;
+
+    /// Delegate `amount` from `delegator` to the registered `key`, adding to the existing
+    /// delegation if any.
+    ///
+    /// # Errors
+    /// Return err if `key` isn't registered, has no stake of its own or the stake overflows.
+    fn delegate(
+        &mut self,
+        delegator: Self::Delegator,
+        key: &Self::Key,
+        amount: Self::Amount,
+    ) -> Result<(), StakeTableError>;
+
+    /// Undelegate `amount` of the stake `delegator` delegated to `key`.
+    /// Return the epoch from which the amount can be withdrawn.
+    ///
+    /// # Errors
+    /// Return err if there's no such delegation or it's less than `amount`.
+    fn undelegate(
+        &mut self,
+        delegator: &Self::Delegator,
+        key: &Self::Key,
+        amount: Self::Amount,
+    ) -> Result<u64, StakeTableError>;
+
+    /// Remove and return the undelegated stakes whose unbonding period is over.
+    fn withdraw_unbonded(&mut self) -> Vec<(Self::Delegator, Self::Amount)>;
+
+    /// Returns the stake delegated to `key` in the `version` of the table.
+    ///
+    /// # Errors
+    /// Return err if the `version` is not supported or `key` doesn't exist.
+    fn delegated_stake(
+        &self,
+        version: SnapshotVersion,
+        key: &Self::Key,
+    ) -> Result<Self::Amount, StakeTableError>;
+
+    /// Returns the delegators of `key` along with their delegated amounts, in the `version` of
+    /// the table.
+    ///
+    /// # Errors
+    /// Return err if the `version` is not supported or `key` doesn't exist.
+    fn delegators(
+        &self,
+        version: SnapshotVersion,
+        key: &Self::Key,
+    ) -> Result<Vec<(Self::Delegator, Self::Amount)>, StakeTableError>;
+}
+
 /// Error type for [`StakeTableScheme`]
 #[derive(Debug, Display)]
 pub enum StakeTableError {
@@ -221,6 +280,12 @@ pub enum StakeTableError {
     StakeOverflow,
     /// The historical snapshot requested is not supported.
     SnapshotUnsupported,
+    /// Block numbers must be recorded in non-decreasing order
+    OutdatedBlockNum,
+    /// Delegation not found
+    DelegationNotFound,
+    /// The stake table is full
+    CapacityExceeded,
 }
 
 impl ark_std::error::Error for StakeTableError {}
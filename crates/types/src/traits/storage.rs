@@ -14,7 +14,6 @@ use std::collections::BTreeMap;
 use anyhow::Result;
 use async_trait::async_trait;
 use committable::Commitment;
-use jf_vid::VidScheme;
 
 use super::node_implementation::NodeType;
 use crate::{
@@ -24,12 +23,13 @@ use crate::{
         DaProposal, DaProposal2, Leaf, Leaf2, QuorumProposal, QuorumProposal2,
         QuorumProposalWrapper,
     },
+    drb::{DrbCheckpoint, DrbResult, DrbSeedInput, DrbSeedProof},
     event::HotShotAction,
     message::{convert_proposal, Proposal},
     simple_certificate::{
         NextEpochQuorumCertificate2, QuorumCertificate, QuorumCertificate2, UpgradeCertificate,
     },
-    vid::VidSchemeType,
+    vid::VidCommitment,
 };
 
 /// Abstraction for storing a variety of consensus payload datum.
@@ -49,13 +49,13 @@ pub trait Storage<TYPES: NodeType>: Send + Sync + Clone {
     async fn append_da(
         &self,
         proposal: &Proposal<TYPES, DaProposal<TYPES>>,
-        vid_commit: <VidSchemeType as VidScheme>::Commit,
+        vid_commit: VidCommitment,
     ) -> Result<()>;
     /// Add a proposal to the stored DA proposals.
     async fn append_da2(
         &self,
         proposal: &Proposal<TYPES, DaProposal2<TYPES>>,
-        vid_commit: <VidSchemeType as VidScheme>::Commit,
+        vid_commit: VidCommitment,
     ) -> Result<()> {
         self.append_da(&convert_proposal(proposal.clone()), vid_commit)
             .await
@@ -134,6 +134,34 @@ pub trait Storage<TYPES: NodeType>: Send + Sync + Clone {
         &self,
         decided_upgrade_certificate: Option<UpgradeCertificate<TYPES>>,
     ) -> Result<()>;
+    /// Save the progress of the DRB calculation for `epoch`, so it can be resumed after a restart.
+    async fn update_drb_checkpoint(
+        &self,
+        _epoch: TYPES::Epoch,
+        _checkpoint: DrbCheckpoint,
+    ) -> Result<()> {
+        Ok(())
+    }
+    /// Add the DRB seed input for `epoch`, derived from the epoch root two epochs earlier.
+    async fn add_drb_seed(
+        &self,
+        _epoch: TYPES::Epoch,
+        _drb_seed_input: DrbSeedInput,
+    ) -> Result<()> {
+        Ok(())
+    }
+    /// Add the proof of the DRB seed for `epoch`, served to peers missing the result.
+    async fn add_drb_seed_proof(
+        &self,
+        _epoch: TYPES::Epoch,
+        _proof: DrbSeedProof<TYPES>,
+    ) -> Result<()> {
+        Ok(())
+    }
+    /// Add the DRB result for `epoch`, computed or received from another node.
+    async fn add_drb_result(&self, _epoch: TYPES::Epoch, _drb_result: DrbResult) -> Result<()> {
+        Ok(())
+    }
     /// Migrate leaves from `Leaf` to `Leaf2`, and proposals from `QuorumProposal` to `QuorumProposal2`
     async fn migrate_consensus(
         &self,
@@ -9,7 +9,11 @@ use std::{env, fs, path::PathBuf};
 use toml;
 use tracing::error;
 
-use crate::{traits::signature_key::SignatureKey, ValidatorConfig};
+use crate::{
+    keystore::{read_password_file, KeystoreError, KeystoreFile},
+    traits::signature_key::SignatureKey,
+    ValidatorConfig,
+};
 
 /// Holds configuration for a validator node
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Default)]
@@ -23,6 +27,15 @@ pub struct ValidatorConfigFile {
     // pub stake_value: u64,
     /// Whether or not we are DA
     pub is_da: bool,
+    /// A keystore holding the keys of the validator, used instead of the seed if present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keystore: Option<PathBuf>,
+    /// A file containing the password of the keystore
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keystore_password_file: Option<PathBuf>,
+    /// Where the highest view the validator signed for is persisted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_guard: Option<PathBuf>,
 }
 
 impl ValidatorConfigFile {
@@ -44,11 +57,36 @@ impl ValidatorConfigFile {
             toml::from_str(&contents).expect("Unable to load data from file");
         data
     }
+
+    /// The configuration of the validator, with the keys of its keystore if it has one or keys
+    /// generated from its seed otherwise
+    ///
+    /// # Errors
+    /// If the keystore or its password can't be read, or the keystore can't be decrypted
+    pub fn validator_config<K: SignatureKey>(&self) -> Result<ValidatorConfig<K>, KeystoreError> {
+        let Some(keystore) = &self.keystore else {
+            return Ok(self.clone().into());
+        };
+        let password_file = self
+            .keystore_password_file
+            .as_ref()
+            .ok_or(KeystoreError::MissingPassword)?;
+
+        let password = read_password_file(password_file)?;
+        let keys = KeystoreFile::<K>::from_file(keystore)?.decrypt(&password)?;
+        // here stake_value is set to 1, since we don't input stake_value from ValidatorConfigFile for now
+        let mut config = keys.validator_config(1, self.is_da);
+        config.signing_guard.clone_from(&self.signing_guard);
+        Ok(config)
+    }
 }
 
 impl<K: SignatureKey> From<ValidatorConfigFile> for ValidatorConfig<K> {
     fn from(val: ValidatorConfigFile) -> Self {
         // here stake_value is set to 1, since we don't input stake_value from ValidatorConfigFile for now
-        ValidatorConfig::generated_from_seed_indexed(val.seed, val.node_id, 1, val.is_da)
+        let mut config =
+            ValidatorConfig::generated_from_seed_indexed(val.seed, val.node_id, 1, val.is_da);
+        config.signing_guard = val.signing_guard;
+        config
     }
 }
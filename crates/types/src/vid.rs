@@ -7,15 +7,21 @@
 //! This module provides:
 //! - an opaque constructor [`vid_scheme`] that returns a new instance of a
 //!   VID scheme.
-//! - type aliases [`VidCommitment`], [`VidCommon`], [`VidShare`]
+//! - type aliases [`ADVZCommitment`], [`ADVZCommon`], [`ADVZShare`]
 //!   for [`VidScheme`] assoc types.
+//! - scheme-tagged [`VidCommitment`], [`VidCommon`] and [`VidShare`], together with
+//!   [`vid_disperse`] and [`verify_vid_share`] which pick the scheme from the protocol version.
 //!
 //! Purpose: the specific choice of VID scheme is an implementation detail.
 //! This crate and all downstream crates should talk to the VID scheme only
 //! via the traits exposed here.
 
 #![allow(missing_docs)]
-use std::{fmt::Debug, ops::Range};
+use std::{
+    cell::Cell,
+    fmt::{self, Debug, Display},
+    ops::Range,
+};
 
 use ark_bn254::Bn254;
 use jf_pcs::{
@@ -31,13 +37,24 @@ use jf_vid::{
     VidDisperse, VidResult, VidScheme,
 };
 use lazy_static::lazy_static;
-use serde::{Deserialize, Serialize};
+use primitive_types::U256;
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
 use sha2::Sha256;
+use tagged_base64::{TaggedBase64, Tb64Error};
+use thiserror::Error;
+use vbs::version::{StaticVersionType, Version};
+
+/// AvidM, a VID scheme without trusted setup
+pub mod avidm;
+/// Bounded compute pool for VID encoding and verification
+pub mod pool;
 
+use self::avidm::{AvidMCommitment, AvidMError, AvidMParam, AvidMScheme, AvidMShare};
 use crate::{
     constants::SRS_DEGREE,
     data::{VidDisperse as HotShotVidDisperse, VidDisperseShare},
     message::Proposal,
+    traits::node_implementation::Versions,
 };
 
 /// VID scheme constructor.
@@ -56,17 +73,24 @@ use crate::{
 /// - [Naming impl trait in return types - Impl trait initiative](https://rust-lang.github.io/impl-trait-initiative/explainer/rpit_names.html)
 /// - [RFC: Type alias impl trait (TAIT)](https://github.com/rust-lang/rfcs/blob/master/text/2515-type_alias_impl_trait.md)
 ///
+/// The recovery threshold is derived from the code rate of `config`.
+///
 /// # Panics
 /// When the construction fails for the underlying VID scheme.
 // TODO(Chengyu): move all things below to advz submodule.
 #[must_use]
-#[memoize::memoize(SharedCache, Capacity: 10)]
-pub fn advz_scheme(num_storage_nodes: usize) -> VidSchemeType {
-    // recovery_threshold is currently num_storage_nodes rounded down to a power of two
-    // TODO recovery_threshold should be a function of the desired erasure code rate
-    // https://github.com/EspressoSystems/HotShot/issues/2152
-    let recovery_threshold = 1 << num_storage_nodes.ilog2();
+pub fn advz_scheme(num_storage_nodes: usize, config: &VidConfig) -> VidSchemeType {
+    let recovery_threshold = config.code_rate.recovery_threshold(num_storage_nodes);
 
+    advz_scheme_with_threshold(num_storage_nodes, recovery_threshold)
+}
+
+/// [`advz_scheme`] with an explicit recovery threshold.
+///
+/// # Panics
+/// When the construction fails for the underlying VID scheme.
+#[memoize::memoize(SharedCache, Capacity: 10)]
+fn advz_scheme_with_threshold(num_storage_nodes: usize, recovery_threshold: u32) -> VidSchemeType {
     #[allow(clippy::panic)]
     let num_storage_nodes = u32::try_from(num_storage_nodes).unwrap_or_else(|err| {
         panic!(
@@ -87,9 +111,18 @@ pub fn advz_scheme(num_storage_nodes: usize) -> VidSchemeType {
 
 /// Similar to [`vid_scheme()`], but with `KZG_SRS_TEST` for testing purpose only.
 #[cfg(feature = "test-srs")]
+#[must_use]
+pub fn vid_scheme_for_test(num_storage_nodes: usize, config: &VidConfig) -> VidSchemeType {
+    test_scheme_with_threshold(
+        num_storage_nodes,
+        config.code_rate.recovery_threshold(num_storage_nodes),
+    )
+}
+
+/// [`vid_scheme_for_test`] with an explicit recovery threshold.
+#[cfg(feature = "test-srs")]
 #[memoize::memoize(SharedCache, Capacity: 10)]
-pub fn vid_scheme_for_test(num_storage_nodes: usize) -> VidSchemeType {
-    let recovery_threshold = 1 << num_storage_nodes.ilog2();
+fn test_scheme_with_threshold(num_storage_nodes: usize, recovery_threshold: u32) -> VidSchemeType {
     #[allow(clippy::panic)]
     let num_storage_nodes = u32::try_from(num_storage_nodes).unwrap_or_else(|err| {
         panic!("num_storage_nodes {num_storage_nodes} should fit into u32; error: {err}")
@@ -103,12 +136,741 @@ pub fn vid_scheme_for_test(num_storage_nodes: usize) -> VidSchemeType {
     )
 }
 
-/// VID commitment type
-pub type VidCommitment = <VidSchemeType as VidScheme>::Commit;
-/// VID common type
-pub type VidCommon = <VidSchemeType as VidScheme>::Common;
-/// VID share type
-pub type VidShare = <VidSchemeType as VidScheme>::Share;
+/// Erasure code rate of VID: the fraction of the shares needed to recover a payload.
+///
+/// A lower rate makes every share larger, so dispersal costs more bandwidth, but a payload stays
+/// available as long as a smaller fraction of the storage nodes is honest and online.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct VidCodeRate {
+    /// Numerator of the rate
+    pub numerator: u32,
+    /// Denominator of the rate
+    pub denominator: u32,
+}
+
+impl Default for VidCodeRate {
+    /// Rate one, which needs every share rounded down to a power of two
+    fn default() -> Self {
+        Self {
+            numerator: 1,
+            denominator: 1,
+        }
+    }
+}
+
+impl VidCodeRate {
+    /// Number of shares out of `num_shares` needed to recover a payload.
+    ///
+    /// This is `num_shares` times the rate, rounded down to a power of two as ADVZ requires and
+    /// at least one.
+    #[must_use]
+    pub fn recovery_threshold(&self, num_shares: usize) -> u32 {
+        let scaled = (num_shares as u128 * u128::from(self.numerator))
+            .checked_div(u128::from(self.denominator))
+            .unwrap_or_default()
+            .max(1);
+
+        1 << scaled.ilog2()
+    }
+}
+
+/// Network-wide VID parameters. Every node must use the same ones, since they determine the
+/// payload commitments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VidConfig {
+    /// Erasure code rate
+    pub code_rate: VidCodeRate,
+    /// Number of AvidM shares dispersed per storage node. ADVZ always disperses one share per node.
+    pub shares_per_node: u32,
+    /// Whether AvidM shares are assigned to storage nodes in proportion to their stake, rather
+    /// than `shares_per_node` to every node. Every node still gets at least one share.
+    pub stake_weighted: bool,
+}
+
+impl Default for VidConfig {
+    fn default() -> Self {
+        Self {
+            code_rate: VidCodeRate::default(),
+            shares_per_node: 1,
+            stake_weighted: false,
+        }
+    }
+}
+
+/// Reasons a [`VidConfig`] is rejected
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum VidConfigError {
+    /// The code rate is not in `(0, 1]`
+    #[error("VID code rate {0}/{1} must be in (0, 1]")]
+    InvalidCodeRate(u32, u32),
+    /// No shares would be dispersed
+    #[error("VID shares per node must be positive")]
+    NoShares,
+    /// Stake weighting has no shares to redistribute
+    #[error("stake weighted VID needs more than one share per node")]
+    NothingToWeight,
+}
+
+impl VidConfig {
+    /// Check that the configuration is usable.
+    ///
+    /// # Errors
+    /// If the code rate is not in `(0, 1]`, there are no shares, or stake weighting is enabled
+    /// with a single share per node
+    pub fn validate(&self) -> Result<(), VidConfigError> {
+        let VidCodeRate {
+            numerator,
+            denominator,
+        } = self.code_rate;
+        if numerator == 0 || numerator > denominator {
+            return Err(VidConfigError::InvalidCodeRate(numerator, denominator));
+        }
+        if self.shares_per_node == 0 {
+            return Err(VidConfigError::NoShares);
+        }
+        if self.stake_weighted && self.shares_per_node == 1 {
+            return Err(VidConfigError::NothingToWeight);
+        }
+
+        Ok(())
+    }
+
+    /// Number of AvidM shares held by each storage node, given their stakes in committee order.
+    ///
+    /// Without stake weighting every node holds `shares_per_node` shares. With it, every node
+    /// holds one share and the remaining ones are split in proportion to stake, with leftovers
+    /// going to the largest remainders. Either way there are `shares_per_node` shares per node
+    /// in total.
+    #[must_use]
+    pub fn share_weights(&self, stakes: &[U256]) -> Vec<usize> {
+        let per_node = self.shares_per_node as usize;
+        let total_stake = stakes.iter().fold(U256::zero(), |acc, stake| acc + *stake);
+        if !self.stake_weighted || total_stake.is_zero() {
+            return vec![per_node; stakes.len()];
+        }
+
+        let extra = stakes.len() * per_node.saturating_sub(1);
+        let extra_u256 = U256::from(extra);
+        let mut weights: Vec<usize> = stakes
+            .iter()
+            .map(|stake| 1 + (*stake * extra_u256 / total_stake).as_usize())
+            .collect();
+
+        let assigned: usize = weights.iter().sum::<usize>() - stakes.len();
+        let mut by_remainder: Vec<usize> = (0..stakes.len()).collect();
+        // Stable, so ties go to the earlier node in committee order.
+        by_remainder.sort_by_key(|&i| std::cmp::Reverse((stakes[i] * extra_u256) % total_stake));
+        for &i in by_remainder.iter().take(extra - assigned) {
+            weights[i] += 1;
+        }
+
+        weights
+    }
+
+    /// Indices of the AvidM shares held by the storage node at `node` in committee order, given
+    /// the stakes of the committee as for [`VidConfig::share_weights`]
+    #[must_use]
+    pub fn share_range(&self, stakes: &[U256], node: usize) -> Range<usize> {
+        let weights = self.share_weights(stakes);
+        let start = weights.iter().take(node).sum();
+
+        start..start + weights.get(node).copied().unwrap_or_default()
+    }
+}
+
+/// AvidM parameters for dispersing among `num_storage_nodes`, each holding
+/// [`VidConfig::shares_per_node`] shares on average.
+///
+/// # Errors
+/// If `num_storage_nodes` is zero or the number of shares does not fit into a share index
+pub fn avidm_param(num_storage_nodes: usize, config: &VidConfig) -> Result<AvidMParam, AvidMError> {
+    if num_storage_nodes == 0 {
+        return Err(AvidMError::InvalidParam(
+            "there must be at least one storage node".to_string(),
+        ));
+    }
+    let total_weight = num_storage_nodes * config.shares_per_node as usize;
+    AvidMParam::new(
+        total_weight,
+        config.code_rate.recovery_threshold(total_weight) as usize,
+    )
+}
+
+/// ADVZ commitment type
+pub type ADVZCommitment = <VidSchemeType as VidScheme>::Commit;
+/// ADVZ common type
+pub type ADVZCommon = <VidSchemeType as VidScheme>::Common;
+/// ADVZ share type
+pub type ADVZShare = <VidSchemeType as VidScheme>::Share;
+
+/// How [`VidCommitment`], [`VidCommon`] and [`VidShare`] are encoded in binary formats
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VidEncoding {
+    /// The bare ADVZ encoding of the versions before the AvidM upgrade, which only fits `V0` data
+    Legacy,
+    /// The encoding tagged with the scheme, which fits the data of both schemes
+    Tagged,
+}
+
+thread_local! {
+    /// The encoding used by the (de)serializers of VID data on this thread
+    static VID_ENCODING: Cell<VidEncoding> = const { Cell::new(VidEncoding::Tagged) };
+}
+
+impl VidEncoding {
+    /// The encoding of VID data in messages of `version`
+    #[must_use]
+    pub fn at<V: Versions>(version: Version) -> Self {
+        if uses_avidm::<V>(version) {
+            Self::Tagged
+        } else {
+            Self::Legacy
+        }
+    }
+
+    /// The encoding in use on this thread
+    fn current() -> Self {
+        VID_ENCODING.with(Cell::get)
+    }
+}
+
+/// Run `f` with VID data (de)serialized in the binary `encoding`.
+///
+/// Outside of this, VID data is encoded as [`VidEncoding::Tagged`]. Data written before the
+/// AvidM upgrade, e.g. by storage, must be read back under [`VidEncoding::Legacy`].
+pub fn with_vid_encoding<R>(encoding: VidEncoding, f: impl FnOnce() -> R) -> R {
+    /// Restores the previous encoding, even if `f` panics
+    struct Restore(VidEncoding);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            VID_ENCODING.with(|current| current.set(self.0));
+        }
+    }
+
+    let _restore = Restore(VID_ENCODING.with(|current| current.replace(encoding)));
+    f()
+}
+
+/// Scheme-tagged representation of VID data, with the encoding of the derived implementations
+#[derive(Serialize, Deserialize)]
+enum Versioned<A, B> {
+    /// ADVZ data
+    V0(A),
+    /// AvidM data
+    V1(B),
+}
+
+/// VID data in a human readable format, which is either tagged or bare ADVZ data
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HumanReadable<A, B> {
+    /// Data of either scheme, tagged with it
+    Tagged(Versioned<A, B>),
+    /// Bare ADVZ data
+    Legacy(A),
+}
+
+/// Serialize VID data of either scheme. `V0` data is encoded as the bare ADVZ type in human
+/// readable formats and under [`VidEncoding::Legacy`].
+fn serialize_versioned<S: Serializer, A: Serialize, B: Serialize>(
+    data: &Versioned<&A, &B>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match (data, serializer.is_human_readable(), VidEncoding::current()) {
+        (Versioned::V0(data), true, _) | (Versioned::V0(data), false, VidEncoding::Legacy) => {
+            data.serialize(serializer)
+        }
+        (Versioned::V1(_), false, VidEncoding::Legacy) => Err(ser::Error::custom(
+            "AvidM data cannot be encoded before the AvidM upgrade",
+        )),
+        (data, ..) => data.serialize(serializer),
+    }
+}
+
+/// Deserialize VID data encoded by [`serialize_versioned`]
+fn deserialize_versioned<'de, D, A, B>(deserializer: D) -> Result<Versioned<A, B>, D::Error>
+where
+    D: Deserializer<'de>,
+    A: Deserialize<'de>,
+    B: Deserialize<'de>,
+{
+    if deserializer.is_human_readable() {
+        return Ok(match HumanReadable::deserialize(deserializer)? {
+            HumanReadable::Tagged(data) => data,
+            HumanReadable::Legacy(data) => Versioned::V0(data),
+        });
+    }
+
+    match VidEncoding::current() {
+        VidEncoding::Legacy => A::deserialize(deserializer).map(Versioned::V0),
+        VidEncoding::Tagged => Versioned::deserialize(deserializer),
+    }
+}
+
+/// VID commitment, tagged with the scheme that produced it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VidCommitment {
+    /// ADVZ commitment, used before the AvidM upgrade
+    V0(ADVZCommitment),
+    /// AvidM commitment
+    V1(AvidMCommitment),
+}
+
+impl Serialize for VidCommitment {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match (self, serializer.is_human_readable()) {
+            // The commitments of both schemes are tagged base64 strings.
+            (Self::V0(commit), true) => commit.serialize(serializer),
+            (Self::V1(commit), true) => commit.serialize(serializer),
+            (Self::V0(commit), false) => {
+                serialize_versioned::<_, _, AvidMCommitment>(&Versioned::V0(commit), serializer)
+            }
+            (Self::V1(commit), false) => {
+                serialize_versioned::<_, ADVZCommitment, _>(&Versioned::V1(commit), serializer)
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for VidCommitment {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let tb64 = TaggedBase64::parse(&String::deserialize(deserializer)?)
+                .map_err(de::Error::custom)?;
+            return Self::try_from(&tb64).map_err(de::Error::custom);
+        }
+
+        Ok(match deserialize_versioned(deserializer)? {
+            Versioned::V0(commit) => Self::V0(commit),
+            Versioned::V1(commit) => Self::V1(commit),
+        })
+    }
+}
+
+impl AsRef<[u8]> for VidCommitment {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::V0(commit) => commit.as_ref(),
+            Self::V1(commit) => commit.as_ref(),
+        }
+    }
+}
+
+impl Display for VidCommitment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::V0(commit) => Display::fmt(commit, f),
+            Self::V1(commit) => Display::fmt(commit, f),
+        }
+    }
+}
+
+impl TryFrom<&TaggedBase64> for VidCommitment {
+    type Error = Tb64Error;
+
+    fn try_from(tb64: &TaggedBase64) -> Result<Self, Self::Error> {
+        AvidMCommitment::try_from(tb64).map(Self::V1).or_else(|_| {
+            ADVZCommitment::try_from(tb64)
+                .map(Self::V0)
+                .map_err(|_| Tb64Error::InvalidTag)
+        })
+    }
+}
+
+impl TryFrom<TaggedBase64> for VidCommitment {
+    type Error = Tb64Error;
+
+    fn try_from(tb64: TaggedBase64) -> Result<Self, Self::Error> {
+        Self::try_from(&tb64)
+    }
+}
+
+/// VID common data, tagged with the scheme that produced it
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum VidCommon {
+    /// ADVZ common data, used before the AvidM upgrade
+    V0(ADVZCommon),
+    /// AvidM parameters
+    V1(AvidMParam),
+}
+
+impl Serialize for VidCommon {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::V0(common) => {
+                serialize_versioned::<_, _, AvidMParam>(&Versioned::V0(common), serializer)
+            }
+            Self::V1(param) => {
+                serialize_versioned::<_, ADVZCommon, _>(&Versioned::V1(param), serializer)
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for VidCommon {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match deserialize_versioned(deserializer)? {
+            Versioned::V0(common) => Self::V0(common),
+            Versioned::V1(param) => Self::V1(param),
+        })
+    }
+}
+
+/// VID share, tagged with the scheme that produced it
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum VidShare {
+    /// ADVZ share, used before the AvidM upgrade
+    V0(ADVZShare),
+    /// AvidM shares held by a single storage node
+    V1(Vec<AvidMShare>),
+}
+
+impl Serialize for VidShare {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::V0(share) => {
+                serialize_versioned::<_, _, Vec<AvidMShare>>(&Versioned::V0(share), serializer)
+            }
+            Self::V1(shares) => {
+                serialize_versioned::<_, ADVZShare, _>(&Versioned::V1(shares), serializer)
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for VidShare {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match deserialize_versioned(deserializer)? {
+            Versioned::V0(share) => Self::V0(share),
+            Versioned::V1(shares) => Self::V1(shares),
+        })
+    }
+}
+
+/// Errors computing or verifying VID data
+#[derive(Debug, Error)]
+pub enum VidError {
+    /// ADVZ failure
+    #[error("ADVZ: {0}")]
+    Advz(#[from] jf_vid::VidError),
+    /// AvidM failure
+    #[error("AvidM: {0}")]
+    AvidM(#[from] AvidMError),
+    /// The share does not verify against the commitment
+    #[error("invalid VID share")]
+    InvalidShare,
+    /// The pieces of VID data were produced by different schemes
+    #[error("VID data of different schemes")]
+    SchemeMismatch,
+    /// The payload range proof does not verify against the commitment
+    #[error("invalid payload range proof")]
+    InvalidRangeProof,
+    /// The VID scheme cannot prove payload ranges
+    #[error("payload range proofs are only supported by ADVZ")]
+    RangeProofsUnsupported,
+}
+
+/// Result of dispersing a payload: its commitment, the common data and one share per storage node
+#[derive(Clone, Debug)]
+pub struct VidDispersal {
+    /// Payload commitment
+    pub commit: VidCommitment,
+    /// Data sent to every storage node
+    pub common: VidCommon,
+    /// One share per storage node, in committee order
+    pub shares: Vec<VidShare>,
+}
+
+impl VidDispersal {
+    /// Reassign AvidM shares to storage nodes in proportion to `stakes`, given in committee
+    /// order, if `config` asks for stake weighting.
+    ///
+    /// ADVZ shares are always one per node and are left alone.
+    pub fn weight_by_stake(&mut self, config: &VidConfig, stakes: &[U256]) {
+        if !config.stake_weighted || self.shares.iter().any(|s| matches!(s, VidShare::V0(_))) {
+            return;
+        }
+
+        let mut shares =
+            std::mem::take(&mut self.shares)
+                .into_iter()
+                .flat_map(|share| match share {
+                    VidShare::V1(shares) => shares,
+                    VidShare::V0(_) => Vec::new(),
+                });
+        self.shares = config
+            .share_weights(stakes)
+            .into_iter()
+            .map(|weight| VidShare::V1(shares.by_ref().take(weight).collect()))
+            .collect();
+    }
+}
+
+impl From<VidDisperse<VidSchemeType>> for VidDispersal {
+    fn from(vid_disperse: VidDisperse<VidSchemeType>) -> Self {
+        Self {
+            commit: VidCommitment::V0(vid_disperse.commit),
+            common: VidCommon::V0(vid_disperse.common),
+            shares: vid_disperse.shares.into_iter().map(VidShare::V0).collect(),
+        }
+    }
+}
+
+/// Whether payloads are dispersed with AvidM, rather than ADVZ, at `version`
+#[must_use]
+pub fn uses_avidm<V: Versions>(version: Version) -> bool {
+    version >= V::AvidM::VERSION
+}
+
+/// Disperse `payload` among `num_storage_nodes` with the VID scheme of `version`.
+///
+/// # Errors
+/// If the underlying scheme fails
+pub fn vid_disperse<V: Versions>(
+    payload: &[u8],
+    num_storage_nodes: usize,
+    version: Version,
+    config: &VidConfig,
+) -> Result<VidDispersal, VidError> {
+    if uses_avidm::<V>(version) {
+        let param = avidm_param(num_storage_nodes, config)?;
+        let (commit, shares) = AvidMScheme::disperse(&param, payload)?;
+        Ok(VidDispersal {
+            commit: VidCommitment::V1(commit),
+            common: VidCommon::V1(param),
+            shares: shares
+                .chunks(config.shares_per_node as usize)
+                .map(|chunk| VidShare::V1(chunk.to_vec()))
+                .collect(),
+        })
+    } else {
+        Ok(advz_scheme(num_storage_nodes, config)
+            .disperse(payload)?
+            .into())
+    }
+}
+
+/// Commit to `payload` dispersed among `num_storage_nodes` with the VID scheme of `version`.
+///
+/// # Errors
+/// If the underlying scheme fails
+pub fn vid_commit<V: Versions>(
+    payload: &[u8],
+    num_storage_nodes: usize,
+    version: Version,
+    config: &VidConfig,
+) -> Result<VidCommitment, VidError> {
+    if uses_avidm::<V>(version) {
+        let param = avidm_param(num_storage_nodes, config)?;
+        Ok(VidCommitment::V1(AvidMScheme::commit(&param, payload)?))
+    } else {
+        Ok(VidCommitment::V0(
+            advz_scheme(num_storage_nodes, config).commit_only(payload)?,
+        ))
+    }
+}
+
+/// Verify the share of the storage node at `recipient` in committee order against the payload
+/// commitment. `stakes` are the stakes of the whole committee, in committee order.
+///
+/// The share, common data and commitment must all come from the same scheme, and an AvidM share
+/// must hold exactly the shares `config` assigns to the recipient.
+///
+/// # Errors
+/// If the share is invalid
+pub fn verify_vid_share(
+    share: &VidShare,
+    common: &VidCommon,
+    commit: &VidCommitment,
+    stakes: &[U256],
+    recipient: usize,
+    config: &VidConfig,
+) -> Result<(), VidError> {
+    let num_storage_nodes = stakes.len();
+    if recipient >= num_storage_nodes {
+        return Err(VidError::InvalidShare);
+    }
+
+    match (share, common, commit) {
+        (VidShare::V0(share), VidCommon::V0(common), VidCommitment::V0(commit)) => {
+            advz_scheme(num_storage_nodes, config)
+                .verify_share(share, common, commit)?
+                .map_err(|()| VidError::InvalidShare)
+        }
+        (VidShare::V1(shares), VidCommon::V1(param), VidCommitment::V1(commit)) => {
+            // Never trust the parameters sent along with the share.
+            let expected = avidm_param(num_storage_nodes, config)?;
+            if *param != expected {
+                return Err(AvidMError::InvalidParam(format!(
+                    "expected {expected:?}, got {param:?}"
+                ))
+                .into());
+            }
+            // Storage nodes must not get more or other shares than their weight.
+            let range = config.share_range(stakes, recipient);
+            if shares.len() != range.len()
+                || shares
+                    .iter()
+                    .zip(range)
+                    .any(|(share, index)| share.index() as usize != index)
+            {
+                return Err(VidError::InvalidShare);
+            }
+            for share in shares {
+                AvidMScheme::verify_share(&expected, commit, share)?;
+            }
+            Ok(())
+        }
+        _ => Err(VidError::SchemeMismatch),
+    }
+}
+
+/// Whether `shares` of a payload dispersed among `num_storage_nodes` reach the recovery threshold.
+///
+/// Shares of a different scheme than `common` are not counted.
+#[must_use]
+pub fn reaches_recovery_threshold(
+    shares: &[VidShare],
+    common: &VidCommon,
+    num_storage_nodes: usize,
+    config: &VidConfig,
+) -> bool {
+    match common {
+        VidCommon::V0(_) => {
+            let threshold = config.code_rate.recovery_threshold(num_storage_nodes);
+            let count = shares
+                .iter()
+                .filter(|share| matches!(share, VidShare::V0(_)))
+                .count();
+            count >= threshold as usize
+        }
+        VidCommon::V1(param) => {
+            let count: usize = shares
+                .iter()
+                .map(|share| match share {
+                    VidShare::V0(_) => 0,
+                    VidShare::V1(shares) => shares.len(),
+                })
+                .sum();
+            count >= param.recovery_threshold()
+        }
+    }
+}
+
+/// Recover a payload dispersed among `num_storage_nodes` from its shares.
+///
+/// The shares should have been checked with [`verify_vid_share`] first.
+///
+/// # Errors
+/// If the pieces of VID data come from different schemes, there are too few shares, or the
+/// recovered payload does not match `commit`
+pub fn vid_recover(
+    shares: &[VidShare],
+    common: &VidCommon,
+    commit: &VidCommitment,
+    num_storage_nodes: usize,
+    config: &VidConfig,
+) -> Result<Vec<u8>, VidError> {
+    match (common, commit) {
+        (VidCommon::V0(common), VidCommitment::V0(commit)) => {
+            let shares = shares
+                .iter()
+                .map(|share| match share {
+                    VidShare::V0(share) => Ok(share.clone()),
+                    VidShare::V1(_) => Err(VidError::SchemeMismatch),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            VidSchemeType::is_consistent(commit, common)?;
+            Ok(advz_scheme(num_storage_nodes, config).recover_payload(&shares, common)?)
+        }
+        (VidCommon::V1(param), VidCommitment::V1(commit)) => {
+            let expected = avidm_param(num_storage_nodes, config)?;
+            if *param != expected {
+                return Err(AvidMError::InvalidParam(format!(
+                    "expected {expected:?}, got {param:?}"
+                ))
+                .into());
+            }
+            let shares = shares
+                .iter()
+                .map(|share| match share {
+                    VidShare::V0(_) => Err(VidError::SchemeMismatch),
+                    VidShare::V1(shares) => Ok(shares.iter().cloned()),
+                })
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+            Ok(AvidMScheme::recover(&expected, commit, &shares)?)
+        }
+        _ => Err(VidError::SchemeMismatch),
+    }
+}
+
+/// Prove that `payload[range]` is part of `payload`, dispersed among `num_storage_nodes`.
+///
+/// Use a [`LargeRangeProofType`] for long ranges such as namespaces, and a
+/// [`SmallRangeProofType`] for short ones such as single transactions. Only ADVZ supports range
+/// proofs, so `common` must be ADVZ common data.
+///
+/// # Errors
+/// If `common` is not ADVZ common data, or the range is out of bounds
+pub fn payload_range_proof<P>(
+    payload: &[u8],
+    range: Range<usize>,
+    common: &VidCommon,
+    num_storage_nodes: usize,
+    config: &VidConfig,
+) -> Result<P, VidError>
+where
+    VidSchemeType: PayloadProver<P>,
+{
+    match common {
+        VidCommon::V0(_) => {
+            Ok(advz_scheme(num_storage_nodes, config).payload_proof(payload, range)?)
+        }
+        VidCommon::V1(_) => Err(VidError::RangeProofsUnsupported),
+    }
+}
+
+/// Verify a proof from [`payload_range_proof`] that `subslice` is `range` of the payload with
+/// commitment `commit`, dispersed among `num_storage_nodes`.
+///
+/// # Errors
+/// If the proof is invalid, or the VID data is not ADVZ data
+pub fn verify_payload_range<P>(
+    subslice: &[u8],
+    range: Range<usize>,
+    proof: &P,
+    commit: &VidCommitment,
+    common: &VidCommon,
+    num_storage_nodes: usize,
+    config: &VidConfig,
+) -> Result<(), VidError>
+where
+    VidSchemeType: PayloadProver<P>,
+{
+    match (commit, common) {
+        (VidCommitment::V0(commit), VidCommon::V0(common)) => {
+            advz_scheme(num_storage_nodes, config)
+                .payload_verify(
+                    Statement {
+                        payload_subslice: subslice,
+                        range,
+                        commit,
+                        common,
+                    },
+                    proof,
+                )?
+                .map_err(|()| VidError::InvalidRangeProof)
+        }
+        (VidCommitment::V1(_), VidCommon::V1(_)) => Err(VidError::RangeProofsUnsupported),
+        _ => Err(VidError::SchemeMismatch),
+    }
+}
+
 /// VID proposal type
 pub type VidProposal<TYPES> = (
     Proposal<TYPES, HotShotVidDisperse<TYPES>>,
@@ -313,3 +1075,179 @@ fn stmt_conversion(stmt: Statement<'_, VidSchemeType>) -> Statement<'_, Advz> {
         common: stmt.common,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rate(numerator: u32, denominator: u32) -> VidCodeRate {
+        VidCodeRate {
+            numerator,
+            denominator,
+        }
+    }
+
+    #[test]
+    fn recovery_threshold_from_code_rate() {
+        // The default rate reproduces the threshold used before it was configurable.
+        for n in 1..100 {
+            assert_eq!(VidCodeRate::default().recovery_threshold(n), 1 << n.ilog2());
+        }
+
+        assert_eq!(rate(1, 2).recovery_threshold(10), 4);
+        assert_eq!(rate(1, 3).recovery_threshold(10), 2);
+        assert_eq!(rate(1, 4).recovery_threshold(64), 16);
+        assert_eq!(rate(1, 100).recovery_threshold(10), 1);
+    }
+
+    #[test]
+    fn validate_vid_config() {
+        assert!(VidConfig::default().validate().is_ok());
+
+        let config = |code_rate, shares_per_node, stake_weighted| VidConfig {
+            code_rate,
+            shares_per_node,
+            stake_weighted,
+        };
+        assert_eq!(
+            config(rate(0, 1), 1, false).validate(),
+            Err(VidConfigError::InvalidCodeRate(0, 1))
+        );
+        assert_eq!(
+            config(rate(3, 2), 1, false).validate(),
+            Err(VidConfigError::InvalidCodeRate(3, 2))
+        );
+        assert_eq!(
+            config(rate(1, 2), 0, false).validate(),
+            Err(VidConfigError::NoShares)
+        );
+        assert_eq!(
+            config(rate(1, 2), 1, true).validate(),
+            Err(VidConfigError::NothingToWeight)
+        );
+        assert!(config(rate(1, 2), 4, true).validate().is_ok());
+    }
+
+    #[test]
+    fn share_weights_follow_stake() {
+        let stakes: Vec<U256> = [1u64, 1, 2, 6].into_iter().map(U256::from).collect();
+
+        let uniform = VidConfig {
+            shares_per_node: 3,
+            ..VidConfig::default()
+        };
+        assert_eq!(uniform.share_weights(&stakes), vec![3; 4]);
+
+        let weighted = VidConfig {
+            stake_weighted: true,
+            ..uniform
+        };
+        let weights = weighted.share_weights(&stakes);
+        assert_eq!(weights.iter().sum::<usize>(), 12);
+        assert!(weights.iter().all(|&w| w >= 1));
+        assert_eq!(weights, vec![2, 2, 2, 6]);
+
+        // Without any stake, fall back to the uniform assignment.
+        assert_eq!(weighted.share_weights(&[U256::zero(); 4]), vec![3; 4]);
+
+        assert_eq!(uniform.share_range(&stakes, 1), 3..6);
+        assert_eq!(weighted.share_range(&stakes, 3), 6..12);
+        assert_eq!(weighted.share_range(&stakes, 4), 12..12);
+    }
+
+    #[test]
+    fn avidm_shares_match_the_recipient_weight() {
+        let config = VidConfig {
+            shares_per_node: 3,
+            stake_weighted: true,
+            ..VidConfig::default()
+        };
+        let stakes: Vec<U256> = [1u64, 1, 2, 6].into_iter().map(U256::from).collect();
+        let param = avidm_param(stakes.len(), &config).unwrap();
+        let (commit, shares) = AvidMScheme::disperse(&param, &[1; 300]).unwrap();
+        let mut dispersal = VidDispersal {
+            commit: VidCommitment::V1(commit),
+            common: VidCommon::V1(param),
+            shares: vec![VidShare::V1(shares)],
+        };
+        dispersal.weight_by_stake(&config, &stakes);
+
+        let verify = |share: &VidShare, recipient| {
+            verify_vid_share(
+                share,
+                &dispersal.common,
+                &dispersal.commit,
+                &stakes,
+                recipient,
+                &config,
+            )
+        };
+        for (recipient, share) in dispersal.shares.iter().enumerate() {
+            verify(share, recipient).unwrap();
+        }
+
+        // Shares of another node, or more shares than the weight, are rejected.
+        assert!(verify(&dispersal.shares[0], 1).is_err());
+        assert!(verify(&dispersal.shares[3], 2).is_err());
+        let VidShare::V1(mut shares) = dispersal.shares[0].clone() else {
+            panic!("expected AvidM shares");
+        };
+        let VidShare::V1(next) = &dispersal.shares[1] else {
+            panic!("expected AvidM shares");
+        };
+        shares.extend(next.iter().cloned());
+        assert!(verify(&VidShare::V1(shares), 0).is_err());
+        assert!(verify(&dispersal.shares[0], stakes.len()).is_err());
+    }
+
+    #[test]
+    fn recover_from_avidm_shares() {
+        let num_storage_nodes = 8;
+        let config = VidConfig::default();
+        let stakes = vec![U256::one(); num_storage_nodes];
+        let param = avidm_param(num_storage_nodes, &config).unwrap();
+        let payload: Vec<u8> = (0..500u32)
+            .map(|i| u8::try_from(i % 256).unwrap())
+            .collect();
+        let (commit, shares) = AvidMScheme::disperse(&param, &payload).unwrap();
+        let commit = VidCommitment::V1(commit);
+        let common = VidCommon::V1(param);
+        let shares: Vec<VidShare> = shares
+            .into_iter()
+            .map(|share| VidShare::V1(vec![share]))
+            .collect();
+
+        for (recipient, share) in shares.iter().enumerate() {
+            verify_vid_share(share, &common, &commit, &stakes, recipient, &config).unwrap();
+        }
+
+        let threshold = param.recovery_threshold();
+        assert!(!reaches_recovery_threshold(
+            &shares[..threshold - 1],
+            &common,
+            num_storage_nodes,
+            &config
+        ));
+        assert!(vid_recover(
+            &shares[..threshold - 1],
+            &common,
+            &commit,
+            num_storage_nodes,
+            &config
+        )
+        .is_err());
+
+        // Every share is needed at the default code rate of one.
+        assert_eq!(threshold, num_storage_nodes);
+        assert!(reaches_recovery_threshold(
+            &shares,
+            &common,
+            num_storage_nodes,
+            &config
+        ));
+        assert_eq!(
+            vid_recover(&shares, &common, &commit, num_storage_nodes, &config).unwrap(),
+            payload
+        );
+    }
+}
@@ -0,0 +1,547 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! AvidM: a VID scheme with Merkle tree commitments and no trusted setup.
+//!
+//! The payload is packed into field elements which are grouped into polynomials of
+//! `recovery_threshold` coefficients each. Every polynomial is evaluated at `total_weight` points,
+//! and storage node `i` receives the `i`-th evaluation of every polynomial together with a Merkle
+//! proof. The payload commitment binds the payload length to the root of the Merkle tree over all
+//! shares, so a share is verified with hashes only. Any `recovery_threshold` valid shares recover
+//! the payload by interpolation.
+
+use std::collections::BTreeMap;
+
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, Field, One, PrimeField, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use jf_utils::canonical;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tagged_base64::tagged;
+use thiserror::Error;
+
+/// Payload bytes packed into each field element, few enough for every chunk to be below the modulus
+const BYTES_PER_ELEM: usize = 31;
+
+/// Domain separator for Merkle tree leaves
+const LEAF_DOMAIN: &[u8] = b"AVIDM_LEAF";
+/// Domain separator for internal Merkle tree nodes
+const NODE_DOMAIN: &[u8] = b"AVIDM_NODE";
+/// Domain separator for the payload commitment
+const COMMIT_DOMAIN: &[u8] = b"AVIDM_COMMIT";
+
+/// Sha256 digest of a Merkle tree node
+type NodeHash = [u8; 32];
+
+/// Errors of the AvidM scheme
+#[derive(Debug, Error)]
+pub enum AvidMError {
+    /// The parameters do not describe a valid code
+    #[error("invalid AvidM parameters: {0}")]
+    InvalidParam(String),
+    /// The payload length does not fit into the share metadata
+    #[error("payload of {0} bytes is too large")]
+    PayloadTooLarge(usize),
+    /// A share does not verify against the commitment
+    #[error("invalid AvidM share: {0}")]
+    InvalidShare(String),
+    /// Too few valid shares were given to recover the payload
+    #[error("need {needed} valid shares to recover the payload, got {got}")]
+    InsufficientShares {
+        /// Number of shares required
+        needed: usize,
+        /// Number of valid shares given
+        got: usize,
+    },
+    /// The recovered payload does not match the commitment
+    #[error("recovered payload is inconsistent with the commitment")]
+    InconsistentPayload,
+}
+
+/// Result type of the AvidM scheme
+pub type AvidMResult<T> = std::result::Result<T, AvidMError>;
+
+/// Public parameters of an AvidM instance, also sent to every storage node as the common data
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AvidMParam {
+    /// Number of shares the payload is dispersed into
+    total_weight: usize,
+    /// Number of shares needed to recover the payload
+    recovery_threshold: usize,
+}
+
+impl AvidMParam {
+    /// Create parameters dispersing into `total_weight` shares, any `recovery_threshold` of which
+    /// recover the payload.
+    ///
+    /// # Errors
+    /// If `recovery_threshold` is zero or larger than `total_weight`, or `total_weight` does not
+    /// fit into a share index
+    pub fn new(total_weight: usize, recovery_threshold: usize) -> AvidMResult<Self> {
+        let param = Self {
+            total_weight,
+            recovery_threshold,
+        };
+        param.validate()?;
+
+        Ok(param)
+    }
+
+    /// Check the parameters, which may come from a peer
+    fn validate(&self) -> AvidMResult<()> {
+        let Self {
+            total_weight,
+            recovery_threshold,
+        } = *self;
+        if recovery_threshold == 0 || recovery_threshold > total_weight {
+            return Err(AvidMError::InvalidParam(format!(
+                "recovery threshold {recovery_threshold} must be in 1..={total_weight}"
+            )));
+        }
+        if u32::try_from(total_weight).is_err() {
+            return Err(AvidMError::InvalidParam(format!(
+                "total weight {total_weight} should fit into u32"
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Number of shares the payload is dispersed into
+    #[must_use]
+    pub fn total_weight(&self) -> usize {
+        self.total_weight
+    }
+
+    /// Number of shares needed to recover the payload
+    #[must_use]
+    pub fn recovery_threshold(&self) -> usize {
+        self.recovery_threshold
+    }
+
+    /// Depth of the Merkle tree over the shares
+    fn tree_depth(&self) -> usize {
+        self.total_weight.next_power_of_two().trailing_zeros() as usize
+    }
+
+    /// Number of polynomials encoding a payload of `payload_byte_len` bytes
+    fn num_polys(&self, payload_byte_len: usize) -> usize {
+        payload_byte_len
+            .div_ceil(BYTES_PER_ELEM)
+            .div_ceil(self.recovery_threshold)
+    }
+}
+
+#[tagged("AVIDM_COMMIT")]
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Hash,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    CanonicalSerialize,
+    CanonicalDeserialize,
+)]
+/// AvidM payload commitment.
+/// A thin wrapper around a Sha256 digest of the share tree root and the payload length.
+pub struct AvidMCommitment(NodeHash);
+
+impl AsRef<[u8]> for AvidMCommitment {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// A storage node's AvidM share
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AvidMShare {
+    /// Index of the storage node the share is intended for
+    index: u32,
+    /// Length of the dispersed payload
+    payload_byte_len: u32,
+    /// Evaluation of every payload polynomial at this share's point
+    #[serde(with = "canonical")]
+    evals: Vec<Fr>,
+    /// Merkle proof of the share against the tree root
+    proof: Vec<NodeHash>,
+}
+
+impl AvidMShare {
+    /// Index of the storage node the share is intended for
+    #[must_use]
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Length of the dispersed payload
+    #[must_use]
+    pub fn payload_byte_len(&self) -> u32 {
+        self.payload_byte_len
+    }
+}
+
+/// The AvidM scheme
+pub struct AvidMScheme;
+
+impl AvidMScheme {
+    /// Compute the commitment to `payload` without producing the shares.
+    ///
+    /// # Errors
+    /// If the payload is too large
+    pub fn commit(param: &AvidMParam, payload: &[u8]) -> AvidMResult<AvidMCommitment> {
+        param.validate()?;
+        let (payload_byte_len, elems) = encode(payload)?;
+        let polys: Vec<&[Fr]> = elems.chunks(param.recovery_threshold).collect();
+
+        // Only the root is needed, so every share's evaluations are hashed and dropped right away.
+        let leaves = (0..param.total_weight)
+            .map(|index| hash_leaf(index, &share_evals(&polys, index)))
+            .collect();
+
+        Ok(commitment(param, &merkle_root(leaves), payload_byte_len))
+    }
+
+    /// Disperse `payload` into `param.total_weight` shares.
+    ///
+    /// # Errors
+    /// If the payload is too large
+    pub fn disperse(
+        param: &AvidMParam,
+        payload: &[u8],
+    ) -> AvidMResult<(AvidMCommitment, Vec<AvidMShare>)> {
+        param.validate()?;
+        let (payload_byte_len, elems) = encode(payload)?;
+        let polys: Vec<&[Fr]> = elems.chunks(param.recovery_threshold).collect();
+
+        let evals: Vec<Vec<Fr>> = (0..param.total_weight)
+            .map(|index| share_evals(&polys, index))
+            .collect();
+        let leaves = evals
+            .iter()
+            .enumerate()
+            .map(|(index, evals)| hash_leaf(index, evals))
+            .collect();
+        let layers = merkle_layers(leaves);
+        let commit = commitment(param, &layers[layers.len() - 1][0], payload_byte_len);
+
+        let shares = evals
+            .into_iter()
+            .enumerate()
+            .map(|(index, evals)| {
+                // `AvidMParam::new` ensures that every index fits.
+                #[allow(clippy::cast_possible_truncation)]
+                let share_index = index as u32;
+                AvidMShare {
+                    index: share_index,
+                    payload_byte_len,
+                    evals,
+                    proof: layers[..layers.len() - 1]
+                        .iter()
+                        .enumerate()
+                        .map(|(level, layer)| layer[(index >> level) ^ 1])
+                        .collect(),
+                }
+            })
+            .collect();
+
+        Ok((commit, shares))
+    }
+
+    /// Verify `share` against `commit`.
+    ///
+    /// # Errors
+    /// If the share is malformed or does not belong to the committed payload
+    pub fn verify_share(
+        param: &AvidMParam,
+        commit: &AvidMCommitment,
+        share: &AvidMShare,
+    ) -> AvidMResult<()> {
+        param.validate()?;
+        let index = share.index as usize;
+        if index >= param.total_weight {
+            return Err(AvidMError::InvalidShare(format!(
+                "index {index} out of range for {} shares",
+                param.total_weight
+            )));
+        }
+        if share.evals.len() != param.num_polys(share.payload_byte_len as usize) {
+            return Err(AvidMError::InvalidShare(format!(
+                "expected {} evaluations, got {}",
+                param.num_polys(share.payload_byte_len as usize),
+                share.evals.len()
+            )));
+        }
+        if share.proof.len() != param.tree_depth() {
+            return Err(AvidMError::InvalidShare(format!(
+                "expected a proof of length {}, got {}",
+                param.tree_depth(),
+                share.proof.len()
+            )));
+        }
+
+        let root = share.proof.iter().enumerate().fold(
+            hash_leaf(index, &share.evals),
+            |node, (level, sibling)| {
+                if (index >> level) & 1 == 0 {
+                    hash_node(&node, sibling)
+                } else {
+                    hash_node(sibling, &node)
+                }
+            },
+        );
+
+        if commitment(param, &root, share.payload_byte_len) == *commit {
+            Ok(())
+        } else {
+            Err(AvidMError::InvalidShare(
+                "share does not match the commitment".to_string(),
+            ))
+        }
+    }
+
+    /// Recover the payload committed to by `commit` from `shares`. Invalid shares are ignored.
+    ///
+    /// # Errors
+    /// If fewer than `param.recovery_threshold` shares are valid, or the payload they encode does
+    /// not match the commitment
+    pub fn recover(
+        param: &AvidMParam,
+        commit: &AvidMCommitment,
+        shares: &[AvidMShare],
+    ) -> AvidMResult<Vec<u8>> {
+        param.validate()?;
+        let valid: BTreeMap<u32, &AvidMShare> = shares
+            .iter()
+            .filter(|share| Self::verify_share(param, commit, share).is_ok())
+            .map(|share| (share.index, share))
+            .collect();
+        if valid.len() < param.recovery_threshold {
+            return Err(AvidMError::InsufficientShares {
+                needed: param.recovery_threshold,
+                got: valid.len(),
+            });
+        }
+
+        let shares: Vec<&AvidMShare> = valid.into_values().take(param.recovery_threshold).collect();
+        let points: Vec<Fr> = shares
+            .iter()
+            .map(|share| eval_point(share.index as usize))
+            .collect();
+        let basis = lagrange_basis(&points);
+        let payload_byte_len = shares[0].payload_byte_len as usize;
+
+        let mut payload = Vec::with_capacity(payload_byte_len + BYTES_PER_ELEM);
+        for poly in 0..param.num_polys(payload_byte_len) {
+            for coeff in 0..param.recovery_threshold {
+                let elem: Fr = shares
+                    .iter()
+                    .zip(&basis)
+                    .map(|(share, basis)| share.evals[poly] * basis[coeff])
+                    .sum();
+                payload.extend_from_slice(&elem.into_bigint().to_bytes_le()[..BYTES_PER_ELEM]);
+            }
+        }
+        payload.truncate(payload_byte_len);
+
+        // A malicious disperser may have committed to evaluations of polynomials of too high a
+        // degree, in which case different subsets of shares recover different payloads.
+        if Self::commit(param, &payload)? == *commit {
+            Ok(payload)
+        } else {
+            Err(AvidMError::InconsistentPayload)
+        }
+    }
+}
+
+/// The length of `payload` and the field elements packing it
+fn encode(payload: &[u8]) -> AvidMResult<(u32, Vec<Fr>)> {
+    let payload_byte_len =
+        u32::try_from(payload.len()).map_err(|_| AvidMError::PayloadTooLarge(payload.len()))?;
+    let elems = payload
+        .chunks(BYTES_PER_ELEM)
+        .map(Fr::from_le_bytes_mod_order)
+        .collect();
+
+    Ok((payload_byte_len, elems))
+}
+
+/// Evaluations of every payload polynomial in `polys` at the point of the share at `index`
+fn share_evals(polys: &[&[Fr]], index: usize) -> Vec<Fr> {
+    let point = eval_point(index);
+    polys.iter().map(|poly| evaluate(poly, point)).collect()
+}
+
+/// Evaluation point of the share at `index`
+fn eval_point(index: usize) -> Fr {
+    Fr::from(index as u64 + 1)
+}
+
+/// Evaluate the polynomial with coefficients `poly` at `point`
+fn evaluate(poly: &[Fr], point: Fr) -> Fr {
+    poly.iter()
+        .rev()
+        .fold(Fr::zero(), |acc, coeff| acc * point + coeff)
+}
+
+/// Coefficients of the Lagrange basis polynomials for distinct `points`
+fn lagrange_basis(points: &[Fr]) -> Vec<Vec<Fr>> {
+    // The vanishing polynomial of all points, from which each basis polynomial is one division away.
+    let mut vanishing = vec![Fr::one()];
+    for point in points {
+        let mut next = vec![Fr::zero(); vanishing.len() + 1];
+        for (i, coeff) in vanishing.iter().enumerate() {
+            next[i + 1] += coeff;
+            next[i] -= *coeff * point;
+        }
+        vanishing = next;
+    }
+
+    points
+        .iter()
+        .map(|point| {
+            let degree = points.len();
+            let mut quotient = vec![Fr::zero(); degree];
+            quotient[degree - 1] = vanishing[degree];
+            for i in (1..degree).rev() {
+                quotient[i - 1] = vanishing[i] + *point * quotient[i];
+            }
+            // Points are distinct, so the quotient does not vanish at `point`.
+            let scale = evaluate(&quotient, *point).inverse().unwrap_or_default();
+            quotient.iter().map(|coeff| *coeff * scale).collect()
+        })
+        .collect()
+}
+
+/// Hash of the share at `index` with evaluations `evals`
+fn hash_leaf(index: usize, evals: &[Fr]) -> NodeHash {
+    let mut hasher = Sha256::new();
+    hasher.update(LEAF_DOMAIN);
+    hasher.update((index as u64).to_le_bytes());
+    for eval in evals {
+        hasher.update(eval.into_bigint().to_bytes_le());
+    }
+    hasher.finalize().into()
+}
+
+/// Hash of an internal Merkle tree node
+fn hash_node(left: &NodeHash, right: &NodeHash) -> NodeHash {
+    let mut hasher = Sha256::new();
+    hasher.update(NODE_DOMAIN);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Every layer of the Merkle tree over `leaves`, from the leaves up to the root
+fn merkle_layers(mut leaves: Vec<NodeHash>) -> Vec<Vec<NodeHash>> {
+    leaves.resize(leaves.len().next_power_of_two(), NodeHash::default());
+
+    let mut layers = vec![leaves];
+    while layers[layers.len() - 1].len() > 1 {
+        let next = layers[layers.len() - 1]
+            .chunks(2)
+            .map(|pair| hash_node(&pair[0], &pair[1]))
+            .collect();
+        layers.push(next);
+    }
+
+    layers
+}
+
+/// Root of the Merkle tree over `leaves`, without keeping the lower layers
+fn merkle_root(mut layer: Vec<NodeHash>) -> NodeHash {
+    layer.resize(layer.len().next_power_of_two(), NodeHash::default());
+    while layer.len() > 1 {
+        layer = layer
+            .chunks(2)
+            .map(|pair| hash_node(&pair[0], &pair[1]))
+            .collect();
+    }
+
+    layer[0]
+}
+
+/// Commitment to a payload of `payload_byte_len` bytes whose shares have Merkle root `root`
+fn commitment(param: &AvidMParam, root: &NodeHash, payload_byte_len: u32) -> AvidMCommitment {
+    let mut hasher = Sha256::new();
+    hasher.update(COMMIT_DOMAIN);
+    hasher.update((param.total_weight as u64).to_le_bytes());
+    hasher.update((param.recovery_threshold as u64).to_le_bytes());
+    hasher.update(root);
+    hasher.update(payload_byte_len.to_le_bytes());
+    AvidMCommitment(hasher.finalize().into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn payload(len: usize) -> Vec<u8> {
+        (0..len).map(|i| u8::try_from(i % 251).unwrap()).collect()
+    }
+
+    #[test]
+    fn disperse_verify_recover() {
+        let param = AvidMParam::new(10, 4).unwrap();
+
+        for len in [0, 1, 31, 200, 1000] {
+            let payload = payload(len);
+            let (commit, shares) = AvidMScheme::disperse(&param, &payload).unwrap();
+            assert_eq!(AvidMScheme::commit(&param, &payload).unwrap(), commit);
+            assert_eq!(shares.len(), 10);
+
+            for share in &shares {
+                AvidMScheme::verify_share(&param, &commit, share).unwrap();
+            }
+
+            // Any subset of `recovery_threshold` shares recovers the payload.
+            assert_eq!(
+                AvidMScheme::recover(&param, &commit, &shares[6..]).unwrap(),
+                payload
+            );
+            assert_eq!(
+                AvidMScheme::recover(&param, &commit, &[&shares[1..3], &shares[7..9]].concat())
+                    .unwrap(),
+                payload
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_bad_shares() {
+        let param = AvidMParam::new(5, 2).unwrap();
+        let (commit, shares) = AvidMScheme::disperse(&param, &payload(100)).unwrap();
+        let (other_commit, _) = AvidMScheme::disperse(&param, &payload(101)).unwrap();
+
+        assert!(AvidMScheme::verify_share(&param, &other_commit, &shares[0]).is_err());
+
+        let mut tampered = shares[0].clone();
+        tampered.evals[0] += Fr::one();
+        assert!(AvidMScheme::verify_share(&param, &commit, &tampered).is_err());
+
+        let mut moved = shares[0].clone();
+        moved.index = 1;
+        assert!(AvidMScheme::verify_share(&param, &commit, &moved).is_err());
+
+        // Invalid shares are ignored on recovery.
+        assert!(matches!(
+            AvidMScheme::recover(&param, &commit, &[tampered, shares[1].clone()]),
+            Err(AvidMError::InsufficientShares { needed: 2, got: 1 })
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_params() {
+        assert!(AvidMParam::new(5, 0).is_err());
+        assert!(AvidMParam::new(5, 6).is_err());
+        assert!(AvidMParam::new(5, 5).is_ok());
+    }
+}
@@ -0,0 +1,257 @@
+// Copyright (c) 2021-2024 Espresso Systems (espressosys.com)
+// This file is part of the HotShot repository.
+
+// You should have received a copy of the MIT License
+// along with the HotShot repository. If not, see <https://mit-license.org/>.
+
+//! Bounded compute pool for VID encoding and share verification.
+//!
+//! VID work is CPU bound and can take seconds for large payloads. Running it on the async runtime
+//! stalls the tokio workers, so the pool runs every job on the blocking thread pool while a
+//! semaphore bounds how many jobs run at once. Jobs are tagged with the view they are computed for,
+//! and waiting for a job of a view which has since been abandoned returns
+//! [`VidPoolError::Cancelled`] right away.
+
+use std::{
+    num::NonZeroUsize,
+    sync::Arc,
+    thread::available_parallelism,
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+use tokio::{
+    sync::{watch, Semaphore},
+    task::spawn_blocking,
+};
+
+use crate::consensus::ConsensusMetricsValue;
+
+/// Reasons a job submitted to the [`VidComputePool`] did not produce a result
+#[derive(Debug, Error)]
+pub enum VidPoolError {
+    /// The view the job was computed for has been abandoned
+    #[error("VID job for view {0} was cancelled")]
+    Cancelled(u64),
+    /// The job panicked or the runtime is shutting down
+    #[error("VID job failed: {0}")]
+    Failed(String),
+}
+
+/// Bounded pool running VID computations off the async runtime
+#[derive(Clone, Debug)]
+pub struct VidComputePool {
+    /// Limits the number of jobs running at once
+    permits: Arc<Semaphore>,
+    /// Jobs for views below this one are abandoned
+    abandoned_before: Arc<watch::Sender<u64>>,
+    /// Metrics recording encode time and cancelled jobs
+    metrics: Arc<ConsensusMetricsValue>,
+    /// Whether jobs are cancelled when their view is abandoned
+    cancellable: bool,
+}
+
+impl VidComputePool {
+    /// Create a pool running at most `max_jobs` jobs at once
+    #[must_use]
+    pub fn new(max_jobs: NonZeroUsize, metrics: Arc<ConsensusMetricsValue>) -> Self {
+        let (abandoned_before, _) = watch::channel(0);
+
+        Self {
+            permits: Arc::new(Semaphore::new(max_jobs.get())),
+            abandoned_before: Arc::new(abandoned_before),
+            metrics,
+            cancellable: true,
+        }
+    }
+
+    /// A handle on the same pool whose jobs run to completion even if their view is abandoned,
+    /// e.g. to answer a peer's request for data of an older view
+    #[must_use]
+    pub fn without_cancellation(&self) -> Self {
+        Self {
+            cancellable: false,
+            ..self.clone()
+        }
+    }
+
+    /// Create a pool running as many jobs at once as there are cores
+    #[must_use]
+    pub fn with_available_parallelism(metrics: Arc<ConsensusMetricsValue>) -> Self {
+        Self::new(
+            available_parallelism().unwrap_or(NonZeroUsize::MIN),
+            metrics,
+        )
+    }
+
+    /// Abandon the jobs of every view before `view`. Jobs still queued are dropped, and callers
+    /// waiting on running jobs get [`VidPoolError::Cancelled`] while the job finishes in the
+    /// background.
+    pub fn abandon_views_before(&self, view: u64) {
+        self.abandoned_before.send_if_modified(|abandoned_before| {
+            if view > *abandoned_before {
+                *abandoned_before = view;
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    /// Encode a payload of `payload_size` bytes, recording the time it took.
+    ///
+    /// # Errors
+    /// If `view` is abandoned before the job completes, or the job panics
+    pub async fn encode<R, F>(
+        &self,
+        view: Option<u64>,
+        payload_size: usize,
+        job: F,
+    ) -> Result<R, VidPoolError>
+    where
+        R: Send + 'static,
+        F: FnOnce() -> R + Send + 'static,
+    {
+        let (result, elapsed) = self.run(view, job).await?;
+        self.metrics
+            .vid_encode_duration
+            .create(vec![payload_size_bucket(payload_size).to_string()])
+            .add_point(elapsed.as_secs_f64());
+
+        Ok(result)
+    }
+
+    /// Verify a VID share.
+    ///
+    /// # Errors
+    /// If `view` is abandoned before the job completes, or the job panics
+    pub async fn verify<R, F>(&self, view: Option<u64>, job: F) -> Result<R, VidPoolError>
+    where
+        R: Send + 'static,
+        F: FnOnce() -> R + Send + 'static,
+    {
+        self.run(view, job).await.map(|(result, _)| result)
+    }
+
+    /// Run `job` once a permit is available, unless `view` is abandoned first
+    async fn run<R, F>(&self, view: Option<u64>, job: F) -> Result<(R, Duration), VidPoolError>
+    where
+        R: Send + 'static,
+        F: FnOnce() -> R + Send + 'static,
+    {
+        let Some(view) = view.filter(|_| self.cancellable) else {
+            return Self::spawn(Arc::clone(&self.permits), job).await;
+        };
+
+        let mut abandoned_before = self.abandoned_before.subscribe();
+        let result = tokio::select! {
+            biased;
+            _ = abandoned_before.wait_for(|abandoned_before| view < *abandoned_before) => {
+                Err(VidPoolError::Cancelled(view))
+            }
+            result = Self::spawn(Arc::clone(&self.permits), job) => result,
+        };
+
+        if matches!(result, Err(VidPoolError::Cancelled(_))) {
+            self.metrics.vid_jobs_cancelled.add(1);
+        }
+
+        result
+    }
+
+    /// Wait for a permit, then run `job` on the blocking thread pool while holding it
+    async fn spawn<R, F>(permits: Arc<Semaphore>, job: F) -> Result<(R, Duration), VidPoolError>
+    where
+        R: Send + 'static,
+        F: FnOnce() -> R + Send + 'static,
+    {
+        let permit = permits
+            .acquire_owned()
+            .await
+            .map_err(|e| VidPoolError::Failed(e.to_string()))?;
+
+        spawn_blocking(move || {
+            // Released when the job is done, even if the caller stopped waiting for it.
+            let _permit = permit;
+            let start = Instant::now();
+            let result = job();
+            (result, start.elapsed())
+        })
+        .await
+        .map_err(|e| VidPoolError::Failed(e.to_string()))
+    }
+}
+
+/// Label of the payload size range used for the encode time metric
+fn payload_size_bucket(payload_size: usize) -> &'static str {
+    const KIB: usize = 1 << 10;
+    const MIB: usize = 1 << 20;
+    const MIB_16: usize = 16 << 20;
+
+    match payload_size {
+        0..KIB => "<1KiB",
+        KIB..MIB => "<1MiB",
+        MIB..MIB_16 => "<16MiB",
+        _ => ">=16MiB",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    fn pool(max_jobs: usize) -> VidComputePool {
+        VidComputePool::new(
+            NonZeroUsize::new(max_jobs).unwrap(),
+            Arc::new(ConsensusMetricsValue::default()),
+        )
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn runs_jobs() {
+        let pool = pool(2);
+
+        assert_eq!(pool.encode(Some(1), 10, || 1 + 1).await.unwrap(), 2);
+        assert!(pool.verify(None, || true).await.unwrap());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn cancels_abandoned_views() {
+        let pool = pool(1);
+
+        // Occupy the only permit until the test releases it.
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let busy = {
+            let pool = pool.clone();
+            tokio::spawn(async move { pool.verify(None, move || release_rx.recv()).await })
+        };
+        while pool.permits.available_permits() > 0 {
+            tokio::task::yield_now().await;
+        }
+
+        let queued = {
+            let pool = pool.clone();
+            tokio::spawn(async move { pool.encode(Some(3), 10, || ()).await })
+        };
+        pool.abandon_views_before(4);
+
+        assert!(matches!(
+            queued.await.unwrap(),
+            Err(VidPoolError::Cancelled(3))
+        ));
+        assert!(matches!(
+            pool.verify(Some(3), || ()).await,
+            Err(VidPoolError::Cancelled(3))
+        ));
+
+        release_tx.send(()).unwrap();
+        busy.await.unwrap().unwrap().unwrap();
+        pool.verify(Some(4), || ()).await.unwrap();
+        pool.without_cancellation()
+            .verify(Some(3), || ())
+            .await
+            .unwrap();
+    }
+}
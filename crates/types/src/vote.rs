@@ -144,6 +144,12 @@ pub struct VoteAccumulator<
         Commitment<VersionedVoteData<TYPES, <VOTE as Vote<TYPES>>::Commitment, V>>,
         TYPES::SignatureKey,
     >,
+    /// Votes whose signatures will be verified together once they could form a certificate
+    pub pending_votes: PendingVotesMap<
+        Commitment<VersionedVoteData<TYPES, <VOTE as Vote<TYPES>>::Commitment, V>>,
+        TYPES::SignatureKey,
+        <VOTE as Vote<TYPES>>::Commitment,
+    >,
     /// Phantom data to specify the types this accumulator is for
     pub phantom: PhantomData<(TYPES, VOTE, CERT)>,
     /// version information
@@ -160,6 +166,9 @@ impl<
     /// Add a vote to the total accumulated votes for the given epoch.
     /// Returns the accumulator or the certificate if we
     /// have accumulated enough votes to exceed the threshold for creating a certificate.
+    ///
+    /// Vote signatures are verified in a batch once the votes could form a certificate, dropping
+    /// the invalid ones.
     pub async fn accumulate(
         &mut self,
         vote: &VOTE,
@@ -182,11 +191,6 @@ impl<
             }
         };
 
-        if !key.validate(&vote.signature(), vote_commitment.as_ref()) {
-            error!("Invalid vote! Vote Data {:?}", vote.date());
-            return None;
-        }
-
         let membership_reader = membership.read().await;
         let stake_table_entry = CERT::stake_table_entry(&*membership_reader, &key, epoch)?;
         let stake_table = CERT::stake_table(&*membership_reader, epoch);
@@ -198,9 +202,6 @@ impl<
             .iter()
             .position(|x| *x == stake_table_entry.clone())?;
 
-        let original_signature: <TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType =
-            vote.signature();
-
         let (total_stake_casted, total_vote_map) = self
             .vote_outcomes
             .entry(vote_commitment)
@@ -210,19 +211,63 @@ impl<
         if total_vote_map.contains_key(&key) {
             return None;
         }
+
+        // Signatures are only verified once the votes claim enough stake for a certificate, so
+        // they can be verified at once. Until then a key may have several candidate votes, or
+        // anyone could prevent its vote from counting by sending an invalid one first.
+        let pending = self.pending_votes.entry(vote_commitment).or_default();
+        let signature = vote.signature();
+        if pending.iter().any(|(pending_key, pending_signature, ..)| {
+            *pending_key == key && *pending_signature == signature
+        }) {
+            return None;
+        }
+        pending.push((
+            key,
+            signature,
+            vote_node_id,
+            stake_table_entry.stake(),
+            vote.date().clone(),
+        ));
+
+        let mut pending_keys = BTreeMap::new();
+        for (key, _, _, stake, _) in &*pending {
+            pending_keys.insert(key, *stake);
+        }
+        let pending_stake = pending_keys
+            .into_values()
+            .fold(U256::zero(), |total, stake| total.saturating_add(stake));
+        // Also verify once there are as many candidates as nodes, so invalid votes don't pile up
+        if total_stake_casted.saturating_add(pending_stake) < threshold.into()
+            && pending.len() < total_nodes
+        {
+            return None;
+        }
+
         let (signers, sig_list) = self
             .signers
             .entry(vote_commitment)
             .or_insert((bitvec![0; total_nodes], Vec::new()));
-        if signers.get(vote_node_id).as_deref() == Some(&true) {
-            error!("Node id is already in signers list");
-            return None;
-        }
-        signers.set(vote_node_id, true);
-        sig_list.push(original_signature);
+        let pending = std::mem::take(pending);
+        let valid = validate_with_fallback(&pending, vote_commitment.as_ref());
+        for ((key, signature, node_id, stake, data), valid) in pending.into_iter().zip(valid) {
+            if !valid {
+                error!("Invalid vote from {key}! Vote Data {data:?}");
+                continue;
+            }
+            if total_vote_map.contains_key(&key) {
+                continue;
+            }
+            if signers.get(node_id).as_deref() == Some(&true) {
+                error!("Node id is already in signers list");
+                continue;
+            }
+            signers.set(node_id, true);
+            sig_list.push(signature.clone());
 
-        *total_stake_casted += stake_table_entry.stake();
-        total_vote_map.insert(key, (vote.signature(), vote_commitment));
+            *total_stake_casted += stake;
+            total_vote_map.insert(key, (signature, vote_commitment));
+        }
 
         if *total_stake_casted >= threshold.into() {
             // Assemble QC
@@ -250,5 +295,42 @@ impl<
     }
 }
 
+/// Validate the signatures of `votes` over `data` in batches, returning whether each is valid.
+///
+/// If a batch is invalid, its halves are validated separately, so a few invalid signatures
+/// among many only cost a few more batches.
+fn validate_with_fallback<KEY: SignatureKey, DATA>(
+    votes: &[PendingVote<KEY, DATA>],
+    data: &[u8],
+) -> Vec<bool> {
+    let (keys, signatures): (Vec<_>, Vec<_>) = votes
+        .iter()
+        .map(|(key, signature, ..)| (key.clone(), signature.clone()))
+        .collect();
+    if KEY::batch_validate(&keys, &signatures, data) {
+        return vec![true; votes.len()];
+    }
+    if votes.len() == 1 {
+        return vec![false];
+    }
+
+    let (left, right) = votes.split_at(votes.len() / 2);
+    let mut valid = validate_with_fallback(left, data);
+    valid.extend(validate_with_fallback(right, data));
+    valid
+}
+
+/// A vote whose signature isn't verified yet: its key, signature, node id, stake and data
+type PendingVote<KEY, DATA> = (
+    KEY,
+    <KEY as SignatureKey>::PureAssembledSignatureType,
+    usize,
+    U256,
+    DATA,
+);
+
+/// Mapping of vote commitments to the votes for them whose signatures aren't verified yet
+type PendingVotesMap<COMMITMENT, KEY, DATA> = HashMap<COMMITMENT, Vec<PendingVote<KEY, DATA>>>;
+
 /// Mapping of commitments to vote tokens by key.
 type VoteMap2<COMMITMENT, PK, SIG> = HashMap<COMMITMENT, (U256, BTreeMap<PK, (SIG, COMMITMENT)>)>;